@@ -0,0 +1,128 @@
+//! Throughput of /tips_list and /tips_create run concurrently against a database of 50k tips.
+//!
+//! Run it with `cargo bench --bench concurrent_tips`. The same workload is run with the writer
+//! alone (every query waits for the previous one, like the old single shared connection)
+//! and with a pool of reader connections.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use rusqlite::params;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use game_bot4_fun::clock::SystemClock;
+use game_bot4_fun::commands::options::CommandOptions;
+use game_bot4_fun::commands::registry::{CommandRegistry, default_registry};
+use game_bot4_fun::commands::slash_command::CommandContext;
+use game_bot4_fun::config::FeatureToggles;
+use game_bot4_fun::database::Database;
+use game_bot4_fun::database::pool::{DatabasePool, DEFAULT_READERS};
+use game_bot4_fun::database::migrations::{migrate, MigrationContext};
+
+const TIPS: usize = 50_000;
+const GUILD_ID: u64 = 1;
+const TAGS: [&str; 10] = ["unreal", "unity", "shaders", "blueprint", "cpp", "godot", "audio", "ui", "tools", "network"];
+const LIST_TASKS: usize = 8;
+const CREATE_TASKS: usize = 2;
+const DURATION: Duration = Duration::from_secs(5);
+
+/**
+ * This method create a new database file filled with TIPS tips.
+ *
+ * @param readers: usize, the number of reader connections of the pool
+ *
+ * @return (DatabasePool, PathBuf), the pool and the path of the database file
+ */
+async fn prepare_database(readers: usize) -> (DatabasePool, PathBuf) {
+    let path = std::env::temp_dir().join(format!("gamebot_bench_{}_readers.sqlite", readers));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+
+    let database = DatabasePool::open(&path, readers).await.expect("Failed to open the database");
+    migrate(database.clone(), MigrationContext { home_guild: None }).await.expect("Failed to run migrations");
+    database.write(|conn| {
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("INSERT INTO tips (guild_id, title, content, tags) VALUES (?1,?2,?3,?4)")?;
+            for i in 0..TIPS {
+                let tags = format!("{},{}", TAGS[i % TAGS.len()], TAGS[(i / TAGS.len()) % TAGS.len()]);
+                stmt.execute(params![GUILD_ID, format!("Tip {}", i), format!("Content of the tip {}", i), tags])?;
+            }
+        }
+        tx.commit()
+    }).await.expect("Failed to fill the database");
+
+    (database, path)
+}
+
+/**
+ * This method run /tips_list and /tips_create concurrently during DURATION.
+ *
+ * @param readers: usize, the number of reader connections of the pool
+ *
+ * @return (usize, usize), the number of /tips_list and /tips_create run
+ */
+async fn run_workload(readers: usize) -> (usize, usize) {
+    let (database, path) = prepare_database(readers).await;
+    let ctx = CommandContext { timezone: None, ..CommandContext::in_memory(Arc::new(SystemClock)) }
+        .with_database(&Database::Sqlite(database));
+    let registry: Arc<CommandRegistry> = Arc::new(default_registry(&FeatureToggles::default()));
+    let deadline = Instant::now() + DURATION;
+
+    let mut lists = Vec::with_capacity(LIST_TASKS);
+    for task in 0..LIST_TASKS {
+        let (ctx, registry) = (ctx.clone(), registry.clone());
+        lists.push(tokio::spawn(async move {
+            let mut count = 0;
+            while Instant::now() < deadline {
+                let tag = TAGS[(task + count) % TAGS.len()];
+                let options = CommandOptions::new().with("tags", CommandDataOptionValue::String(tag.to_string()));
+                registry.dispatch(&ctx, "tips_list", GUILD_ID, &options).await;
+                count += 1;
+            }
+            count
+        }));
+    }
+
+    let mut creates = Vec::with_capacity(CREATE_TASKS);
+    for task in 0..CREATE_TASKS {
+        let (ctx, registry) = (ctx.clone(), registry.clone());
+        creates.push(tokio::spawn(async move {
+            let mut count = 0;
+            while Instant::now() < deadline {
+                let options = CommandOptions::new()
+                    .with("title", CommandDataOptionValue::String(format!("Bench tip {}-{}", task, count)))
+                    .with("content", CommandDataOptionValue::String(String::from("Created by the benchmark")))
+                    .with("tags", CommandDataOptionValue::String(String::from("bench")));
+                registry.dispatch(&ctx, "tips_create", GUILD_ID, &options).await;
+                count += 1;
+            }
+            count
+        }));
+    }
+
+    let mut listed = 0;
+    for handle in lists {
+        listed += handle.await.expect("A /tips_list task panicked");
+    }
+    let mut created = 0;
+    for handle in creates {
+        created += handle.await.expect("A /tips_create task panicked");
+    }
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+    (listed, created)
+}
+
+#[tokio::main]
+async fn main() {
+    println!("{} tips, {} /tips_list tasks and {} /tips_create tasks during {:?}", TIPS, LIST_TASKS, CREATE_TASKS, DURATION);
+    println!("{:<24} {:>16} {:>18}", "configuration", "/tips_list per s", "/tips_create per s");
+    for (label, readers) in [("writer only", 0), ("writer + readers", DEFAULT_READERS)] {
+        let (listed, created) = run_workload(readers).await;
+        let seconds = DURATION.as_secs_f64();
+        println!("{:<24} {:>16.1} {:>18.1}", label, listed as f64 / seconds, created as f64 / seconds);
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use chrono::Utc;
+use crate::backup::{self, BackupError, Backups, SnapshotKind};
+use crate::config::{CliArguments, Config, ConfigError};
+use crate::database::{ConnectionError, Database, run_migrations};
+use crate::database::migrations::MigrationError;
+use crate::repositories::{NewAuditEntry, NewTip, RepositoryError, Tip, TipChanges};
+use crate::utils::parse_tags;
+
+// Longest title and content of a tip : the limits of the title and the description of an embed
+pub const MAX_TITLE_LENGTH: usize = 256;
+pub const MAX_CONTENT_LENGTH: usize = 4096;
+
+// Author of the changes made with gamebot-admin in the audit log : there is no discord user
+pub const ADMIN_ACTOR: u64 = 0;
+
+// Help printed by gamebot-admin help
+pub const USAGE: &str = "\
+Usage: gamebot-admin <command> [options] [configuration flags]
+
+Commands:
+  tips list --guild ID [--tags TAGS]                               List the tips : id, title and tags separated by tabs
+  tips show --guild ID --id ID                                     Show a tip
+  tips create --guild ID --title TITLE --content TEXT [--tags TAGS] Create a tip
+  tips edit --guild ID --id ID [--title T] [--content C] [--tags T] Change the values given
+  tips delete --guild ID --id ID                                   Delete a tip
+  tips export --guild ID [--file PATH]                             Write the tips as JSON, on the standard output by default
+  tips import --guild ID --file PATH                               Create the tips of a JSON file exported before
+  migrations status                                                Show the schema version and the pending migrations
+  migrations run                                                   Apply the pending migrations
+  scheduler show --guild ID                                        Show the configuration of the tips scheduler
+  backup now                                                       Write a snapshot of the database in the backup directory
+  backup list                                                      List the snapshots of the backup directory
+  backup verify --file PATH                                        Check the integrity and the schema version of a snapshot
+  backup restore --file PATH                                       Replace the database with a snapshot, the bot must be stopped
+  validate --guild ID                                              Check every tip and the scheduler configuration
+  help                                                             Show this help
+
+The configuration of the bot is used : config.toml, the environment and the flags
+of the bot, like --config PATH or --database-url URL. The discord token is not needed.";
+
+// Flags of the commands, the other flags are the configuration of the bot
+const COMMAND_FLAGS: [&str; 6] = ["--guild", "--id", "--title", "--content", "--tags", "--file"];
+
+// Flags of the bot without value
+const CONFIG_SWITCHES: [&str; 2] = ["--dry-run-migrations", "--print-config"];
+
+/*
+This enum is every command of gamebot-admin.
+ */
+#[derive(Debug, Clone)]
+pub enum AdminCommand {
+    TipsList { guild_id: u64, tags: Vec<String> },
+    TipsShow { guild_id: u64, id: u64 },
+    TipsCreate { guild_id: u64, tip: NewTip },
+    TipsEdit { guild_id: u64, id: u64, changes: TipChanges },
+    TipsDelete { guild_id: u64, id: u64 },
+    TipsExport { guild_id: u64, file: Option<PathBuf> }, // None: the standard output
+    TipsImport { guild_id: u64, file: PathBuf },
+    MigrationsStatus,
+    MigrationsRun,
+    SchedulerShow { guild_id: u64 },
+    BackupNow,
+    BackupList,
+    BackupVerify { file: PathBuf },
+    BackupRestore { file: PathBuf }, // the current database is saved first, see Backups::restore
+    Validate { guild_id: u64 },
+    Help,
+}
+
+/*
+This enum describe every reason a command of gamebot-admin can fail.
+ */
+#[derive(Debug)]
+pub enum AdminError {
+    Usage(String), // the command line is invalid
+    Config(ConfigError),
+    Connection(ConnectionError),
+    Migration(MigrationError),
+    Repository(RepositoryError),
+    Backup(BackupError),
+    BackupsUnavailable, // the database is not a sqlite file
+    PendingMigrations(usize), // the database must be migrated before being used
+    UnknownTip(u64),
+    File(PathBuf, std::io::Error), // a file can't be read or written
+    Json(PathBuf, serde_json::Error), // an imported file isn't a list of tips
+    Output(std::io::Error), // the standard output is closed
+    Invalid(usize), // the number of problems found by validate
+}
+
+impl Display for AdminError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdminError::Usage(problem) => write!(f, "{}\nRun gamebot-admin help to see every command.", problem),
+            AdminError::Config(err) => write!(f, "{}", err),
+            AdminError::Connection(err) => write!(f, "Couldn't connect to the database. {}", err),
+            AdminError::Migration(err) => write!(f, "{}", err),
+            AdminError::Repository(err) => write!(f, "Database error: {}", err),
+            AdminError::Backup(err) => write!(f, "{}", err),
+            AdminError::BackupsUnavailable => write!(f, "Only sqlite databases can be backed up by gamebot-admin. Use the tools of PostgreSQL, like pg_dump and pg_restore."),
+            AdminError::PendingMigrations(count) => write!(f,
+                "The database has {} pending migration(s). Run gamebot-admin migrations run, or start the bot, before using it.", count
+            ),
+            AdminError::UnknownTip(id) => write!(f, "There is no tip with the id {} in this guild.", id),
+            AdminError::File(path, err) => write!(f, "Can't use the file {}: {}", path.display(), err),
+            AdminError::Json(path, err) => write!(f, "{} is not a list of tips: {}", path.display(), err),
+            AdminError::Output(err) => write!(f, "Can't write the output: {}", err),
+            AdminError::Invalid(count) => write!(f, "{} problem(s) found.", count),
+        }
+    }
+}
+
+impl std::error::Error for AdminError {}
+
+impl From<RepositoryError> for AdminError {
+    fn from(err: RepositoryError) -> Self {
+        AdminError::Repository(err)
+    }
+}
+
+impl From<MigrationError> for AdminError {
+    fn from(err: MigrationError) -> Self {
+        AdminError::Migration(err)
+    }
+}
+
+impl From<BackupError> for AdminError {
+    fn from(err: BackupError) -> Self {
+        AdminError::Backup(err)
+    }
+}
+
+impl From<std::io::Error> for AdminError {
+    fn from(err: std::io::Error) -> Self {
+        AdminError::Output(err)
+    }
+}
+
+/*
+This structure is the command line of gamebot-admin.
+ */
+#[derive(Debug, Clone)]
+pub struct AdminArguments {
+    pub command: AdminCommand,
+    pub config: CliArguments, // the flags of the bot, like --config or --database-url
+}
+
+/*
+This structure is the flags given to a command, by name without --.
+ */
+struct CommandFlags {
+    values: HashMap<String, String>,
+}
+
+impl CommandFlags {
+    fn optional(&self, name: &str) -> Option<String> {
+        self.values.get(name).cloned()
+    }
+
+    fn required(&self, name: &str) -> Result<String, AdminError> {
+        self.optional(name).ok_or_else(|| AdminError::Usage(format!("--{} is required.", name)))
+    }
+
+    fn id(&self, name: &str) -> Result<u64, AdminError> {
+        let value = self.required(name)?;
+        value.trim().parse().map_err(|_| AdminError::Usage(format!("--{} must be a number, got \"{}\".", name, value)))
+    }
+
+    fn tags(&self) -> Result<Option<String>, AdminError> {
+        self.optional("tags")
+            .map(|tags| parse_tags("tags", tags).map_err(|err| AdminError::Usage(err.to_string())))
+            .transpose()
+    }
+}
+
+impl AdminArguments {
+    /**
+     * This method parse the command line of gamebot-admin, without the name of the program.
+     * The flags of the commands are read here, the other flags are given to the configuration.
+     *
+     * @param args: impl IntoIterator<Item = String>, the arguments
+     *
+     * @return Result<AdminArguments, AdminError>
+     */
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<AdminArguments, AdminError> {
+        // 1 - Split the words of the command, its flags and the configuration flags
+        let mut words = Vec::new();
+        let mut flags = CommandFlags { values: HashMap::new() };
+        let mut config = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                words.push(arg);
+                continue;
+            }
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            if COMMAND_FLAGS.contains(&flag.as_str()) {
+                let value = inline_value.or_else(|| args.next())
+                    .ok_or_else(|| AdminError::Usage(format!("{} expects a value.", flag)))?;
+                flags.values.insert(flag.trim_start_matches('-').to_string(), value);
+                continue;
+            }
+            // The configuration checks its own flags
+            config.push(arg);
+            if inline_value.is_none() && !CONFIG_SWITCHES.contains(&flag.as_str()) {
+                config.extend(args.next());
+            }
+        }
+        let config = CliArguments::parse(config).map_err(AdminError::Config)?;
+
+        // 2 - Find the command
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let command = match words.as_slice() {
+            [] | ["help"] => AdminCommand::Help,
+            ["tips", "list"] => AdminCommand::TipsList {
+                guild_id: flags.id("guild")?,
+                tags: flags.tags()?.map(|tags| tags.split(',').map(String::from).collect()).unwrap_or_default(),
+            },
+            ["tips", "show"] => AdminCommand::TipsShow { guild_id: flags.id("guild")?, id: flags.id("id")? },
+            ["tips", "create"] => AdminCommand::TipsCreate {
+                guild_id: flags.id("guild")?,
+                tip: NewTip { title: flags.required("title")?, content: flags.required("content")?, tags: flags.tags()?.unwrap_or_default() },
+            },
+            ["tips", "edit"] => AdminCommand::TipsEdit {
+                guild_id: flags.id("guild")?,
+                id: flags.id("id")?,
+                changes: TipChanges { title: flags.optional("title"), content: flags.optional("content"), tags: flags.tags()? },
+            },
+            ["tips", "delete"] => AdminCommand::TipsDelete { guild_id: flags.id("guild")?, id: flags.id("id")? },
+            ["tips", "export"] => AdminCommand::TipsExport { guild_id: flags.id("guild")?, file: flags.optional("file").map(PathBuf::from) },
+            ["tips", "import"] => AdminCommand::TipsImport { guild_id: flags.id("guild")?, file: PathBuf::from(flags.required("file")?) },
+            ["migrations", "status"] => AdminCommand::MigrationsStatus,
+            ["migrations", "run"] => AdminCommand::MigrationsRun,
+            ["scheduler", "show"] => AdminCommand::SchedulerShow { guild_id: flags.id("guild")? },
+            ["backup", "now"] => AdminCommand::BackupNow,
+            ["backup", "list"] => AdminCommand::BackupList,
+            ["backup", "verify"] => AdminCommand::BackupVerify { file: PathBuf::from(flags.required("file")?) },
+            ["backup", "restore"] => AdminCommand::BackupRestore { file: PathBuf::from(flags.required("file")?) },
+            ["validate"] => AdminCommand::Validate { guild_id: flags.id("guild")? },
+            _ => return Err(AdminError::Usage(format!("Unknown command \"{}\".", words.join(" ")))),
+        };
+        Ok(AdminArguments { command, config })
+    }
+}
+
+/**
+ * This method check the values of a tip, as the bot displays them.
+ *
+ * @param tip: &NewTip, the values of the tip
+ *
+ * @return Vec<String>, every problem found, empty if the tip is valid
+ */
+pub fn check_tip(tip: &NewTip) -> Vec<String> {
+    let mut problems = Vec::new();
+    if tip.title.trim().is_empty() {
+        problems.push(String::from("the title is empty."));
+    }
+    if tip.title.chars().count() > MAX_TITLE_LENGTH {
+        problems.push(format!("the title is longer than {} characters.", MAX_TITLE_LENGTH));
+    }
+    if tip.content.trim().is_empty() {
+        problems.push(String::from("the content is empty."));
+    }
+    if tip.content.chars().count() > MAX_CONTENT_LENGTH {
+        problems.push(format!("the content is longer than {} characters.", MAX_CONTENT_LENGTH));
+    }
+    if !tip.tags.is_empty() && parse_tags("tags", tip.tags.clone()).ok().as_deref() != Some(tip.tags.as_str()) {
+        problems.push(format!("the tags \"{}\" are not lowercase csv with no spaces around coma, like tag1,tag2.", tip.tags));
+    }
+    problems
+}
+
+/**
+ * This method write a change made with gamebot-admin in the audit log of the guild.
+ *
+ * @param database: &Database, the database of the bot
+ * @param guild_id: u64, the guild changed
+ * @param arguments: String, the command run, like tips delete id=3
+ * @param before: Option<&Tip>, the tip before the change
+ * @param after: Option<&Tip>, the tip after the change
+ *
+ * @return Result<(), AdminError>
+ */
+async fn audit(database: &Database, guild_id: u64, arguments: String, before: Option<&Tip>, after: Option<&Tip>) -> Result<(), AdminError> {
+    let entry = NewAuditEntry {
+        actor: ADMIN_ACTOR,
+        command: String::from("gamebot-admin"),
+        arguments,
+        before: before.map(|tip| serde_json::json!(tip).to_string()),
+        after: after.map(|tip| serde_json::json!(tip).to_string()),
+        created_at: Utc::now(),
+    };
+    database.audit_repository().record(guild_id, entry).await?;
+    Ok(())
+}
+
+/**
+ * This method return the snapshots of the database.
+ *
+ * @param database: &Database, the database of the bot
+ * @param config: &Config, the configuration of the bot : the backup directory and the retention
+ *
+ * @return Result<Backups, AdminError>, BackupsUnavailable if the database is not a sqlite file
+ */
+fn backups(database: &Database, config: &Config) -> Result<Backups, AdminError> {
+    database.backups(config.backup.clone()).ok_or(AdminError::BackupsUnavailable)
+}
+
+/**
+ * This method run a command of gamebot-admin on the database.
+ * Every command but migrations, backups and help refuse a database not migrated yet.
+ *
+ * @param database: &Database, the database of the bot
+ * @param config: &Config, the configuration of the bot : the home guild of migrations and the backup directory
+ * @param command: AdminCommand, the command to run
+ * @param out: &mut dyn Write, where the result is written, the standard output
+ *
+ * @return Result<(), AdminError>
+ */
+pub async fn run(database: &Database, config: &Config, command: AdminCommand, out: &mut dyn Write) -> Result<(), AdminError> {
+    // 1 - Commands that don't need an up to date database
+    match command {
+        AdminCommand::Help => {
+            writeln!(out, "{}", USAGE)?;
+            return Ok(());
+        }
+        AdminCommand::MigrationsStatus => {
+            let pending = database.pending_migrations().await?;
+            writeln!(out, "Schema version {} of {}.", database.latest_version() - pending.len() as u32, database.latest_version())?;
+            for (version, name) in pending {
+                writeln!(out, "  pending: {} - {}", version, name)?;
+            }
+            return Ok(());
+        }
+        AdminCommand::MigrationsRun => {
+            run_migrations(database, config.home_guild).await?;
+            writeln!(out, "The database is up to date (schema version {}).", database.latest_version())?;
+            return Ok(());
+        }
+        AdminCommand::BackupNow => {
+            let snapshot = backups(database, config)?.snapshot(Utc::now(), SnapshotKind::Scheduled).await?;
+            writeln!(out, "Snapshot written in {} ({} bytes).", snapshot.path.display(), snapshot.size)?;
+            return Ok(());
+        }
+        AdminCommand::BackupList => {
+            let snapshots = backups(database, config)?.list()?;
+            writeln!(out, "{} snapshot(s) in {}.", snapshots.len(), config.backup.directory.display())?;
+            for snapshot in snapshots {
+                writeln!(out, "  {}\t{} bytes", snapshot.file_name(), snapshot.size)?;
+            }
+            return Ok(());
+        }
+        AdminCommand::BackupVerify { file } => {
+            let version = backup::verify_snapshot(&file)?;
+            writeln!(out, "{} is valid (schema version {} of {}).", file.display(), version, database.latest_version())?;
+            return Ok(());
+        }
+        AdminCommand::BackupRestore { file } => {
+            let restored = backups(database, config)?.restore(&file, Utc::now()).await?;
+            writeln!(out, "The current database has been saved in {}.", restored.previous.display())?;
+            writeln!(out, "The database has been restored from {} (schema version {}).", file.display(), restored.version)?;
+            if restored.version < database.latest_version() {
+                writeln!(out, "Run gamebot-admin migrations run, or start the bot, to migrate it.")?;
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+    let pending = database.pending_migrations().await?.len();
+    if pending > 0 {
+        return Err(AdminError::PendingMigrations(pending));
+    }
+
+    // 2 - Commands on the tips and the scheduler, with the same storage as the bot
+    let tips = database.tip_repository();
+    match command {
+        AdminCommand::TipsList { guild_id, tags } => {
+            writeln!(out, "id\ttitle\ttags")?;
+            for tip in tips.list(guild_id, &tags).await? {
+                writeln!(out, "{}\t{}\t{}", tip.id, tip.title, tip.tags)?;
+            }
+        }
+        AdminCommand::TipsShow { guild_id, id } => {
+            let tip = tips.get(guild_id, id).await?.ok_or(AdminError::UnknownTip(id))?;
+            writeln!(out, "{}\n\n{}\n\n#: {}", tip.title, tip.content, tip.tags)?;
+        }
+        AdminCommand::TipsCreate { guild_id, tip } => {
+            if let Some(problem) = check_tip(&tip).into_iter().next() {
+                return Err(AdminError::Usage(format!("The tip is invalid : {}", problem)));
+            }
+            let tip = tips.create(guild_id, tip).await?;
+            audit(database, guild_id, format!("tips create title={:?}", tip.title), None, Some(&tip)).await?;
+            writeln!(out, "Tip {} created.", tip.id)?;
+        }
+        AdminCommand::TipsEdit { guild_id, id, changes } => {
+            let before = tips.get(guild_id, id).await?.ok_or(AdminError::UnknownTip(id))?;
+            let edited = NewTip {
+                title: changes.title.clone().unwrap_or_else(|| before.title.clone()),
+                content: changes.content.clone().unwrap_or_else(|| before.content.clone()),
+                tags: changes.tags.clone().unwrap_or_else(|| before.tags.clone()),
+            };
+            if let Some(problem) = check_tip(&edited).into_iter().next() {
+                return Err(AdminError::Usage(format!("The tip is invalid : {}", problem)));
+            }
+            let after = tips.update(guild_id, id, changes).await?.ok_or(AdminError::UnknownTip(id))?;
+            audit(database, guild_id, format!("tips edit id={}", id), Some(&before), Some(&after)).await?;
+            writeln!(out, "Tip {} updated. Messages where it has been posted are not edited.", id)?;
+        }
+        AdminCommand::TipsDelete { guild_id, id } => {
+            let before = tips.get(guild_id, id).await?.ok_or(AdminError::UnknownTip(id))?;
+            tips.delete(guild_id, id).await?;
+            audit(database, guild_id, format!("tips delete id={}", id), Some(&before), None).await?;
+            writeln!(out, "Tip {} deleted.", id)?;
+        }
+        AdminCommand::TipsExport { guild_id, file } => {
+            let tips = tips.list(guild_id, &[]).await?;
+            let json = serde_json::to_string_pretty(&tips).expect("Tips are always serializable");
+            match file {
+                Some(path) => {
+                    fs::write(&path, json).map_err(|err| AdminError::File(path.clone(), err))?;
+                    writeln!(out, "{} tip(s) exported in {}.", tips.len(), path.display())?;
+                }
+                None => writeln!(out, "{}", json)?,
+            }
+        }
+        AdminCommand::TipsImport { guild_id, file } => {
+            // Files exported before : the ids are ignored, tips get new ones
+            let content = fs::read_to_string(&file).map_err(|err| AdminError::File(file.clone(), err))?;
+            let imported: Vec<NewTip> = serde_json::from_str(&content).map_err(|err| AdminError::Json(file.clone(), err))?;
+            let (mut created, mut skipped) = (0, 0);
+            for mut tip in imported {
+                tip.tags = parse_tags("tags", tip.tags.clone()).unwrap_or(tip.tags);
+                if let Some(problem) = check_tip(&tip).into_iter().next() {
+                    writeln!(out, "Skipped \"{}\": {}", tip.title, problem)?;
+                    skipped += 1;
+                    continue;
+                }
+                match tips.create(guild_id, tip).await {
+                    Ok(tip) => {
+                        audit(database, guild_id, format!("tips import title={:?}", tip.title), None, Some(&tip)).await?;
+                        created += 1;
+                    }
+                    Err(RepositoryError::DuplicateTitle(title)) => {
+                        writeln!(out, "Skipped \"{}\": a tip with this title already exists.", title)?;
+                        skipped += 1;
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            writeln!(out, "{} tip(s) imported, {} skipped.", created, skipped)?;
+        }
+        AdminCommand::SchedulerShow { guild_id } => match database.scheduler_repository().get(guild_id).await? {
+            Some(scheduler) => {
+                writeln!(out, "channel = {}\nhour = {:02}:{:02}", scheduler.channel, scheduler.hour, scheduler.minute)?;
+                if let Some(persona) = scheduler.persona {
+                    writeln!(out, "webhook = {}", persona.name)?;
+                    if let Some(avatar_url) = persona.avatar_url {
+                        writeln!(out, "avatar = {}", avatar_url)?;
+                    }
+                }
+            }
+            None => writeln!(out, "The tips scheduler of this guild is not configured.")?,
+        },
+        AdminCommand::Validate { guild_id } => {
+            let mut problems = 0;
+            let tips = tips.list(guild_id, &[]).await?;
+            for tip in &tips {
+                let values = NewTip { title: tip.title.clone(), content: tip.content.clone(), tags: tip.tags.clone() };
+                for problem in check_tip(&values) {
+                    writeln!(out, "tip {}: {}", tip.id, problem)?;
+                    problems += 1;
+                }
+            }
+            if let Some(scheduler) = database.scheduler_repository().get(guild_id).await? {
+                if scheduler.hour > 23 || scheduler.minute > 59 {
+                    writeln!(out, "scheduler: {:02}:{:02} is not an hour.", scheduler.hour, scheduler.minute)?;
+                    problems += 1;
+                }
+            }
+            writeln!(out, "{} tip(s) checked, {} problem(s) found.", tips.len(), problems)?;
+            if problems > 0 {
+                return Err(AdminError::Invalid(problems));
+            }
+        }
+        AdminCommand::Help | AdminCommand::MigrationsStatus | AdminCommand::MigrationsRun
+        | AdminCommand::BackupNow | AdminCommand::BackupList | AdminCommand::BackupVerify { .. } | AdminCommand::BackupRestore { .. } => unreachable!("run above"),
+    }
+    Ok(())
+}
@@ -0,0 +1,418 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use log::{debug, error, info};
+use rusqlite::{DatabaseName, OpenFlags};
+use rusqlite::backup::{Backup, StepResult};
+use tokio::task::JoinHandle;
+use tracing::{info_span, Instrument};
+use crate::clock::Clock;
+use crate::config::BackupConfig;
+use crate::database::migrations;
+use crate::database::pool::DatabasePool;
+use crate::messenger::AdminChannel;
+use crate::shutdown::Shutdown;
+
+// Name of the snapshot files : database-20230415-093000.sqlite, the time is UTC
+const SNAPSHOT_PREFIX: &str = "database-";
+// Name of the snapshots asked with /backup Now : manual-20230415-093000.sqlite
+const MANUAL_PREFIX: &str = "manual-";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+const SNAPSHOT_EXTENSION: &str = ".sqlite";
+// Snapshot being written : it's renamed once complete, so it's never listed half written
+const PARTIAL_EXTENSION: &str = ".partial";
+// Copy of the database replaced by a restore : it's not a snapshot, the retention never deletes it
+const BEFORE_RESTORE_PREFIX: &str = "before-restore-";
+// Wait before copying again when the database is locked by a checkpoint
+const BUSY_PAUSE: Duration = Duration::from_millis(100);
+
+/*
+This enum is who asked for a snapshot. Each kind has its own retention :
+the managers of a guild can't delete the scheduled snapshots with /backup Now.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    Scheduled, // written every interval, or with gamebot-admin
+    Manual, // written with /backup Now
+}
+
+impl SnapshotKind {
+    /**
+     * This method return the start of the name of the snapshot files of this kind.
+     *
+     * @return &'static str
+     */
+    fn prefix(&self) -> &'static str {
+        match self {
+            SnapshotKind::Scheduled => SNAPSHOT_PREFIX,
+            SnapshotKind::Manual => MANUAL_PREFIX,
+        }
+    }
+}
+
+/*
+This structure is a snapshot of the database written in the backup directory.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>, // read from the name of the file
+    pub size: u64, // bytes
+    pub kind: SnapshotKind, // read from the name of the file
+}
+
+impl Snapshot {
+    /**
+     * This method return the name of the file of the snapshot.
+     *
+     * @return String, like database-20230415-093000.sqlite
+     */
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+    }
+}
+
+/*
+This structure is the result of a restore.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Restored {
+    pub version: u32, // the schema version of the restored database, migrated when the bot starts
+    pub previous: PathBuf, // the copy of the database before the restore
+}
+
+/*
+This enum describe every reason a backup or a restore can fail.
+ */
+#[derive(Debug)]
+pub enum BackupError {
+    File(PathBuf, std::io::Error), // the backup directory or a snapshot can't be read or written
+    Database(tokio_rusqlite::Error), // the copy of the database failed
+    Corrupted(PathBuf, String), // the snapshot is not a sqlite database or fails the integrity check
+    NotABotDatabase(PathBuf), // the snapshot has no schema_version table
+    TooNew { database: u32, binary: u32 }, // the snapshot has been migrated by a newer version of the bot
+}
+
+impl Display for BackupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackupError::File(path, err) => write!(f, "Can't use {}: {}", path.display(), err),
+            BackupError::Database(err) => write!(f, "Database error: {}", err),
+            BackupError::Corrupted(path, problem) => write!(f, "The snapshot {} is corrupted: {}", path.display(), problem),
+            BackupError::NotABotDatabase(path) => write!(f, "{} is not a database of the bot : it has no schema_version table.", path.display()),
+            BackupError::TooNew { database, binary } => write!(f,
+                "The snapshot schema is at version {} but this version of the bot only knows versions up to {}. Update the bot before restoring it.",
+                database, binary
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
+impl From<tokio_rusqlite::Error> for BackupError {
+    fn from(err: tokio_rusqlite::Error) -> Self {
+        BackupError::Database(err)
+    }
+}
+
+/**
+ * This method return the path of the snapshot taken at the given time.
+ *
+ * @param directory: &Path, the backup directory
+ * @param prefix: &str, the start of the name of the file
+ * @param time: DateTime<Utc>, the time of the snapshot
+ *
+ * @return PathBuf
+ */
+fn snapshot_path(directory: &Path, prefix: &str, time: DateTime<Utc>) -> PathBuf {
+    directory.join(format!("{}{}{}", prefix, time.format(SNAPSHOT_TIME_FORMAT), SNAPSHOT_EXTENSION))
+}
+
+/**
+ * This method return the kind and the time of a snapshot from the name of its file.
+ *
+ * @param name: &str, the name of the file
+ *
+ * @return Option<(SnapshotKind, DateTime<Utc>)>, None if the file is not a snapshot
+ */
+fn parse_snapshot_name(name: &str) -> Option<(SnapshotKind, DateTime<Utc>)> {
+    [SnapshotKind::Scheduled, SnapshotKind::Manual].into_iter().find_map(|kind| {
+        let time = name.strip_prefix(kind.prefix())?.strip_suffix(SNAPSHOT_EXTENSION)?;
+        NaiveDateTime::parse_from_str(time, SNAPSHOT_TIME_FORMAT).ok().map(|time| (kind, Utc.from_utc_datetime(&time)))
+    })
+}
+
+/**
+ * This method return the snapshots of the backup directory.
+ * Other files of the directory are ignored.
+ *
+ * @param directory: &Path, the backup directory
+ *
+ * @return Result<Vec<Snapshot>, BackupError>, from the oldest to the most recent. Empty if the directory doesn't exist
+ */
+pub fn list_snapshots(directory: &Path) -> Result<Vec<Snapshot>, BackupError> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(BackupError::File(directory.to_path_buf(), err)),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|err| BackupError::File(directory.to_path_buf(), err))?;
+        if let Some((kind, created_at)) = parse_snapshot_name(&entry.file_name().to_string_lossy()) {
+            let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            snapshots.push(Snapshot { path: entry.path(), created_at, size, kind });
+        }
+    }
+    snapshots.sort_by_key(|snapshot| snapshot.created_at);
+    Ok(snapshots)
+}
+
+/**
+ * This method check a snapshot before it's restored : it must be a
+ * database of the bot that pass the sqlite integrity check, with a schema
+ * this version of the bot knows. Older schemas are migrated once restored.
+ *
+ * @param path: &Path, the snapshot
+ *
+ * @return Result<u32, BackupError>, the schema version of the snapshot
+ */
+pub fn verify_snapshot(path: &Path) -> Result<u32, BackupError> {
+    // 1 - The file must exist : sqlite would create an empty database
+    fs::metadata(path).map_err(|err| BackupError::File(path.to_path_buf(), err))?;
+    let corrupted = |err: rusqlite::Error| BackupError::Corrupted(path.to_path_buf(), err.to_string());
+    let conn = rusqlite::Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+        .map_err(corrupted)?;
+
+    // 2 - Every page and index is checked
+    let mut statement = conn.prepare("PRAGMA integrity_check").map_err(corrupted)?;
+    let problems = statement.query_map([], |row| row.get::<_, String>(0)).map_err(corrupted)?
+        .collect::<Result<Vec<String>, _>>().map_err(corrupted)?;
+    if problems != ["ok"] {
+        return Err(BackupError::Corrupted(path.to_path_buf(), problems.join(", ")));
+    }
+
+    // 3 - The schema version
+    let versioned: usize = conn.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'", [], |row| row.get(0))
+        .map_err(corrupted)?;
+    if versioned == 0 {
+        return Err(BackupError::NotABotDatabase(path.to_path_buf()));
+    }
+    let version: Option<u32> = conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0)).map_err(corrupted)?;
+    let version = version.unwrap_or(0);
+    if version > migrations::latest_version() {
+        return Err(BackupError::TooNew { database: version, binary: migrations::latest_version() });
+    }
+    Ok(version)
+}
+
+/**
+ * This method return the time of the next scheduled snapshot :
+ * one interval after the most recent snapshot, now if it's already passed.
+ * Restarting the bot doesn't delay nor multiply the snapshots.
+ *
+ * @param last: Option<DateTime<Utc>>, the time of the most recent snapshot
+ * @param now: DateTime<Utc>, the current time
+ * @param interval: Duration, the time between two snapshots
+ *
+ * @return DateTime<Utc>
+ */
+pub fn next_snapshot_time(last: Option<DateTime<Utc>>, now: DateTime<Utc>, interval: Duration) -> DateTime<Utc> {
+    let interval = chrono::Duration::from_std(interval).unwrap_or_else(|_| chrono::Duration::days(1));
+    match last {
+        Some(last) if last + interval > now => last + interval,
+        _ => now,
+    }
+}
+
+/*
+This structure write the snapshots of the sqlite database with the
+online backup API : the bot keeps running while the copy is made.
+Only the most recent snapshots are kept.
+ */
+pub struct Backups {
+    database: DatabasePool,
+    config: BackupConfig,
+}
+
+impl Backups {
+    /**
+     * This method create the backups of the database.
+     *
+     * @param database: DatabasePool, the database to copy
+     * @param config: BackupConfig, the directory, the interval and the retention of the snapshots
+     *
+     * @return Backups
+     */
+    pub fn new(database: DatabasePool, config: BackupConfig) -> Backups {
+        Backups { database, config }
+    }
+
+    /**
+     * This method return the backup settings.
+     *
+     * @return &BackupConfig
+     */
+    pub fn config(&self) -> &BackupConfig {
+        &self.config
+    }
+
+    /**
+     * This method return the snapshots of the backup directory.
+     *
+     * @return Result<Vec<Snapshot>, BackupError>, from the oldest to the most recent
+     */
+    pub fn list(&self) -> Result<Vec<Snapshot>, BackupError> {
+        list_snapshots(&self.config.directory)
+    }
+
+    /**
+     * This method write a snapshot of the database, then delete the oldest
+     * snapshots of the same kind beyond the retention.
+     *
+     * @param now: DateTime<Utc>, the time of the snapshot, in its name
+     * @param kind: SnapshotKind, who asked for the snapshot
+     *
+     * @return Result<Snapshot, BackupError>, the snapshot written
+     */
+    pub async fn snapshot(&self, now: DateTime<Utc>, kind: SnapshotKind) -> Result<Snapshot, BackupError> {
+        let path = snapshot_path(&self.config.directory, kind.prefix(), now);
+        let size = self.copy_to(&path).await?;
+        info!("Database snapshot written in {} ({} bytes)", path.display(), size);
+
+        self.prune(kind)?;
+        // The name doesn't keep fractions of seconds
+        let created_at = path.file_name().and_then(|name| parse_snapshot_name(&name.to_string_lossy())).map_or(now, |(_, time)| time);
+        Ok(Snapshot { path, created_at, size, kind })
+    }
+
+    /**
+     * This method copy the database in a file of the backup directory with the
+     * online backup API. The copy is read by a reader connection : commands keep
+     * writing while it's made.
+     *
+     * @param path: &Path, the file written
+     *
+     * @return Result<u64, BackupError>, the size of the file in bytes
+     */
+    async fn copy_to(&self, path: &Path) -> Result<u64, BackupError> {
+        // 1 - Copy the database in a partial file
+        let directory = &self.config.directory;
+        fs::create_dir_all(directory).map_err(|err| BackupError::File(directory.clone(), err))?;
+        let partial = path.with_extension(&PARTIAL_EXTENSION[1..]);
+        let _ = fs::remove_file(&partial);
+        let destination = partial.clone();
+        let copied = self.database.read(move |conn| {
+            let mut snapshot = rusqlite::Connection::open(&destination)?;
+            // Every page in one step : the read transaction of a WAL database doesn't block the writer
+            let backup = Backup::new(conn, &mut snapshot)?;
+            while backup.step(-1)? != StepResult::Done {
+                std::thread::sleep(BUSY_PAUSE);
+            }
+            drop(backup);
+            // The snapshot is a single file, readable without its -wal and -shm files
+            snapshot.pragma_update_and_check(None, "journal_mode", "DELETE", |row| row.get::<_, String>(0))?;
+            Ok(())
+        }).await;
+        if let Err(err) = copied {
+            let _ = fs::remove_file(&partial);
+            return Err(err.into());
+        }
+
+        // 2 - The copy is complete
+        fs::rename(&partial, path).map_err(|err| BackupError::File(path.to_path_buf(), err))?;
+        Ok(fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0))
+    }
+
+    /**
+     * This method replace the content of the database with a snapshot, once it
+     * has been verified. The current database is copied first, next to the
+     * snapshots, so the restore can be undone. It's used by gamebot-admin
+     * while the bot is offline.
+     *
+     * @param path: &Path, the snapshot
+     * @param now: DateTime<Utc>, the time of the restore, in the name of the copy
+     *
+     * @return Result<Restored, BackupError>
+     */
+    pub async fn restore(&self, path: &Path, now: DateTime<Utc>) -> Result<Restored, BackupError> {
+        let version = verify_snapshot(path)?;
+        let previous = snapshot_path(&self.config.directory, BEFORE_RESTORE_PREFIX, now);
+        self.copy_to(&previous).await?;
+
+        let snapshot = path.to_path_buf();
+        self.database.write(move |conn| conn.restore(DatabaseName::Main, &snapshot, None::<fn(rusqlite::backup::Progress)>)).await?;
+        info!("Database restored from {} (schema version {}), the previous one is saved in {}", path.display(), version, previous.display());
+        Ok(Restored { version, previous })
+    }
+
+    /**
+     * This method delete the oldest snapshots of a kind beyond the retention.
+     *
+     * @param kind: SnapshotKind, the kind of snapshots pruned, the others are kept
+     *
+     * @return Result<usize, BackupError>, the number of snapshots deleted
+     */
+    fn prune(&self, kind: SnapshotKind) -> Result<usize, BackupError> {
+        let snapshots: Vec<Snapshot> = self.list()?.into_iter().filter(|snapshot| snapshot.kind == kind).collect();
+        let expired = snapshots.len().saturating_sub(self.config.keep);
+        for snapshot in &snapshots[..expired] {
+            fs::remove_file(&snapshot.path).map_err(|err| BackupError::File(snapshot.path.clone(), err))?;
+            debug!("Snapshot {} deleted by the retention", snapshot.path.display());
+        }
+        Ok(expired)
+    }
+}
+
+/**
+ * This method start the scheduled snapshots : one every interval, the first
+ * one interval after the most recent snapshot. Failures are logged and reported in
+ * the admin channel, the next snapshot is still taken. A snapshot being written is
+ * waited for when the bot stops.
+ *
+ * @param backups: Arc<Backups>, the backups of the database
+ * @param interval: Duration, the time between two snapshots
+ * @param clock: Arc<dyn Clock>, give the time and make the task wait
+ * @param shutdown: Arc<Shutdown>, track the snapshots so the bot stops once they are written
+ * @param admin_channel: Option<AdminChannel>, where failures are reported. None: they are only logged
+ *
+ * @return JoinHandle<()>, the task, aborted when the bot stops
+ */
+pub fn schedule(backups: Arc<Backups>, interval: Duration, clock: Arc<dyn Clock>, shutdown: Arc<Shutdown>, admin_channel: Option<AdminChannel>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let last = match backups.list() {
+                // Manual snapshots don't delay the scheduled ones
+                Ok(snapshots) => snapshots.iter().rev().find(|snapshot| snapshot.kind == SnapshotKind::Scheduled).map(|snapshot| snapshot.created_at),
+                Err(err) => {
+                    error!("Failed to list the database snapshots. Error:\n{}", err);
+                    None
+                }
+            };
+            let next = next_snapshot_time(last, clock.now(), interval);
+            debug!("Next database snapshot at {}", next);
+            clock.sleep_until(next).await;
+
+            // Only the snapshot is tracked : the bot doesn't wait for the retry after a failure
+            let snapshot = {
+                let _activity = match shutdown.track() {
+                    Some(activity) => activity,
+                    None => break,
+                };
+                backups.snapshot(clock.now(), SnapshotKind::Scheduled).await
+            };
+            if let Err(err) = snapshot {
+                error!("Failed to write the scheduled database snapshot. Error:\n{}", err);
+                if let Some(admin_channel) = &admin_channel {
+                    admin_channel.report("Scheduled backup failed", &err.to_string()).await;
+                }
+                // Don't retry in a loop : wait for the next interval
+                clock.sleep_until(clock.now() + chrono::Duration::from_std(interval).unwrap_or_else(|_| chrono::Duration::days(1))).await;
+            }
+        }
+    }.instrument(info_span!("backups")))
+}
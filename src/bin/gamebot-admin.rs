@@ -0,0 +1,44 @@
+use std::env;
+use std::io::stdout;
+use std::process::exit;
+use dotenv::dotenv;
+use game_bot4_fun::admin::{self, AdminArguments, AdminCommand, AdminError};
+use game_bot4_fun::config::Config;
+use game_bot4_fun::database::Database;
+
+/**
+ * This method run a command of gamebot-admin, see admin.rs.
+ *
+ * @return Result<(), AdminError>
+ */
+async fn run() -> Result<(), AdminError> {
+    // 1 - Read the command and the configuration of the bot : the token is not needed
+    let arguments = AdminArguments::parse(env::args().skip(1))?;
+    if let AdminCommand::Help = arguments.command {
+        println!("{}", admin::USAGE);
+        return Ok(());
+    }
+    let config = Config::load(&arguments.config, |name| env::var(name).ok()).map_err(AdminError::Config)?;
+
+    // 2 - Work directly on the database, the bot can be offline
+    // The status of the migrations only reads the database, it's opened without modifying it
+    let database = match arguments.command {
+        AdminCommand::MigrationsStatus => Database::connect_read_only(&config.database_url).await,
+        _ => Database::connect(&config.database_url).await,
+    }.map_err(AdminError::Connection)?;
+    let result = admin::run(&database, &config, arguments.command, &mut stdout()).await;
+    if let Err(err) = database.close().await {
+        eprintln!("Failed to close the database. {}", err);
+    }
+    result
+}
+
+#[tokio::main]
+async fn main() {
+    // Load .env var in the system environment, the file is optional
+    dotenv().ok();
+    if let Err(err) = run().await {
+        eprintln!("{}", err);
+        exit(1);
+    }
+}
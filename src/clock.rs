@@ -0,0 +1,92 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serenity::async_trait;
+use tokio::time::Instant;
+
+// Longest sleep of the system clock : changes of the time of the machine are noticed within this delay
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/**
+ * This trait give the current time to the tips scheduler
+ * and make it wait. It's injected so the scheduler can be
+ * tested without waiting for real days.
+ */
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /**
+     * This method return the current time.
+     *
+     * @return DateTime<Utc>
+     */
+    fn now(&self) -> DateTime<Utc>;
+
+    /**
+     * This method wait until the given time. It return
+     * immediately if the time is already passed.
+     *
+     * @param deadline: DateTime<Utc>, the time to wait for
+     */
+    async fn sleep_until(&self, deadline: DateTime<Utc>);
+}
+
+/*
+This structure is the clock of the machine, used by the bot.
+ */
+#[derive(Default, Clone, Copy)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn sleep_until(&self, deadline: DateTime<Utc>) {
+        // Sleep by steps : the time of the machine can change (NTP, suspend) during a long sleep
+        while let Ok(remaining) = (deadline - Utc::now()).to_std() {
+            if remaining.is_zero() {
+                break;
+            }
+            tokio::time::sleep(remaining.min(MAX_SLEEP)).await;
+        }
+    }
+}
+
+/*
+This structure is a clock for tests. Its time starts at the given date
+and advances with the time of tokio : with a paused runtime
+(`#[tokio::test(start_paused = true)]`) days pass in milliseconds.
+ */
+#[derive(Clone, Copy)]
+pub struct ManualClock {
+    start: DateTime<Utc>, // time of the clock when it has been created
+    started_at: Instant, // tokio time when the clock has been created
+}
+
+impl ManualClock {
+    /**
+     * This method create a clock starting at the given time.
+     *
+     * @param start: DateTime<Utc>, the current time of the clock
+     *
+     * @return ManualClock
+     */
+    pub fn new(start: DateTime<Utc>) -> ManualClock {
+        ManualClock { start, started_at: Instant::now() }
+    }
+
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        let elapsed = Instant::now() - self.started_at;
+        self.start + chrono::Duration::from_std(elapsed).unwrap_or_else(|_| chrono::Duration::zero())
+    }
+
+    async fn sleep_until(&self, deadline: DateTime<Utc>) {
+        if let Ok(remaining) = (deadline - self.now()).to_std() {
+            tokio::time::sleep(remaining).await;
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use std::fmt::Write;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use log::{error, warn};
+use serde_json::{json, Value};
+use serenity::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::channel::{ChannelType, PartialChannel};
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::Timestamp;
+use serenity::model::user::User;
+use serenity::utils::Color;
+use crate::commands::options::{CommandOptions, OptionError};
+use crate::commands::slash_command::{Category, AuditChange, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy};
+use crate::error::BotError;
+use crate::messenger::OutgoingMessage;
+use crate::permissions::{Access, MANAGER_COMMAND_PERMISSIONS};
+use crate::repositories::{AuditEntry, AuditFilter, NewAuditEntry};
+
+// Number of entries shown by the search, the export contains every entry
+const SEARCH_LENGTH: usize = 10;
+// Longest arguments shown by the search and values shown in the mod-log channel
+const PREVIEW_LENGTH: usize = 100;
+const VALUE_LENGTH: usize = 1000;
+// Name of the file containing the entries exported
+const EXPORT_FILE_NAME: &str = "audit.csv";
+
+/**
+ * This method write the change made by a command in the audit log,
+ * then mirror it in the mod-log channel of the guild if there is one.
+ * Failures are logged : the change is already made.
+ *
+ * @param ctx: &CommandContext, the shared context of the bot
+ * @param command: &str, the name of the command
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ * @param options: &CommandOptions, the options given to the command
+ * @param change: &AuditChange, the state before and after the change
+ */
+pub async fn record(ctx: &CommandContext, command: &str, guild_id: u64, options: &CommandOptions, change: &AuditChange) {
+    // 1 - Write the entry
+    let entry = NewAuditEntry {
+        actor: ctx.invoker.user_id,
+        command: command.to_string(),
+        arguments: options.describe(),
+        before: change.before.as_ref().map(Value::to_string),
+        after: change.after.as_ref().map(Value::to_string),
+        created_at: ctx.clock.now(),
+    };
+    let entry = match ctx.audit.record(guild_id, entry).await {
+        Ok(entry) => entry,
+        Err(err) => {
+            error!("Failed to write the audit log of the command {}. Error:\n{}", command, err);
+            return;
+        }
+    };
+
+    // 2 - Mirror it in the mod-log channel
+    match ctx.audit.mirror_channel(guild_id).await {
+        Ok(Some(channel)) => {
+            if let Err(why) = ctx.messenger.send(channel, OutgoingMessage::embed(entry_embed(&entry))).await {
+                warn!("Failed to mirror the audit entry {} in the channel {}. Error:\n{}", entry.id, channel, why);
+            }
+        }
+        Ok(None) => {}
+        Err(err) => error!("Failed to get the mod-log channel of the audit log. Error:\n{}", err),
+    }
+}
+
+/**
+ * This method cut a text too long to be displayed.
+ *
+ * @param text: &str, the text to display
+ * @param length: usize, the longest text displayed, in characters
+ *
+ * @return String, the text ending with … if it has been cut
+ */
+fn preview(text: &str, length: usize) -> String {
+    match text.char_indices().nth(length) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+/**
+ * This method make the embed of an entry posted in the mod-log channel.
+ *
+ * @param entry: &AuditEntry, the entry to display
+ *
+ * @return CreateEmbed
+ */
+fn entry_embed(entry: &AuditEntry) -> CreateEmbed {
+    let mut embed = CreateEmbed::default()
+        .title(format!("/{}", entry.command))
+        .description(format!("Run by <@{}>\n{}", entry.actor, preview(&entry.arguments, VALUE_LENGTH)))
+        .colour(Color::from_rgb(153, 102, 255))
+        .timestamp(Timestamp::from_unix_timestamp(entry.created_at.timestamp()).unwrap_or_else(|_| Timestamp::now()))
+        .footer(|footer| footer.text(format!("Audit entry #{}", entry.id)))
+        .to_owned();
+    for (name, value) in [("Before", &entry.before), ("After", &entry.after)] {
+        if let Some(value) = value {
+            embed.field(name, format!("```json\n{}\n```", preview(value, VALUE_LENGTH)), false);
+        }
+    }
+    embed
+}
+
+/**
+ * This method parse a date given to the command.
+ *
+ * @param name: &str, the name of the option
+ * @param value: String, the date given by the user. format: YYYY-MM-DD
+ *
+ * @return Result<NaiveDate, OptionError>, the date or the reason it is rejected
+ */
+fn parse_date(name: &str, value: String) -> Result<NaiveDate, OptionError> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| OptionError::invalid(name, "Expected a date with the following format : YYYY-MM-DD"))
+}
+
+/**
+ * This method return the first instant of a day in the timezone of the bot.
+ *
+ * @param date: NaiveDate, the day
+ * @param timezone: Option<Tz>, the timezone of the bot. None: the timezone of the machine
+ *
+ * @return DateTime<Utc>
+ */
+fn day_start(date: NaiveDate, timezone: Option<Tz>) -> DateTime<Utc> {
+    match timezone {
+        Some(timezone) => day_start_in(&timezone, date),
+        None => day_start_in(&Local, date),
+    }
+}
+
+/**
+ * This method is the generic part of day_start.
+ *
+ * @param timezone: &T, the timezone of the day
+ * @param date: NaiveDate, the day
+ *
+ * @return DateTime<Utc>
+ */
+fn day_start_in<T: TimeZone>(timezone: &T, date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    timezone.from_local_datetime(&midnight).earliest()
+        // Midnight skipped by a daylight saving time change : the day starts one hour later
+        .or_else(|| timezone.from_local_datetime(&(midnight + Duration::hours(1))).earliest())
+        .map_or_else(|| Utc.from_utc_datetime(&midnight), |start| start.with_timezone(&Utc))
+}
+
+/**
+ * This method read the filters given to the command.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param timezone: Option<Tz>, the timezone of the dates
+ *
+ * @return Result<AuditFilter, OptionError>
+ */
+fn filter(options: &CommandOptions, timezone: Option<Tz>) -> Result<AuditFilter, OptionError> {
+    let actor: Option<User> = options.get_optional("user")?;
+    let command: Option<String> = options.get_optional("command")?;
+    let from = options.get_optional_validated("from", parse_date)?;
+    let to = options.get_optional_validated("to", parse_date)?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(OptionError::invalid("to", "The end of the range is before its start."));
+        }
+    }
+    Ok(AuditFilter {
+        actor: actor.map(|user| user.id.0),
+        command: command.map(|command| command.trim().trim_start_matches('/').to_string()),
+        since: from.map(|from| day_start(from, timezone)),
+        // The last day is included
+        until: to.map(|to| day_start(to + Duration::days(1), timezone)),
+    })
+}
+
+/**
+ * This method format a field of a CSV file.
+ * A value read as a formula by spreadsheets is prefixed by a quote, so opening the export doesn't run it.
+ *
+ * @param value: &str, the value of the field
+ *
+ * @return String, escaped and quoted if required
+ */
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    value
+}
+
+/**
+ * This method is the execution of the command /audit.
+ * This is here that all the workflow occur.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param ctx: &CommandContext, the audit log and the timezone of the dates.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the messages to say in response
+ */
+async fn run(options: &CommandOptions, ctx: &CommandContext, guild_id: u64) -> Result<CommandResponse, BotError> {
+    // 1 - get action value to chose the procedure to execute
+    let action = &*options.get::<String>("action")?;
+    match action {
+        "search" => search(options, ctx, guild_id).await,
+        "export" => export(options, ctx, guild_id).await,
+        "mirror" => mirror(options, ctx, guild_id).await,
+        _ => Err(OptionError::invalid("action", "Expected search, export or mirror.").into()),
+    }
+}
+
+/**
+ * Action SEARCH : show the most recent entries matching the filters.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param ctx: &CommandContext, the audit log and the timezone of the dates.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the messages to say in response
+ */
+async fn search(options: &CommandOptions, ctx: &CommandContext, guild_id: u64) -> Result<CommandResponse, BotError> {
+    let entries = ctx.audit.search(guild_id, &filter(options, ctx.timezone)?).await?;
+    let mut description = String::new();
+    for entry in entries.iter().take(SEARCH_LENGTH) {
+        // Discord display the date in the timezone of the user
+        let _ = writeln!(description, "`#{}` <t:{}:f> <@{}> **/{}** {}",
+            entry.id, entry.created_at.timestamp(), entry.actor, entry.command, preview(&entry.arguments, PREVIEW_LENGTH));
+    }
+    if entries.is_empty() {
+        description.push_str("No entry matches the filters.");
+    }
+    Ok(CommandResponse::from(CreateEmbed::default()
+        .title("Audit log")
+        .description(description)
+        .colour(Color::from_rgb(153, 102, 255))
+        .footer(|footer| footer.text(format!(
+            "{} of {} entries. Export them to see the values before and after each change.",
+            entries.len().min(SEARCH_LENGTH), entries.len()
+        )))
+        .timestamp(Timestamp::now())
+        .to_owned()))
+}
+
+/**
+ * Action EXPORT : send every entry matching the filters in a CSV file.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param ctx: &CommandContext, the audit log and the timezone of the dates.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the messages to say in response
+ */
+async fn export(options: &CommandOptions, ctx: &CommandContext, guild_id: u64) -> Result<CommandResponse, BotError> {
+    let entries = ctx.audit.search(guild_id, &filter(options, ctx.timezone)?).await?;
+    let file = entries.iter().fold(String::from("id,date,actor,command,arguments,before,after\n"), |mut file, entry| {
+        let _ = writeln!(file, "{},{},{},{},{},{},{}",
+            entry.id,
+            entry.created_at.to_rfc3339(),
+            entry.actor,
+            csv_field(&entry.command),
+            csv_field(&entry.arguments),
+            csv_field(entry.before.as_deref().unwrap_or_default()),
+            csv_field(entry.after.as_deref().unwrap_or_default()),
+        );
+        file
+    });
+    let embed = CreateEmbed::default()
+        .title("Audit log exported")
+        .description(format!("{} entries are in {}.", entries.len(), EXPORT_FILE_NAME))
+        .colour(Color::from_rgb(153, 102, 255))
+        .timestamp(Timestamp::now())
+        .to_owned();
+    Ok(CommandResponse {
+        message: OutgoingMessage::embed(embed).attach(EXPORT_FILE_NAME, file.into_bytes()),
+        ..CommandResponse::default()
+    })
+}
+
+/**
+ * Action MIRROR : set the channel where new entries are posted.
+ * Without a channel, entries are not posted anymore.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param ctx: &CommandContext, the audit log.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the messages to say in response
+ */
+async fn mirror(options: &CommandOptions, ctx: &CommandContext, guild_id: u64) -> Result<CommandResponse, BotError> {
+    let channel: Option<PartialChannel> = options.get_optional("channel")?;
+    let channel = channel.map(|channel| channel.id.0);
+    let previous = ctx.audit.mirror_channel(guild_id).await?;
+    ctx.audit.set_mirror_channel(guild_id, channel).await?;
+
+    let description = match channel {
+        Some(channel) => format!("New entries of the audit log are posted in <#{}>.", channel),
+        None => String::from("New entries of the audit log are not posted anymore."),
+    };
+    Ok(CommandResponse::from(CreateEmbed::default()
+        .title("Mod-log channel updated")
+        .description(description)
+        .colour(Color::from_rgb(102, 255, 51))
+        .timestamp(Timestamp::now())
+        .to_owned())
+        .audited(previous.map(|channel| json!({ "channel": channel })), channel.map(|channel| json!({ "channel": channel }))))
+}
+
+/*
+This structure is the slash command /audit.
+ */
+pub struct AuditCommand;
+
+#[async_trait]
+impl SlashCommand for AuditCommand {
+    fn name(&self) -> &'static str {
+        "audit"
+    }
+
+    fn category(&self) -> Category {
+        Category::Administration
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["search command=tips_delete from=2024-01-01", "export"]
+    }
+
+    // Only managers can read who changed what
+    fn access(&self) -> Access {
+        Access::Managers
+    }
+
+    // Admin command : the log is only shown to the user
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::Always(Visibility::Ephemeral)
+    }
+
+    // The log of a big server is slow to read
+    fn defer(&self) -> bool {
+        true
+    }
+
+    /**
+     * This method is the signature of the command /audit.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Show who changed tips, the scheduler and the permissions.")
+            .default_member_permissions(MANAGER_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+                option
+                    .name("action")
+                    .description("What to do with the audit log.")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+                    .add_string_choice("Search", "search")
+                    .add_string_choice("Export as CSV", "export")
+                    .add_string_choice("Mirror in a mod-log channel", "mirror")
+            })
+            .create_option(|option| {
+                option
+                    .name("user")
+                    .description("Only the changes made by this user.")
+                    .kind(CommandOptionType::User)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("command")
+                    .description("Only the changes made by this command, like tips_delete.")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("from")
+                    .description("Only the changes made since this day. format: YYYY-MM-DD")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("to")
+                    .description("Only the changes made until this day, included. format: YYYY-MM-DD")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("channel")
+                    .description("The mod-log channel of the mirror action. Without it, entries are not posted anymore.")
+                    .kind(CommandOptionType::Channel)
+                    .channel_types(&[ChannelType::Text])
+                    .required(false)
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx, guild_id).await
+    }
+}
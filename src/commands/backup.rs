@@ -0,0 +1,133 @@
+use std::fmt::Write;
+use serenity::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::Timestamp;
+use serenity::utils::Color;
+use crate::backup::{Backups, SnapshotKind};
+use crate::clock::Clock;
+use crate::commands::options::CommandOptions;
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy};
+use crate::error::BotError;
+use crate::permissions::{Access, MANAGER_COMMAND_PERMISSIONS};
+
+/**
+ * This method is the execution of the command /backup.
+ * This is here that all the workflow occur.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param backups: &Backups, the snapshots of the database
+ * @param clock: &dyn Clock, give the time of the snapshot
+ *
+ * @return Result<CommandResponse, BotError>, the embed message to say in response
+ */
+async fn run(options: &CommandOptions, backups: &Backups, clock: &dyn Clock) -> Result<CommandResponse, BotError> {
+    match &*options.get::<String>("action")? {
+        "now" => {
+            let snapshot = backups.snapshot(clock.now(), SnapshotKind::Manual).await?;
+            Ok(CommandResponse::from(CreateEmbed::default()
+                .title("Backup done")
+                .description(format!(
+                    "The database has been saved in `{}` ({} KB).\nThe {} most recent snapshots written with `/backup Now` are kept, the scheduled ones are not deleted.",
+                    snapshot.file_name(), snapshot.size.div_ceil(1024), backups.config().keep
+                ))
+                .colour(Color::from_rgb(102, 255, 51))
+                .timestamp(Timestamp::now())
+                .to_owned()))
+        }
+        "list" => {
+            let snapshots = backups.list()?;
+            let mut description = match backups.config().interval {
+                Some(interval) => format!("A snapshot is written every {} hour(s).\n", interval.as_secs() / 3600),
+                None => String::from("Scheduled snapshots are disabled.\n"),
+            };
+            if snapshots.is_empty() {
+                description.push_str("There is no snapshot yet. Use `/backup Now` to write one.");
+            }
+            // Most recent first
+            for snapshot in snapshots.iter().rev() {
+                let _ = writeln!(description, "- `{}` ({} KB)", snapshot.file_name(), snapshot.size.div_ceil(1024));
+            }
+            Ok(CommandResponse::from(CreateEmbed::default()
+                .title("Database snapshots")
+                .description(description)
+                .colour(Color::from_rgb(0, 153, 255))
+                .timestamp(Timestamp::now())
+                .to_owned()))
+        }
+        action => {
+            // Action invalid or not implemented
+            Ok(CommandResponse::from(CreateEmbed::default()
+                .title(format!("Action  `{}`  not implemented :(", action))
+                .colour(Color::from_rgb(255, 0, 0))
+                .description("Please retry later. If you think it's an error contact the administrator of the server.")
+                .timestamp(Timestamp::now())
+                .to_owned()))
+        }
+    }
+}
+
+/*
+This structure is the slash command /backup.
+ */
+pub struct BackupCommand;
+
+#[async_trait]
+impl SlashCommand for BackupCommand {
+    fn name(&self) -> &'static str {
+        "backup"
+    }
+
+    fn category(&self) -> Category {
+        Category::Administration
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["now", "list"]
+    }
+
+    // Copying a large database takes more than 3 seconds
+    fn defer(&self) -> bool {
+        true
+    }
+
+    // The database holds the data of every guild : only managers save it
+    fn access(&self) -> Access {
+        Access::Managers
+    }
+
+    // Admin command : the response is only shown to the user
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::Always(Visibility::Ephemeral)
+    }
+
+    /**
+     * This method is the signature of the command /backup.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Save the database of the bot.")
+            .default_member_permissions(MANAGER_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+                option
+                    .name("action")
+                    .description("The action you want to execute.")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+                    .add_string_choice("Now", "now")
+                    .add_string_choice("List", "list")
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, _guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        match &ctx.backups {
+            Some(backups) => run(options, backups, ctx.clock.as_ref()).await,
+            None => Err(BotError::BackupsUnavailable),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use serde_json::json;
+use serenity::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::Timestamp;
+use serenity::utils::Color;
+use crate::commands::options::{CommandOptions, OptionError};
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy};
+use crate::cooldowns::CooldownScope;
+use crate::error::BotError;
+use crate::permissions::{Access, Capability, MANAGER_COMMAND_PERMISSIONS};
+use crate::repositories::CooldownRule;
+
+// Most uses of a rule, and longest period : one day
+const MAX_USES: i64 = 1000;
+const MAX_PERIOD: i64 = 24 * 3600;
+
+/**
+ * This method parse the scope given to the command.
+ *
+ * @param name: &str, the name of the option
+ * @param value: String, the name of the scope, like user
+ *
+ * @return Result<CooldownScope, OptionError>, the scope or the reason it is rejected
+ */
+fn parse_scope(name: &str, value: String) -> Result<CooldownScope, OptionError> {
+    CooldownScope::parse(value.trim()).ok_or_else(|| OptionError::invalid(name, "Expected user, channel or guild."))
+}
+
+/**
+ * This method display a rule.
+ *
+ * @param rule: &CooldownRule, the rule to display
+ *
+ * @return String, like `/tips_list` : 3 uses every 60 s per user
+ */
+fn describe(rule: &CooldownRule) -> String {
+    format!("`/{}` : {} use(s) every {} s per {}", rule.command, rule.uses, rule.period, rule.scope)
+}
+
+/*
+This structure is the slash command /cooldowns.
+It knows the commands of the registry : they are the only ones that can be limited.
+ */
+pub struct CooldownsCommand {
+    commands: Vec<&'static str>, // names of the commands that can be limited
+}
+
+impl CooldownsCommand {
+    /**
+     * This method create the command /cooldowns.
+     *
+     * @param commands: Vec<&'static str>, names of the commands that can be limited
+     *
+     * @return CooldownsCommand
+     */
+    pub fn new(commands: Vec<&'static str>) -> CooldownsCommand {
+        CooldownsCommand { commands }
+    }
+
+    /**
+     * This method parse the command given to the command.
+     *
+     * @param name: &str, the name of the option
+     * @param value: String, the name of the command, with or without /
+     *
+     * @return Result<String, OptionError>, the name of the command or the reason it is rejected
+     */
+    fn parse_command(&self, name: &str, value: String) -> Result<String, OptionError> {
+        let command = value.trim().trim_start_matches('/');
+        match self.commands.iter().find(|known| **known == command) {
+            Some(known) => Ok(known.to_string()),
+            None => Err(OptionError::invalid(name, format!("Expected one of : {}", self.commands.join(", ")))),
+        }
+    }
+
+    /**
+     * This method is the execution of the command /cooldowns.
+     * This is here that all the workflow occur.
+     *
+     * @param options: &CommandOptions, the options given to the command
+     * @param ctx: &CommandContext, the cooldowns of every guild.
+     * @param guild_id: u64, the id of the guild where the command has been run.
+     *
+     * @return Result<CommandResponse, BotError>, the embed message to say in response, audited when a rule changed
+     */
+    async fn run(&self, options: &CommandOptions, ctx: &CommandContext, guild_id: u64) -> Result<CommandResponse, BotError> {
+        // 1 - get action value to chose the procedure to execute
+        let action = &*options.get::<String>("action")?;
+        if action == "list" {
+            return list(ctx, guild_id).await.map(CommandResponse::from);
+        }
+
+        // 2 - set and remove need a command, the scope is the user by default
+        let command = options.get_validated("command", |name, value| self.parse_command(name, value))?;
+        let scope = options.get_optional_validated("scope", parse_scope)?.unwrap_or(CooldownScope::User);
+
+        // 3 - Save the change and return a response message
+        let (title, description, change) = match action {
+            "set" => {
+                let rule = CooldownRule {
+                    command,
+                    scope,
+                    uses: options.get_in_range("uses", 1..=MAX_USES)? as u32,
+                    period: options.get_in_range("period", 1..=MAX_PERIOD)? as u64,
+                };
+                let previous = ctx.cooldowns.set(guild_id, rule.clone()).await?;
+                ("Cooldown set", format!("{}.", describe(&rule)), Some((previous.map(|previous| json!(previous)), Some(json!(rule)))))
+            },
+            "remove" => match ctx.cooldowns.remove(guild_id, &command, scope).await? {
+                Some(rule) => ("Cooldown removed", format!("`/{}` isn't limited per {} anymore.", command, scope), Some((Some(json!(rule)), None))),
+                None => ("Nothing to change", format!("`/{}` isn't limited per {}.", command, scope), None),
+            },
+            _ => return Err(OptionError::invalid("action", "Expected set, remove or list.").into()),
+        };
+        let response = CommandResponse::from(CreateEmbed::default()
+            .title(title)
+            .description(description)
+            .colour(Color::from_rgb(102, 255, 51))
+            .timestamp(Timestamp::now())
+            .to_owned());
+        Ok(match change {
+            Some((before, after)) => response.audited(before, after),
+            None => response,
+        })
+    }
+}
+
+/**
+ * Action LIST : show the cooldowns of the guild.
+ *
+ * @param ctx: &CommandContext, the cooldowns of every guild.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CreateEmbed, BotError>, the embed message to say in response
+ */
+async fn list(ctx: &CommandContext, guild_id: u64) -> Result<CreateEmbed, BotError> {
+    let rules = ctx.cooldowns.rules(guild_id).await?;
+    let lines: Vec<String> = rules.iter().map(|rule| format!("- {}", describe(rule))).collect();
+    let lines = if lines.is_empty() { String::from("No cooldown : commands can be used without limit.") } else { lines.join("\n") };
+    Ok(CreateEmbed::default()
+        .title("Cooldowns of the server")
+        .description(format!("{}\n\nManagers and roles granted `{}` are not limited.", lines, Capability::CooldownBypass))
+        .colour(Color::from_rgb(102, 255, 255))
+        .timestamp(Timestamp::now())
+        .to_owned())
+}
+
+#[async_trait]
+impl SlashCommand for CooldownsCommand {
+    fn name(&self) -> &'static str {
+        "cooldowns"
+    }
+
+    fn category(&self) -> Category {
+        Category::Administration
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["set tips_read user 3 60", "list"]
+    }
+
+    // Only managers limit the other commands
+    fn access(&self) -> Access {
+        Access::Managers
+    }
+
+    // Admin command : the cooldowns are only shown to the user
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::Always(Visibility::Ephemeral)
+    }
+
+    /**
+     * This method is the signature of the command /cooldowns.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Limit how often the commands can be used.")
+            .default_member_permissions(MANAGER_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+                option
+                    .name("action")
+                    .description("What to do with the cooldowns.")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+                    .add_string_choice("Set", "set")
+                    .add_string_choice("Remove", "remove")
+                    .add_string_choice("List", "list")
+            })
+            .create_option(|option| {
+                let option = option
+                    .name("command")
+                    .description("The command to limit. Required to set and remove.")
+                    .kind(CommandOptionType::String)
+                    .required(false);
+                for command in &self.commands {
+                    option.add_string_choice(command, command);
+                }
+                option
+            })
+            .create_option(|option| {
+                let option = option
+                    .name("scope")
+                    .description("Who shares the uses. Each user by default.")
+                    .kind(CommandOptionType::String)
+                    .required(false);
+                for scope in CooldownScope::ALL {
+                    option.add_string_choice(scope.as_str(), scope.as_str());
+                }
+                option
+            })
+            .create_option(|option| {
+                option
+                    .name("uses")
+                    .description("The number of uses allowed in the period. Required to set.")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .max_int_value(MAX_USES)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("period")
+                    .description("The period, in seconds. Required to set.")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .max_int_value(MAX_PERIOD)
+                    .required(false)
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        self.run(options, ctx, guild_id).await
+    }
+}
@@ -0,0 +1,281 @@
+use std::fmt::Write;
+use serenity::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::Permissions;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::Timestamp;
+use serenity::utils::Color;
+use crate::commands::message::{self, OptionSignature};
+use crate::commands::options::{CommandOptions, OptionError};
+use crate::commands::registry::CommandRegistry;
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy, VISIBILITY_OPTION};
+use crate::error::BotError;
+use crate::messenger::OutgoingMessage;
+use crate::permissions::Access;
+
+/*
+This structure is what /help display about a command.
+It's read from the registration of the command, so it never differ from what discord shows.
+ */
+struct CommandHelp {
+    name: &'static str,
+    category: Category,
+    description: String,
+    options: Vec<OptionSignature>, // in the order they are declared
+    visibility: VisibilityPolicy, // the visibility option is added by the registry when the user choose
+    access: Access,
+    permissions: Option<Permissions>, // the discord permissions needed to see the command by default
+    aliases: &'static [&'static str],
+    examples: &'static [&'static str],
+}
+
+/**
+ * This method read the help of a command from its registration.
+ *
+ * @param command: &dyn SlashCommand, the command
+ *
+ * @return CommandHelp
+ */
+fn describe(command: &dyn SlashCommand) -> CommandHelp {
+    let mut builder = CreateApplicationCommand::default();
+    command.register(&mut builder);
+    CommandHelp {
+        name: command.name(),
+        category: command.category(),
+        description: builder.0.get("description").and_then(|description| description.as_str()).unwrap_or_default().to_string(),
+        options: message::signature(command),
+        visibility: command.visibility(),
+        access: command.access(),
+        permissions: builder.0.get("default_member_permissions")
+            .and_then(|permissions| permissions.as_str())
+            .and_then(|permissions| permissions.parse().ok())
+            .map(Permissions::from_bits_truncate),
+        aliases: command.aliases(),
+        examples: command.examples(),
+    }
+}
+
+/**
+ * This method return the name of the type of an option.
+ *
+ * @param kind: CommandOptionType, the type of the option
+ *
+ * @return &'static str
+ */
+fn kind_name(kind: CommandOptionType) -> &'static str {
+    match kind {
+        CommandOptionType::String => "text",
+        CommandOptionType::Integer => "integer",
+        CommandOptionType::Number => "number",
+        CommandOptionType::Boolean => "yes/no",
+        CommandOptionType::User => "member",
+        CommandOptionType::Channel => "channel",
+        CommandOptionType::Role => "role",
+        CommandOptionType::Mentionable => "member or role",
+        CommandOptionType::Attachment => "file",
+        _ => "other",
+    }
+}
+
+/**
+ * This method describe who can run a command.
+ *
+ * @param access: Access, who the bot allow to run the command
+ *
+ * @return String
+ */
+fn access_text(access: Access) -> String {
+    match access {
+        Access::Everyone => String::from("Everyone"),
+        Access::Capability(capability) => format!("Managers and roles granted `{}`", capability),
+        Access::Managers => String::from("Managers only"),
+    }
+}
+
+/*
+This structure is the slash command /help.
+It knows the commands of the registry : they are described from their registration.
+ */
+pub struct HelpCommand {
+    commands: Vec<CommandHelp>, // in the order of the registry, /help included
+}
+
+impl HelpCommand {
+    /**
+     * This method create the command /help.
+     *
+     * @param registry: &CommandRegistry, the commands to describe
+     *
+     * @return HelpCommand
+     */
+    pub fn new(registry: &CommandRegistry) -> HelpCommand {
+        let mut commands: Vec<CommandHelp> = registry.commands().map(describe).collect();
+        commands.push(describe(&HelpCommand { commands: Vec::new() }));
+        HelpCommand { commands }
+    }
+
+    /**
+     * This method parse the command given to the command.
+     *
+     * @param name: &str, the name of the option
+     * @param value: String, the name of the command or one of its aliases, with or without / or the prefix
+     * @param prefix: &str, the prefix of the text commands of the guild
+     *
+     * @return Result<&CommandHelp, OptionError>, the help of the command or the reason it is rejected
+     */
+    fn parse_command(&self, name: &str, value: String, prefix: &str) -> Result<&CommandHelp, OptionError> {
+        let typed = value.trim();
+        let typed = typed.strip_prefix(prefix).unwrap_or(typed).trim_start_matches('/');
+        self.commands.iter()
+            .find(|command| command.name == typed)
+            .or_else(|| self.commands.iter().find(|command| command.aliases.contains(&typed)))
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.commands.iter().map(|command| command.name).collect();
+                OptionError::invalid(name, format!("Expected one of : {}", names.join(", ")))
+            })
+    }
+
+    /**
+     * This method create the pages of /help : one per category, listing its commands.
+     *
+     * @param prefix: &str, the prefix of the text commands of the guild
+     *
+     * @return CommandResponse, the first page as response, the next ones as follow-ups
+     */
+    fn pages(&self, prefix: &str) -> CommandResponse {
+        let categories: Vec<Category> = Category::ALL.into_iter()
+            .filter(|category| self.commands.iter().any(|command| command.category == *category))
+            .collect();
+        let count = categories.len();
+        let mut pages = categories.iter().enumerate().map(|(index, category)| {
+            let mut description = format!("Every command can also be typed in a message, starting with `{}`.\n\n", prefix);
+            for command in self.commands.iter().filter(|command| command.category == *category) {
+                let _ = write!(description, "`/{}` : {}", command.name, command.description);
+                if command.access != Access::Everyone {
+                    let _ = write!(description, " *{}.*", access_text(command.access));
+                }
+                description.push('\n');
+            }
+            CreateEmbed::default()
+                .title(format!("Help : {}", category.title()))
+                .description(description)
+                .colour(Color::from_rgb(0, 153, 255))
+                .footer(|footer| footer.text(format!("Page {}/{}, use /help command to see the details of a command", index + 1, count)))
+                .timestamp(Timestamp::now())
+                .to_owned()
+        });
+        let first = pages.next().expect("/help is always in a category");
+        pages.fold(CommandResponse::from(first), |response, page| response.follow_up(OutgoingMessage::embed(page)))
+    }
+
+    /**
+     * This method create the details of a command : usage, options, permissions and examples.
+     *
+     * @param command: &CommandHelp, the command to describe
+     * @param prefix: &str, the prefix of the text commands of the guild
+     *
+     * @return CreateEmbed
+     */
+    fn details(command: &CommandHelp, prefix: &str) -> CreateEmbed {
+        // 1 - The slash command, then the text command and its aliases
+        let mut usage = message::usage_of("/", command.name, &command.options);
+        if let VisibilityPolicy::UserChoice(_) = command.visibility {
+            let _ = write!(usage, " [{}]", VISIBILITY_OPTION);
+        }
+        let mut usage = format!("`{}`", usage);
+        for name in std::iter::once(&command.name).chain(command.aliases) {
+            let _ = write!(usage, "\n`{}`", message::usage_of(prefix, name, &command.options));
+        }
+
+        // 2 - The options, with their type and the values allowed
+        let mut options = String::new();
+        for option in &command.options {
+            let _ = write!(options, "- `{}` ({}, {}) : {}", option.name, kind_name(option.kind),
+                if option.required { "required" } else { "optional" }, option.description);
+            if !option.choices.is_empty() {
+                let _ = write!(options, " One of : {}.", option.choices.join(", "));
+            }
+            options.push('\n');
+        }
+        if let VisibilityPolicy::UserChoice(default) = command.visibility {
+            let _ = writeln!(options, "- `{}` (text, optional) : Who can see the response, only you or everyone. {} by default.", VISIBILITY_OPTION,
+                if default == Visibility::Public { "Everyone" } else { "Only you" });
+        }
+        if options.is_empty() {
+            options.push_str("No option.");
+        }
+
+        // 3 - Who can run it
+        let mut permission = access_text(command.access);
+        if let Some(permissions) = command.permissions {
+            let _ = write!(permission, ".\nHidden by default to the members without the permission : {}", permissions.get_permission_names().join(", "));
+        }
+
+        let mut embed = CreateEmbed::default();
+        embed.title(format!("Help : /{}", command.name))
+            .description(&command.description)
+            .colour(Color::from_rgb(0, 153, 255))
+            .field("Usage", usage, false)
+            .field("Options", options, false)
+            .field("Permission", permission, false)
+            .footer(|footer| footer.text(command.category.title()))
+            .timestamp(Timestamp::now());
+        // 4 - Examples, typed with the shortest name
+        if !command.examples.is_empty() {
+            let name = command.aliases.first().unwrap_or(&command.name);
+            let examples: Vec<String> = command.examples.iter()
+                .map(|example| format!("`{}`", format!("{}{} {}", prefix, name, example).trim_end()))
+                .collect();
+            embed.field("Examples", examples.join("\n"), false);
+        }
+        embed
+    }
+}
+
+#[async_trait]
+impl SlashCommand for HelpCommand {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn category(&self) -> Category {
+        Category::General
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["", "tips_read"]
+    }
+
+    // Nothing is shared : only the user see the help by default
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::UserChoice(Visibility::Ephemeral)
+    }
+
+    /**
+     * This method is the signature of the command /help.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Describe the commands of the bot.")
+            .create_option(|option| {
+                option
+                    .name("command")
+                    .description("The command to describe. Without it, every command is listed.")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        let prefix = ctx.prefixes.get(guild_id).await?;
+        match options.get_optional_validated("command", |name, value| self.parse_command(name, value, &prefix))? {
+            Some(command) => Ok(CommandResponse::from(HelpCommand::details(command, &prefix))),
+            None => Ok(self.pages(&prefix)),
+        }
+    }
+}
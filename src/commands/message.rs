@@ -0,0 +1,220 @@
+use serde_json::Value;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::channel::PartialChannel;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::model::user::User;
+use crate::commands::options::{CommandOptions, OptionError};
+use crate::commands::slash_command::SlashCommand;
+use crate::error::BotError;
+
+/*
+This structure is an option of a command, as described by its signature.
+ */
+pub(crate) struct OptionSignature {
+    pub name: String,
+    pub description: String,
+    pub kind: CommandOptionType,
+    pub required: bool,
+    pub choices: Vec<String>, // the values allowed, empty if every value is
+}
+
+/**
+ * This method read the options of a command from its signature : text
+ * commands and /help take the same options as the slash command.
+ *
+ * @param command: &dyn SlashCommand, the command
+ *
+ * @return Vec<OptionSignature>, in the order they are declared
+ */
+pub(crate) fn signature(command: &dyn SlashCommand) -> Vec<OptionSignature> {
+    let mut builder = CreateApplicationCommand::default();
+    command.register(&mut builder);
+    let options = match builder.0.get("options") {
+        Some(Value::Array(options)) => options.clone(),
+        _ => Vec::new(),
+    };
+    options.iter()
+        .map(|option| OptionSignature {
+            name: option["name"].as_str().unwrap_or_default().to_string(),
+            description: option["description"].as_str().unwrap_or_default().to_string(),
+            kind: serde_json::from_value(option["type"].clone()).unwrap_or(CommandOptionType::Unknown),
+            required: option["required"].as_bool().unwrap_or(false),
+            choices: option["choices"].as_array().map(|choices| choices.iter()
+                .filter_map(|choice| choice["value"].as_str().map(String::from))
+                .collect()
+            ).unwrap_or_default(),
+        })
+        .collect()
+}
+
+/**
+ * This method split a message in the name of the command and its arguments.
+ *
+ * @param content: &str, the content of the message
+ * @param prefix: &str, the prefix of the text commands of the guild
+ *
+ * @return Option<(&str, &str)>, the name and the arguments. None if the message isn't a command
+ */
+pub fn split_command<'a>(content: &'a str, prefix: &str) -> Option<(&'a str, &'a str)> {
+    let line = content.strip_prefix(prefix)?;
+    let (name, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    if name.is_empty() {
+        return None;
+    }
+    Some((name, arguments))
+}
+
+/**
+ * This method split the arguments of a text command. Arguments are separated
+ * by spaces, text between double quotes is kept in one argument and a
+ * backslash keep the next character as it is.
+ *
+ * @param arguments: &str, the arguments, like 12 "Shader tip"
+ *
+ * @return Result<Vec<String>, String>, the arguments or why they can't be read
+ */
+pub fn split_arguments(arguments: &str) -> Result<Vec<String>, String> {
+    let mut split = Vec::new();
+    let mut current: Option<String> = None; // the argument being read
+    let mut quoted = false;
+    let mut characters = arguments.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => match characters.next() {
+                Some(escaped) => current.get_or_insert_with(String::new).push(escaped),
+                None => return Err(String::from("Nothing follows the last backslash.")),
+            },
+            '"' => {
+                quoted = !quoted;
+                // "" is an empty argument
+                current.get_or_insert_with(String::new);
+            }
+            character if character.is_whitespace() && !quoted => {
+                if let Some(argument) = current.take() {
+                    split.push(argument);
+                }
+            }
+            character => current.get_or_insert_with(String::new).push(character),
+        }
+    }
+    if quoted {
+        return Err(String::from("A quote is not closed."));
+    }
+    split.extend(current);
+    Ok(split)
+}
+
+/**
+ * This method describe how to type a command in a message.
+ *
+ * @param prefix: &str, the prefix of the text commands of the guild
+ * @param name: &str, the name typed, the command or one of its aliases
+ * @param command: &dyn SlashCommand, the command
+ *
+ * @return String, like !tip <id> [random]
+ */
+pub fn usage(prefix: &str, name: &str, command: &dyn SlashCommand) -> String {
+    usage_of(prefix, name, &signature(command))
+}
+
+/**
+ * This method describe how to type a command from its signature.
+ * /help use it for the slash command too, with the prefix /.
+ *
+ * @param prefix: &str, the prefix of the command
+ * @param name: &str, the name typed, the command or one of its aliases
+ * @param options: &[OptionSignature], the signature of the command
+ *
+ * @return String, like !tip <id> [random]
+ */
+pub(crate) fn usage_of(prefix: &str, name: &str, options: &[OptionSignature]) -> String {
+    options.iter()
+        .fold(format!("{}{}", prefix, name), |usage, option| match option.required {
+            true => format!("{} <{}>", usage, option.name),
+            false => format!("{} [{}]", usage, option.name),
+        })
+}
+
+/**
+ * This method convert an argument into the value of an option.
+ *
+ * @param option: &OptionSignature, the option
+ * @param value: &str, the argument
+ * @param mentions: &[User], the users mentioned in the message
+ * @param name: &str, the name of the command, used when the option can't be typed
+ *
+ * @return Result<CommandDataOptionValue, BotError>
+ */
+fn convert(option: &OptionSignature, value: &str, mentions: &[User], name: &str) -> Result<CommandDataOptionValue, BotError> {
+    // A mention, like <@12>, <@!12> or <#12>, or the id itself
+    let id = || value.trim_start_matches(['<', '@', '!', '#']).trim_end_matches('>').parse::<u64>().ok();
+    let wrong_type = |expected: &'static str, expected_fr: &'static str| BotError::from(OptionError::WrongType { name: option.name.clone(), expected, expected_fr });
+    match option.kind {
+        CommandOptionType::String if option.choices.is_empty() => Ok(CommandDataOptionValue::String(value.to_string())),
+        CommandOptionType::String => option.choices.iter()
+            .find(|choice| choice.eq_ignore_ascii_case(value))
+            .map(|choice| CommandDataOptionValue::String(choice.clone()))
+            .ok_or_else(|| OptionError::invalid(&option.name, format!("Expected one of : {}", option.choices.join(", "))).into()),
+        CommandOptionType::Integer => value.parse().map(CommandDataOptionValue::Integer).map_err(|_| OptionError::wrong_type::<i64>(&option.name).into()),
+        CommandOptionType::Number => value.parse().map(CommandDataOptionValue::Number).map_err(|_| OptionError::wrong_type::<f64>(&option.name).into()),
+        CommandOptionType::Boolean => match value.to_lowercase().as_str() {
+            "true" | "yes" | "on" => Ok(CommandDataOptionValue::Boolean(true)),
+            "false" | "no" | "off" => Ok(CommandDataOptionValue::Boolean(false)),
+            _ => Err(wrong_type("yes or no", "oui ou non")),
+        },
+        CommandOptionType::User => id()
+            .and_then(|id| mentions.iter().find(|user| user.id.0 == id))
+            .map(|user| CommandDataOptionValue::User(user.clone(), None))
+            .ok_or_else(|| wrong_type("a mention of a member, like @member", "la mention d'un membre, comme @membre")),
+        CommandOptionType::Channel => id()
+            .and_then(|id| serde_json::from_value::<PartialChannel>(serde_json::json!({ "id": id.to_string(), "type": 0 })).ok())
+            .map(CommandDataOptionValue::Channel)
+            .ok_or_else(|| wrong_type("a mention of a channel, like #channel", "la mention d'un salon, comme #salon")),
+        _ => Err(OptionError::invalid(&option.name, format!("This parameter can only be given with the slash command /{}.", name)).into()),
+    }
+}
+
+/**
+ * This method read the options of a text command from its arguments. An argument
+ * name=value give the option with this name. The name of a yes/no option turns it on.
+ * The other arguments give the other options, in the order of the slash command.
+ *
+ * @param prefix: &str, the prefix of the text commands of the guild, shown in errors
+ * @param name: &str, the name typed, the command or one of its aliases
+ * @param command: &dyn SlashCommand, the command
+ * @param arguments: &str, the arguments typed after the name
+ * @param mentions: &[User], the users mentioned in the message
+ *
+ * @return Result<CommandOptions, BotError>, the options as if they were given to the slash command
+ */
+pub fn parse_options(prefix: &str, name: &str, command: &dyn SlashCommand, arguments: &str, mentions: &[User]) -> Result<CommandOptions, BotError> {
+    let invalid = |reason: String| BotError::InvalidArguments { reason, usage: usage(prefix, name, command) };
+    let signature = signature(command);
+    let mut given: Vec<(&OptionSignature, CommandDataOptionValue)> = Vec::new();
+    let is_given = |given: &[(&OptionSignature, CommandDataOptionValue)], option: &OptionSignature| given.iter().any(|(other, _)| other.name == option.name);
+
+    for argument in split_arguments(arguments).map_err(invalid)? {
+        // 1 - name=value
+        let named = argument.split_once('=')
+            .and_then(|(option, value)| signature.iter().find(|known| known.name == option).map(|option| (option, value.to_string())));
+        // 2 - the name of a yes/no option
+        let flag = || signature.iter()
+            .find(|option| option.kind == CommandOptionType::Boolean && option.name.eq_ignore_ascii_case(&argument))
+            .map(|option| (option, String::from("yes")));
+        // 3 - the next option in order
+        let next = || signature.iter()
+            .find(|option| option.kind != CommandOptionType::Boolean && !is_given(&given, option))
+            .map(|option| (option, argument.clone()));
+        let (option, value) = match named.or_else(flag).or_else(next) {
+            Some(found) => found,
+            None => return Err(invalid(format!("Too many arguments : \"{}\" isn't expected. Put values containing spaces between quotes.", argument))),
+        };
+        if is_given(&given, option) {
+            return Err(invalid(format!("The parameter {} is given twice.", option.name)));
+        }
+        given.push((option, convert(option, &value, mentions, command.name())?));
+    }
+
+    Ok(given.into_iter().fold(CommandOptions::new(), |options, (option, value)| options.with(&option.name, value)))
+}
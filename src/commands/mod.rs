@@ -1,2 +1,12 @@
 pub mod tips;
-pub mod tips_scheduler;
\ No newline at end of file
+pub mod tips_scheduler;
+pub mod permissions;
+pub mod audit;
+pub mod cooldowns;
+pub mod prefix;
+pub mod backup;
+pub mod help;
+pub mod slash_command;
+pub mod registry;
+pub mod options;
+pub mod message;
\ No newline at end of file
@@ -0,0 +1,435 @@
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use serenity::model::channel::{Attachment, PartialChannel};
+use serenity::model::guild::Role;
+use serenity::model::prelude::interaction::application_command::{CommandDataOption, CommandDataOptionValue};
+use serenity::model::user::User;
+use crate::locale::Locale;
+
+/*
+This enum describe every reason an option
+given to a command can be rejected.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionError {
+    Missing(String), // the required option is absent
+    WrongType { name: String, expected: &'static str, expected_fr: &'static str }, // the value is not of the expected type, described in english and french
+    OutOfRange { name: String, min: i64, max: i64 }, // the integer value is outside of the bounds
+    Invalid { name: String, reason: String }, // the value doesn't pass the validation of the command
+}
+
+impl OptionError {
+    /**
+     * This method create a validation error for the given option.
+     *
+     * @param name: &str, the name of the option
+     * @param reason: impl Into<String>, why the value is rejected
+     *
+     * @return OptionError
+     */
+    pub fn invalid(name: &str, reason: impl Into<String>) -> OptionError {
+        OptionError::Invalid { name: name.to_string(), reason: reason.into() }
+    }
+
+    /**
+     * This method create the error of an option whose value is not of the type T.
+     *
+     * @param name: &str, the name of the option
+     *
+     * @return OptionError
+     */
+    pub fn wrong_type<T: FromOptionValue>(name: &str) -> OptionError {
+        OptionError::WrongType { name: name.to_string(), expected: T::EXPECTED, expected_fr: T::EXPECTED_FR }
+    }
+
+    /**
+     * This method return the name of the option rejected.
+     *
+     * @return &str
+     */
+    pub fn option_name(&self) -> &str {
+        match self {
+            OptionError::Missing(name) => name,
+            OptionError::WrongType { name, .. } => name,
+            OptionError::OutOfRange { name, .. } => name,
+            OptionError::Invalid { name, .. } => name,
+        }
+    }
+
+    /**
+     * This method explain why the option is rejected in the language of the user.
+     * The reason of a validation error is written by the command, it's not translated.
+     *
+     * @param locale: Locale, the language of the user
+     *
+     * @return String
+     */
+    pub fn message(&self, locale: Locale) -> String {
+        match (self, locale) {
+            (_, Locale::English) => self.to_string(),
+            (OptionError::Missing(name), Locale::French) => format!("Le paramètre {} est obligatoire.", name),
+            (OptionError::WrongType { name, expected_fr, .. }, Locale::French) => format!("Le paramètre {} a un mauvais format.\nAttendu : {}.", name, expected_fr),
+            (OptionError::OutOfRange { name, min, max }, Locale::French) => format!("Le paramètre {} doit être entre {} et {}.", name, min, max),
+            (OptionError::Invalid { name, reason }, Locale::French) => format!("Le paramètre {} n'est pas valide.\n{}", name, reason),
+        }
+    }
+}
+
+impl Display for OptionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionError::Missing(name) => write!(f, "The parameter {} is required.", name),
+            OptionError::WrongType { name, expected, .. } => write!(f, "The parameter {} has a bad format.\nExpected {}.", name, expected),
+            OptionError::OutOfRange { name, min, max } => write!(f, "The parameter {} must be between {} and {}.", name, min, max),
+            OptionError::Invalid { name, reason } => write!(f, "The parameter {} is not valid.\n{}", name, reason),
+        }
+    }
+}
+
+/**
+ * This trait convert the resolved value of an option
+ * into a rust type.
+ */
+pub trait FromOptionValue: Sized {
+    // Description of the type shown to the user when the conversion fail
+    const EXPECTED: &'static str;
+    // The same description in french
+    const EXPECTED_FR: &'static str;
+
+    /**
+     * This method convert the resolved value.
+     *
+     * @param value: &CommandDataOptionValue, the value sent by discord
+     *
+     * @return Option<Self>, None if the value has another type
+     */
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self>;
+}
+
+impl FromOptionValue for String {
+    const EXPECTED: &'static str = "a text";
+    const EXPECTED_FR: &'static str = "un texte";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::String(value) => Some(value.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOptionValue for i64 {
+    const EXPECTED: &'static str = "an integer";
+    const EXPECTED_FR: &'static str = "un nombre entier";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromOptionValue for u64 {
+    const EXPECTED: &'static str = "a positive integer";
+    const EXPECTED_FR: &'static str = "un nombre entier positif";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Integer(value) => u64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromOptionValue for f64 {
+    const EXPECTED: &'static str = "a number";
+    const EXPECTED_FR: &'static str = "un nombre";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Number(value) => Some(*value),
+            CommandDataOptionValue::Integer(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+}
+
+impl FromOptionValue for bool {
+    const EXPECTED: &'static str = "True or False";
+    const EXPECTED_FR: &'static str = "Vrai ou Faux";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromOptionValue for PartialChannel {
+    const EXPECTED: &'static str = "a channel";
+    const EXPECTED_FR: &'static str = "un salon";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Channel(channel) => Some(channel.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOptionValue for User {
+    const EXPECTED: &'static str = "a user";
+    const EXPECTED_FR: &'static str = "un utilisateur";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::User(user, _) => Some(user.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOptionValue for Role {
+    const EXPECTED: &'static str = "a role";
+    const EXPECTED_FR: &'static str = "un rôle";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Role(role) => Some(role.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOptionValue for Attachment {
+    const EXPECTED: &'static str = "a file";
+    const EXPECTED_FR: &'static str = "un fichier";
+    fn from_option_value(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Attachment(attachment) => Some(attachment.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+/*
+This structure is a single option given to a command.
+Subcommands and subcommand groups have nested options.
+ */
+#[derive(Debug, Clone)]
+struct CommandOption {
+    name: String,
+    value: Option<CommandDataOptionValue>,
+    options: CommandOptions,
+}
+
+/**
+ * This structure hold the options given to a command
+ * and extract them by name as typed values.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CommandOptions {
+    options: Vec<CommandOption>,
+}
+
+impl CommandOptions {
+    /**
+     * This method create an empty list of options.
+     *
+     * @return CommandOptions
+     */
+    pub fn new() -> CommandOptions {
+        CommandOptions::default()
+    }
+
+    /**
+     * This method copy the options received in an interaction.
+     *
+     * @param options: &[CommandDataOption], A slice of command option found in the interaction
+     *
+     * @return CommandOptions
+     */
+    pub fn from_interaction(options: &[CommandDataOption]) -> CommandOptions {
+        CommandOptions {
+            options: options.iter().map(|option| CommandOption {
+                name: option.name.to_owned(),
+                value: option.resolved.to_owned(),
+                options: CommandOptions::from_interaction(&option.options),
+            }).collect(),
+        }
+    }
+
+    /**
+     * This method add an option with a value.
+     *
+     * @param name: &str, the name of the option
+     * @param value: CommandDataOptionValue, the value of the option
+     *
+     * @return CommandOptions, used to chain operations
+     */
+    pub fn with(mut self, name: &str, value: CommandDataOptionValue) -> CommandOptions {
+        self.options.push(CommandOption { name: name.to_string(), value: Some(value), options: CommandOptions::new() });
+        self
+    }
+
+    /**
+     * This method add a subcommand (or a subcommand group) with its own options.
+     *
+     * @param name: &str, the name of the subcommand
+     * @param options: CommandOptions, the options of the subcommand
+     *
+     * @return CommandOptions, used to chain operations
+     */
+    pub fn with_subcommand(mut self, name: &str, options: CommandOptions) -> CommandOptions {
+        self.options.push(CommandOption { name: name.to_string(), value: None, options });
+        self
+    }
+
+    /**
+     * This method return true if no option has been given.
+     *
+     * @return bool
+     */
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    /**
+     * This method describe the options given without their values.
+     * It's used in logs : values may contain private content.
+     *
+     * @return String, like "title, content" or "add(title, content)"
+     */
+    pub fn redacted(&self) -> String {
+        self.options.iter()
+            .map(|option| match option.value {
+                None if !option.options.is_empty() => format!("{}({})", option.name, option.options.redacted()),
+                _ => option.name.clone(),
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /**
+     * This method describe the options given with their values.
+     * It's written in the audit log : discord objects are mentioned by id.
+     *
+     * @return String, like title="Shader tip", channel=<#42> or add(title="Shader tip")
+     */
+    pub fn describe(&self) -> String {
+        self.options.iter()
+            .map(|option| match &option.value {
+                Some(value) => format!("{}={}", option.name, match value {
+                    CommandDataOptionValue::String(value) => format!("{:?}", value),
+                    CommandDataOptionValue::Integer(value) => value.to_string(),
+                    CommandDataOptionValue::Number(value) => value.to_string(),
+                    CommandDataOptionValue::Boolean(value) => value.to_string(),
+                    CommandDataOptionValue::Channel(channel) => format!("<#{}>", channel.id),
+                    CommandDataOptionValue::User(user, _) => format!("<@{}>", user.id),
+                    CommandDataOptionValue::Role(role) => format!("<@&{}>", role.id),
+                    CommandDataOptionValue::Attachment(attachment) => format!("{:?}", attachment.filename),
+                    _ => String::from("?"),
+                }),
+                None => format!("{}({})", option.name, option.options.describe()),
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /**
+     * This method return the value of a required option.
+     *
+     * @param name: &str, the name of the option
+     *
+     * @return Result<T, OptionError>, the value or the reason it is rejected
+     */
+    pub fn get<T: FromOptionValue>(&self, name: &str) -> Result<T, OptionError> {
+        self.get_optional(name)?.ok_or_else(|| OptionError::Missing(name.to_string()))
+    }
+
+    /**
+     * This method return the value of an optional option.
+     *
+     * @param name: &str, the name of the option
+     *
+     * @return Result<Option<T>, OptionError>, None if absent or the reason it is rejected
+     */
+    pub fn get_optional<T: FromOptionValue>(&self, name: &str) -> Result<Option<T>, OptionError> {
+        match self.options.iter().find(|option| option.name == name) {
+            Some(CommandOption { value: Some(value), .. }) => {
+                T::from_option_value(value)
+                    .map(Some)
+                    .ok_or_else(|| OptionError::wrong_type::<T>(name))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /**
+     * This method return the value of a required option
+     * converted and checked by the validation function.
+     *
+     * @param name: &str, the name of the option
+     * @param validate: impl FnOnce(&str, T) -> Result<R, OptionError>, the validation function, called with the option name and value
+     *
+     * @return Result<R, OptionError>
+     */
+    pub fn get_validated<T: FromOptionValue, R>(&self, name: &str, validate: impl FnOnce(&str, T) -> Result<R, OptionError>) -> Result<R, OptionError> {
+        self.get_optional_validated(name, validate)?.ok_or_else(|| OptionError::Missing(name.to_string()))
+    }
+
+    /**
+     * This method return the value of an optional option
+     * converted and checked by the validation function.
+     *
+     * @param name: &str, the name of the option
+     * @param validate: impl FnOnce(&str, T) -> Result<R, OptionError>, the validation function, called with the option name and value
+     *
+     * @return Result<Option<R>, OptionError>
+     */
+    pub fn get_optional_validated<T: FromOptionValue, R>(&self, name: &str, validate: impl FnOnce(&str, T) -> Result<R, OptionError>) -> Result<Option<R>, OptionError> {
+        self.get_optional::<T>(name)?
+            .map(|value| validate(name, value))
+            .transpose()
+    }
+
+    /**
+     * This method return the value of a required integer option
+     * and check it is inside the range.
+     *
+     * @param name: &str, the name of the option
+     * @param range: RangeInclusive<i64>, the accepted values
+     *
+     * @return Result<i64, OptionError>
+     */
+    pub fn get_in_range(&self, name: &str, range: RangeInclusive<i64>) -> Result<i64, OptionError> {
+        self.get_optional_in_range(name, range)?.ok_or_else(|| OptionError::Missing(name.to_string()))
+    }
+
+    /**
+     * This method return the value of an optional integer option
+     * and check it is inside the range.
+     *
+     * @param name: &str, the name of the option
+     * @param range: RangeInclusive<i64>, the accepted values
+     *
+     * @return Result<Option<i64>, OptionError>
+     */
+    pub fn get_optional_in_range(&self, name: &str, range: RangeInclusive<i64>) -> Result<Option<i64>, OptionError> {
+        match self.get_optional::<i64>(name)? {
+            Some(value) if !range.contains(&value) => Err(OptionError::OutOfRange {
+                name: name.to_string(),
+                min: *range.start(),
+                max: *range.end(),
+            }),
+            value => Ok(value),
+        }
+    }
+
+    /**
+     * This method return the subcommand (or subcommand group)
+     * selected by the user and its options.
+     *
+     * @return Result<(&str, &CommandOptions), OptionError>
+     */
+    pub fn subcommand(&self) -> Result<(&str, &CommandOptions), OptionError> {
+        self.options.iter()
+            .find(|option| option.value.is_none())
+            .map(|option| (option.name.as_str(), &option.options))
+            .ok_or_else(|| OptionError::Missing(String::from("subcommand")))
+    }
+}
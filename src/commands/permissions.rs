@@ -0,0 +1,196 @@
+use serde_json::json;
+use serenity::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::guild::Role;
+use serenity::model::id::RoleId;
+use serenity::model::mention::Mention;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::Timestamp;
+use serenity::utils::Color;
+use crate::commands::options::{CommandOptions, OptionError};
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy};
+use crate::error::BotError;
+use crate::permissions::{Access, Capability, MANAGER_COMMAND_PERMISSIONS};
+use crate::repositories::{PermissionGrant, PermissionRepository};
+
+/**
+ * This method parse the capability given to the command.
+ *
+ * @param name: &str, the name of the option
+ * @param value: String, the name of the capability, like tips.write
+ *
+ * @return Result<Capability, OptionError>, the capability or the reason it is rejected
+ */
+fn parse_capability(name: &str, value: String) -> Result<Capability, OptionError> {
+    Capability::parse(value.trim()).ok_or_else(|| {
+        let known: Vec<&str> = Capability::ALL.iter().map(|capability| capability.as_str()).collect();
+        OptionError::invalid(name, format!("Expected one of : {}", known.join(", ")))
+    })
+}
+
+/**
+ * This method display a role. The id of the @everyone role is the id of the guild.
+ *
+ * @param role_id: u64, the id of the role
+ * @param guild_id: u64, the id of the guild
+ *
+ * @return String, a mention of the role
+ */
+fn role_mention(role_id: u64, guild_id: u64) -> String {
+    if role_id == guild_id {
+        return String::from("@everyone");
+    }
+    Mention::Role(RoleId::from(role_id)).to_string()
+}
+
+/**
+ * This method is the execution of the command /permissions.
+ * This is here that all the workflow occur.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param permissions: &dyn PermissionRepository, the storage of the capabilities granted to roles.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the embed message to say in response, audited when a grant changed
+ */
+async fn run(options: &CommandOptions, permissions: &dyn PermissionRepository, guild_id: u64) -> Result<CommandResponse, BotError> {
+    // 1 - get action value to chose the procedure to execute
+    let action = &*options.get::<String>("action")?;
+    if action == "list" {
+        return list(permissions, guild_id).await.map(CommandResponse::from);
+    }
+
+    // 2 - grant and revoke need a role and a capability
+    let role: Role = options.get("role")?;
+    let capability = options.get_validated("capability", parse_capability)?;
+    let grant = PermissionGrant { role_id: role.id.0, capability };
+    let value = json!(grant);
+    let role = role_mention(role.id.0, guild_id);
+
+    // 3 - Save the change and return a response message
+    let (title, description, change) = match action {
+        "grant" => match permissions.grant(guild_id, grant).await? {
+            true => ("Permission granted", format!("{} can now use `{}`.", role, capability), Some((None, Some(value)))),
+            false => ("Nothing to change", format!("{} can already use `{}`.", role, capability), None),
+        },
+        "revoke" => match permissions.revoke(guild_id, grant).await? {
+            true => ("Permission revoked", format!("{} can't use `{}` anymore.", role, capability), Some((Some(value), None))),
+            false => ("Nothing to change", format!("{} hasn't been granted `{}`.", role, capability), None),
+        },
+        _ => return Err(OptionError::invalid("action", "Expected grant, revoke or list.").into()),
+    };
+    let response = CommandResponse::from(CreateEmbed::default()
+        .title(title)
+        .description(description)
+        .colour(Color::from_rgb(102, 255, 51))
+        .timestamp(Timestamp::now())
+        .to_owned());
+    Ok(match change {
+        Some((before, after)) => response.audited(before, after),
+        None => response,
+    })
+}
+
+/**
+ * Action LIST : show the roles granted each capability.
+ *
+ * @param permissions: &dyn PermissionRepository, the storage of the capabilities granted to roles.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CreateEmbed, BotError>, the embed message to say in response
+ */
+async fn list(permissions: &dyn PermissionRepository, guild_id: u64) -> Result<CreateEmbed, BotError> {
+    let grants = permissions.list(guild_id).await?;
+    let lines: Vec<String> = Capability::ALL.iter()
+        .map(|capability| {
+            let roles: Vec<String> = grants.iter()
+                .filter(|grant| grant.capability == *capability)
+                .map(|grant| role_mention(grant.role_id, guild_id))
+                .collect();
+            let roles = if roles.is_empty() { String::from("managers only") } else { roles.join(", ") };
+            format!("- `{}` : {}", capability, roles)
+        })
+        .collect();
+    Ok(CreateEmbed::default()
+        .title("Permissions of the server")
+        .description(format!("{}\n\nAdministrators and members who can manage the server can use every command.", lines.join("\n")))
+        .colour(Color::from_rgb(102, 255, 255))
+        .timestamp(Timestamp::now())
+        .to_owned())
+}
+
+/*
+This structure is the slash command /permissions.
+ */
+pub struct PermissionsCommand;
+
+#[async_trait]
+impl SlashCommand for PermissionsCommand {
+    fn name(&self) -> &'static str {
+        "permissions"
+    }
+
+    fn category(&self) -> Category {
+        Category::Administration
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["list"]
+    }
+
+    // Only managers decide who can use the other commands
+    fn access(&self) -> Access {
+        Access::Managers
+    }
+
+    // Admin command : the permissions are only shown to the user
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::Always(Visibility::Ephemeral)
+    }
+
+    /**
+     * This method is the signature of the command /permissions.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Grant or revoke the capabilities of a role.")
+            .default_member_permissions(MANAGER_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+                option
+                    .name("action")
+                    .description("What to do with the permissions.")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+                    .add_string_choice("Grant", "grant")
+                    .add_string_choice("Revoke", "revoke")
+                    .add_string_choice("List", "list")
+            })
+            .create_option(|option| {
+                option
+                    .name("role")
+                    .description("The role to grant or revoke the capability. Required to grant and revoke.")
+                    .kind(CommandOptionType::Role)
+                    .required(false)
+            })
+            .create_option(|option| {
+                let option = option
+                    .name("capability")
+                    .description("The capability to grant or revoke. Required to grant and revoke.")
+                    .kind(CommandOptionType::String)
+                    .required(false);
+                for capability in Capability::ALL {
+                    option.add_string_choice(capability.as_str(), capability.as_str());
+                }
+                option
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx.permissions.as_ref(), guild_id).await
+    }
+}
@@ -0,0 +1,112 @@
+use serde_json::json;
+use serenity::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::Timestamp;
+use serenity::utils::Color;
+use crate::commands::options::{CommandOptions, OptionError};
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy};
+use crate::error::BotError;
+use crate::permissions::{Access, MANAGER_COMMAND_PERMISSIONS};
+use crate::prefixes::{DEFAULT_PREFIX, MAX_PREFIX_LENGTH, Prefixes};
+
+/**
+ * This method check the prefix given to the command.
+ *
+ * @param name: &str, the name of the option
+ * @param value: String, the prefix, like ?
+ *
+ * @return Result<String, OptionError>, the prefix or the reason it is rejected
+ */
+fn parse_prefix(name: &str, value: String) -> Result<String, OptionError> {
+    let prefix = value.trim();
+    if prefix.is_empty() || prefix.chars().count() > MAX_PREFIX_LENGTH || prefix.contains(char::is_whitespace) {
+        return Err(OptionError::invalid(name, format!("Expected 1 to {} characters without spaces.", MAX_PREFIX_LENGTH)));
+    }
+    if prefix.starts_with('/') {
+        return Err(OptionError::invalid(name, "/ is used by the slash commands."));
+    }
+    Ok(prefix.to_string())
+}
+
+/**
+ * This method is the execution of the command /prefix.
+ * This is here that all the workflow occur.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param prefixes: &Prefixes, the prefix of every guild.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the embed message to say in response, audited with the prefix before and after
+ */
+async fn run(options: &CommandOptions, prefixes: &Prefixes, guild_id: u64) -> Result<CommandResponse, BotError> {
+    // 1 - Without a prefix, the default one is used again
+    let prefix = options.get_optional_validated("prefix", parse_prefix)?;
+
+    // 2 - Save it and return a response message
+    let previous = prefixes.set(guild_id, prefix.clone()).await?;
+    let prefix = prefix.unwrap_or_else(|| String::from(DEFAULT_PREFIX));
+    Ok(CommandResponse::from(CreateEmbed::default()
+        .title("Prefix updated")
+        .description(format!("Text commands now start with `{0}`, like `{0}tip 12` or `{0}tips unity,shaders`.", prefix))
+        .colour(Color::from_rgb(102, 255, 51))
+        .timestamp(Timestamp::now())
+        .to_owned())
+        .audited(Some(json!({ "prefix": previous })), Some(json!({ "prefix": prefix }))))
+}
+
+/*
+This structure is the slash command /prefix.
+ */
+pub struct PrefixCommand;
+
+#[async_trait]
+impl SlashCommand for PrefixCommand {
+    fn name(&self) -> &'static str {
+        "prefix"
+    }
+
+    fn category(&self) -> Category {
+        Category::Administration
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["?", ""]
+    }
+
+    // Only managers change how every member type commands
+    fn access(&self) -> Access {
+        Access::Managers
+    }
+
+    // Admin command : the response is only shown to the user
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::Always(Visibility::Ephemeral)
+    }
+
+    /**
+     * This method is the signature of the command /prefix.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Change the prefix of the text commands, like !tip 12.")
+            .default_member_permissions(MANAGER_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+                option
+                    .name("prefix")
+                    .description("The new prefix, like ? or gb!. Without it, the prefix is ! again.")
+                    .kind(CommandOptionType::String)
+                    .max_length(MAX_PREFIX_LENGTH as u16)
+                    .required(false)
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx.prefixes.as_ref(), guild_id).await
+    }
+}
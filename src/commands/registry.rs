@@ -0,0 +1,346 @@
+use serenity::builder::{CreateApplicationCommands, CreateEmbed};
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::Timestamp;
+use serenity::model::user::User;
+use serenity::utils::Color;
+use crate::commands::options::CommandOptions;
+use serenity::model::prelude::command::CommandOptionType;
+use crate::commands::slash_command::{CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy, VISIBILITY_OPTION};
+use crate::commands::{audit, backup, cooldowns, help, message, permissions, prefix, tips, tips_scheduler};
+use crate::config::FeatureToggles;
+use crate::confirmations::{self, ConfirmationError, PendingConfirmation, CONFIRMATION_TIMEOUT};
+use crate::error::BotError;
+use crate::messenger::Button;
+use crate::permissions::authorize;
+
+/*
+This enum is how the bot answer a button pressed under one of its messages.
+ */
+#[derive(Debug)]
+pub enum ButtonResponse {
+    Update(CommandResponse), // replace the message of the button, without buttons
+    Reply(CommandResponse), // answer only the user, the message of the button is kept
+}
+
+/**
+ * This structure hold every slash command of the bot.
+ * It's the only list of commands : it's used to register
+ * them on discord and to dispatch interactions.
+ */
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<Box<dyn SlashCommand>>,
+}
+
+impl CommandRegistry {
+    /**
+     * This method create an empty registry.
+     *
+     * @return CommandRegistry
+     */
+    pub fn new() -> CommandRegistry {
+        CommandRegistry::default()
+    }
+
+    /**
+     * This method add a command to the registry.
+     *
+     * @param command: impl SlashCommand, the command to add
+     *
+     * @return &mut CommandRegistry, used to chain operations
+     */
+    pub fn add(&mut self, command: impl SlashCommand + 'static) -> &mut CommandRegistry {
+        self.commands.push(Box::new(command));
+        self
+    }
+
+    /**
+     * This method return the command with the given name.
+     *
+     * @param name: &str, the name of the command
+     *
+     * @return Option<&dyn SlashCommand>
+     */
+    pub fn get(&self, name: &str) -> Option<&dyn SlashCommand> {
+        self.commands.iter()
+            .find(|command| command.name() == name)
+            .map(|command| command.as_ref())
+    }
+
+    /**
+     * This method return the command typed in a text command : its name or one of its aliases.
+     *
+     * @param name: &str, the name typed
+     *
+     * @return Option<&dyn SlashCommand>
+     */
+    pub fn find(&self, name: &str) -> Option<&dyn SlashCommand> {
+        self.get(name).or_else(|| self.commands.iter()
+            .find(|command| command.aliases().contains(&name))
+            .map(|command| command.as_ref()))
+    }
+
+    /**
+     * This method return the name of every command of the registry.
+     *
+     * @return Vec<&'static str>, in the order they have been added
+     */
+    pub fn names(&self) -> Vec<&'static str> {
+        self.commands.iter().map(|command| command.name()).collect()
+    }
+
+    /**
+     * This method return every command of the registry.
+     *
+     * @return impl Iterator<Item = &dyn SlashCommand>, in the order they have been added
+     */
+    pub fn commands(&self) -> impl Iterator<Item = &dyn SlashCommand> {
+        self.commands.iter().map(|command| command.as_ref())
+    }
+
+    /**
+     * This method add the signature of every command of the registry.
+     * Commands letting the user choose the visibility of their
+     * responses get the visibility option.
+     *
+     * @param commands: &mut CreateApplicationCommands, the builder of the guild commands
+     *
+     * @return &mut CreateApplicationCommands, used to chain operations
+     */
+    pub fn register_all<'a>(&self, commands: &'a mut CreateApplicationCommands) -> &'a mut CreateApplicationCommands {
+        for command in &self.commands {
+            commands.create_application_command(|builder| {
+                command.register(builder.name(command.name()));
+                if let VisibilityPolicy::UserChoice(default) = command.visibility() {
+                    builder.create_option(|option| {
+                        option
+                            .name(VISIBILITY_OPTION)
+                            .description(match default {
+                                Visibility::Public => "Who can see the response. Everyone by default.",
+                                Visibility::Ephemeral => "Who can see the response. Only you by default.",
+                            })
+                            .kind(CommandOptionType::String)
+                            .required(false)
+                            .add_string_choice("Everyone", "public")
+                            .add_string_choice("Only me", "ephemeral")
+                    });
+                }
+                builder
+            });
+        }
+        commands
+    }
+
+    /**
+     * This method return who can see the response of a command.
+     * Unknown commands are answered with an ephemeral error.
+     *
+     * @param name: &str, the name of the command
+     * @param options: &CommandOptions, the options given to the command
+     *
+     * @return Visibility
+     */
+    pub fn visibility(&self, name: &str, options: &CommandOptions) -> Visibility {
+        self.get(name).map_or(Visibility::Ephemeral, |command| command.visibility().resolve(options))
+    }
+
+    /**
+     * This method execute the command with the given name, once
+     * the member who run it is allowed to. Errors are displayed in the language of the user, only
+     * to them, and internal errors are logged with an incident id.
+     *
+     * @param ctx: &CommandContext, the shared context of the bot
+     * @param name: &str, the name of the command
+     * @param guild_id: u64, the id of the guild where the command has been run.
+     * @param options: &CommandOptions, the options given to the command
+     *
+     * @return CommandResponse, the messages to say in response
+     */
+    pub async fn dispatch(&self, ctx: &CommandContext, name: &str, guild_id: u64, options: &CommandOptions) -> CommandResponse {
+        match self.get(name) {
+            Some(command) => match self.run(ctx, command, guild_id, options).await {
+                Ok(response) => CommandResponse { visibility: self.visibility(name, options), ..response },
+                Err(err) => error_response(ctx, name, err),
+            },
+            None => {
+                CommandResponse {
+                    visibility: Visibility::Ephemeral,
+                    ..CommandResponse::from(CreateEmbed::default()
+                    .title("Not implemented :(")
+                    .colour(Color::from_rgb(255, 204, 0))
+                    .description("Please retry later. If you think it's an error contact the administrator of the server.")
+                    .timestamp(Timestamp::now())
+                    .to_owned())
+                }
+            }
+        }
+    }
+
+    /**
+     * This method execute the command typed in a message, like !tip 12. Its arguments
+     * are read as the options of the slash command, then it runs like dispatch.
+     *
+     * @param ctx: &CommandContext, the shared context of the bot
+     * @param prefix: &str, the prefix of the text commands of the guild
+     * @param content: &str, the content of the message
+     * @param mentions: &[User], the users mentioned in the message
+     * @param guild_id: u64, the id of the guild where the message has been sent.
+     *
+     * @return Option<CommandResponse>, the messages to say in response. None if the message isn't a command of the bot
+     */
+    /**
+     * This method return the command typed in a message, like !tip 12, without running it.
+     *
+     * @param prefix: &str, the prefix of the text commands of the guild
+     * @param content: &str, the content of the message
+     *
+     * @return Option<&dyn SlashCommand>, None if the message isn't a command of the bot
+     */
+    pub fn find_in_message(&self, prefix: &str, content: &str) -> Option<&dyn SlashCommand> {
+        let (name, _) = message::split_command(content, prefix)?;
+        self.find(name)
+    }
+
+    pub async fn dispatch_message(&self, ctx: &CommandContext, prefix: &str, content: &str, mentions: &[User], guild_id: u64) -> Option<CommandResponse> {
+        // Other bots may use the same prefix : unknown commands are ignored
+        let (name, arguments) = message::split_command(content, prefix)?;
+        let command = self.find(name)?;
+        Some(match message::parse_options(prefix, name, command, arguments, mentions) {
+            Ok(options) => self.dispatch(ctx, command.name(), guild_id, &options).await,
+            Err(err) => error_response(ctx, command.name(), err),
+        })
+    }
+
+    /**
+     * This method answer a button of a confirmation : the command is run with the options
+     * of the preview, as the member who run it, or cancelled.
+     *
+     * @param ctx: &CommandContext, the shared context of the bot, with the user who pressed the button
+     * @param custom_id: &str, the custom id of the button
+     *
+     * @return Option<ButtonResponse>, how to answer. None if it's not a button of a confirmation
+     */
+    pub async fn dispatch_button(&self, ctx: &CommandContext, custom_id: &str) -> Option<ButtonResponse> {
+        let (id, confirmed) = confirmations::parse_button_id(custom_id)?;
+        let pending = match ctx.confirmations.answer(id, ctx.invoker.user_id, ctx.clock.now()) {
+            Ok(pending) => pending,
+            Err(err @ ConfirmationError::NotYours) => return Some(ButtonResponse::Reply(error_response(ctx, "confirmation", err.into()))),
+            Err(err @ ConfirmationError::Expired) => return Some(ButtonResponse::Update(error_response(ctx, "confirmation", err.into()))),
+        };
+        if !confirmed {
+            return Some(ButtonResponse::Update(CommandResponse::from(CreateEmbed::default()
+                .title("Cancelled")
+                .colour(Color::from_rgb(153, 153, 153))
+                .description("Nothing has been changed.")
+                .timestamp(Timestamp::now())
+                .to_owned())));
+        }
+        // The command runs as if the member just run it : the audit log records them
+        let ctx = CommandContext { invoker: pending.invoker, ..ctx.clone() };
+        let response = match self.get(&pending.command) {
+            Some(command) => match command.execute_confirmed(&ctx, pending.guild_id, &pending.options).await {
+                Ok(response) => {
+                    if let Some(change) = &response.audit {
+                        audit::record(&ctx, command.name(), pending.guild_id, &pending.options, change).await;
+                    }
+                    response
+                }
+                Err(err) => error_response(&ctx, command.name(), err),
+            },
+            None => error_response(&ctx, "confirmation", ConfirmationError::Expired.into()),
+        };
+        Some(ButtonResponse::Update(response))
+    }
+
+    /**
+     * This method check the permissions of the member and the cooldowns of the command, then execute the command.
+     * The changes made by the command are written in the audit log. Previews get the buttons of a confirmation.
+     *
+     * @param ctx: &CommandContext, the shared context of the bot
+     * @param command: &dyn SlashCommand, the command to run
+     * @param guild_id: u64, the id of the guild where the command has been run.
+     * @param options: &CommandOptions, the options given to the command
+     *
+     * @return Result<CommandResponse, BotError>
+     */
+    async fn run(&self, ctx: &CommandContext, command: &dyn SlashCommand, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        authorize(ctx.permissions.as_ref(), &ctx.invoker, command.access(), guild_id).await?;
+        ctx.cooldowns.check(ctx.permissions.as_ref(), &ctx.invoker, command.name(), guild_id, ctx.clock.now()).await?;
+        let mut response = command.execute(ctx, guild_id, options).await?;
+        if let Some(change) = &response.audit {
+            audit::record(ctx, command.name(), guild_id, options, change).await;
+        }
+        if response.confirmation {
+            let now = ctx.clock.now();
+            let pending = PendingConfirmation {
+                command: command.name().to_string(),
+                guild_id,
+                options: options.clone(),
+                invoker: ctx.invoker.clone(),
+                expires_at: now + chrono::Duration::from_std(CONFIRMATION_TIMEOUT).expect("The timeout fits in a chrono duration"),
+            };
+            let id = ctx.confirmations.ask(pending, now);
+            response.message.buttons = vec![
+                Button { custom_id: confirmations::button_id(id, true), label: String::from("Confirm"), style: ButtonStyle::Danger },
+                Button { custom_id: confirmations::button_id(id, false), label: String::from("Cancel"), style: ButtonStyle::Secondary },
+            ];
+        }
+        Ok(response)
+    }
+}
+
+/**
+ * This method make the response displaying an error, only to the user.
+ * Internal errors are logged with an incident id.
+ *
+ * @param ctx: &CommandContext, the shared context of the bot : the language of the user
+ * @param name: &str, the name of the command
+ * @param err: BotError, the error to display
+ *
+ * @return CommandResponse
+ */
+fn error_response(ctx: &CommandContext, name: &str, err: BotError) -> CommandResponse {
+    let incident = err.report(name);
+    CommandResponse {
+        visibility: Visibility::Ephemeral,
+        ..CommandResponse::from(err.to_embed(ctx.locale, incident.as_deref()))
+    }
+}
+
+/**
+ * This method create the registry with every command of the bot.
+ * Commands of disabled features are not added.
+ *
+ * @param features: &FeatureToggles, the features enabled in the configuration
+ *
+ * @return CommandRegistry
+ */
+pub fn default_registry(features: &FeatureToggles) -> CommandRegistry {
+    let mut registry = CommandRegistry::new();
+    registry
+        // tips
+        .add(tips::list::TipsListCommand)
+        .add(tips::create::TipsCreateCommand)
+        .add(tips::read::TipsReadCommand)
+        .add(tips::update::TipsUpdateCommand)
+        .add(tips::delete::TipsDeleteCommand);
+    // scheduler
+    if features.scheduler {
+        registry
+            .add(tips_scheduler::config::SchedulerConfigCommand)
+            .add(tips_scheduler::scheduler::SchedulerCommand);
+    }
+    // administration
+    registry
+        .add(permissions::PermissionsCommand)
+        .add(prefix::PrefixCommand)
+        .add(audit::AuditCommand)
+        .add(backup::BackupCommand);
+    // Every command added before can be limited
+    let names = registry.names();
+    registry.add(cooldowns::CooldownsCommand::new(names));
+    // Every command added before is described
+    let help = help::HelpCommand::new(&registry);
+    registry.add(help);
+    registry
+}
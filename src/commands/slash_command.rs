@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono_tz::Tz;
+use serde_json::Value;
+use serenity::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use tokio::sync::RwLock;
+use crate::backup::Backups;
+use crate::clock::Clock;
+use crate::commands::options::CommandOptions;
+use crate::confirmations::Confirmations;
+use crate::cooldowns::Cooldowns;
+use crate::database::Database;
+use crate::error::BotError;
+use crate::locale::Locale;
+use crate::messenger::{Messenger, OutgoingMessage};
+use crate::messenger::recording::RecordingMessenger;
+use crate::permissions::{Access, Invoker};
+use crate::prefixes::Prefixes;
+use crate::repositories::{AuditRepository, PermissionRepository, SchedulerRepository, TipRepository};
+use crate::repositories::memory::{InMemoryAuditRepository, InMemoryCooldownRepository, InMemoryPermissionRepository, InMemoryPrefixRepository, InMemorySchedulerRepository, InMemoryTipRepository};
+use crate::shutdown::Shutdown;
+use crate::SharedSchedulers;
+
+/*
+This structure is the shared context given to every command.
+It holds everything a command may need to run.
+ */
+#[derive(Clone)]
+pub struct CommandContext {
+    pub tips: Arc<dyn TipRepository>, // the storage of tips and of the messages where they have been posted
+    pub scheduler_configs: Arc<dyn SchedulerRepository>, // the storage of the configuration of every tips scheduler
+    pub schedulers: SharedSchedulers, // the joinHandle of every tips scheduler
+    pub permissions: Arc<dyn PermissionRepository>, // the capabilities granted to the roles of every guild
+    pub audit: Arc<dyn AuditRepository>, // the changes made by commands
+    pub cooldowns: Arc<Cooldowns>, // how often each command can be used, checked before it runs
+    pub prefixes: Arc<Prefixes>, // the prefix of the text commands of every guild
+    pub backups: Option<Arc<Backups>>, // the snapshots of the database. None: the storage can't be backed up
+    pub confirmations: Arc<Confirmations>, // the commands waiting for the user to press confirm or cancel
+    pub messenger: Arc<dyn Messenger>, // used to post and edit messages on the discord server
+    pub clock: Arc<dyn Clock>, // give the time to the tips scheduler
+    pub shutdown: Arc<Shutdown>, // track tip posts so the bot stops once they are done
+    pub timezone: Option<Tz>, // timezone of the scheduler hours. None: the timezone of the machine
+    pub locale: Locale, // language of the user who run the command
+    pub invoker: Invoker, // the member who run the command, their roles are checked before it runs
+}
+
+impl CommandContext {
+    /**
+     * This method create a context keeping everything in memory : nothing is
+     * written to a database and the messages are recorded instead of sent to discord.
+     * The invoker is a manager, the scheduler hours are in UTC.
+     * Used by the tests and the benchmarks, fields are replaced with the struct update syntax.
+     *
+     * @param clock: Arc<dyn Clock>, give the time to the tips scheduler
+     *
+     * @return CommandContext
+     */
+    pub fn in_memory(clock: Arc<dyn Clock>) -> CommandContext {
+        CommandContext {
+            tips: Arc::new(InMemoryTipRepository::new()),
+            scheduler_configs: Arc::new(InMemorySchedulerRepository::new()),
+            schedulers: Arc::new(RwLock::new(HashMap::new())),
+            permissions: Arc::new(InMemoryPermissionRepository::new()),
+            audit: Arc::new(InMemoryAuditRepository::new()),
+            cooldowns: Arc::new(Cooldowns::new(Arc::new(InMemoryCooldownRepository::new()))),
+            prefixes: Arc::new(Prefixes::new(Arc::new(InMemoryPrefixRepository::new()))),
+            backups: None,
+            confirmations: Arc::new(Confirmations::new()),
+            messenger: Arc::new(RecordingMessenger::new()),
+            clock,
+            shutdown: Arc::new(Shutdown::default()),
+            timezone: Some(chrono_tz::UTC),
+            locale: Locale::default(),
+            invoker: Invoker { manager: true, ..Invoker::default() },
+        }
+    }
+
+    /**
+     * This method replace every storage of the context with the repositories of the database.
+     *
+     * @param database: &Database, the storage backend
+     *
+     * @return CommandContext, used to chain operations
+     */
+    pub fn with_database(self, database: &Database) -> CommandContext {
+        CommandContext {
+            tips: database.tip_repository(),
+            scheduler_configs: database.scheduler_repository(),
+            permissions: database.permission_repository(),
+            audit: database.audit_repository(),
+            cooldowns: Arc::new(Cooldowns::new(database.cooldown_repository())),
+            prefixes: Arc::new(Prefixes::new(database.prefix_repository())),
+            ..self
+        }
+    }
+}
+
+// Name of the option added to commands letting the user choose the visibility of the response
+pub const VISIBILITY_OPTION: &str = "visibility";
+
+/*
+This enum is who can see the response of a command.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visibility {
+    #[default]
+    Public, // everyone in the channel
+    Ephemeral, // only the user who run the command
+}
+
+/*
+This enum is the group of a command in /help.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Tips, // create, read and share tips
+    Scheduler, // the daily tips scheduler
+    Administration, // the settings of the bot, reserved to managers
+    General, // help about the bot
+}
+
+impl Category {
+    // Every category, in the order of the pages of /help
+    pub const ALL: [Category; 4] = [Category::Tips, Category::Scheduler, Category::Administration, Category::General];
+
+    /**
+     * This method return the name of the category, displayed by /help.
+     *
+     * @return &'static str
+     */
+    pub fn title(&self) -> &'static str {
+        match self {
+            Category::Tips => "Tips",
+            Category::Scheduler => "Scheduler",
+            Category::Administration => "Administration",
+            Category::General => "General",
+        }
+    }
+}
+
+/*
+This enum is how a command decide the visibility of its responses.
+Errors are always ephemeral.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisibilityPolicy {
+    Always(Visibility), // the command decide
+    UserChoice(Visibility), // the user decide with the visibility option, the value is used without it
+}
+
+impl VisibilityPolicy {
+    /**
+     * This method return the visibility of a response.
+     *
+     * @param options: &CommandOptions, the options given to the command
+     *
+     * @return Visibility, the default one if the option is missing or invalid
+     */
+    pub fn resolve(&self, options: &CommandOptions) -> Visibility {
+        match self {
+            VisibilityPolicy::Always(visibility) => *visibility,
+            VisibilityPolicy::UserChoice(default) => match options.get_optional::<String>(VISIBILITY_OPTION).ok().flatten().as_deref() {
+                Some("public") => Visibility::Public,
+                Some("ephemeral") => Visibility::Ephemeral,
+                _ => *default,
+            },
+        }
+    }
+}
+
+/*
+This structure is the response of a command : the message answering
+the interaction, then follow-up messages sent after it, in order.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CommandResponse {
+    pub message: OutgoingMessage, // the response. When deferred, it replace the "thinking" message
+    pub follow_ups: Vec<OutgoingMessage>,
+    pub visibility: Visibility, // of the response and its follow-ups, set by the registry
+    pub audit: Option<AuditChange>, // the change made by the command, written in the audit log by the registry
+    pub confirmation: bool, // the response is a preview : the registry add the confirm and cancel buttons
+}
+
+/*
+This structure is the state changed by a command, before and after the change.
+None when there is nothing before (a creation) or after (a deletion).
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditChange {
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl CommandResponse {
+    /**
+     * This method add a message sent after the response.
+     *
+     * @param message: OutgoingMessage, the follow-up message
+     *
+     * @return CommandResponse, used to chain operations
+     */
+    pub fn follow_up(mut self, message: OutgoingMessage) -> CommandResponse {
+        self.follow_ups.push(message);
+        self
+    }
+
+    /**
+     * This method record the change made by the command :
+     * the registry write it in the audit log.
+     *
+     * @param before: Option<Value>, the state before the change
+     * @param after: Option<Value>, the state after the change
+     *
+     * @return CommandResponse, used to chain operations
+     */
+    pub fn audited(mut self, before: Option<Value>, after: Option<Value>) -> CommandResponse {
+        self.audit = Some(AuditChange { before, after });
+        self
+    }
+
+    /**
+     * This method make the response a preview of what the command will do.
+     * The registry add the confirm and cancel buttons : the command only
+     * runs execute_confirmed once the user pressed confirm.
+     *
+     * @return CommandResponse, used to chain operations
+     */
+    pub fn ask_confirmation(mut self) -> CommandResponse {
+        self.confirmation = true;
+        self
+    }
+
+    /**
+     * This method return every embed of the response and of the follow-ups, in order.
+     *
+     * @return Vec<&CreateEmbed>
+     */
+    pub fn embeds(&self) -> Vec<&CreateEmbed> {
+        std::iter::once(&self.message).chain(&self.follow_ups)
+            .flat_map(|message| &message.embeds)
+            .collect()
+    }
+}
+
+impl From<CreateEmbed> for CommandResponse {
+    fn from(embed: CreateEmbed) -> Self {
+        CommandResponse { message: OutgoingMessage::embed(embed), ..CommandResponse::default() }
+    }
+}
+
+/**
+ * This trait describe a slash command of the bot.
+ * Every command implement it and is added to the CommandRegistry
+ * which handle both the registration and the execution of commands.
+ */
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+    /**
+     * This method return the name of the command, the one typed after the "/".
+     *
+     * @return &'static str, the name of the command
+     */
+    fn name(&self) -> &'static str;
+
+    /**
+     * This method return the group of the command in /help.
+     *
+     * @return Category
+     */
+    fn category(&self) -> Category;
+
+    /**
+     * This method is the signature of the command.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method. The name is
+     * already set by the registry.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand;
+
+    /**
+     * This method return true if the command can take more than the 3 seconds
+     * given by discord to respond. The interaction is then acknowledged before
+     * the command runs and the response replace the "thinking" message.
+     *
+     * @return bool, false by default
+     */
+    fn defer(&self) -> bool {
+        false
+    }
+
+    /**
+     * This method return who can see the responses of the command.
+     * With UserChoice, the registry add the visibility option to the command.
+     *
+     * @return VisibilityPolicy, up to the user and public by default
+     */
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::UserChoice(Visibility::Public)
+    }
+
+    /**
+     * This method return who can run the command. It's checked by the
+     * registry before the execution. Discord also hide commands from members
+     * without the default member permissions set by register.
+     *
+     * @return Access, every member by default
+     */
+    fn access(&self) -> Access {
+        Access::Everyone
+    }
+
+    /**
+     * This method return the other names of the command in text commands,
+     * like tip for !tip. The name of the command can always be typed.
+     *
+     * @return &'static [&'static str], none by default
+     */
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /**
+     * This method return examples of use shown by /help : the arguments
+     * typed after the name of the text command, like 12 random. They are
+     * checked against the signature of the command by the tests.
+     *
+     * @return &'static [&'static str], none by default
+     */
+    fn examples(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /**
+     * This method is the execution of the command.
+     *
+     * @param ctx: &CommandContext, the shared context of the bot
+     * @param guild_id: u64, the id of the guild where the command has been run.
+     * @param options: &CommandOptions, the options given to the command
+     *
+     * @return Result<CommandResponse, BotError>, the messages to say in response. Errors are displayed by the registry
+     */
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError>;
+
+    /**
+     * This method is the execution of the command once the user confirmed it.
+     * Commands asking a confirmation in execute do here what they previewed.
+     * The options are the ones given to execute.
+     *
+     * @param ctx: &CommandContext, the shared context of the bot, with the member who run the command
+     * @param guild_id: u64, the id of the guild where the command has been run.
+     * @param options: &CommandOptions, the options given to the command
+     *
+     * @return Result<CommandResponse, BotError>, the message replacing the preview. execute by default
+     */
+    async fn execute_confirmed(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        self.execute(ctx, guild_id, options).await
+    }
+}
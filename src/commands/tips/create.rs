@@ -1,85 +1,99 @@
-use rusqlite::params;
-use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serde_json::json;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
 use serenity::model::prelude::command::CommandOptionType;
-use serenity::model::prelude::interaction::application_command::{
-    CommandDataOption,
-};
-use crate::database::SharedConnection;
-use crate::utils::{display_full_tip_in_embed, get_optional_string_param_from_options, get_required_string_param_from_options, make_error_embed};
+use crate::commands::options::CommandOptions;
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand};
+use crate::error::BotError;
+use crate::permissions::{Access, Capability, CAPABILITY_COMMAND_PERMISSIONS};
+use crate::repositories::{NewTip, TipRepository};
+use crate::utils::{display_full_tip_in_embed, parse_tags};
 
 
 /**
  * This method is the execution of the command /tips_create.
  * This is here that all the workflow occur.
  *
- * @param options: &[CommandDataOption], A slice of command option found in the interaction
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
+ * @param options: &CommandOptions, the options given to the command
+ * @param tips: &dyn TipRepository, the storage of tips.
+ * @param guild_id: u64, the id of the guild where the command has been run.
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return Result<CommandResponse, BotError>, the embed message to say in response, audited with the tip created
  */
-pub async fn run(options: &[CommandDataOption], conn: SharedConnection) -> CreateEmbed {
+async fn run(options: &CommandOptions, tips: &dyn TipRepository, guild_id: u64) -> Result<CommandResponse, BotError> {
     // 1 - check if optional values are present
-    let tags: String = get_optional_string_param_from_options(options, 2);
-    let tags_clone = tags.clone();
+    let tags: String = options.get_optional_validated("tags", parse_tags)?.unwrap_or_default();
 
     // 2 - Get required param (title and content)
-    let title = match get_required_string_param_from_options(options, 0, "title"){
-        Ok(title) => title,
-        Err(err) => return make_error_embed("tips_create::run", err),
-    };
-    let title_clone = title.clone();
+    let title: String = options.get("title")?;
+    let content: String = options.get("content")?;
 
-    let content = match get_required_string_param_from_options(options, 1, "content"){
-        Ok(content) => content,
-        Err(err) => return make_error_embed("tips_create::run", err),
-    };
-    let content_clone = content.clone();
-
-    // 3 - Insert the new tip in the database and return a response message
-    return match conn.lock().await.call(move |conn| {
-        let query = &*format!("INSERT INTO tips (title, content, tags) VALUES (?1,?2,?3)");
-        conn.execute(query, params![title_clone, content_clone, tags_clone])?;
-        Ok(())
-    }).await {
-        Ok(_) => {
-            display_full_tip_in_embed(title, content, Some(tags))
-        }
-        Err(err) => {
-            make_error_embed("tips_create::run", err.to_string())
-        }
-    };
+    // 3 - Insert the new tip in the storage and return a response message
+    let tip = tips.create(guild_id, NewTip { title, content, tags }).await?;
+    let after = json!(tip);
+    Ok(CommandResponse::from(display_full_tip_in_embed(tip.title, tip.content, Some(tip.tags))).audited(None, Some(after)))
 }
 
-/**
- * This method is the signature of the command /tips_create.
- * This is here that we describe the name, the options, all
- * descriptions and hints of the method.
- *
- * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
- *
- * @return &mut CreateApplicationCommand, used to chain operations
+/*
+This structure is the slash command /tips_create.
  */
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("tips_create").description("Create a new tip.")
-        .create_option(|option| {
-        option
-            .name("title")
-            .description("The title of the tip. It will be shown in tips list and on the top of the daily tips.")
-            .kind(CommandOptionType::String)
-            .required(true)
-        })
-        .create_option(|option| {
-        option
-            .name("content")
-            .description("The body of the tip. This is here you must put the tip's message.")
-            .kind(CommandOptionType::String)
-            .required(true)
-        })
-        .create_option(|option| {
-        option
-            .name("tags")
-            .description("Tags are used to sort tips. Format: lowercase csv with no spaces around coma. tag1,tag2,tag3,...")
-            .kind(CommandOptionType::String)
-            .required(false)
-        })
-}
\ No newline at end of file
+pub struct TipsCreateCommand;
+
+#[async_trait]
+impl SlashCommand for TipsCreateCommand {
+    fn name(&self) -> &'static str {
+        "tips_create"
+    }
+
+    fn category(&self) -> Category {
+        Category::Tips
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["\"Bake your lights\" \"Static lights can be baked in lightmaps.\" unity,lighting"]
+    }
+
+    // Only the roles granted tips.write can create tips
+    fn access(&self) -> Access {
+        Access::Capability(Capability::TipsWrite)
+    }
+
+    /**
+     * This method is the signature of the command /tips_create.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Create a new tip.")
+            .default_member_permissions(CAPABILITY_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+            option
+                .name("title")
+                .description("The title of the tip. It will be shown in tips list and on the top of the daily tips.")
+                .kind(CommandOptionType::String)
+                .required(true)
+            })
+            .create_option(|option| {
+            option
+                .name("content")
+                .description("The body of the tip. This is here you must put the tip's message.")
+                .kind(CommandOptionType::String)
+                .required(true)
+            })
+            .create_option(|option| {
+            option
+                .name("tags")
+                .description("Tags are used to sort tips. Format: lowercase csv with no spaces around coma. tag1,tag2,tag3,...")
+                .kind(CommandOptionType::String)
+                .required(false)
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx.tips.as_ref(), guild_id).await
+    }
+}
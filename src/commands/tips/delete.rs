@@ -1,111 +1,127 @@
-use rusqlite::params;
+use serde_json::json;
+use serenity::async_trait;
 use serenity::builder::{CreateApplicationCommand, CreateEmbed};
 use serenity::model::prelude::command::CommandOptionType;
-use serenity::model::prelude::interaction::application_command::{
-    CommandDataOption,
-};
 use serenity::model::Timestamp;
 use serenity::utils::Color;
-use crate::database::SharedConnection;
-use crate::utils::{get_required_integer_param_from_options, make_error_embed};
+use crate::commands::options::CommandOptions;
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy};
+use crate::confirmations::CONFIRMATION_TIMEOUT;
+use crate::error::BotError;
+use crate::permissions::{Access, Capability, CAPABILITY_COMMAND_PERMISSIONS};
+use crate::repositories::TipRepository;
+use crate::utils::display_full_tip_in_embed;
 
 /**
- * This method is the execution of the command /tips_delete.
+ * This method is the preview of the command /tips_delete : the tip
+ * to delete, above the buttons confirming or cancelling it.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param tips: &dyn TipRepository, the storage of tips.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the tip to delete, waiting for a confirmation
+ */
+async fn preview(options: &CommandOptions, tips: &dyn TipRepository, guild_id: u64) -> Result<CommandResponse, BotError> {
+    let tip_id: u64 = options.get("id")?;
+    let tip = match tips.get(guild_id, tip_id).await? {
+        Some(tip) => tip,
+        None => return Err(BotError::UnknownTip(tip_id)),
+    };
+    let mut response = CommandResponse::from(display_full_tip_in_embed(tip.title, tip.content, Some(tip.tags)));
+    response.message.content = Some(format!(
+        "Delete the tip {} ? Press **Confirm** within {} s, the posts of the tip are kept.", tip_id, CONFIRMATION_TIMEOUT.as_secs()
+    ));
+    Ok(response.ask_confirmation())
+}
+
+/**
+ * This method is the execution of the command /tips_delete, once confirmed.
  * This is here that all the workflow occur.
  *
- * @param options: &[CommandDataOption], A slice of command option found in the interaction
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
+ * @param options: &CommandOptions, the options given to the command
+ * @param tips: &dyn TipRepository, the storage of tips.
+ * @param guild_id: u64, the id of the guild where the command has been run.
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return Result<CommandResponse, BotError>, the embed message to say in response, audited with the tip deleted
  */
-pub async fn run(options: &[CommandDataOption], conn: SharedConnection) -> CreateEmbed {
+async fn run(options: &CommandOptions, tips: &dyn TipRepository, guild_id: u64) -> Result<CommandResponse, BotError> {
     // 1 - get parm values
-    let tip_id: u64 = match get_required_integer_param_from_options(options, 0, "Id"){
-        Ok(val) => val,
-        Err(err) => {
-            return make_error_embed("tips_delete::run", err.to_string())
-        }
-    };
-    let conf_tip_id: u64 = match get_required_integer_param_from_options(options, 1, "confirm_id"){
-        Ok(val) => val,
-        Err(err) => {
-            return make_error_embed("tips_delete::run", err.to_string())
-        }
+    let tip_id: u64 = options.get("id")?;
+
+    // 2 - Delete the tip from the storage and return a response message. It may have been deleted during the confirmation
+    let before = match tips.get(guild_id, tip_id).await? {
+        Some(tip) => json!(tip),
+        None => return Err(BotError::UnknownTip(tip_id)),
     };
+    if !tips.delete(guild_id, tip_id).await? {
+        return Err(BotError::UnknownTip(tip_id));
+    }
+    Ok(CommandResponse::from(CreateEmbed::default()
+        .title("Tip deleted successfully :)")
+        .colour(Color::from_rgb(102, 255, 51))
+        .description("Nothing to say so here is a smiley `◖ᵔᴥᵔ◗ ♪ ♫`")
+        .timestamp(Timestamp::now())
+        .to_owned())
+        .audited(Some(before), None))
+}
 
-    // 2 - Ensure val and confirmation are equal
-    if tip_id != conf_tip_id {
-        return CreateEmbed::default()
-            .title("Tip id and confirmation are different !")
-            .colour(Color::from_rgb(255, 204, 0))
-            .description("Please confirm the id of the tip you want to delete.\nIf you think it's an error contact the administrator of the server.")
-            .timestamp(Timestamp::now())
-            .to_owned();
+/*
+This structure is the slash command /tips_delete.
+ */
+pub struct TipsDeleteCommand;
+
+#[async_trait]
+impl SlashCommand for TipsDeleteCommand {
+    fn name(&self) -> &'static str {
+        "tips_delete"
     }
 
-    // 3 - Delete the tip from the database and return a response message
-    return match conn.lock().await.call(move |conn| {
-        let affected_row = conn.execute("DELETE FROM tips WHERE id = ?1", params![tip_id])?;
+    fn category(&self) -> Category {
+        Category::Tips
+    }
 
-        // 3 - return avery row found in a Vec<String>
-        Ok(affected_row)
-    }).await {
-        Ok(row) => {
-            if row == 1 {
-                CreateEmbed::default()
-                    .title("Tip deleted successfully :)")
-                    .colour(Color::from_rgb(102, 255, 51))
-                    .description("Nothing to say so here is a smiley `◖ᵔᴥᵔ◗ ♪ ♫`")
-                    .timestamp(Timestamp::now())
-                    .to_owned()
-            }else{
-                CreateEmbed::default()
-                    .title("Tip id unknown")
-                    .colour(Color::from_rgb(255, 102, 51))
-                    .description("The id requested is not valid. If you think this is an error, please contact server administrator")
-                    .timestamp(Timestamp::now())
-                    .to_owned()
-            }
-        }
-        Err(err) => {
-            if let tokio_rusqlite::Error::Rusqlite(rusqlite_err) = &err {
-                if let rusqlite::Error::QueryReturnedNoRows = rusqlite_err {
-                    return CreateEmbed::default()
-                        .title("Tip id unknown")
-                        .description("The id requested is not valid. If you think this is an error, please contact server administrator")
-                        .timestamp(Timestamp::now())
-                        .color(Color::from_rgb(255, 0, 0)).to_owned();
-                }
-            }
-            make_error_embed("tips_read::run", err.to_string())
-        }
-    };
-}
+    fn examples(&self) -> &'static [&'static str] {
+        &["12"]
+    }
 
-/**
- * This method is the signature of the command /tips_delete.
- * This is here that we describe the name, the options, all
- * descriptions and hints of the method.
- *
- * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
- *
- * @return &mut CreateApplicationCommand, used to chain operations
- */
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("tips_delete").description("Delete the tip using it's id.")
-        .create_option(|option| {
-            option
-                .name("id")
-                .description("The tip id you want to delete.")
-                .kind(CommandOptionType::Integer)
-                .required(true)
-        })
-        .create_option(|option| {
-            option
-                .name("confirm_id")
-                .description("The confirmationn of tip id you want to delete.")
-                .kind(CommandOptionType::Integer)
-                .required(true)
-        })
+    // Only the roles granted tips.delete can delete tips
+    fn access(&self) -> Access {
+        Access::Capability(Capability::TipsDelete)
+    }
 
-}
\ No newline at end of file
+    // The preview is only shown to the user, unless they choose otherwise
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::UserChoice(Visibility::Ephemeral)
+    }
+
+    /**
+     * This method is the signature of the command /tips_delete.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Delete the tip using it's id.")
+            .default_member_permissions(CAPABILITY_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+                option
+                    .name("id")
+                    .description("The tip id you want to delete.")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(true)
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        preview(options, ctx.tips.as_ref(), guild_id).await
+    }
+
+    async fn execute_confirmed(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx.tips.as_ref(), guild_id).await
+    }
+}
@@ -1,24 +1,26 @@
-use log::{debug, error};
+use log::error;
+use serenity::async_trait;
 use serenity::builder::{CreateApplicationCommand, CreateEmbed};
 use serenity::model::prelude::command::CommandOptionType;
-use serenity::model::prelude::interaction::application_command::{
-    CommandDataOption,
-};
+use std::borrow::Cow;
 use std::fmt::{Write};
 use serenity::model::Timestamp;
 use serenity::utils::Color;
-use crate::database::SharedConnection;
-use crate::utils::{display_minimized_tip, get_optional_string_param_from_options};
+use crate::commands::options::CommandOptions;
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand};
+use crate::error::BotError;
+use crate::messenger::OutgoingMessage;
+use crate::repositories::TipRepository;
+use crate::utils::{display_minimized_tip, parse_tags};
 
-/*
-This structure is used to group fetched data
-from the database and then iterate over vec<ListTip>
- */
-struct ListTip {
-    id: u32,
-    title: String,
-    tags: String
-}
+// Longest description of a page, discord accept up to 4096 characters
+const PAGE_LENGTH: usize = 4000;
+// Longest line of a page, with its line break : longer lines are cut and end with an ellipsis
+const LINE_LENGTH: usize = PAGE_LENGTH - 1;
+// Longer lists are sent as a file
+const MAX_PAGES: usize = 5;
+// Name of the file containing the full list, one tip per line : id, title and tags separated by tabs
+const LIST_FILE_NAME: &str = "tips.txt";
 
 /**
  * Method used by the /tips_list command only used
@@ -43,137 +45,157 @@ fn embed_from_param(title: String, content: String)-> CreateEmbed
 
 
 /**
- * This method is the execution of the command /tips_list.
- * This is here that all the workflow occur.
+ * This method split the list of tips in pages fitting in an embed.
  *
- * @param options: &[CommandDataOption], A slice of command option found in the interaction
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
+ * @param lines: &[String], a line per tip, cut when it is longer than a page
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return Vec<String>, the description of each page, at least one
  */
-pub async fn run(options: &[CommandDataOption], conn: SharedConnection) -> CreateEmbed
-{
-    let tags_string = get_optional_string_param_from_options(options, 0);
-
-    // 1 - Check if there is tags parameter
-    if tags_string != "" {
-        // 2 - Clone tags string to give it to the database closer and keep the original for this function
-        let tags: String = tags_string.trim().to_string();
-        let tags_clone = tags.clone();
-
-        // 3 - call database execution
-        match conn.lock().await.call(move |conn|{
-            // transform the tags string into a list
-            let tags_list: Vec<&str> = tags_clone.trim().split(',').collect();
-            // format tags to be in the query like this : SELECT ... ... LIKE '%unreal%' OR tags LIKE '%tools%'
-            let tags_placeholder = tags_list.iter().map(|i| format!("'%{}%'",i)).collect::<Vec<_>>().join(" OR tags LIKE ");
-
-
-            let query: &str = &*format!("SELECT id, title, tags FROM tips WHERE tags LIKE {}", tags_placeholder);
-            debug!("Query executed for tips_list : {}",query);
-            // run the prepared query and return the result into a Vec<String>
-            let mut stmt = conn.prepare(query)?;
-            let rows_data = stmt.query_map([], |row|
-                Ok(
-                    ListTip{
-                        id: row.get(0)?,
-                        title: row.get(1)?,
-                        tags: row.get(2)?,
-                    }
-                )
-            )?
-                .collect::<Result<Vec<ListTip>, rusqlite::Error>>()?;
-
-            // return every rows found in a Vec<ListTip>
-            Ok::<_, rusqlite::Error>(rows_data)
-        }).await {
-            Ok(tips) => {
-                // 4 - Create the response message ...
-                let mut response: String = "".to_string();
-                // ... and add all tittles found
-                for tip in tips {
-                    match writeln!(response, "{}", display_minimized_tip(tip.id, tip.title, Some(tip.tags))){
-                        Ok(_) => {}
-                        Err(err) => {
-                            error!("Failed to write a new line in !tips_list command. Error:\n{}", err);
-                        }
-                    };
-                }
-                embed_from_param(
-                    format!("List of created  `TIPS`  with tags  `{}`", tags),
-                    response,
-                )
-            }
-            Err(err) => {
-                embed_from_param(
-                    format!("Failed to get the list of tips title."),
-                    format!("Error:\n{}", err),
-                )
-            }
+fn paginate(lines: &[String]) -> Vec<String> {
+    let mut pages = vec![String::new()];
+    for line in lines {
+        let line = truncate(line);
+        let page = pages.last_mut().expect("There is always a page");
+        if !page.is_empty() && page.len() + line.len() + 1 > PAGE_LENGTH {
+            pages.push(String::new());
         }
-    }else{
-        // There is no tag so return the full list
-
-        // 2 - call database execution
-        match conn.lock().await.call(|conn|{
-            let mut stmt = conn.prepare("SELECT id, title, tags FROM tips")?;
-            let rows_data = stmt.query_map([], |row|
-                Ok(
-                    ListTip{
-                        id: row.get(0)?,
-                        title: row.get(1)?,
-                        tags: row.get(2)?,
-                    }
-                )
-            )?
-                .collect::<Result<Vec<ListTip>, rusqlite::Error>>()?;
-
-            // return every row found in a Vec<ListTip>
-            Ok::<_, rusqlite::Error>(rows_data)
-        }).await{
-            Ok(rows_data) => {
-                // 4 - Create the response message ...
-                let mut response = "".to_string();
-                // ... and fill it with titles founds
-                for tip in rows_data {
-                    match writeln!(response, "{}", display_minimized_tip(tip.id, tip.title, Some(tip.tags))){
-                        Ok(_) => {}
-                        Err(err) => {
-                            error!("Failed to write a new line in !tips_list command. Error:\n{}", err);
-                        }
-                    };
-                }
-                embed_from_param(
-                    format!("Here is the list of created  `TIPS`"),
-                    response
-                )
-            }
-            Err(err) => {
-                embed_from_param(
-                    format!("Failed to get the list of tips title."),
-                    format!("Error:\n{}", err),
-                )
-            }
+        let page = pages.last_mut().expect("There is always a page");
+        if let Err(err) = writeln!(page, "{}", line) {
+            error!("Failed to write a new line in !tips_list command. Error:\n{}", err);
         }
     }
+    pages
 }
 
 /**
- * This method is the signature of the command /tips_list.
- * This is here that we describe the name, the options, all
- * descriptions and hints of the method.
+ * This method cut a line too long to fit in a page, like a tip
+ * with a very long title. The cut line ends with an ellipsis.
  *
- * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+ * @param line: &str, the line of a tip
  *
- * @return &mut CreateApplicationCommand, used to chain operations
+ * @return Cow<str>, the line itself when it fits
  */
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("tips_list").description("Show the list of every tips title")
-        .create_option(|option| {
-        option
-            .name("tags")
-            .description("The tag you want to search in tips list. Format:tag1,tag2,tag3,... Don't put spaces around coma!")
-            .kind(CommandOptionType::String)
-            .required(false)
+fn truncate(line: &str) -> Cow<'_, str> {
+    if line.len() <= LINE_LENGTH {
+        return Cow::Borrowed(line);
+    }
+    let mut end = LINE_LENGTH - '…'.len_utf8();
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!("{}…", &line[..end]))
+}
+
+/**
+ * This method is the execution of the command /tips_list.
+ * This is here that all the workflow occur.
+ * Long lists are sent in several messages, or as a file
+ * when there are more than MAX_PAGES pages.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param tips: &dyn TipRepository, the storage of tips.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the messages to say in response
+ */
+async fn run(options: &CommandOptions, tips: &dyn TipRepository, guild_id: u64) -> Result<CommandResponse, BotError>
+{
+    // 1 - Check if there is tags parameter
+    let tags_opt: Option<String> = options.get_optional_validated("tags", parse_tags)?;
+    let tags: Vec<String> = tags_opt.as_ref()
+        .map(|tags| tags.split(',').map(String::from).collect())
+        .unwrap_or_default();
+
+    // 2 - Get the tips from the storage : every tip if there is no tag
+    let tips = tips.list(guild_id, &tags).await?;
+
+    // 3 - Create the response message with all tittles found
+    let lines: Vec<String> = tips.iter()
+        .map(|tip| display_minimized_tip(tip.id, tip.title.clone(), Some(tip.tags.clone())))
+        .collect();
+    let title = match tags_opt {
+        Some(tags) => format!("List of created  `TIPS`  with tags  `{}`", tags),
+        None => String::from("Here is the list of created  `TIPS`"),
+    };
+    let pages = paginate(&lines);
+    let count = pages.len();
+    let mut embeds: Vec<CreateEmbed> = pages.into_iter()
+        .map(|page| embed_from_param(title.clone(), page))
+        .collect();
+    if count > 1 {
+        for (index, embed) in embeds.iter_mut().enumerate() {
+            embed.footer(|footer| footer.text(format!("Page {}/{}", index + 1, count)));
+        }
+    }
+
+    // 4 - The first page is the response, the next ones are follow-ups
+    let mut embeds = embeds.into_iter();
+    let mut first = embeds.next().expect("There is always a page");
+    if count <= MAX_PAGES {
+        return Ok(embeds.fold(CommandResponse::from(first), |response, page| response.follow_up(OutgoingMessage::embed(page))));
+    }
+    // Too many pages : the whole list is in a file
+    first.footer(|footer| footer.text(format!("Page 1/{}, the {} tips are in {}", count, tips.len(), LIST_FILE_NAME)));
+    let file = tips.iter().fold(String::from("id\ttitle\ttags\n"), |mut file, tip| {
+        let _ = writeln!(file, "{}\t{}\t{}", tip.id, tip.title, tip.tags);
+        file
+    });
+    Ok(CommandResponse {
+        message: OutgoingMessage::embed(first).attach(LIST_FILE_NAME, file.into_bytes()),
+        ..CommandResponse::default()
     })
-}
\ No newline at end of file
+}
+
+/*
+This structure is the slash command /tips_list.
+ */
+pub struct TipsListCommand;
+
+#[async_trait]
+impl SlashCommand for TipsListCommand {
+    fn name(&self) -> &'static str {
+        "tips_list"
+    }
+
+    fn category(&self) -> Category {
+        Category::Tips
+    }
+
+    // !tips unity,shaders
+    fn aliases(&self) -> &'static [&'static str] {
+        &["tips"]
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["", "unity,shaders"]
+    }
+
+    /**
+     * This method is the signature of the command /tips_list.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Show the list of every tips title")
+            .create_option(|option| {
+            option
+                .name("tags")
+                .description("The tag you want to search in tips list. Format:tag1,tag2,tag3,... Don't put spaces around coma!")
+                .kind(CommandOptionType::String)
+                .required(false)
+        })
+    }
+
+    // The list of a big server is slow to read
+    fn defer(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx.tips.as_ref(), guild_id).await
+    }
+}
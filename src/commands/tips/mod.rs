@@ -2,4 +2,5 @@ pub mod list;
 pub mod create;
 pub mod read;
 pub mod update;
-pub mod delete;
\ No newline at end of file
+pub mod delete;
+pub mod posts;
\ No newline at end of file
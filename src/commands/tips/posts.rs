@@ -0,0 +1,57 @@
+use log::{info, warn};
+use serenity::builder::CreateEmbed;
+use crate::messenger::{Messenger, MessengerError, OutgoingMessage};
+use crate::repositories::{RepositoryError, TipRepository};
+
+/*
+This structure is the report of an edition of
+every message where a tip has been posted.
+ */
+#[derive(Default)]
+pub struct EditReport {
+    pub edited: usize,   // messages updated with the new content
+    pub removed: usize,  // messages deleted from discord, forgotten by the bot
+    pub failed: usize,   // messages that couldn't be edited (permissions, network, ...)
+}
+
+/**
+ * This method replace the embed of every message where the tip has been
+ * posted by the one given. Messages deleted on discord are forgotten.
+ *
+ * @param tips: &dyn TipRepository, the storage of tips and posts.
+ * @param messenger: &dyn Messenger, used to edit messages on the discord server.
+ * @param tip_id: u64, the id of the tip updated
+ * @param embed: CreateEmbed, the new embed of the tip
+ *
+ * @return Result<EditReport, RepositoryError>, the count of messages edited, removed and failed
+ */
+pub async fn edit_posts(tips: &dyn TipRepository, messenger: &dyn Messenger, tip_id: u64, embed: CreateEmbed) -> Result<EditReport, RepositoryError>
+{
+    // 1 - Get every message of the tip
+    let posts = tips.posts(tip_id).await?;
+
+    // 2 - Edit them one by one and keep track of the messages that doesn't exist anymore
+    let mut report = EditReport::default();
+    let mut removed_posts: Vec<u64> = Vec::new();
+    for post in posts {
+        match messenger.edit(post.channel, post.message, OutgoingMessage::embed(embed.clone())).await {
+            Ok(_) => report.edited += 1,
+            Err(MessengerError::NotFound) => {
+                info!("Message {} of the tip {} doesn't exist anymore, it will be forgotten.", post.message, tip_id);
+                removed_posts.push(post.id);
+            }
+            Err(why) => {
+                warn!("Failed to edit the message {} of the tip {}. Error:\n{}", post.message, tip_id, why);
+                report.failed += 1;
+            }
+        }
+    }
+
+    // 3 - Forget deleted messages
+    report.removed = removed_posts.len();
+    if !removed_posts.is_empty() {
+        tips.forget_posts(&removed_posts).await?;
+    }
+
+    Ok(report)
+}
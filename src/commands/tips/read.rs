@@ -1,94 +1,103 @@
+use rand::{Rng, thread_rng};
+use serenity::async_trait;
 use serenity::builder::{CreateApplicationCommand, CreateEmbed};
 use serenity::model::prelude::command::CommandOptionType;
-use serenity::model::prelude::interaction::application_command::{
-    CommandDataOption,
-};
 use serenity::model::Timestamp;
 use serenity::utils::Color;
-use crate::database::SharedConnection;
-use crate::utils::{display_full_tip_in_embed, get_required_integer_param_from_options, make_error_embed};
+use crate::commands::options::CommandOptions;
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand};
+use crate::error::BotError;
+use crate::repositories::TipRepository;
+use crate::utils::display_full_tip_in_embed;
 
 
-/*
-This structure is used to group fetched data
-from the database and then compute it
- */
-pub struct ReadTip {
-    pub title: String,
-    pub content: String,
-    pub tags: String
-}
-
 /**
  * This method is the execution of the command /tips_read.
  * This is here that all the workflow occur.
  *
- * @param options: &[CommandDataOption], A slice of command option found in the interaction
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
+ * @param options: &CommandOptions, the options given to the command
+ * @param tips: &dyn TipRepository, the storage of tips.
+ * @param guild_id: u64, the id of the guild where the command has been run.
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return Result<CreateEmbed, BotError>, the embed message to say in response
  */
-pub async fn run(options: &[CommandDataOption], conn: SharedConnection) -> CreateEmbed {
-    // 1 - Get the tip id you want to show
-    let tip_id: u64 = match get_required_integer_param_from_options(options, 0, "Id"){
-        Ok(val) => val,
-        Err(err) => {
-            return make_error_embed("tips_read::run", err.to_string())
+async fn run(options: &CommandOptions, tips: &dyn TipRepository, guild_id: u64) -> Result<CreateEmbed, BotError> {
+    // 1 - A random tip of the guild, or the tip id you want to show
+    if options.get_optional::<bool>("random")?.unwrap_or(false) {
+        let mut tips = tips.list(guild_id, &[]).await?;
+        if tips.is_empty() {
+            return Ok(CreateEmbed::default()
+                .title("No tip yet")
+                .description("There is no tip on this server. Create the first one with /tips_create.")
+                .colour(Color::from_rgb(255, 204, 0))
+                .timestamp(Timestamp::now())
+                .to_owned());
         }
-    };
-
-    // 2 - Get the tip from the database and return a response message
-    return match conn.lock().await.call(move |conn| {
-        let mut stmt = conn.prepare("SELECT title, content, tags FROM tips WHERE id = ?1")?;
-        let row_data = stmt.query_row([tip_id], |row|
-            Ok(
-                ReadTip{
-                    title: row.get(0)?,
-                    content: row.get(1)?,
-                    tags: row.get(2)?,
-                }
-            )
-        )?;
+        let tip = tips.swap_remove(thread_rng().gen_range(0..tips.len()));
+        return Ok(display_full_tip_in_embed(tip.title, tip.content, Some(tip.tags)));
+    }
+    let tip_id: u64 = options.get("id")?;
 
-        // Return the readTip found or a rusqlite::Error
-        Ok::<_, rusqlite::Error>(row_data)
-    }).await {
-        Ok(val) => {
-            // Display the fetched tip
-            display_full_tip_in_embed(val.title, val.content, Some(val.tags))
-        }
-        Err(err) => {
-            // No tip found or rusqlite::Error
-            if let tokio_rusqlite::Error::Rusqlite(rusqlite_err) = &err {
-                if let rusqlite::Error::QueryReturnedNoRows = rusqlite_err {
-                    return CreateEmbed::default()
-                        .title("Tip id unknown")
-                        .description("The id requested is not valid. If you think this is an error, please contact server administrator")
-                        .timestamp(Timestamp::now())
-                        .color(Color::from_rgb(255, 0, 0)).to_owned();
-                }
-            }
-            make_error_embed("tips_read::run", err.to_string())
-        }
-    };
+    // 2 - Get the tip from the storage and display it
+    match tips.get(guild_id, tip_id).await? {
+        Some(tip) => Ok(display_full_tip_in_embed(tip.title, tip.content, Some(tip.tags))),
+        None => Err(BotError::UnknownTip(tip_id)),
+    }
 }
 
-/**
- * This method is the signature of the command /tips_read.
- * This is here that we describe the name, the options, all
- * descriptions and hints of the method.
- *
- * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
- *
- * @return &mut CreateApplicationCommand, used to chain operations
+/*
+This structure is the slash command /tips_read.
  */
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("tips_read").description("Display a tip.")
-        .create_option(|option| {
-            option
-                .name("id")
-                .description("The tip id you want to see.")
-                .kind(CommandOptionType::Integer)
-                .required(true)
-        })
-}
\ No newline at end of file
+pub struct TipsReadCommand;
+
+#[async_trait]
+impl SlashCommand for TipsReadCommand {
+    fn name(&self) -> &'static str {
+        "tips_read"
+    }
+
+    fn category(&self) -> Category {
+        Category::Tips
+    }
+
+    // !tip 12 and !tip random
+    fn aliases(&self) -> &'static [&'static str] {
+        &["tip"]
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["12", "random"]
+    }
+
+    /**
+     * This method is the signature of the command /tips_read.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Display a tip.")
+            .create_option(|option| {
+                option
+                    .name("id")
+                    .description("The tip id you want to see. Required unless random is set.")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("random")
+                    .description("See a random tip of the server instead.")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx.tips.as_ref(), guild_id).await.map(CommandResponse::from)
+    }
+}
@@ -1,197 +1,161 @@
-use log::debug;
-use rusqlite::params;
-use serenity::builder::{CreateApplicationCommand, CreateEmbed};
-use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+use log::error;
+use serde_json::json;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
 use serenity::model::prelude::command::CommandOptionType;
-use serenity::model::prelude::interaction::application_command::{
-    CommandDataOption,
-};
-use serenity::model::Timestamp;
-use serenity::utils::Color;
-use crate::commands::tips::read::ReadTip;
-use crate::database::SharedConnection;
-use crate::utils::{display_full_tip_in_embed, get_required_integer_param_from_options, make_error_embed};
+use crate::commands::options::CommandOptions;
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand};
+use crate::commands::tips::posts::edit_posts;
+use crate::error::BotError;
+use crate::messenger::Messenger;
+use crate::permissions::{Access, Capability, CAPABILITY_COMMAND_PERMISSIONS};
+use crate::repositories::{TipChanges, TipRepository};
+use crate::utils::{display_full_tip_in_embed, parse_tags};
 
 /**
- * This method is the execution of the command /tips_create.
+ * This method is the execution of the command /tips_update.
  * This is here that all the workflow occur.
  *
- * @param options: &[CommandDataOption], A slice of command option found in the interaction
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
+ * @param options: &CommandOptions, the options given to the command
+ * @param tips: &dyn TipRepository, the storage of tips and posts.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ * @param messenger: &dyn Messenger, used to edit messages where the tip has already been posted.
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return Result<CommandResponse, BotError>, the embed message to say in response, audited with the tip before and after the update
  */
-pub async fn run(options: &[CommandDataOption], conn: SharedConnection) -> CreateEmbed {
-    let mut updated_columns:Vec<&str> = Vec::with_capacity(3); // we will add column name updated for the query creation
-    let mut updated_values:Vec<String> = Vec::with_capacity(3); // we will add new values
+async fn run(options: &CommandOptions, tips: &dyn TipRepository, guild_id: u64, messenger: &dyn Messenger) -> Result<CommandResponse, BotError> {
+    // 1 - Get the id of the tip to update
+    let tip_id: u64 = options.get("id")?;
 
+    // 2 - check if optional values are present
+    let title: Option<String> = options.get_optional("title")?;
+    let content: Option<String> = options.get_optional("content")?;
+    let tags: Option<String> = options.get_optional_validated("tags", parse_tags)?;
+    let edit_posted_messages: bool = options.get_optional("edit_posts")?.unwrap_or(true);
 
-    // 1 - check if optional values are present
-    let mut title: String = String::from("");
-    let mut content: String = String::from("");
-    let mut tags: String = String::from("");
+    // 3 - Update the tip in the storage, the previous values are kept for the audit log
+    let before = match tips.get(guild_id, tip_id).await? {
+        Some(tip) => json!(tip),
+        None => return Err(BotError::UnknownTip(tip_id)),
+    };
+    let tip = match tips.update(guild_id, tip_id, TipChanges { title, content, tags }).await? {
+        Some(tip) => tip,
+        None => return Err(BotError::UnknownTip(tip_id)),
+    };
+    let after = json!(tip);
 
-    for option in options {
-        match option.name.as_str() {
-            "tags" => {
-                if let Some(value) = &option.resolved {
-                    match value {
-                        CommandDataOptionValue::String(param) => {
-                            tags = param.to_owned();
-                        }
-                        _ => {
-                            return make_error_embed("tips_update::run", format!("The parameter tags given has a bad format.\nExpected lowercase csv with no spaces around coma. like this : tag,tag2,tag3,..."));
-                        }
-                    }
-                } else {
-                    return make_error_embed("tips_update::run", format!("The parameter tags is empty.\nExpected lowercase csv with no spaces around coma. like this : tag,tag2,tag3,..."));
-                }
+    // 4 - Edit messages where the tip has already been posted and return a response message
+    let mut embed = display_full_tip_in_embed(tip.title, tip.content, Some(tip.tags));
+    if edit_posted_messages {
+        match edit_posts(tips, messenger, tip_id, embed.clone()).await {
+            Ok(report) if report.edited + report.failed > 0 => {
+                embed.author(|a| {
+                    a.name(format!("{} posted message(s) updated, {} failed", report.edited, report.failed))
+                });
             }
-            "content" => {
-                if let Some(value) = &option.resolved {
-                    match value {
-                        CommandDataOptionValue::String(param) => {
-                            content = param.to_owned();
-                        }
-                        _ => {
-                            return make_error_embed("tips_update::run", format!("The parameter content given has a bad format.\nExpected a string."));
-                        }
-                    }
-                } else {
-                    return make_error_embed("tips_update::run", format!("The parameter content is empty."));
-                }
+            Ok(_) => {}
+            Err(err) => {
+                error!("Failed to edit messages of the tip {}. Error:\n{}", tip_id, err);
             }
-            "title" => {
-                if let Some(value) = &option.resolved {
-                    match value {
-                        CommandDataOptionValue::String(param) => {
-                            title = param.to_owned();
-                        }
-                        _ => {
-                            return make_error_embed("tips_update::run", format!("The parameter title given has a bad format.\nExpected a string."));
-                        }
-                    }
-                } else {
-                    return make_error_embed("tips_update::run", format!("The parameter title is empty."));
-                }
+        }
+    } else {
+        // Offer to edit the messages still displaying the old content
+        match tips.posts(tip_id).await {
+            Ok(posts) if !posts.is_empty() => {
+                embed.author(|a| {
+                    a.name(format!("{} posted message(s) still show the old content. Run this command again with edit_posts: True to update them", posts.len()))
+                });
             }
-            _ => {
-                println!("Unknown option name.\n{:?}", option);
-                // Handle unknown option names
+            Ok(_) => {}
+            Err(err) => {
+                error!("Failed to count messages of the tip {}. Error:\n{}", tip_id, err);
             }
         }
     }
+    // Display the tip
+    Ok(CommandResponse::from(embed).audited(Some(before), Some(after)))
+}
 
-    // if there is value, add the column name and the value to prepare the sql query
-    if title != "" {
-        updated_columns.push("title");
-        updated_values.push(title);
-    }
+/*
+This structure is the slash command /tips_update.
+ */
+pub struct TipsUpdateCommand;
 
-    if content != "" {
-        updated_columns.push("content");
-        updated_values.push(content);
+#[async_trait]
+impl SlashCommand for TipsUpdateCommand {
+    fn name(&self) -> &'static str {
+        "tips_update"
     }
 
-    if tags != "" {
-        updated_columns.push("tags");
-        updated_values.push(tags);
+    fn category(&self) -> Category {
+        Category::Tips
     }
 
-    // 2 - Get the id of the tip to update
-    let tip_id = match get_required_integer_param_from_options(options, 0, "id"){
-        Ok(title) => title,
-        Err(err) => return make_error_embed("tips_create::run", err),
-    };
-    let tip_id_clone = tip_id.clone();
-
-    // 3 - Prepare the sql query
-    let mut set_clause_tmp: Vec<String> = Vec::with_capacity(3);
-    for id in 0..updated_columns.len() {
-        set_clause_tmp.push(format!("{}='{}'",updated_columns.get(id).unwrap(), updated_values.get(id).unwrap()));
+    fn examples(&self) -> &'static [&'static str] {
+        &["12 title=\"Bake your lights\"", "12 tags=unity edit_posts"]
     }
-    let set_clause = set_clause_tmp.join(", ");
 
+    // Every post of the tip is edited, one request per message
+    fn defer(&self) -> bool {
+        true
+    }
 
-    // 4 - Insert the new tip in the database and return a response message
-    return match conn.lock().await.call(move |conn| {
-        if set_clause != ""{
-            let query = &*format!("UPDATE tips SET {} WHERE id = {}", set_clause, tip_id_clone);
-            debug!("Update query run : {}", query);
-            conn.execute(query, params![])?;
-        }
+    // Only the roles granted tips.write can update tips
+    fn access(&self) -> Access {
+        Access::Capability(Capability::TipsWrite)
+    }
 
-        let mut stmt = conn.prepare("SELECT title, content, tags FROM tips WHERE id = ?1")?;
-        let row_data = stmt.query_row([tip_id], |row|
-            Ok(
-                ReadTip{
-                    title: row.get(0)?,
-                    content: row.get(1)?,
-                    tags: row.get(2)?,
-                }
-            )
-        )?;
+    /**
+     * This method is the signature of the command /tips_update.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Update an already created tips.  /!\\ Warning: new values will override old ones !")
+            .default_member_permissions(CAPABILITY_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+            option
+                .name("id")
+                .description("The id of the tip you want to update.")
+                .kind(CommandOptionType::Integer)
+                .min_int_value(1)
+                .required(true)
+            })
+            .create_option(|option| {
+            option
+                .name("title")
+                .description("The title of the tip. It will be shown in tips list and on the top of the daily tips.")
+                .kind(CommandOptionType::String)
+                .required(false)
+            })
+            .create_option(|option| {
+            option
+                .name("content")
+                .description("The body of the tip. This is here you must put the tip's message.")
+                .kind(CommandOptionType::String)
+                .required(false)
+            })
+            .create_option(|option| {
+            option
+                .name("tags")
+                .description("Tags are used to sort tips. Format: lowercase csv with no spaces around coma. tag1,tag2,tag3,...")
+                .kind(CommandOptionType::String)
+                .required(false)
+            })
+            .create_option(|option| {
+            option
+                .name("edit_posts")
+                .description("Also edit messages where the tip has already been posted by the scheduler. Default: True")
+                .kind(CommandOptionType::Boolean)
+                .required(false)
+            })
+    }
 
-        // Return the updated value of the tip or an rusqlite::Error
-        Ok::<_, rusqlite::Error>(row_data)
-    }).await {
-        Ok(tip) => {
-            // Display the tip
-            display_full_tip_in_embed(tip.title, tip.content, Some(tip.tags))
-        }
-        Err(err) => {
-            // No tip updated or rusqlite::Error
-            if let tokio_rusqlite::Error::Rusqlite(rusqlite_err) = &err {
-                if let rusqlite::Error::QueryReturnedNoRows = rusqlite_err {
-                    return CreateEmbed::default()
-                        .title("Tip id unknown")
-                        .description("The id requested is not valid. If you think this is an error, please contact server administrator")
-                        .timestamp(Timestamp::now())
-                        .color(Color::from_rgb(255, 0, 0)).to_owned();
-                }
-            }
-            make_error_embed("tips_update::run", err.to_string())
-        }
-    };
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx.tips.as_ref(), guild_id, ctx.messenger.as_ref()).await
+    }
 }
-
-/**
- * This method is the signature of the command /tips_update.
- * This is here that we describe the name, the options, all
- * descriptions and hints of the method.
- *
- * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
- *
- * @return &mut CreateApplicationCommand, used to chain operations
- */
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("tips_update").description("Update an already created tips.  /!\\ Warning: new values will override old ones !")
-        .create_option(|option| {
-        option
-            .name("id")
-            .description("The id of the tip you want to update.")
-            .kind(CommandOptionType::Integer)
-            .required(true)
-        })
-        .create_option(|option| {
-        option
-            .name("title")
-            .description("The title of the tip. It will be shown in tips list and on the top of the daily tips.")
-            .kind(CommandOptionType::String)
-            .required(false)
-        })
-        .create_option(|option| {
-        option
-            .name("content")
-            .description("The body of the tip. This is here you must put the tip's message.")
-            .kind(CommandOptionType::String)
-            .required(false)
-        })
-        .create_option(|option| {
-        option
-            .name("tags")
-            .description("Tags are used to sort tips. Format: lowercase csv with no spaces around coma. tag1,tag2,tag3,...")
-            .kind(CommandOptionType::String)
-            .required(false)
-        })
-}
\ No newline at end of file
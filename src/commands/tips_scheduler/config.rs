@@ -1,246 +1,252 @@
-use std::sync::Arc;
-use rusqlite::{Error, OptionalExtension, params};
-use rusqlite::Error::InvalidParameterCount;
+use serde_json::json;
+use serenity::async_trait;
 use serenity::builder::{CreateApplicationCommand, CreateEmbed};
-use serenity::http::Http;
-use serenity::model::application::interaction::application_command::CommandDataOptionValue;
-use serenity::model::channel::PartialChannel;
-use serenity::model::id::ChannelId;
-use serenity::model::mention::Mention;
-use serenity::model::mention::Mention::Channel;
+use serenity::model::channel::{ChannelType, PartialChannel};
 use serenity::model::prelude::command::CommandOptionType;
-use serenity::model::prelude::interaction::application_command::{
-    CommandDataOption,
-};
 use serenity::model::Timestamp;
 use serenity::utils::Color;
-use crate::commands::tips_scheduler::scheduler::{start, stop};
-use crate::database::SharedConnection;
-use crate::SharedJoinHandle;
-use crate::utils::{display_full_tip_in_embed, make_error_embed};
+use crate::commands::options::{CommandOptions, OptionError};
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy};
+use crate::commands::tips_scheduler::scheduler::{describe_config, start, stop};
+use crate::error::BotError;
+use crate::permissions::{Access, Capability, CAPABILITY_COMMAND_PERMISSIONS};
+use crate::repositories::{Persona, SchedulerConfig};
+use crate::utils::display_full_tip_in_embed;
+
+// Name of the webhook when only post_as=webhook is given
+const DEFAULT_PERSONA_NAME: &str = "Tip of the Day";
+// Discord refuse longer names for the author of a webhook message
+const MAX_PERSONA_NAME_LENGTH: usize = 80;
 
-
-pub static CONFIG_ID: usize = 1; // Static configuration id
-
-/*
-This struct is used to access fetched data
-from the database and give it to sub-functions.
+/**
+ * This method parse the hour given to the command.
+ *
+ * @param name: &str, the name of the option
+ * @param value: String, the hour given by the user. format (24h): HH:mm
+ *
+ * @return Result<(usize, usize), OptionError>, the hour and the minutes or the reason they are rejected
  */
-#[derive(Clone)]
-pub struct SchedulerConfig {
-    pub channel: u64,
-    pub hour: u32,
-    pub minute: u32,
+fn parse_hour(name: &str, value: String) -> Result<(usize, usize), OptionError>
+{
+    let vals: Vec<&str> = value.split(':').collect();
+    if vals.len() != 2 {
+        return Err(OptionError::invalid(name, "Expected a string with the following format : HH:mm"));
+    }
+    let hour = match vals[0].trim().parse::<usize>() {
+        Ok(hour) if hour < 24 => hour,
+        _ => return Err(OptionError::invalid(name, "The hour must be a number between 00 and 23. Format : HH:mm")),
+    };
+    let min = match vals[1].trim().parse::<usize>() {
+        Ok(min) if min < 60 => min,
+        _ => return Err(OptionError::invalid(name, "The minutes must be a number between 00 and 59. Format : HH:mm")),
+    };
+    Ok((hour, min))
 }
 
 /**
- * This method is the execution of the command /scheduler_config.
- * This is here that all the workflow occur.
+ * This method parse the name of the webhook given to the command.
+ * Discord refuse the names containing clyde or discord.
  *
- * @param options: &[CommandDataOption], A slice of command option found in the interaction
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
- * @param scheduler_status: SharedJoinHandle, the joinHandle of the scheduler to perform action on it.
- * @param http: &Arc<Http>, Http element used to send message on the discord server.
+ * @param name: &str, the name of the option
+ * @param value: String, the name displayed as author of the tips
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return Result<String, OptionError>, the name or the reason it is rejected
  */
-pub async fn run(options: &[CommandDataOption], conn: SharedConnection, scheduler: SharedJoinHandle, http: &Arc<Http>) -> CreateEmbed {
-    let mut updated_columns:Vec<&str> = Vec::with_capacity(3); // we will add column name updated for the query creation
-    let mut updated_values:Vec<usize> = Vec::with_capacity(3); // we will add new values
-
-    // 1 - check if optional values are present
-    let mut message_channel: Option<PartialChannel> = None;
-    let mut message_channel_id = 0;
-    let mut hour: Option<usize> = None;
-    let mut min: Option<usize> = None;
-
-    for option in options {
-        match option.name.as_str() {
-            "message_chanel" => {
-                if let Some(value) = &option.resolved {
-                    match value {
-                        CommandDataOptionValue::Channel(param) => {
-                            message_channel = Some(param.to_owned());
-                        }
-                        _ => {
-                            return make_error_embed("scheduler_config::run", format!("The parameter message_chanel given has a bad format.\nIt must be an integer."));
-                        }
-                    }
-                } else {
-                    return make_error_embed("scheduler_config::run", format!("The parameter message_chanel is empty.\nExpected an integer."));
-                }
-            }
-            "hour" => {
-                if let Some(value) = &option.resolved {
-                    match value {
-                        CommandDataOptionValue::String(param) => {
-                            let vals: Vec<&str> = param.split(":").collect();
-                            if vals.len() != 2 {
-                                return make_error_embed("scheduler_config::run", format!("The parameter hour given has a bad format.\nExpected a string. with the following format : HH:mm"));
-                            }
-                            hour = match vals.get(0).unwrap().parse::<usize>() {
-                                Ok(val) =>  Some(val),
-                                Err(err) => {
-                                    return make_error_embed("scheduler_config::run", format!("The parameter hour given has a bad format.\nExpected a string with the following format: HH:mm. {}", err));
-                                }
-                            };
-                            min = match vals.get(1).unwrap().parse::<usize>() {
-                                Ok(val) =>  Some(val),
-                                Err(err) => {
-                                    return make_error_embed("scheduler_config::run", format!("The parameter hour given has a bad format.\nExpected a string with the following format: HH:mm. {}", err));
-                                }
-                            };
-
-                        }
-                        _ => {
-                            return make_error_embed("scheduler_config::run", format!("The parameter hour given has a bad format.\nExpected a string."));
-                        }
-                    }
-                } else {
-                    return make_error_embed("scheduler_config::run", format!("The parameter hour is empty."));
-                }
-            }
-            _ => {
-                println!("Unknown option name.\n{:?}", option);
-                // Handle unknown option names
-            }
-        }
+fn parse_persona_name(name: &str, value: String) -> Result<String, OptionError>
+{
+    let value = value.trim();
+    if value.is_empty() || value.chars().count() > MAX_PERSONA_NAME_LENGTH {
+        return Err(OptionError::invalid(name, format!("The name must have between 1 and {} characters.", MAX_PERSONA_NAME_LENGTH)));
     }
-
-    // if there is value, add the column name and the value to prepare the sql query
-    if message_channel.is_some() {
-        message_channel_id = message_channel.unwrap().id.0;
-        updated_columns.push("channel");
-        updated_values.push(message_channel_id as usize);
-    }
-
-    if hour.is_some() {
-        updated_columns.push("hour");
-        updated_values.push(hour.unwrap());
+    let lowercase = value.to_lowercase();
+    if lowercase.contains("clyde") || lowercase.contains("discord") {
+        return Err(OptionError::invalid(name, "Discord doesn't allow names containing \"clyde\" or \"discord\"."));
     }
+    Ok(value.to_string())
+}
 
-    if min.is_some() {
-        updated_columns.push("minute");
-        updated_values.push(min.unwrap());
+/**
+ * This method parse the avatar of the webhook given to the command.
+ *
+ * @param name: &str, the name of the option
+ * @param value: String, the url of the image
+ *
+ * @return Result<String, OptionError>, the url or the reason it is rejected
+ */
+fn parse_avatar_url(name: &str, value: String) -> Result<String, OptionError>
+{
+    let value = value.trim();
+    if !value.starts_with("https://") || value.len() == "https://".len() || value.contains(char::is_whitespace) {
+        return Err(OptionError::invalid(name, "Expected the url of an image, starting with https://"));
     }
+    Ok(value.to_string())
+}
 
-    // 2 - Prepare the sql query
-    let mut set_clause_tmp: Vec<String> = Vec::with_capacity(3);
-    for id in 0..updated_columns.len() {
-        set_clause_tmp.push(format!("{}='{}'",updated_columns.get(id).unwrap(), updated_values.get(id).unwrap()));
+/**
+ * This method is the execution of the command /scheduler_config.
+ * This is here that all the workflow occur.
+ *
+ * @param options: &CommandOptions, the options given to the command
+ * @param ctx: &CommandContext, the storages, the schedulers and everything a restarted scheduler needs.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CommandResponse, BotError>, the embed message to say in response, audited with the configuration before and after
+ */
+async fn run(options: &CommandOptions, ctx: &CommandContext, guild_id: u64) -> Result<CommandResponse, BotError> {
+    let configs = ctx.scheduler_configs.as_ref();
+    // 1 - check if optional values are present
+    let message_channel: Option<PartialChannel> = options.get_optional("message_chanel")?;
+    let (hour, min): (Option<u32>, Option<u32>) = match options.get_optional_validated("hour", parse_hour)? {
+        Some((hour, min)) => (Some(hour as u32), Some(min as u32)),
+        None => (None, None),
+    };
+    let post_as: Option<String> = options.get_optional("post_as")?;
+    let persona_name = options.get_optional_validated("webhook_name", parse_persona_name)?;
+    let avatar_url = options.get_optional_validated("webhook_avatar", parse_avatar_url)?;
+    if post_as.as_deref() == Some("bot") && (persona_name.is_some() || avatar_url.is_some()) {
+        let name = if persona_name.is_some() { "webhook_name" } else { "webhook_avatar" };
+        return Err(OptionError::invalid(name, "The name and the avatar are only used with post_as Webhook. Choose Webhook or remove them.").into());
     }
-    let set_clause = set_clause_tmp.join(", ");
-
-    // 3 - Insert the new tip in the database and return a response message
-    let mut respons_embed = match conn.lock().await.call(move |conn| {
-        // Check if the config object exist in db :
-        let config_opt: Option<usize> = conn.query_row("SELECT id FROM scheduler_config WHERE id = ?1", params![CONFIG_ID], |row|{
-            row.get(0)
-        }).optional()?;
-
-        // Config exist : update it
-        if let Some(_) = config_opt
-        {
-            let query = &format!("UPDATE scheduler_config SET {} WHERE id = {}", set_clause, CONFIG_ID);
-            conn.execute(
-                query,
-                [],
-            )?;
-        }else{
-            // check if all args are present
-            if updated_columns.len() != 3 {
-                return Err(InvalidParameterCount(updated_columns.len(),3));
-            }
-
-            let query = &*format!("INSERT INTO scheduler_config (id, channel, hour, minute) VALUES (?1,?2,?3,?4)");
-            conn.execute(query, params![CONFIG_ID.to_string(), message_channel_id, hour, min])?;
-        }
 
-
-        // Get the final config object:
-        let mut stmt = conn.prepare("SELECT channel, hour, minute FROM scheduler_config WHERE id = ?1")?;
-        let row_data = stmt.query_row([CONFIG_ID.to_string()], |row|
-            Ok(
-                SchedulerConfig{
-                    channel: row.get(0)?,
-                    hour: row.get(1)?,
-                    minute: row.get(2)?,
-                }
-            )
-        )?;
-
-        // Return the config found or an rusqlite::Error
-        Ok::<_, Error>(row_data)
-    }).await {
-        Ok(config) => {
-            // Display the config in the response embed
-            let channel: Mention = Channel(ChannelId::from(config.channel)); // transform the channel id in a channel mention "#channel_name"
-            display_full_tip_in_embed(
-                format!("Here is the new config of the tips scheduler :"),
-                format!("- Channel : {}\n- Hour:{:02}H{:02}", channel, config.hour, config.minute),
-                None
-            )
-        }
-        Err(err) => {
-            if let tokio_rusqlite::Error::Rusqlite(stmt_err) = &err {
-                if let InvalidParameterCount(_,_) = stmt_err {
-                    return CreateEmbed::default()
-                        .title("Config not initialised !")
-                        .description("For the first time you set the config, you need to provide every arguments (channel and hour).")
-                        .timestamp(Timestamp::now())
-                        .color(Color::from_rgb(255, 0, 0)).to_owned();
-                }
-            }
-
-            make_error_embed("scheduler_config::run", err.to_string())
+    // 2 - Get the current config : missing values are kept from it
+    let current = configs.get(guild_id).await?;
+    let current_persona = current.as_ref().and_then(|config| config.persona.clone());
+    // Giving a name or an avatar is enough to post through a webhook
+    let persona = match post_as.as_deref() {
+        Some("bot") => None,
+        None if persona_name.is_none() && avatar_url.is_none() => current_persona,
+        _ => Some(Persona {
+            name: persona_name.or(current_persona.as_ref().map(|persona| persona.name.clone())).unwrap_or_else(|| DEFAULT_PERSONA_NAME.to_string()),
+            avatar_url: avatar_url.or(current_persona.and_then(|persona| persona.avatar_url)),
+        }),
+    };
+    let config = match (
+        message_channel.map(|channel| channel.id.0).or(current.as_ref().map(|config| config.channel)),
+        hour.or(current.as_ref().map(|config| config.hour)),
+        min.or(current.as_ref().map(|config| config.minute)),
+    ) {
+        (Some(channel), Some(hour), Some(minute)) => SchedulerConfig { channel, hour, minute, persona },
+        _ => {
+            // The first time, every value is required
+            return Ok(CommandResponse::from(CreateEmbed::default()
+                .title("Config not initialised !")
+                .description("For the first time you set the config, you need to provide every arguments (channel and hour).")
+                .timestamp(Timestamp::now())
+                .color(Color::from_rgb(255, 0, 0)).to_owned()));
         }
     };
 
+    // 3 - Save the new config and return a response message
+    configs.save(guild_id, config.clone()).await?;
+    let (before, after) = (current.map(|current| json!(current)), Some(json!(config)));
+    // Display the config in the response embed
+    let mut respons_embed = display_full_tip_in_embed(
+        String::from("Here is the new config of the tips scheduler :"),
+        describe_config(&config),
+        None
+    );
+
     // 4 - restart scheduler if he was running
     let is_scheduler_running = {
-        let scheduler_write = scheduler.read().await;
-        let mut finished: bool = true;
-        if  scheduler_write.is_some() {
-            finished = scheduler_write.as_ref().unwrap().is_finished();
-        }
-        !finished // scheduler is running if the task is not complete
+        let schedulers_read = ctx.schedulers.read().await;
+        // scheduler is running if the task of the guild is not complete
+        schedulers_read.get(&guild_id).is_some_and(|handle| !handle.is_finished())
     };
 
     if is_scheduler_running {
-        stop(conn.clone(), scheduler.clone()).await;
-        start(conn, scheduler, http.clone()).await;
+        stop(configs, ctx.schedulers.clone(), guild_id).await?;
+        start(ctx, guild_id).await?;
         // custom the title embed if the scheduler has restart
-        return respons_embed.author(|a|{
+        return Ok(CommandResponse::from(respons_embed.author(|a|{
             a.name("Scheduler has been restarted to sync with the new configuration")
-        }).to_owned()
+        }).to_owned()).audited(before, after))
     }
     // return the default embed instead
-    respons_embed
+    Ok(CommandResponse::from(respons_embed).audited(before, after))
 }
 
-/**
- * This method is the signature of the command /scheduler_config.
- * This is here that we describe the name, the options, all
- * descriptions and hints of the method.
- *
- * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
- *
- * @return &mut CreateApplicationCommand, used to chain operations
+/*
+This structure is the slash command /scheduler_config.
  */
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("scheduler_config").description("Set a new configuration for the tips scheduler.")
-        .create_option(|option| {
-            option
-                .name("message_chanel")
-                .description("The chanel where the bot should say the tips every day.")
-                .kind(CommandOptionType::Channel)
-                .required(false)
-        })
-        .create_option(|option| {
-            option
-                .name("hour")
-                .description("The hour and minutes when the message should be sent every day. format (24h): HH:mm")
-                .kind(CommandOptionType::String)
-                .required(false)
-        })
-}
\ No newline at end of file
+pub struct SchedulerConfigCommand;
+
+#[async_trait]
+impl SlashCommand for SchedulerConfigCommand {
+    fn name(&self) -> &'static str {
+        "scheduler_config"
+    }
+
+    fn category(&self) -> Category {
+        Category::Scheduler
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["hour=09:30", "post_as=webhook webhook_name=\"Tip of the Day\""]
+    }
+
+    // Only the roles granted scheduler.manage can configure the scheduler
+    fn access(&self) -> Access {
+        Access::Capability(Capability::SchedulerManage)
+    }
+
+    // Admin command : the configuration is only shown to the user
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::Always(Visibility::Ephemeral)
+    }
+
+    /**
+     * This method is the signature of the command /scheduler_config.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Set a new configuration for the tips scheduler.")
+            .default_member_permissions(CAPABILITY_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+                option
+                    .name("message_chanel")
+                    .description("The chanel where the bot should say the tips every day.")
+                    .kind(CommandOptionType::Channel)
+                    .channel_types(&[ChannelType::Text, ChannelType::News])
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("hour")
+                    .description("The hour and minutes when the message should be sent every day. format (24h): HH:mm")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("post_as")
+                    .description("Post the tips as the bot, or through a webhook with an other name and avatar.")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+                    .add_string_choice("Bot", "bot")
+                    .add_string_choice("Webhook", "webhook")
+            })
+            .create_option(|option| {
+                option
+                    .name("webhook_name")
+                    .description("The name displayed as author of the tips posted through a webhook. Tip of the Day by default.")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("webhook_avatar")
+                    .description("The url of the avatar of the tips posted through a webhook.")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx, guild_id).await
+    }
+}
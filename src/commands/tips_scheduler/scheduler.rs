@@ -1,309 +1,423 @@
-use std::sync::Arc;
-use std::time::Duration;
-use chrono::{Local, Timelike};
-use log::error;
+use chrono::{DateTime, Local, LocalResult, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use log::{debug, error, warn};
 use rand::{Rng, thread_rng};
-use rusqlite::{Error};
-use rusqlite::Error::InvalidParameterCount;
+use serde_json::json;
+use serenity::async_trait;
 use serenity::builder::{CreateApplicationCommand,CreateEmbed};
-use serenity::http::Http;
 use serenity::model::id::ChannelId;
 use serenity::model::mention::Mention;
 use serenity::model::mention::Mention::Channel;
 use serenity::model::prelude::command::{CommandOptionType};
-use serenity::model::prelude::interaction::application_command::{
-    CommandDataOption,
-};
 use serenity::model::Timestamp;
 use serenity::utils::Color;
-use tokio::time::sleep;
-use crate::commands::tips_scheduler::config::{CONFIG_ID, SchedulerConfig};
-use crate::database::SharedConnection;
-use crate::SharedJoinHandle;
-use crate::utils::{display_full_tip_in_embed, get_required_string_param_from_options, make_error_embed};
+use tracing::{info_span, Instrument};
+use crate::commands::options::CommandOptions;
+use crate::commands::slash_command::{Category, CommandContext, CommandResponse, SlashCommand, Visibility, VisibilityPolicy};
+use crate::error::BotError;
+use crate::locale::Locale;
+use crate::messenger::{Messenger, MessengerError, OutgoingMessage};
+use crate::permissions::{Access, Capability, CAPABILITY_COMMAND_PERMISSIONS};
+use crate::repositories::{Persona, SchedulerConfig, SchedulerRepository, TipRepository};
+use crate::SharedSchedulers;
+use crate::utils::display_full_tip_in_embed;
 
 
-/*
-This structure is used to group fetched data
-from the database and then iterate over vec<Tip>
- */
-#[derive(Clone)]
-struct Tip {
-    title: String,
-    content: String,
-    tags: String
-}
-
 /**
- * This method is the execution of the command /tips_create.
+ * This method is the execution of the command /scheduler.
  * This is here that all the workflow occur.
  *
- * @param options: &[CommandDataOption], A slice of command option found in the interaction
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
- * @param scheduler_status: SharedJoinHandle, the joinHandle of the scheduler to perform action on it.
- * @param http: &Arc<Http>, Http element used to send message on the discord server.
+ * @param options: &CommandOptions, the options given to the command
+ * @param ctx: &CommandContext, the storages, the schedulers, the messenger and the clock given to the scheduler.
+ * @param guild_id: u64, the id of the guild where the command has been run.
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return Result<CommandResponse, BotError>, the embed message to say in response. Start and stop are audited with the status before and after
  */
-pub async fn run(options: &[CommandDataOption], conn: SharedConnection, scheduler_status: SharedJoinHandle, http: &Arc<Http>) -> CreateEmbed {
+async fn run(options: &CommandOptions, ctx: &CommandContext, guild_id: u64) -> Result<CommandResponse, BotError> {
     // 1 - get action value to chose the procedure to execute :
-    let action = &*match get_required_string_param_from_options(options, 0, "action") {
-        Ok(val) => {val}
-        Err(err) => {
-            return make_error_embed("scheduler::run", err.to_string());
-        }
-    };
+    let action = &*options.get::<String>("action")?;
+    let before = json!({ "status": status(&ctx.schedulers, guild_id).await });
 
     // Return the embed resulting of the procedure executed
-    return match action {
+    match action {
         "start" => {
-            start(conn, scheduler_status,http.clone()).await
+            let embed = start(ctx, guild_id).await?;
+            Ok(CommandResponse::from(embed).audited(Some(before), Some(json!({ "status": status(&ctx.schedulers, guild_id).await }))))
         },
         "stop" => {
-            stop(conn, scheduler_status).await
+            let embed = stop(ctx.scheduler_configs.as_ref(), ctx.schedulers.clone(), guild_id).await?;
+            Ok(CommandResponse::from(embed).audited(Some(before), Some(json!({ "status": status(&ctx.schedulers, guild_id).await }))))
         },
         "info" => {
-            info(conn, scheduler_status).await
+            info(ctx.scheduler_configs.as_ref(), ctx.schedulers.clone(), guild_id).await.map(CommandResponse::from)
         },
         _ => {
             // Action invalid or not implemented
-            CreateEmbed::default()
+            Ok(CommandResponse::from(CreateEmbed::default()
                 .title(format!("Action  `{}`  not implemented :(", action))
                 .colour(Color::from_rgb(255, 0, 0))
                 .description("Please retry later. If you think it's an error contact the administrator of the server.")
                 .timestamp(Timestamp::now())
-                .to_owned()
+                .to_owned()))
         }
-    };
+    }
 }
 
 /**
- * Action START : start the tips scheduler with the current configuration.
+ * This method return the status of the tips scheduler of the guild.
+ *
+ * @param schedulers: &SharedSchedulers, the joinHandle of every scheduler.
+ * @param guild_id: u64, the id of the guild.
+ *
+ * @return &'static str, RUNNING or STOPPED
+ */
+async fn status(schedulers: &SharedSchedulers, guild_id: u64) -> &'static str {
+    match schedulers.read().await.get(&guild_id) {
+        Some(handle) if !handle.is_finished() => "RUNNING",
+        _ => "STOPPED",
+    }
+}
+
+/**
+ * This method make the embed displayed when
+ * the scheduler of the guild has no configuration.
  *
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
- * @param scheduler_status: SharedJoinHandle, the joinHandle of the scheduler to perform action on it.
- * @param http: &Arc<Http>, Http element used to send message on the discord server.
+ * @param description: &str, what the user should do
  *
  * @return CreateEmbed, the embed message to say in response
  */
-pub async fn start(conn: SharedConnection, scheduler_status: SharedJoinHandle, http: Arc<Http>) -> CreateEmbed
-{
-    return match conn.lock().await.call(move |conn| {
-        // Get the config object to pass it to the async task:
-        let mut stmt = conn.prepare("SELECT channel, hour, minute FROM scheduler_config WHERE id = ?1")?;
-        let row_data = stmt.query_row([CONFIG_ID.to_string()], |row|
-            Ok(
-                SchedulerConfig{
-                    channel: row.get(0)?,
-                    hour: row.get(1)?,
-                    minute: row.get(2)?,
-                }
-            )
-        )?;
+fn config_not_initialised(description: &str) -> CreateEmbed {
+    CreateEmbed::default()
+        .title("Config not initialised !")
+        .description(description)
+        .timestamp(Timestamp::now())
+        .color(Color::from_rgb(255, 0, 0)).to_owned()
+}
 
-        // return the element found or an rusqlite::Error
-        Ok::<_, Error>(row_data)
-    }).await {
-        Ok(config) => {
-            // Successfully found a configuration :
-            // Spawn a tips_scheduler async task
-            {
-                let mut scheduler_mut =scheduler_status.write().await;
-                let task_conn = conn.clone();
-                let handler = tokio::spawn(async move {
-                    // While task not aborted or crashed:
-                    loop {
-                        // Check if time is equal to the config time
-                        let now = Local::now();
-                        println!("now : {}:{}    config: {}:{}", now.hour(), now.minute(), config.hour, config.minute);
-                        if now.hour() == config.hour && now.minute() == config.minute {
-                            // It's time to send a tips !!
-                            // Get all tips from the database
-                            match task_conn.lock().await.call(|conn|{
-                                let mut stmt = conn.prepare("SELECT title, content, tags FROM tips")?;
-                                let rows_data = stmt.query_map([], |row|
-                                    Ok(
-                                        Tip{
-                                            title: row.get(0)?,
-                                            content: row.get(1)?,
-                                            tags: row.get(2)?,
-                                        }
-                                    )
-                                )?
-                                    .collect::<Result<Vec<Tip>, Error>>()?;
+/**
+ * This method return the next time the tips scheduler posts a tip :
+ * today at the configured hour if it's not passed yet, tomorrow otherwise.
+ * When the clocks go forward the hour may not exist, the tip is then posted
+ * one hour later. When they go back the hour exists twice, the tip is only
+ * posted the first time.
+ *
+ * @param now: DateTime<Utc>, the current time
+ * @param hour: u32, the hour of the configuration
+ * @param minute: u32, the minute of the configuration
+ * @param timezone: Option<Tz>, timezone of the hour. None: the timezone of the machine.
+ *
+ * @return DateTime<Utc>, the time of the next post, always after now
+ */
+pub fn next_post_time(now: DateTime<Utc>, hour: u32, minute: u32, timezone: Option<Tz>) -> DateTime<Utc> {
+    match timezone {
+        Some(timezone) => next_time_in(now, hour, minute, &timezone),
+        None => next_time_in(now, hour, minute, &Local),
+    }
+}
 
-                                // return avery rows found in a Vec<Tip>
-                                Ok::<_, Error>(rows_data)
-                            }).await{
-                                Ok(rows_data) => {
-                                    // List of tip successfully fetched :
-                                    // Select a random one to display.
-                                    let tip = rows_data[thread_rng().gen_range(0..rows_data.len())].clone();
-                                    // Send the message
-                                    if let Err(why) = ChannelId::from(config.channel).send_message(&http, |m| {
-                                        m.set_embed(
-                                            display_full_tip_in_embed(tip.title, tip.content, Some(tip.tags))
-                                        )
-                                    }).await {
-                                        error!("Failed to send embed message. Error:\n{}", why);
-                                    }
-                                }
-                                Err(err) => {
-                                    // Failed to fetch tips from database
-                                    let msg = ChannelId::from(config.channel).send_message(&http, |m| {
-                                        m.set_embed(
-                                            make_error_embed(
-                                                "scheduler::run",
-                                                format!("Failed to get the list of tips title. Error:\n{}", err),
-                                            )
-                                        )
-                                    }).await;
+/**
+ * This method is next_post_time for any timezone.
+ *
+ * @param now: DateTime<Utc>, the current time
+ * @param hour: u32, the hour of the configuration
+ * @param minute: u32, the minute of the configuration
+ * @param timezone: &Z, timezone of the hour
+ *
+ * @return DateTime<Utc>, the time of the next post, always after now
+ */
+fn next_time_in<Z: TimeZone>(now: DateTime<Utc>, hour: u32, minute: u32, timezone: &Z) -> DateTime<Utc> {
+    let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or(NaiveTime::MIN);
+    let today = now.with_timezone(timezone).date_naive();
+    // The hour of today may be passed : try the next days
+    for day in 0..3 {
+        let local_time = (today + chrono::Duration::days(day)).and_time(time);
+        let post_time = match timezone.from_local_datetime(&local_time) {
+            LocalResult::Single(post_time) => post_time,
+            // The clocks go back : the first occurrence
+            LocalResult::Ambiguous(first, _) => first,
+            // The clocks go forward : the hour is skipped
+            LocalResult::None => match timezone.from_local_datetime(&(local_time + chrono::Duration::hours(1))) {
+                LocalResult::Single(post_time) | LocalResult::Ambiguous(post_time, _) => post_time,
+                LocalResult::None => continue,
+            },
+        }.with_timezone(&Utc);
+        if post_time > now {
+            return post_time;
+        }
+    }
+    // Unreachable with real timezones
+    now + chrono::Duration::days(1)
+}
 
-                                    if let Err(why) = msg {
-                                        error!("Failed to send embed message. Error:\n{}", why);
-                                    }
-                                }
-                            }
-                        }
+/**
+ * This method describe the configuration of the scheduler in the embeds of the commands.
+ *
+ * @param config: &SchedulerConfig, the configuration of the guild
+ *
+ * @return String, the channel, the hour and who post the tips
+ */
+pub fn describe_config(config: &SchedulerConfig) -> String {
+    let channel: Mention = Channel(ChannelId::from(config.channel)); // transform the channel id in a channel mention "#channel_name"
+    let posted_as = match &config.persona {
+        Some(persona) => format!("webhook **{}**", persona.name),
+        None => String::from("the bot"),
+    };
+    format!("- Channel : {}\n- Hour:{:02}H{:02}\n- Posted as : {}", channel, config.hour, config.minute, posted_as)
+}
 
-                        // Delay for a minute before checking the time again
-                        sleep(Duration::from_secs(60)).await;
-                    }
-                });
-                // Set the scheduler JoinHandle to keep control on it even after the end of this command
-                *scheduler_mut = Some(handler);
-            } // End spawn task
+/**
+ * This method post a message with the persona of the scheduler. Without
+ * the permission to manage webhooks, it's posted by the bot instead.
+ *
+ * @param messenger: &dyn Messenger, used to post the message on the discord server.
+ * @param channel: u64, the channel where the message is posted.
+ * @param persona: Option<&Persona>, the name and avatar of the webhook. None: posted by the bot
+ * @param message: OutgoingMessage, the message to post
+ *
+ * @return Result<u64, MessengerError>, the id of the message posted
+ */
+async fn send(messenger: &dyn Messenger, channel: u64, persona: Option<&Persona>, message: OutgoingMessage) -> Result<u64, MessengerError> {
+    if let Some(persona) = persona {
+        match messenger.send_as(channel, &persona.name, persona.avatar_url.as_deref(), message.clone()).await {
+            Err(MessengerError::Forbidden) => {
+                warn!("Missing the permission to manage the webhooks of the channel {}, the tip is posted by the bot.", channel);
+            }
+            result => return result,
+        }
+    }
+    messenger.send(channel, message).await
+}
 
-            // return the response embed with the current config and the scheduler status
-            let channel: Mention = Channel(ChannelId::from(config.channel));
-            display_full_tip_in_embed(
-                format!("Scheduler is now running:"),
-                format!("- Channel : {}\n- Hour:{:02}H{:02}", channel, config.hour, config.minute),
-                None
-            )
+/**
+ * This method post a random tip of the guild in the channel of the scheduler.
+ * Failures are logged, the scheduler continue to run.
+ *
+ * @param tips: &dyn TipRepository, the storage of tips to post.
+ * @param messenger: &dyn Messenger, used to post the tip on the discord server.
+ * @param guild_id: u64, the id of the guild of the scheduler.
+ * @param config: &SchedulerConfig, the channel where the tip is posted and the persona posting it.
+ */
+async fn post_tip(tips: &dyn TipRepository, messenger: &dyn Messenger, guild_id: u64, config: &SchedulerConfig) {
+    let channel = config.channel;
+    // Get all tips of the guild
+    match tips.list(guild_id, &[]).await {
+        Ok(rows_data) if rows_data.is_empty() => {
+            warn!("No tip to post on the guild {}.", guild_id);
         }
-        Err(err) => {
-            // fail to get the config
-            if let tokio_rusqlite::Error::Rusqlite(stmt_err) = &err {
-                if let InvalidParameterCount(_,_) = stmt_err {
-                    return CreateEmbed::default()
-                        .title("Config not initialised !")
-                        .description("Use the command  `/scheduler_config`  and fulfill all parameters before running the scheduler.")
-                        .timestamp(Timestamp::now())
-                        .color(Color::from_rgb(255, 0, 0)).to_owned();
+        Ok(rows_data) => {
+            // List of tip successfully fetched :
+            // Select a random one to display.
+            let tip = rows_data[thread_rng().gen_range(0..rows_data.len())].clone();
+            // Send the message
+            let embed = display_full_tip_in_embed(tip.title, tip.content, Some(tip.tags));
+            match send(messenger, channel, config.persona.as_ref(), OutgoingMessage::embed(embed)).await {
+                Ok(message) => {
+                    // Remember where the tip has been posted to edit it if the tip is updated
+                    if let Err(why) = tips.save_post(tip.id, channel, message).await {
+                        error!("Failed to save the tip post. Error:\n{}", why);
+                    }
+                }
+                Err(why) => {
+                    error!("Failed to send embed message. Error:\n{}", why);
                 }
             }
+        }
+        Err(err) => {
+            // Failed to fetch tips from the storage : only the incident id is posted
+            let err = BotError::from(err);
+            let incident = err.report("scheduler");
+            let msg = send(messenger, channel, config.persona.as_ref(), OutgoingMessage::embed(err.to_embed(Locale::default(), incident.as_deref()))).await;
 
-            make_error_embed("scheduler::run", err.to_string())
+            if let Err(why) = msg {
+                error!("Failed to send embed message. Error:\n{}", why);
+            }
         }
+    }
+}
+
+/**
+ * Action START : start the tips scheduler with the current configuration.
+ *
+ * @param ctx: &CommandContext, the storages, the schedulers, the messenger and the clock given to the scheduler.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CreateEmbed, BotError>, the embed message to say in response
+ */
+pub async fn start(ctx: &CommandContext, guild_id: u64) -> Result<CreateEmbed, BotError>
+{
+    // Get the config object to pass it to the async task:
+    let config = match ctx.scheduler_configs.get(guild_id).await? {
+        Some(config) => config,
+        None => return Ok(config_not_initialised("Use the command  `/scheduler_config`  and fulfill all parameters before running the scheduler.")),
     };
+
+    // Successfully found a configuration :
+    // Spawn a tips_scheduler async task
+    {
+        let mut schedulers_mut = ctx.schedulers.write().await;
+        // Only one scheduler per guild
+        if let Some(previous) = schedulers_mut.remove(&guild_id) {
+            previous.abort();
+        }
+        let task_config = config.clone();
+        let tips = ctx.tips.clone();
+        let messenger = ctx.messenger.clone();
+        let clock = ctx.clock.clone();
+        let shutdown = ctx.shutdown.clone();
+        let timezone = ctx.timezone;
+        let handler = tokio::spawn(async move {
+            let config = task_config;
+            // While task not aborted or crashed:
+            loop {
+                // Wait for the hour of the config
+                let next = next_post_time(clock.now(), config.hour, config.minute, timezone);
+                debug!("Next tip posted at {}", next);
+                clock.sleep_until(next).await;
+                // It's time to send a tips !!
+                // The post is tracked : the bot doesn't stop in the middle of it
+                let _activity = match shutdown.track() {
+                    Some(activity) => activity,
+                    None => break,
+                };
+                post_tip(tips.as_ref(), messenger.as_ref(), guild_id, &config).await;
+            }
+        }.instrument(info_span!("scheduler", guild = guild_id)));
+        // Set the scheduler JoinHandle to keep control on it even after the end of this command
+        schedulers_mut.insert(guild_id, handler);
+    } // End spawn task
+
+    // return the response embed with the current config and the scheduler status
+    Ok(display_full_tip_in_embed(
+        String::from("Scheduler is now running:"),
+        describe_config(&config),
+        None
+    ))
 }
 
 /**
  * Action STOP : stop the tips scheduler.
  *
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
- * @param scheduler_status: SharedJoinHandle, the joinHandle of the scheduler to perform action on it.
+ * @param configs: &dyn SchedulerRepository, the storage of scheduler configurations.
+ * @param schedulers: SharedSchedulers, the joinHandle of every scheduler to perform action on the guild one.
+ * @param guild_id: u64, the id of the guild where the command has been run.
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return Result<CreateEmbed, BotError>, the embed message to say in response
  */
-pub async fn stop(conn: SharedConnection, scheduler_status: SharedJoinHandle) -> CreateEmbed
+pub async fn stop(configs: &dyn SchedulerRepository, schedulers: SharedSchedulers, guild_id: u64) -> Result<CreateEmbed, BotError>
 {
     // Stop the task and drop the joinHandle
     {
-        let mut scheduler_write = scheduler_status.write().await;
-        if  scheduler_write.is_some() {
-            scheduler_write.as_ref().unwrap().abort();
-            *scheduler_write = None;
+        let mut schedulers_write = schedulers.write().await;
+        if let Some(handle) = schedulers_write.remove(&guild_id) {
+            handle.abort();
         }
     }
     // Return the current info of the scheduler but change the title.
-    info(conn, scheduler_status).await.title("Scheduler is now stopped").to_owned()
+    Ok(info(configs, schedulers, guild_id).await?.title("Scheduler is now stopped").to_owned())
 }
 
 /**
- * Action INFO : Show every information about the tips scheduler.
+ * This method stop the tips scheduler of every guild, when the bot stops.
+ * Tasks are waited for after being aborted.
  *
- * @param conn: SharedConnection, the database access to run queries on the sqlite database.
- * @param scheduler_status: SharedJoinHandle, the joinHandle of the scheduler to perform action on it.
+ * @param schedulers: &SharedSchedulers, the joinHandle of every scheduler.
  *
- * @return CreateEmbed, the embed message to say in response
+ * @return usize, the number of schedulers stopped
  */
-async fn info(conn: SharedConnection, scheduler_status: SharedJoinHandle) -> CreateEmbed
+pub async fn stop_all(schedulers: &SharedSchedulers) -> usize
 {
-    return match conn.lock().await.call(move |conn| {
-        // Get the config object:
-        let mut stmt = conn.prepare("SELECT channel, hour, minute FROM scheduler_config WHERE id = ?1")?;
-        let row_data = stmt.query_row([CONFIG_ID.to_string()], |row|
-            Ok(
-                SchedulerConfig{
-                    channel: row.get(0)?,
-                    hour: row.get(1)?,
-                    minute: row.get(2)?,
-                }
-            )
-        )?;
+    let handles: Vec<_> = schedulers.write().await.drain().map(|(_, handle)| handle).collect();
+    let count = handles.len();
+    for handle in handles {
+        handle.abort();
+        // The task is cancelled : the error only says so
+        let _ = handle.await;
+    }
+    count
+}
 
-        // Return the SchedulerConfig found or a rusqlite::Error instead
-        Ok::<_, Error>(row_data)
-    }).await {
-        Ok(config) => {
+/**
+ * Action INFO : Show every information about the tips scheduler.
+ *
+ * @param configs: &dyn SchedulerRepository, the storage of scheduler configurations.
+ * @param schedulers: SharedSchedulers, the joinHandle of every scheduler to perform action on the guild one.
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<CreateEmbed, BotError>, the embed message to say in response
+ */
+async fn info(configs: &dyn SchedulerRepository, schedulers: SharedSchedulers, guild_id: u64) -> Result<CreateEmbed, BotError>
+{
+    match configs.get(guild_id).await? {
+        Some(config) => {
             // Display the configuration fetched
-            let channel: Mention = Channel(ChannelId::from(config.channel));
-            let status = {
-                let scheduler_read = scheduler_status.read().await;
-                if scheduler_read.is_some(){
-                    if !scheduler_read.as_ref().unwrap().is_finished(){
-                        "RUNNING"
-                    }else{
-                        "STOPPED"
-                    }
-                }else{
-                    "STOPPED"
-                }
-            };
-            display_full_tip_in_embed(
-                format!("He is the current configuration of the tips scheduler :"),
-                format!("- Channel : {}\n- Hour:{:02}H{:02}\n- Scheduler :{}", channel, config.hour, config.minute, status),
+            let status = status(&schedulers, guild_id).await;
+            Ok(display_full_tip_in_embed(
+                String::from("He is the current configuration of the tips scheduler :"),
+                format!("{}\n- Scheduler :{}", describe_config(&config), status),
                 None
-            )
+            ))
         }
-        Err(err) => {
+        None => {
             // Can't find any configuration
-            if let tokio_rusqlite::Error::Rusqlite(stmt_err) = &err {
-                if let InvalidParameterCount(_,_) = stmt_err {
-                    return CreateEmbed::default()
-                        .title("Config not initialised !")
-                        .description("Use the command  `/scheduler_config`  and fulfill all parameters.")
-                        .timestamp(Timestamp::now())
-                        .color(Color::from_rgb(255, 0, 0)).to_owned();
-                }
-            }
-
-            make_error_embed("scheduler::run", err.to_string())
+            Ok(config_not_initialised("Use the command  `/scheduler_config`  and fulfill all parameters."))
         }
-    };
+    }
 }
 
-/**
- * This method is the signature of the command /scheduler.
- * This is here that we describe the name, the options, all
- * descriptions and hints of the method.
- *
- * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
- *
- * @return &mut CreateApplicationCommand, used to chain operations
+/*
+This structure is the slash command /scheduler.
  */
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command.name("scheduler").description("Set a new configuration for the tips scheduler.")
-        .create_option(|option| {
-            option
-                .name("action")
-                .description("The action you want the scheduler execute.")
-                .kind(CommandOptionType::String)
-                .required(true)
-                .add_string_choice("Start", "start")
-                .add_string_choice("Stop", "stop")
-                .add_string_choice("Info", "info")
-        })
-}
\ No newline at end of file
+pub struct SchedulerCommand;
+
+#[async_trait]
+impl SlashCommand for SchedulerCommand {
+    fn name(&self) -> &'static str {
+        "scheduler"
+    }
+
+    fn category(&self) -> Category {
+        Category::Scheduler
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["start", "info"]
+    }
+
+    // Only the roles granted scheduler.manage can start and stop the scheduler
+    fn access(&self) -> Access {
+        Access::Capability(Capability::SchedulerManage)
+    }
+
+    // Admin command : the status is only shown to the user
+    fn visibility(&self) -> VisibilityPolicy {
+        VisibilityPolicy::Always(Visibility::Ephemeral)
+    }
+
+    /**
+     * This method is the signature of the command /scheduler.
+     * This is here that we describe the options, all
+     * descriptions and hints of the method.
+     *
+     * @param command: &mut CreateApplicationCommand, The command object that handle the creation of new application commands.
+     *
+     * @return &mut CreateApplicationCommand, used to chain operations
+     */
+    fn register<'a>(&self, command: &'a mut CreateApplicationCommand) -> &'a mut CreateApplicationCommand {
+        command.description("Set a new configuration for the tips scheduler.")
+            .default_member_permissions(CAPABILITY_COMMAND_PERMISSIONS)
+            .create_option(|option| {
+                option
+                    .name("action")
+                    .description("The action you want the scheduler execute.")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+                    .add_string_choice("Start", "start")
+                    .add_string_choice("Stop", "stop")
+                    .add_string_choice("Info", "info")
+            })
+    }
+
+    async fn execute(&self, ctx: &CommandContext, guild_id: u64, options: &CommandOptions) -> Result<CommandResponse, BotError> {
+        run(options, ctx, guild_id).await
+    }
+}
@@ -0,0 +1,600 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use chrono_tz::Tz;
+use serde::Deserialize;
+use tracing_subscriber::EnvFilter;
+use crate::database::url::{DatabaseUrl, DEFAULT_DATABASE_URL};
+
+// Configuration file read when no other file is given, if it exists
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+// Log filter used when no level is given : serenity is verbose at the info level
+pub const DEFAULT_LOG_LEVEL: &str = "info,serenity=warn";
+
+// Time given to running commands when the bot stops, below the 10s of `docker stop`
+pub const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 8;
+
+// Directory of the database snapshots and number of snapshots kept when none is given
+pub const DEFAULT_BACKUP_DIRECTORY: &str = "backups";
+pub const DEFAULT_BACKUP_KEEP: usize = 7;
+
+/*
+This structure is the configuration of the bot once every
+layer has been merged and every value has been validated.
+ */
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub token: Option<String>, // the discord bot token, read from the token file if required
+    pub token_file: Option<PathBuf>, // the file the token has been read from
+    pub database_url: DatabaseUrl, // the database used by the bot
+    pub home_guild: Option<u64>, // the guild that owns data created before the multi-guild support
+    pub timezone: Option<Tz>, // timezone of the scheduler hours. None: the timezone of the machine
+    pub admin_channel: Option<u64>, // channel where the bot reports the failures of the scheduled backups. None: they are only logged
+    pub shutdown_timeout: Duration, // time given to running commands and posts when the bot stops
+    pub log: LogConfig,
+    pub backup: BackupConfig,
+    pub features: FeatureToggles,
+}
+
+/*
+This structure is the log settings of the bot.
+ */
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub level: String, // level of every module, like info,game_bot4_fun::commands=debug
+    pub format: LogFormat, // format of each line, on stdout and in files
+    pub stdout: bool, // print logs on the standard output
+    pub directory: Option<PathBuf>, // directory of the log files. None: no file is written
+    pub rotation: LogRotation, // when a new log file is started
+}
+
+/*
+This structure is the backup settings of the bot : where the snapshots
+of the database are written, how often and how many are kept.
+ */
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    pub directory: PathBuf, // directory of the snapshots
+    pub interval: Option<Duration>, // time between two scheduled snapshots. None: only /backup now
+    pub keep: usize, // number of snapshots kept, the oldest ones are deleted
+}
+
+/*
+This enum is the format of log lines.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Human, // readable lines with the level, the module and the span
+    Json, // one JSON object per line, for log collectors
+}
+
+/*
+This enum is how often log files are rotated.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+/*
+This structure enable or disable parts of the bot.
+Every feature is enabled by default.
+ */
+#[derive(Debug, Clone)]
+pub struct FeatureToggles {
+    pub scheduler: bool, // the daily tips scheduler and its commands
+    pub ping: bool, // the !ping message command
+}
+
+impl Default for FeatureToggles {
+    fn default() -> Self {
+        FeatureToggles { scheduler: true, ping: true }
+    }
+}
+
+/*
+This structure is the list of every problem found in the configuration.
+They are all reported at once so they can be fixed together.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl ConfigError {
+    fn new(problem: impl Into<String>) -> ConfigError {
+        ConfigError { problems: vec![problem.into()] }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Invalid configuration:")?;
+        for problem in &self.problems {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/*
+This structure is a layer of configuration : the file, the environment
+or the command line. A None value is taken from the layer below.
+ */
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartialConfig {
+    pub token: Option<String>,
+    pub token_file: Option<PathBuf>,
+    pub database_url: Option<String>,
+    pub home_guild: Option<u64>,
+    pub timezone: Option<String>,
+    pub admin_channel: Option<u64>,
+    pub shutdown_timeout: Option<u64>, // seconds
+    pub log: PartialLogConfig,
+    pub backup: PartialBackupConfig,
+    pub features: PartialFeatureToggles,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartialLogConfig {
+    pub level: Option<String>,
+    pub format: Option<String>,
+    pub stdout: Option<bool>,
+    pub directory: Option<PathBuf>,
+    pub rotation: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartialBackupConfig {
+    pub directory: Option<PathBuf>,
+    pub interval: Option<u64>, // hours, 0 disable the scheduled snapshots
+    pub keep: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartialFeatureToggles {
+    pub scheduler: Option<bool>,
+    pub ping: Option<bool>,
+}
+
+/**
+ * This method parse an id (guild, channel) given as text.
+ *
+ * @param source: &str, where the value comes from, used in errors
+ * @param value: &str, the value
+ * @param problems: &mut Vec<String>, the list where the problem is added
+ *
+ * @return Option<u64>
+ */
+fn parse_id(source: &str, value: &str, problems: &mut Vec<String>) -> Option<u64> {
+    match value.trim().parse::<u64>() {
+        Ok(id) => Some(id),
+        Err(_) => {
+            problems.push(format!("{} must be a discord id (a number), got \"{}\".", source, value));
+            None
+        }
+    }
+}
+
+/**
+ * This method parse a number of seconds given as text.
+ *
+ * @param source: &str, where the value comes from, used in errors
+ * @param value: &str, the value
+ * @param problems: &mut Vec<String>, the list where the problem is added
+ *
+ * @return Option<u64>
+ */
+fn parse_seconds(source: &str, value: &str, problems: &mut Vec<String>) -> Option<u64> {
+    match value.trim().parse::<u64>() {
+        Ok(seconds) => Some(seconds),
+        Err(_) => {
+            problems.push(format!("{} must be a number of seconds, got \"{}\".", source, value));
+            None
+        }
+    }
+}
+
+/**
+ * This method parse a number (hours, count) given as text.
+ *
+ * @param source: &str, where the value comes from, used in errors
+ * @param value: &str, the value
+ * @param problems: &mut Vec<String>, the list where the problem is added
+ *
+ * @return Option<u64>
+ */
+fn parse_number(source: &str, value: &str, problems: &mut Vec<String>) -> Option<u64> {
+    match value.trim().parse::<u64>() {
+        Ok(number) => Some(number),
+        Err(_) => {
+            problems.push(format!("{} must be a positive number, got \"{}\".", source, value));
+            None
+        }
+    }
+}
+
+/**
+ * This method parse a boolean given as text.
+ *
+ * @param source: &str, where the value comes from, used in errors
+ * @param value: &str, the value: true/false, yes/no, on/off or 1/0
+ * @param problems: &mut Vec<String>, the list where the problem is added
+ *
+ * @return Option<bool>
+ */
+fn parse_bool(source: &str, value: &str, problems: &mut Vec<String>) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => {
+            problems.push(format!("{} must be true or false, got \"{}\".", source, value));
+            None
+        }
+    }
+}
+
+impl PartialConfig {
+    /**
+     * This method read a layer from the content of a TOML file.
+     *
+     * @param content: &str, the TOML document
+     * @param source: &str, the name of the file, used in errors
+     *
+     * @return Result<PartialConfig, ConfigError>
+     */
+    pub fn from_toml(content: &str, source: &str) -> Result<PartialConfig, ConfigError> {
+        toml::from_str(content).map_err(|err| ConfigError::new(format!("{}: {}", source, err.to_string().trim())))
+    }
+
+    /**
+     * This method read a layer from a TOML file.
+     *
+     * @param path: &Path, the configuration file
+     *
+     * @return Result<PartialConfig, ConfigError>
+     */
+    pub fn from_file(path: &Path) -> Result<PartialConfig, ConfigError> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| ConfigError::new(format!("Can't read the configuration file {}: {}", path.display(), err)))?;
+        PartialConfig::from_toml(&content, &path.display().to_string())
+    }
+
+    /**
+     * This method read a layer from environment variables.
+     *
+     * @param lookup: impl Fn(&str) -> Option<String>, return the value of a variable
+     *
+     * @return Result<PartialConfig, ConfigError>
+     */
+    pub fn from_env(lookup: impl Fn(&str) -> Option<String>) -> Result<PartialConfig, ConfigError> {
+        let mut problems = Vec::new();
+        let mut layer = PartialConfig {
+            token: lookup("DISCORD_TOKEN"),
+            token_file: lookup("DISCORD_TOKEN_FILE").map(PathBuf::from),
+            database_url: lookup("DATABASE_URL"),
+            timezone: lookup("TIMEZONE"),
+            ..PartialConfig::default()
+        };
+        layer.home_guild = lookup("HOME_GUILD_ID").and_then(|value| parse_id("HOME_GUILD_ID", &value, &mut problems));
+        layer.admin_channel = lookup("ADMIN_CHANNEL_ID").and_then(|value| parse_id("ADMIN_CHANNEL_ID", &value, &mut problems));
+        layer.shutdown_timeout = lookup("SHUTDOWN_TIMEOUT").and_then(|value| parse_seconds("SHUTDOWN_TIMEOUT", &value, &mut problems));
+        layer.log.level = lookup("LOG_LEVEL");
+        layer.log.format = lookup("LOG_FORMAT");
+        layer.log.stdout = lookup("LOG_STDOUT").and_then(|value| parse_bool("LOG_STDOUT", &value, &mut problems));
+        layer.log.directory = lookup("LOG_DIRECTORY").map(PathBuf::from);
+        layer.log.rotation = lookup("LOG_ROTATION");
+        layer.backup.directory = lookup("BACKUP_DIRECTORY").map(PathBuf::from);
+        layer.backup.interval = lookup("BACKUP_INTERVAL").and_then(|value| parse_number("BACKUP_INTERVAL", &value, &mut problems));
+        layer.backup.keep = lookup("BACKUP_KEEP").and_then(|value| parse_number("BACKUP_KEEP", &value, &mut problems)).map(|keep| keep as usize);
+        layer.features.scheduler = lookup("FEATURE_SCHEDULER").and_then(|value| parse_bool("FEATURE_SCHEDULER", &value, &mut problems));
+        layer.features.ping = lookup("FEATURE_PING").and_then(|value| parse_bool("FEATURE_PING", &value, &mut problems));
+
+        if problems.is_empty() { Ok(layer) } else { Err(ConfigError { problems }) }
+    }
+
+    /**
+     * This method put a layer over this one : its values replace the ones of this layer.
+     * The token and the token file replace each other : the token of the highest layer is used.
+     *
+     * @param higher: PartialConfig, the layer with the highest precedence
+     *
+     * @return PartialConfig, the merged layer
+     */
+    pub fn merge(self, higher: PartialConfig) -> PartialConfig {
+        let (token, token_file) = if higher.token.is_some() || higher.token_file.is_some() {
+            (higher.token, higher.token_file)
+        } else {
+            (self.token, self.token_file)
+        };
+        PartialConfig {
+            token,
+            token_file,
+            database_url: higher.database_url.or(self.database_url),
+            home_guild: higher.home_guild.or(self.home_guild),
+            timezone: higher.timezone.or(self.timezone),
+            admin_channel: higher.admin_channel.or(self.admin_channel),
+            shutdown_timeout: higher.shutdown_timeout.or(self.shutdown_timeout),
+            log: PartialLogConfig {
+                level: higher.log.level.or(self.log.level),
+                format: higher.log.format.or(self.log.format),
+                stdout: higher.log.stdout.or(self.log.stdout),
+                directory: higher.log.directory.or(self.log.directory),
+                rotation: higher.log.rotation.or(self.log.rotation),
+            },
+            backup: PartialBackupConfig {
+                directory: higher.backup.directory.or(self.backup.directory),
+                interval: higher.backup.interval.or(self.backup.interval),
+                keep: higher.backup.keep.or(self.backup.keep),
+            },
+            features: PartialFeatureToggles {
+                scheduler: higher.features.scheduler.or(self.features.scheduler),
+                ping: higher.features.ping.or(self.features.ping),
+            },
+        }
+    }
+
+    /**
+     * This method check every value and build the configuration.
+     * Missing values take their default value.
+     *
+     * @return Result<Config, ConfigError>, the configuration or every problem found
+     */
+    pub fn validate(self) -> Result<Config, ConfigError> {
+        let mut problems = Vec::new();
+
+        // 1 - The token is given directly or read from a file
+        let token = match (self.token, &self.token_file) {
+            (Some(token), _) => Some(token.trim().to_string()),
+            (None, Some(path)) => match fs::read_to_string(path) {
+                Ok(token) => Some(token.trim().to_string()),
+                Err(err) => {
+                    problems.push(format!("Can't read the token file {}: {}", path.display(), err));
+                    None
+                }
+            },
+            (None, None) => None,
+        };
+        if token.as_deref() == Some("") {
+            problems.push(String::from("The discord token is empty."));
+        }
+
+        // 2 - Database
+        let database_url = DatabaseUrl::parse(self.database_url.as_deref().unwrap_or(DEFAULT_DATABASE_URL))
+            .map_err(|err| problems.push(format!("database_url: {}", err)))
+            .ok();
+
+        // 3 - Timezone
+        let timezone = match self.timezone.as_deref().map(str::trim) {
+            None | Some("") | Some("local") => None,
+            Some(name) => match name.parse::<Tz>() {
+                Ok(timezone) => Some(timezone),
+                Err(_) => {
+                    problems.push(format!("timezone: \"{}\" is not a timezone. Use a name like Europe/Paris or UTC.", name));
+                    None
+                }
+            },
+        };
+
+        // 4 - Logs
+        let level = self.log.level.unwrap_or_else(|| String::from(DEFAULT_LOG_LEVEL));
+        if let Err(err) = EnvFilter::try_new(&level) {
+            problems.push(format!("log level: \"{}\" is invalid ({}). Use a level (Off, Trace, Debug, Info, Warn, Error) optionally followed by module=level, like info,game_bot4_fun::commands=debug.", level, err));
+        }
+        let format = match self.log.format.as_deref().map(|format| format.trim().to_lowercase()).as_deref() {
+            None | Some("human") => LogFormat::Human,
+            Some("json") => LogFormat::Json,
+            Some(format) => {
+                problems.push(format!("log format: \"{}\" is not a format. Use human or json.", format));
+                LogFormat::Human
+            }
+        };
+        let rotation = match self.log.rotation.as_deref().map(|rotation| rotation.trim().to_lowercase()).as_deref() {
+            None | Some("daily") => LogRotation::Daily,
+            Some("hourly") => LogRotation::Hourly,
+            Some("never") => LogRotation::Never,
+            Some(rotation) => {
+                problems.push(format!("log rotation: \"{}\" is not a rotation. Use hourly, daily or never.", rotation));
+                LogRotation::Daily
+            }
+        };
+        let stdout = self.log.stdout.unwrap_or(true);
+        if !stdout && self.log.directory.is_none() {
+            problems.push(String::from("log: the standard output is disabled and there is no log directory, logs would be lost."));
+        }
+
+        // 5 - Backups
+        let keep = self.backup.keep.unwrap_or(DEFAULT_BACKUP_KEEP);
+        if keep == 0 {
+            problems.push(String::from("backup keep: at least 1 snapshot must be kept."));
+        }
+        let backup = BackupConfig {
+            directory: self.backup.directory.unwrap_or_else(|| PathBuf::from(DEFAULT_BACKUP_DIRECTORY)),
+            interval: self.backup.interval.filter(|hours| *hours > 0).map(|hours| Duration::from_secs(hours * 3600)),
+            keep,
+        };
+
+        match database_url {
+            Some(database_url) if problems.is_empty() => Ok(Config {
+                token,
+                token_file: self.token_file,
+                database_url,
+                home_guild: self.home_guild,
+                timezone,
+                admin_channel: self.admin_channel,
+                shutdown_timeout: Duration::from_secs(self.shutdown_timeout.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT)),
+                log: LogConfig { level, format, stdout, directory: self.log.directory, rotation },
+                backup,
+                features: FeatureToggles {
+                    scheduler: self.features.scheduler.unwrap_or(true),
+                    ping: self.features.ping.unwrap_or(true),
+                },
+            }),
+            _ => Err(ConfigError { problems }),
+        }
+    }
+}
+
+/*
+This structure is the command line of the bot.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CliArguments {
+    pub config_file: Option<PathBuf>, // --config PATH
+    pub overrides: PartialConfig, // values given with flags, the highest layer of configuration
+    pub dry_run_migrations: bool, // --dry-run-migrations : list pending migrations and exit
+    pub print_config: bool, // --print-config : print the configuration without secrets and exit
+}
+
+impl CliArguments {
+    /**
+     * This method parse the command line arguments, without the name of the program.
+     * Values are given as `--flag value` or `--flag=value`.
+     *
+     * @param args: impl IntoIterator<Item = String>, the arguments
+     *
+     * @return Result<CliArguments, ConfigError>
+     */
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliArguments, ConfigError> {
+        let mut cli = CliArguments::default();
+        let mut problems = Vec::new();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            // flags without value, then flags followed by a value
+            match flag.as_str() {
+                "--dry-run-migrations" => { cli.dry_run_migrations = true; continue; }
+                "--print-config" => { cli.print_config = true; continue; }
+                "--config" | "--token" | "--token-file" | "--database-url" | "--home-guild" | "--timezone"
+                | "--admin-channel" | "--shutdown-timeout" | "--log-level" | "--log-format" | "--log-directory" | "--backup-directory"
+                | "--enable" | "--disable" => {}
+                _ => {
+                    problems.push(format!("Unknown argument \"{}\".", arg));
+                    continue;
+                }
+            }
+
+            let value = match inline_value.or_else(|| args.next()) {
+                Some(value) => value,
+                None => {
+                    problems.push(format!("{} expects a value.", flag));
+                    continue;
+                }
+            };
+            let overrides = &mut cli.overrides;
+            match flag.as_str() {
+                "--config" => cli.config_file = Some(PathBuf::from(value)),
+                "--token" => overrides.token = Some(value),
+                "--token-file" => overrides.token_file = Some(PathBuf::from(value)),
+                "--database-url" => overrides.database_url = Some(value),
+                "--home-guild" => overrides.home_guild = parse_id("--home-guild", &value, &mut problems),
+                "--timezone" => overrides.timezone = Some(value),
+                "--admin-channel" => overrides.admin_channel = parse_id("--admin-channel", &value, &mut problems),
+                "--shutdown-timeout" => overrides.shutdown_timeout = parse_seconds("--shutdown-timeout", &value, &mut problems),
+                "--log-level" => overrides.log.level = Some(value),
+                "--log-format" => overrides.log.format = Some(value),
+                "--log-directory" => overrides.log.directory = Some(PathBuf::from(value)),
+                "--backup-directory" => overrides.backup.directory = Some(PathBuf::from(value)),
+                "--enable" | "--disable" => {
+                    let enabled = Some(flag == "--enable");
+                    match value.as_str() {
+                        "scheduler" => overrides.features.scheduler = enabled,
+                        "ping" => overrides.features.ping = enabled,
+                        _ => problems.push(format!("{}: unknown feature \"{}\". Features: scheduler, ping.", flag, value)),
+                    }
+                }
+                _ => unreachable!("flags are checked above"),
+            }
+        }
+
+        if problems.is_empty() { Ok(cli) } else { Err(ConfigError { problems }) }
+    }
+}
+
+impl Config {
+    /**
+     * This method load the configuration : the TOML file, then
+     * environment variables, then command line flags. Each layer
+     * overrides the values of the previous one.
+     *
+     * The file is the one given by --config or CONFIG_FILE, or
+     * config.toml if it exists in the working directory.
+     *
+     * @param cli: &CliArguments, the command line
+     * @param lookup: impl Fn(&str) -> Option<String>, return the value of an environment variable
+     *
+     * @return Result<Config, ConfigError>
+     */
+    pub fn load(cli: &CliArguments, lookup: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        // 1 - Configuration file
+        let file = match cli.config_file.clone().or_else(|| lookup("CONFIG_FILE").map(PathBuf::from)) {
+            Some(path) => PartialConfig::from_file(&path),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => PartialConfig::from_file(Path::new(DEFAULT_CONFIG_FILE)),
+            None => Ok(PartialConfig::default()),
+        };
+        // 2 - Environment
+        let env = PartialConfig::from_env(lookup);
+
+        // Report the problems of every layer at once
+        match (file, env) {
+            (Ok(file), Ok(env)) => file.merge(env).merge(cli.overrides.clone()).validate(),
+            (file, env) => Err(ConfigError {
+                problems: [file.err(), env.err()].into_iter().flatten().flat_map(|err| err.problems).collect(),
+            }),
+        }
+    }
+
+    /**
+     * This method return the discord token, required to connect the bot.
+     *
+     * @return Result<&str, ConfigError>
+     */
+    pub fn require_token(&self) -> Result<&str, ConfigError> {
+        self.token.as_deref().ok_or_else(|| ConfigError::new(
+            "No discord token : set token or token_file in the configuration file, DISCORD_TOKEN or DISCORD_TOKEN_FILE, or --token or --token-file."
+        ))
+    }
+}
+
+impl Display for Config {
+    // Secrets are never printed : the output can be shared
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let optional_id = |id: Option<u64>| id.map_or(String::from("not set"), |id| id.to_string());
+        let enabled = |enabled: bool| if enabled { "enabled" } else { "disabled" };
+
+        writeln!(f, "token = {}", match (&self.token, &self.token_file) {
+            (None, _) => String::from("not set"),
+            (Some(_), Some(path)) => format!("******** (read from {})", path.display()),
+            (Some(_), None) => String::from("********"),
+        })?;
+        writeln!(f, "database_url = {}", self.database_url)?;
+        writeln!(f, "home_guild = {}", optional_id(self.home_guild))?;
+        writeln!(f, "timezone = {}", self.timezone.map_or(String::from("local"), |timezone| timezone.name().to_string()))?;
+        writeln!(f, "admin_channel = {}", optional_id(self.admin_channel))?;
+        writeln!(f, "shutdown_timeout = {}s", self.shutdown_timeout.as_secs())?;
+        writeln!(f, "log.level = {}", self.log.level)?;
+        writeln!(f, "log.format = {}", match self.log.format { LogFormat::Human => "human", LogFormat::Json => "json" })?;
+        writeln!(f, "log.stdout = {}", enabled(self.log.stdout))?;
+        writeln!(f, "log.directory = {}", self.log.directory.as_ref().map_or(String::from("not set"), |directory| directory.display().to_string()))?;
+        writeln!(f, "log.rotation = {}", match self.log.rotation { LogRotation::Hourly => "hourly", LogRotation::Daily => "daily", LogRotation::Never => "never" })?;
+        writeln!(f, "backup.directory = {}", self.backup.directory.display())?;
+        writeln!(f, "backup.interval = {}", self.backup.interval.map_or(String::from("disabled"), |interval| format!("{}h", interval.as_secs() / 3600)))?;
+        writeln!(f, "backup.keep = {}", self.backup.keep)?;
+        writeln!(f, "features.scheduler = {}", enabled(self.features.scheduler))?;
+        write!(f, "features.ping = {}", enabled(self.features.ping))
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use crate::commands::options::CommandOptions;
+use crate::permissions::Invoker;
+
+// Time given to the user to press a button
+pub const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+// Start of the custom id of the buttons, like confirmation:12:confirm
+const BUTTON_PREFIX: &str = "confirmation";
+
+/*
+This structure is a command waiting for the user to confirm it.
+ */
+#[derive(Debug, Clone)]
+pub struct PendingConfirmation {
+    pub command: String, // the name of the command to run once confirmed
+    pub guild_id: u64,
+    pub options: CommandOptions, // the options given to the command
+    pub invoker: Invoker, // the member who run the command, the only one who can answer
+    pub expires_at: DateTime<Utc>,
+}
+
+/*
+This enum describe every reason a button can't be answered.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationError {
+    Expired, // the timeout is over, or the bot restarted since the command
+    NotYours, // an other user pressed the button
+}
+
+/**
+ * This method return the custom id of a button of a confirmation.
+ *
+ * @param id: u64, the id of the confirmation
+ * @param confirmed: bool, true for the confirm button, false for the cancel button
+ *
+ * @return String, like confirmation:12:confirm
+ */
+pub fn button_id(id: u64, confirmed: bool) -> String {
+    format!("{}:{}:{}", BUTTON_PREFIX, id, if confirmed { "confirm" } else { "cancel" })
+}
+
+/**
+ * This method read the custom id of a button of a confirmation.
+ *
+ * @param custom_id: &str, the custom id of the button pressed
+ *
+ * @return Option<(u64, bool)>, the id of the confirmation and true if it's confirmed. None if it's not a button of a confirmation
+ */
+pub fn parse_button_id(custom_id: &str) -> Option<(u64, bool)> {
+    let mut parts = custom_id.split(':');
+    if parts.next()? != BUTTON_PREFIX {
+        return None;
+    }
+    let id = parts.next()?.parse().ok()?;
+    let confirmed = match parts.next()? {
+        "confirm" => true,
+        "cancel" => false,
+        _ => return None,
+    };
+    parts.next().is_none().then_some((id, confirmed))
+}
+
+/*
+This structure hold the commands waiting for a confirmation.
+They are only kept in memory : buttons pressed after a restart are expired.
+Ids are random, so the button of a confirmation asked before a restart
+never answers a new one.
+ */
+#[derive(Default)]
+pub struct Confirmations {
+    pending: Mutex<HashMap<u64, PendingConfirmation>>,
+}
+
+impl Confirmations {
+    pub fn new() -> Confirmations {
+        Confirmations::default()
+    }
+
+    /**
+     * This method keep a command until the user confirm it. Expired confirmations are forgotten.
+     *
+     * @param pending: PendingConfirmation, the command to confirm
+     * @param now: DateTime<Utc>, the current time
+     *
+     * @return u64, the id of the confirmation, given to its buttons
+     */
+    pub fn ask(&self, pending: PendingConfirmation, now: DateTime<Utc>) -> u64 {
+        let mut confirmations = self.pending.lock().unwrap();
+        confirmations.retain(|_, confirmation| confirmation.expires_at > now);
+        let mut id: u64 = rand::random();
+        while confirmations.contains_key(&id) {
+            id = rand::random();
+        }
+        confirmations.insert(id, pending);
+        id
+    }
+
+    /**
+     * This method remove a confirmation answered by a user.
+     * It's kept when an other user pressed the button.
+     *
+     * @param id: u64, the id of the confirmation
+     * @param user_id: u64, the user who pressed the button
+     * @param now: DateTime<Utc>, the current time
+     *
+     * @return Result<PendingConfirmation, ConfirmationError>, the command to run or cancel
+     */
+    pub fn answer(&self, id: u64, user_id: u64, now: DateTime<Utc>) -> Result<PendingConfirmation, ConfirmationError> {
+        let mut confirmations = self.pending.lock().unwrap();
+        match confirmations.get(&id) {
+            None => Err(ConfirmationError::Expired),
+            Some(pending) if pending.expires_at <= now => {
+                confirmations.remove(&id);
+                Err(ConfirmationError::Expired)
+            }
+            Some(pending) if pending.invoker.user_id != user_id => Err(ConfirmationError::NotYours),
+            Some(_) => Ok(confirmations.remove(&id).expect("The confirmation exists")),
+        }
+    }
+
+    /**
+     * This method forget a confirmation once its timeout is over.
+     *
+     * @param id: u64, the id of the confirmation
+     *
+     * @return bool, true if it was still waiting for an answer
+     */
+    pub fn expire(&self, id: u64) -> bool {
+        self.pending.lock().unwrap().remove(&id).is_some()
+    }
+}
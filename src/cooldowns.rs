@@ -0,0 +1,226 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Serialize, Serializer};
+use crate::error::BotError;
+use crate::permissions::{Access, authorize, Capability, Invoker};
+use crate::repositories::{CooldownRepository, CooldownRule, PermissionRepository, RepositoryError};
+
+// Number of buckets above which the buckets without recent uses are forgotten
+const BUCKETS_CLEANUP_THRESHOLD: usize = 10_000;
+
+/*
+This enum is who shares the uses counted by a cooldown rule.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CooldownScope {
+    User, // each member has their own uses
+    Channel, // the members of a channel share the uses
+    Guild, // every member of the guild shares the uses
+}
+
+impl CooldownScope {
+    // Every scope, in the order they are displayed
+    pub const ALL: [CooldownScope; 3] = [CooldownScope::User, CooldownScope::Channel, CooldownScope::Guild];
+
+    /**
+     * This method return the name of the scope, as stored and typed by users.
+     *
+     * @return &'static str, like user
+     */
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CooldownScope::User => "user",
+            CooldownScope::Channel => "channel",
+            CooldownScope::Guild => "guild",
+        }
+    }
+
+    /**
+     * This method return the scope with the given name.
+     *
+     * @param name: &str, the name of the scope, like user
+     *
+     * @return Option<CooldownScope>, None if the name is unknown
+     */
+    pub fn parse(name: &str) -> Option<CooldownScope> {
+        CooldownScope::ALL.into_iter().find(|scope| scope.as_str() == name)
+    }
+}
+
+impl Display for CooldownScope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Stored and displayed by its name, like user
+impl Serialize for CooldownScope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/*
+This structure identify the uses counted together by a rule.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BucketKey {
+    guild_id: u64,
+    command: String,
+    scope: CooldownScope,
+    owner: u64, // the id of the user, the channel or the guild, depending on the scope
+}
+
+/*
+This structure is the recent uses of a bucket.
+ */
+#[derive(Debug)]
+struct Bucket {
+    period: Duration, // uses older than the period are forgotten
+    uses: VecDeque<DateTime<Utc>>, // the oldest first
+}
+
+/*
+This structure limit how often commands are used. The rules of each guild
+are read once from the storage and kept until they are changed.
+Uses are only counted in memory : they are forgotten when the bot restarts.
+ */
+pub struct Cooldowns {
+    storage: Arc<dyn CooldownRepository>,
+    rules: Mutex<HashMap<u64, Vec<CooldownRule>>>, // the rules of each guild already read
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+impl Cooldowns {
+    /**
+     * This method create the limiter of every guild.
+     *
+     * @param storage: Arc<dyn CooldownRepository>, the storage of the rules
+     *
+     * @return Cooldowns
+     */
+    pub fn new(storage: Arc<dyn CooldownRepository>) -> Cooldowns {
+        Cooldowns { storage, rules: Mutex::new(HashMap::new()), buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /**
+     * This method return every rule of the guild, ordered by command.
+     *
+     * @param guild_id: u64, the guild of the rules
+     *
+     * @return Result<Vec<CooldownRule>, RepositoryError>
+     */
+    pub async fn rules(&self, guild_id: u64) -> Result<Vec<CooldownRule>, RepositoryError> {
+        if let Some(rules) = self.rules.lock().unwrap().get(&guild_id) {
+            return Ok(rules.clone());
+        }
+        let rules = self.storage.list(guild_id).await?;
+        self.rules.lock().unwrap().insert(guild_id, rules.clone());
+        Ok(rules)
+    }
+
+    /**
+     * This method save a rule, replacing the rule of the same command and scope.
+     * It applies to the next uses of the command.
+     *
+     * @param guild_id: u64, the guild of the rule
+     * @param rule: CooldownRule, the rule to save
+     *
+     * @return Result<Option<CooldownRule>, RepositoryError>, the rule replaced
+     */
+    pub async fn set(&self, guild_id: u64, rule: CooldownRule) -> Result<Option<CooldownRule>, RepositoryError> {
+        let previous = self.storage.set(guild_id, rule).await;
+        self.rules.lock().unwrap().remove(&guild_id);
+        previous
+    }
+
+    /**
+     * This method delete the rule of a command and a scope.
+     *
+     * @param guild_id: u64, the guild of the rule
+     * @param command: &str, the name of the command
+     * @param scope: CooldownScope, the scope of the rule
+     *
+     * @return Result<Option<CooldownRule>, RepositoryError>, the rule deleted, None if there was none
+     */
+    pub async fn remove(&self, guild_id: u64, command: &str, scope: CooldownScope) -> Result<Option<CooldownRule>, RepositoryError> {
+        let removed = self.storage.remove(guild_id, command, scope).await;
+        self.rules.lock().unwrap().remove(&guild_id);
+        removed
+    }
+
+    /**
+     * This method count a use of a command, or refuse it if a rule of the
+     * command has no use left. Members granted cooldowns.bypass and managers
+     * are never refused, and their uses are not counted.
+     *
+     * @param permissions: &dyn PermissionRepository, the roles granted each capability
+     * @param invoker: &Invoker, the member who run the command
+     * @param command: &str, the name of the command
+     * @param guild_id: u64, the id of the guild where the command has been run.
+     * @param now: DateTime<Utc>, the time of the use
+     *
+     * @return Result<(), BotError>, Cooldown with the seconds to wait if the use is refused
+     */
+    pub async fn check(&self, permissions: &dyn PermissionRepository, invoker: &Invoker, command: &str, guild_id: u64, now: DateTime<Utc>) -> Result<(), BotError> {
+        // 1 - Get the rules of the command
+        let rules: Vec<CooldownRule> = self.rules(guild_id).await?.into_iter()
+            .filter(|rule| rule.command == command)
+            .collect();
+        if rules.is_empty() {
+            return Ok(());
+        }
+
+        // 2 - Members who bypass the cooldowns are never refused, their uses don't fill the buckets of the others
+        match authorize(permissions, invoker, Access::Capability(Capability::CooldownBypass), guild_id).await {
+            Ok(()) => return Ok(()),
+            Err(BotError::MissingCapability(_)) => {}
+            Err(err) => return Err(err),
+        }
+
+        // 3 - Find the longest wait before each bucket has a use left
+        let keys: Vec<(BucketKey, Duration, u32)> = rules.iter()
+            .map(|rule| {
+                let owner = match rule.scope {
+                    CooldownScope::User => invoker.user_id,
+                    CooldownScope::Channel => invoker.channel_id,
+                    CooldownScope::Guild => guild_id,
+                };
+                let key = BucketKey { guild_id, command: command.to_string(), scope: rule.scope, owner };
+                (key, Duration::seconds(rule.period as i64), rule.uses)
+            })
+            .collect();
+        // The buckets stay locked until the use is counted : concurrent uses can't both take the last one
+        let mut buckets = self.buckets.lock().unwrap();
+        let wait = keys.iter()
+            .filter_map(|(key, period, uses)| {
+                let bucket = buckets.get_mut(key)?;
+                bucket.period = *period;
+                while bucket.uses.front().is_some_and(|used| *used + *period <= now) {
+                    bucket.uses.pop_front();
+                }
+                // The oldest use counted must expire for a new one to fit
+                let blocking = bucket.uses.len().checked_sub(*uses as usize)?;
+                Some(bucket.uses[blocking] + *period - now)
+            })
+            .max();
+
+        // 4 - Refuse the use
+        if let Some(wait) = wait {
+            return Err(BotError::Cooldown((wait.num_milliseconds() as u64).div_ceil(1000)));
+        }
+
+        // 5 - Count the use in every bucket
+        if buckets.len() > BUCKETS_CLEANUP_THRESHOLD {
+            buckets.retain(|_, bucket| bucket.uses.back().is_some_and(|used| *used + bucket.period > now));
+        }
+        for (key, period, _) in keys {
+            buckets.entry(key)
+                .or_insert_with(|| Bucket { period, uses: VecDeque::new() })
+                .uses.push_back(now);
+        }
+        Ok(())
+    }
+}
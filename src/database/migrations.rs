@@ -0,0 +1,408 @@
+use std::fmt::{Display, Formatter};
+use chrono::Utc;
+use log::info;
+use rusqlite::{OptionalExtension, params, Transaction};
+use crate::database::pool::DatabasePool;
+
+/*
+This structure is given to every migration.
+It holds values that can't be found in the database.
+ */
+#[derive(Clone, Copy)]
+pub struct MigrationContext {
+    pub home_guild: Option<u64>, // the guild that owns data created before the multi-guild support
+}
+
+/*
+This structure describe a single change of the database schema.
+Migrations are applied in the order of their version, each one
+in its own transaction, and are never modified once released :
+add a new migration instead.
+ */
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    up: fn(&Transaction, &MigrationContext) -> Result<(), MigrationError>,
+}
+
+/*
+This enum describe every reason the migration of
+the database can fail.
+ */
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(rusqlite::Error), // a query failed
+    Connection(tokio_rusqlite::Error), // the connection to the database failed
+    #[cfg(feature = "postgres")]
+    Postgres(tokio_postgres::Error), // a query on the PostgreSQL database failed
+    DatabaseTooNew { database: u32, binary: u32 }, // the database has been migrated by a newer version of the bot
+    MissingHomeGuild, // existing data can't be scoped by guild without HOME_GUILD_ID
+}
+
+impl Display for MigrationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationError::Database(err) => write!(f, "Database error: {}", err),
+            MigrationError::Connection(err) => write!(f, "Connection error: {}", err),
+            #[cfg(feature = "postgres")]
+            MigrationError::Postgres(err) => write!(f, "PostgreSQL error: {}", err),
+            MigrationError::DatabaseTooNew { database, binary } => write!(f,
+                "The database schema is at version {} but this version of the bot only knows versions up to {}. Update the bot before using this database.",
+                database, binary
+            ),
+            MigrationError::MissingHomeGuild => write!(f,
+                "The database contains data created before the multi-guild support. Set HOME_GUILD_ID in the .env file to the server that owns it."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(err: rusqlite::Error) -> Self {
+        MigrationError::Database(err)
+    }
+}
+
+impl From<tokio_rusqlite::Error> for MigrationError {
+    fn from(err: tokio_rusqlite::Error) -> Self {
+        match err {
+            tokio_rusqlite::Error::Rusqlite(err) => MigrationError::Database(err),
+            err => MigrationError::Connection(err),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<tokio_postgres::Error> for MigrationError {
+    fn from(err: tokio_postgres::Error) -> Self {
+        MigrationError::Postgres(err)
+    }
+}
+
+/**
+ * Every migration of the database, ordered by version.
+ * Databases created before the schema_version table existed
+ * start at version 0 : the first migrations don't fail on
+ * tables that already exist.
+ */
+pub static MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "create tips and scheduler_config tables", up: create_tips_and_scheduler_config },
+    Migration { version: 2, name: "create tip_posts table", up: create_tip_posts },
+    Migration { version: 3, name: "scope tips and scheduler_config by guild", up: scope_by_guild },
+    Migration { version: 4, name: "create permissions table", up: create_permissions },
+    Migration { version: 5, name: "create audit_log and audit_mirror tables", up: create_audit_log },
+    Migration { version: 6, name: "create cooldowns table", up: create_cooldowns },
+    Migration { version: 7, name: "create command_prefixes table", up: create_command_prefixes },
+    Migration { version: 8, name: "add the webhook persona to scheduler_config", up: add_scheduler_persona },
+];
+
+/**
+ * This method return the version of the most recent migration known by the bot.
+ *
+ * @return u32
+ */
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/**
+ * This method return the version of the database schema, without modifying it.
+ *
+ * @param conn: &rusqlite::Connection, the database access
+ *
+ * @return Result<u32, rusqlite::Error>, 0 if no migration has been applied or the schema_version table doesn't exist
+ */
+fn read_version(conn: &rusqlite::Connection) -> Result<u32, rusqlite::Error> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')", [], |row| row.get(0)
+    )?;
+    if !exists {
+        return Ok(0);
+    }
+    let version: Option<u32> = conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0)).optional()?.flatten();
+    Ok(version.unwrap_or(0))
+}
+
+/**
+ * This method return the version of the database schema.
+ * The schema_version table is created if it doesn't exist yet.
+ *
+ * @param conn: &rusqlite::Connection, the database access
+ *
+ * @return Result<u32, rusqlite::Error>, 0 if no migration has been applied
+ */
+fn current_version(conn: &rusqlite::Connection) -> Result<u32, rusqlite::Error> {
+    conn.execute_batch(
+    "
+        CREATE TABLE IF NOT EXISTS schema_version (
+              version INTEGER PRIMARY KEY,
+              name TEXT NOT NULL,
+              applied_at TEXT NOT NULL
+        );
+        "
+    )?;
+    read_version(conn)
+}
+
+/**
+ * This method return the migrations not applied yet on the database,
+ * without applying them. It's used for dry-runs : the database is not modified.
+ *
+ * @param database: DatabasePool, the database access
+ *
+ * @return Result<Vec<&'static Migration>, MigrationError>
+ */
+pub async fn pending_migrations(database: DatabasePool) -> Result<Vec<&'static Migration>, MigrationError> {
+    let version = database.read(|conn| read_version(conn)).await?;
+    if version > latest_version() {
+        return Err(MigrationError::DatabaseTooNew { database: version, binary: latest_version() });
+    }
+    Ok(MIGRATIONS.iter().filter(|migration| migration.version > version).collect())
+}
+
+/**
+ * This method apply every pending migration on the database.
+ * Each migration run in its own transaction and is recorded
+ * in the schema_version table. It refuses to touch a database
+ * migrated by a newer version of the bot.
+ *
+ * @param database: DatabasePool, the database access
+ * @param context: MigrationContext, values needed by migrations
+ *
+ * @return Result<u32, MigrationError>, the version of the database schema
+ */
+pub async fn migrate(database: DatabasePool, context: MigrationContext) -> Result<u32, MigrationError> {
+    database.write(move |conn| {
+        Ok(apply_pending(conn, &context))
+    }).await?
+}
+
+/**
+ * This method is the synchronous part of migrate.
+ *
+ * @param conn: &mut rusqlite::Connection, the database access
+ * @param context: &MigrationContext, values needed by migrations
+ *
+ * @return Result<u32, MigrationError>, the version of the database schema
+ */
+fn apply_pending(conn: &mut rusqlite::Connection, context: &MigrationContext) -> Result<u32, MigrationError> {
+    let initial_version = current_version(conn)?;
+    if initial_version > latest_version() {
+        return Err(MigrationError::DatabaseTooNew { database: initial_version, binary: latest_version() });
+    }
+
+    let mut version = initial_version;
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > initial_version) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx, context)?;
+        tx.execute(
+            "INSERT INTO schema_version (version, name, applied_at) VALUES (?1,?2,?3)",
+            params![migration.version, migration.name, Utc::now().to_rfc3339()]
+        )?;
+        tx.commit()?;
+
+        info!("Database migration {} applied : {}", migration.version, migration.name);
+        version = migration.version;
+    }
+    Ok(version)
+}
+
+/*
+ * Migration 1 : the original schema of the bot.
+ */
+fn create_tips_and_scheduler_config(tx: &Transaction, _context: &MigrationContext) -> Result<(), MigrationError> {
+    tx.execute_batch(
+    "
+        CREATE TABLE IF NOT EXISTS tips (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              title TEXT NOT NULL UNIQUE,
+              content TEXT NOT NULL,
+              tags TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS scheduler_config (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              channel INTEGER NOT NULL,
+              hour INTEGER NOT NULL,
+              minute INTEGER NOT NULL
+        );
+        "
+    )?;
+    Ok(())
+}
+
+/*
+ * Migration 2 : remember where tips have been posted.
+ */
+fn create_tip_posts(tx: &Transaction, _context: &MigrationContext) -> Result<(), MigrationError> {
+    tx.execute_batch(
+    "
+        CREATE TABLE IF NOT EXISTS tip_posts (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              tip_id INTEGER NOT NULL,
+              channel INTEGER NOT NULL,
+              message INTEGER NOT NULL
+        );
+        "
+    )?;
+    Ok(())
+}
+
+/*
+ * Migration 3 : rebuild tips and scheduler_config with a guild_id column.
+ * Every existing row is given to the home guild.
+ */
+fn scope_by_guild(tx: &Transaction, context: &MigrationContext) -> Result<(), MigrationError> {
+    // A table that doesn't have the guild_id column yet
+    let is_legacy = |table: &str| -> Result<bool, rusqlite::Error> {
+        let guild_column: usize = tx.query_row("SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = 'guild_id'", params![table], |row| row.get(0))?;
+        Ok(guild_column == 0)
+    };
+    let legacy_tips = is_legacy("tips")?;
+    let legacy_config = is_legacy("scheduler_config")?;
+    if !legacy_tips && !legacy_config {
+        return Ok(());
+    }
+
+    // Existing data can't be scoped without knowing its guild
+    let rows: usize = tx.query_row("SELECT (SELECT COUNT(*) FROM tips) + (SELECT COUNT(*) FROM scheduler_config)", [], |row| row.get(0))?;
+    let home_guild = match (context.home_guild, rows) {
+        (Some(guild), _) => guild,
+        (None, 0) => 0, // nothing to assign
+        (None, _) => return Err(MigrationError::MissingHomeGuild),
+    };
+
+    if legacy_tips {
+        tx.execute_batch(
+        "
+            ALTER TABLE tips RENAME TO tips_legacy;
+            CREATE TABLE tips (
+                  id INTEGER PRIMARY KEY AUTOINCREMENT,
+                  guild_id INTEGER NOT NULL,
+                  title TEXT NOT NULL,
+                  content TEXT NOT NULL,
+                  tags TEXT,
+                  UNIQUE (guild_id, title)
+            );
+            "
+        )?;
+        tx.execute("INSERT INTO tips (id, guild_id, title, content, tags) SELECT id, ?1, title, content, tags FROM tips_legacy", params![home_guild])?;
+        tx.execute("DROP TABLE tips_legacy", [])?;
+    }
+    if legacy_config {
+        tx.execute_batch(
+        "
+            ALTER TABLE scheduler_config RENAME TO scheduler_config_legacy;
+            CREATE TABLE scheduler_config (
+                  id INTEGER PRIMARY KEY AUTOINCREMENT,
+                  guild_id INTEGER NOT NULL UNIQUE,
+                  channel INTEGER NOT NULL,
+                  hour INTEGER NOT NULL,
+                  minute INTEGER NOT NULL
+            );
+            "
+        )?;
+        // Only one configuration existed: keep the first one
+        tx.execute("INSERT INTO scheduler_config (guild_id, channel, hour, minute) SELECT ?1, channel, hour, minute FROM scheduler_config_legacy ORDER BY id LIMIT 1", params![home_guild])?;
+        tx.execute("DROP TABLE scheduler_config_legacy", [])?;
+    }
+
+    if rows > 0 {
+        info!("Existing tips and scheduler configuration have been assigned to the guild {}", home_guild);
+    }
+    Ok(())
+}
+
+/*
+ * Migration 4 : the capabilities granted to roles with /permissions.
+ */
+fn create_permissions(tx: &Transaction, _context: &MigrationContext) -> Result<(), MigrationError> {
+    tx.execute_batch(
+    "
+        CREATE TABLE IF NOT EXISTS permissions (
+              guild_id INTEGER NOT NULL,
+              role_id INTEGER NOT NULL,
+              capability TEXT NOT NULL,
+              PRIMARY KEY (guild_id, role_id, capability)
+        );
+        "
+    )?;
+    Ok(())
+}
+
+/*
+ * Migration 5 : the changes made by commands, and where they are mirrored.
+ */
+fn create_audit_log(tx: &Transaction, _context: &MigrationContext) -> Result<(), MigrationError> {
+    tx.execute_batch(
+    "
+        CREATE TABLE IF NOT EXISTS audit_log (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              guild_id INTEGER NOT NULL,
+              actor INTEGER NOT NULL,
+              command TEXT NOT NULL,
+              arguments TEXT NOT NULL,
+              before_value TEXT,
+              after_value TEXT,
+              created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS audit_log_guild_id ON audit_log (guild_id, created_at);
+
+        CREATE TABLE IF NOT EXISTS audit_mirror (
+              guild_id INTEGER PRIMARY KEY,
+              channel INTEGER NOT NULL
+        );
+        "
+    )?;
+    Ok(())
+}
+
+/*
+ * Migration 6 : the cooldown rules of the commands, set with /cooldowns.
+ */
+fn create_cooldowns(tx: &Transaction, _context: &MigrationContext) -> Result<(), MigrationError> {
+    tx.execute_batch(
+    "
+        CREATE TABLE IF NOT EXISTS cooldowns (
+              guild_id INTEGER NOT NULL,
+              command TEXT NOT NULL,
+              scope TEXT NOT NULL,
+              uses INTEGER NOT NULL,
+              period INTEGER NOT NULL,
+              PRIMARY KEY (guild_id, command, scope)
+        );
+        "
+    )?;
+    Ok(())
+}
+
+/*
+ * Migration 7 : the prefix of the text commands of each guild, set with /prefix.
+ */
+fn create_command_prefixes(tx: &Transaction, _context: &MigrationContext) -> Result<(), MigrationError> {
+    tx.execute_batch(
+    "
+        CREATE TABLE IF NOT EXISTS command_prefixes (
+              guild_id INTEGER PRIMARY KEY,
+              prefix TEXT NOT NULL
+        );
+        "
+    )?;
+    Ok(())
+}
+
+/*
+ * Migration 8 : the name and the avatar of the webhook posting the tips, set with /scheduler_config.
+ * Without name, the tips are posted by the bot.
+ */
+fn add_scheduler_persona(tx: &Transaction, _context: &MigrationContext) -> Result<(), MigrationError> {
+    tx.execute_batch(
+    "
+        ALTER TABLE scheduler_config ADD COLUMN persona_name TEXT;
+        ALTER TABLE scheduler_config ADD COLUMN persona_avatar TEXT;
+        "
+    )?;
+    Ok(())
+}
@@ -0,0 +1,265 @@
+pub mod migrations;
+pub mod pool;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod url;
+
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::sync::Arc;
+use log::info;
+use crate::backup::Backups;
+use crate::config::BackupConfig;
+use crate::database::migrations::{MigrationContext, MigrationError};
+use crate::database::pool::{DatabasePool, DEFAULT_READERS};
+use crate::database::url::DatabaseUrl;
+use crate::repositories::{AuditRepository, CooldownRepository, PermissionRepository, PrefixRepository, SchedulerRepository, TipRepository};
+use crate::repositories::sqlite::{SqliteAuditRepository, SqliteCooldownRepository, SqlitePermissionRepository, SqlitePrefixRepository, SqliteSchedulerRepository, SqliteTipRepository};
+
+/*
+This enum is the storage backend selected by DATABASE_URL.
+Every backend has its own migrations and repositories.
+ */
+#[derive(Clone)]
+pub enum Database {
+    Sqlite(DatabasePool),
+    #[cfg(feature = "postgres")]
+    Postgres(postgres::PostgresDatabase),
+}
+
+/*
+This enum describe every reason the connection to the database can fail.
+ */
+#[derive(Debug)]
+pub enum ConnectionError {
+    Sqlite(tokio_rusqlite::Error),
+    MissingFile(PathBuf), // a read-only connection doesn't create the sqlite file
+    #[cfg(feature = "postgres")]
+    Postgres(tokio_postgres::Error),
+}
+
+impl Display for ConnectionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionError::Sqlite(err) => write!(f, "{}", err),
+            ConnectionError::MissingFile(path) => write!(f, "The database file {} doesn't exist.", path.display()),
+            #[cfg(feature = "postgres")]
+            ConnectionError::Postgres(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {}
+
+impl Database {
+    /**
+     * This method open the database given by the url.
+     *
+     * @param url: &DatabaseUrl, the database to open
+     *
+     * @return Result<Database, ConnectionError>
+     */
+    pub async fn connect(url: &DatabaseUrl) -> Result<Database, ConnectionError> {
+        match url {
+            DatabaseUrl::Sqlite(path) => DatabasePool::open(path, DEFAULT_READERS).await
+                .map(Database::Sqlite)
+                .map_err(ConnectionError::Sqlite),
+            #[cfg(feature = "postgres")]
+            DatabaseUrl::Postgres(url) => postgres::PostgresDatabase::connect(url).await
+                .map(Database::Postgres)
+                .map_err(ConnectionError::Postgres),
+        }
+    }
+
+    /**
+     * This method open the database given by the url without modifying it,
+     * to look at its state. A missing sqlite file is an error instead of being created.
+     *
+     * @param url: &DatabaseUrl, the database to open
+     *
+     * @return Result<Database, ConnectionError>
+     */
+    pub async fn connect_read_only(url: &DatabaseUrl) -> Result<Database, ConnectionError> {
+        match url {
+            DatabaseUrl::Sqlite(path) if !path.is_file() => Err(ConnectionError::MissingFile(path.clone())),
+            DatabaseUrl::Sqlite(path) => DatabasePool::open_read_only(path).await
+                .map(Database::Sqlite)
+                .map_err(ConnectionError::Sqlite),
+            // Connecting to postgres doesn't modify the database
+            #[cfg(feature = "postgres")]
+            DatabaseUrl::Postgres(_) => Database::connect(url).await,
+        }
+    }
+
+    /**
+     * This method return the version of the most recent migration of the backend.
+     *
+     * @return u32
+     */
+    pub fn latest_version(&self) -> u32 {
+        match self {
+            Database::Sqlite(_) => migrations::latest_version(),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(_) => postgres::latest_version(),
+        }
+    }
+
+    /**
+     * This method return the version and the name of the migrations
+     * not applied yet, without applying them.
+     *
+     * @return Result<Vec<(u32, &'static str)>, MigrationError>
+     */
+    pub async fn pending_migrations(&self) -> Result<Vec<(u32, &'static str)>, MigrationError> {
+        match self {
+            Database::Sqlite(pool) => Ok(migrations::pending_migrations(pool.clone()).await?
+                .into_iter().map(|migration| (migration.version, migration.name)).collect()),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => Ok(postgres::pending_migrations(database).await?
+                .into_iter().map(|migration| (migration.version, migration.name)).collect()),
+        }
+    }
+
+    /**
+     * This method apply every pending migration.
+     *
+     * @param context: MigrationContext, values needed by migrations
+     *
+     * @return Result<u32, MigrationError>, the version of the database schema
+     */
+    pub async fn migrate(&self, context: MigrationContext) -> Result<u32, MigrationError> {
+        match self {
+            Database::Sqlite(pool) => migrations::migrate(pool.clone(), context).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => postgres::migrate(database).await,
+        }
+    }
+
+    /**
+     * This method return the storage of tips of the backend.
+     *
+     * @return Arc<dyn TipRepository>
+     */
+    pub fn tip_repository(&self) -> Arc<dyn TipRepository> {
+        match self {
+            Database::Sqlite(pool) => Arc::new(SqliteTipRepository::new(pool.clone())),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => Arc::new(crate::repositories::postgres::PostgresTipRepository::new(database.clone())),
+        }
+    }
+
+    /**
+     * This method return the storage of scheduler configurations of the backend.
+     *
+     * @return Arc<dyn SchedulerRepository>
+     */
+    pub fn scheduler_repository(&self) -> Arc<dyn SchedulerRepository> {
+        match self {
+            Database::Sqlite(pool) => Arc::new(SqliteSchedulerRepository::new(pool.clone())),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => Arc::new(crate::repositories::postgres::PostgresSchedulerRepository::new(database.clone())),
+        }
+    }
+
+    /**
+     * This method return the storage of the capabilities granted to roles.
+     *
+     * @return Arc<dyn PermissionRepository>
+     */
+    pub fn permission_repository(&self) -> Arc<dyn PermissionRepository> {
+        match self {
+            Database::Sqlite(pool) => Arc::new(SqlitePermissionRepository::new(pool.clone())),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => Arc::new(crate::repositories::postgres::PostgresPermissionRepository::new(database.clone())),
+        }
+    }
+
+    /**
+     * This method return the storage of the audit log.
+     *
+     * @return Arc<dyn AuditRepository>
+     */
+    pub fn audit_repository(&self) -> Arc<dyn AuditRepository> {
+        match self {
+            Database::Sqlite(pool) => Arc::new(SqliteAuditRepository::new(pool.clone())),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => Arc::new(crate::repositories::postgres::PostgresAuditRepository::new(database.clone())),
+        }
+    }
+
+    /**
+     * This method return the storage of the cooldown rules.
+     *
+     * @return Arc<dyn CooldownRepository>
+     */
+    pub fn cooldown_repository(&self) -> Arc<dyn CooldownRepository> {
+        match self {
+            Database::Sqlite(pool) => Arc::new(SqliteCooldownRepository::new(pool.clone())),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => Arc::new(crate::repositories::postgres::PostgresCooldownRepository::new(database.clone())),
+        }
+    }
+
+    /**
+     * This method return the storage of the prefix of the text commands.
+     *
+     * @return Arc<dyn PrefixRepository>
+     */
+    pub fn prefix_repository(&self) -> Arc<dyn PrefixRepository> {
+        match self {
+            Database::Sqlite(pool) => Arc::new(SqlitePrefixRepository::new(pool.clone())),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => Arc::new(crate::repositories::postgres::PostgresPrefixRepository::new(database.clone())),
+        }
+    }
+
+    /**
+     * This method return the snapshots of the database. Only sqlite
+     * files are copied by the bot : PostgreSQL has its own tools.
+     *
+     * @param config: BackupConfig, the directory, the interval and the retention of the snapshots
+     *
+     * @return Option<Backups>, None if the backend can't be backed up
+     */
+    pub fn backups(&self, config: BackupConfig) -> Option<Backups> {
+        match self {
+            Database::Sqlite(pool) => Some(Backups::new(pool.clone(), config)),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(_) => None,
+        }
+    }
+
+    /**
+     * This method close the database when the bot stops.
+     * Repositories can't be used anymore.
+     *
+     * @return Result<(), ConnectionError>
+     */
+    pub async fn close(&self) -> Result<(), ConnectionError> {
+        match self {
+            Database::Sqlite(pool) => pool.close().await.map_err(ConnectionError::Sqlite),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(database) => {
+                database.close();
+                Ok(())
+            }
+        }
+    }
+}
+
+/**
+ * This method execute migration on the database
+ * to ensure every table are created before the bot
+ * use it. See migrations.rs for the list of migrations.
+ *
+ * @param database: &Database, the database access
+ * @param home_guild: Option<u64>, the guild that owns data created before the multi-guild support
+ *
+ * @return Result<(), MigrationError>
+ */
+pub async fn run_migrations(database: &Database, home_guild: Option<u64>) -> Result<(), MigrationError> {
+    let version = database.migrate(MigrationContext { home_guild }).await?;
+
+    info!("Database has been migrated successfully (schema version {})", version);
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use rusqlite::OpenFlags;
+use tokio_rusqlite::Connection;
+
+// Number of reader connections opened by default
+pub const DEFAULT_READERS: usize = 4;
+// Time a connection waits for a lock held by another connection before failing with SQLITE_BUSY
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * This structure give access to the sqlite database.
+ * The database is in WAL mode : readers don't block the writer
+ * and the writer doesn't block readers.
+ *
+ * Every connection run queries on its own thread. Writes are sent to
+ * the single writer connection, so they never compete for the lock, and
+ * reads are spread across a small pool of read-only connections.
+ * Cloning the pool is cheap : clones share the same connections.
+ */
+#[derive(Clone)]
+pub struct DatabasePool {
+    writer: Connection,
+    readers: Arc<Vec<Connection>>,
+    next_reader: Arc<AtomicUsize>,
+}
+
+impl DatabasePool {
+    /**
+     * This method open the database file, creating it if required,
+     * with one writer connection and the given number of readers.
+     *
+     * @param path: impl AsRef<Path>, the path of the database file
+     * @param readers: usize, the number of reader connections. With 0, reads are run by the writer.
+     *
+     * @return Result<DatabasePool, tokio_rusqlite::Error>
+     */
+    pub async fn open(path: impl AsRef<Path>, readers: usize) -> Result<DatabasePool, tokio_rusqlite::Error> {
+        // 1 - The writer creates the file and switch it in WAL mode (saved in the file)
+        let writer = Connection::open(path.as_ref()).await?;
+        writer.call(|conn| {
+            conn.busy_timeout(BUSY_TIMEOUT)?;
+            conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+            conn.pragma_update(None, "synchronous", "NORMAL")?;
+            Ok(())
+        }).await?;
+
+        // 2 - Readers can't modify the database
+        let mut reader_connections = Vec::with_capacity(readers);
+        for _ in 0..readers {
+            let reader = Connection::open_with_flags(
+                path.as_ref(),
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            ).await?;
+            reader.call(|conn| conn.busy_timeout(BUSY_TIMEOUT)).await?;
+            reader_connections.push(reader);
+        }
+
+        Ok(DatabasePool {
+            writer,
+            readers: Arc::new(reader_connections),
+            next_reader: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /**
+     * This method open an existing database file without modifying it : the file
+     * isn't created and its journal mode is kept. The single connection is read-only,
+     * so every write fails.
+     *
+     * @param path: impl AsRef<Path>, the path of the database file
+     *
+     * @return Result<DatabasePool, tokio_rusqlite::Error>
+     */
+    pub async fn open_read_only(path: impl AsRef<Path>) -> Result<DatabasePool, tokio_rusqlite::Error> {
+        let connection = Connection::open_with_flags(
+            path.as_ref(),
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        ).await?;
+        connection.call(|conn| conn.busy_timeout(BUSY_TIMEOUT)).await?;
+
+        // Without readers, the reads are run by this connection
+        Ok(DatabasePool {
+            writer: connection,
+            readers: Arc::new(Vec::new()),
+            next_reader: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /**
+     * This method run a query that doesn't modify the database
+     * on the next reader connection.
+     *
+     * @param function: FnOnce(&mut rusqlite::Connection) -> rusqlite::Result<R>, the queries to run
+     *
+     * @return Result<R, tokio_rusqlite::Error>
+     */
+    pub async fn read<F, R>(&self, function: F) -> Result<R, tokio_rusqlite::Error>
+    where
+        F: FnOnce(&mut rusqlite::Connection) -> rusqlite::Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        if self.readers.is_empty() {
+            return self.writer.call(function).await;
+        }
+        // Readers are used in turn
+        let index = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        self.readers[index].call(function).await
+    }
+
+    /**
+     * This method run queries that modify the database on the writer connection.
+     * Writes are run one after the other, in the order they are received.
+     *
+     * @param function: FnOnce(&mut rusqlite::Connection) -> rusqlite::Result<R>, the queries to run
+     *
+     * @return Result<R, tokio_rusqlite::Error>
+     */
+    pub async fn write<F, R>(&self, function: F) -> Result<R, tokio_rusqlite::Error>
+    where
+        F: FnOnce(&mut rusqlite::Connection) -> rusqlite::Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.writer.call(function).await
+    }
+
+    /**
+     * This method close every connection. The writer is closed last :
+     * the last connection write the WAL back in the database file.
+     * Queries run after it fail with ConnectionClosed.
+     *
+     * @return Result<(), tokio_rusqlite::Error>
+     */
+    pub async fn close(&self) -> Result<(), tokio_rusqlite::Error> {
+        for reader in self.readers.iter() {
+            reader.clone().close().await?;
+        }
+        self.writer.clone().close().await
+    }
+}
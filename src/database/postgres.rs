@@ -0,0 +1,278 @@
+use std::sync::Arc;
+use chrono::Utc;
+use log::{error, info};
+use tokio::task::JoinHandle;
+use tokio_postgres::{Client, NoTls};
+use crate::database::migrations::MigrationError;
+
+/**
+ * This structure give access to a PostgreSQL database.
+ * The client pipeline queries sent concurrently on the same
+ * connection, so it's shared without lock.
+ */
+#[derive(Clone)]
+pub struct PostgresDatabase {
+    client: Arc<Client>,
+    connection: Arc<JoinHandle<()>>, // the task driving the connection
+}
+
+impl PostgresDatabase {
+    /**
+     * This method connect to the database. The connection
+     * is driven by a task running in the background.
+     *
+     * @param url: &str, the postgres:// url of the database
+     *
+     * @return Result<PostgresDatabase, tokio_postgres::Error>
+     */
+    pub async fn connect(url: &str) -> Result<PostgresDatabase, tokio_postgres::Error> {
+        let (client, connection) = tokio_postgres::connect(url, NoTls).await?;
+        let connection = tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                error!("The connection to the PostgreSQL database has been lost. Error:\n{}", err);
+            }
+        });
+        Ok(PostgresDatabase { client: Arc::new(client), connection: Arc::new(connection) })
+    }
+
+    /**
+     * This method close the connection. Queries run after it fail.
+     */
+    pub fn close(&self) {
+        self.connection.abort();
+    }
+
+    /**
+     * This method return the client used to run queries.
+     *
+     * @return &Client
+     */
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+/*
+This structure describe a single change of the PostgreSQL schema.
+PostgreSQL databases didn't exist before the multi-guild support :
+the first migration creates the schema of the version 3 of sqlite.
+ */
+pub struct PostgresMigration {
+    pub version: u32,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+/**
+ * Every migration of the PostgreSQL database, ordered by version.
+ */
+pub static MIGRATIONS: &[PostgresMigration] = &[
+    PostgresMigration {
+        version: 1,
+        name: "create tips, tip_posts and scheduler_config tables",
+        sql: "
+            CREATE TABLE tips (
+                  id BIGSERIAL PRIMARY KEY,
+                  guild_id BIGINT NOT NULL,
+                  title TEXT NOT NULL,
+                  content TEXT NOT NULL,
+                  tags TEXT NOT NULL DEFAULT '',
+                  UNIQUE (guild_id, title)
+            );
+
+            CREATE TABLE tip_posts (
+                  id BIGSERIAL PRIMARY KEY,
+                  tip_id BIGINT NOT NULL,
+                  channel BIGINT NOT NULL,
+                  message BIGINT NOT NULL
+            );
+            CREATE INDEX tip_posts_tip_id ON tip_posts (tip_id);
+
+            CREATE TABLE scheduler_config (
+                  id BIGSERIAL PRIMARY KEY,
+                  guild_id BIGINT NOT NULL UNIQUE,
+                  channel BIGINT NOT NULL,
+                  hour INTEGER NOT NULL,
+                  minute INTEGER NOT NULL
+            );
+        ",
+    },
+    PostgresMigration {
+        version: 2,
+        name: "create permissions table",
+        sql: "
+            CREATE TABLE permissions (
+                  guild_id BIGINT NOT NULL,
+                  role_id BIGINT NOT NULL,
+                  capability TEXT NOT NULL,
+                  PRIMARY KEY (guild_id, role_id, capability)
+            );
+        ",
+    },
+    PostgresMigration {
+        version: 3,
+        name: "create audit_log and audit_mirror tables",
+        sql: "
+            CREATE TABLE audit_log (
+                  id BIGSERIAL PRIMARY KEY,
+                  guild_id BIGINT NOT NULL,
+                  actor BIGINT NOT NULL,
+                  command TEXT NOT NULL,
+                  arguments TEXT NOT NULL,
+                  before_value TEXT,
+                  after_value TEXT,
+                  created_at TEXT NOT NULL
+            );
+            CREATE INDEX audit_log_guild_id ON audit_log (guild_id, created_at);
+
+            CREATE TABLE audit_mirror (
+                  guild_id BIGINT PRIMARY KEY,
+                  channel BIGINT NOT NULL
+            );
+        ",
+    },
+    PostgresMigration {
+        version: 4,
+        name: "create cooldowns table",
+        sql: "
+            CREATE TABLE cooldowns (
+                  guild_id BIGINT NOT NULL,
+                  command TEXT NOT NULL,
+                  scope TEXT NOT NULL,
+                  uses INTEGER NOT NULL,
+                  period BIGINT NOT NULL,
+                  PRIMARY KEY (guild_id, command, scope)
+            );
+        ",
+    },
+    PostgresMigration {
+        version: 5,
+        name: "create command_prefixes table",
+        sql: "
+            CREATE TABLE command_prefixes (
+                  guild_id BIGINT PRIMARY KEY,
+                  prefix TEXT NOT NULL
+            );
+        ",
+    },
+    PostgresMigration {
+        version: 6,
+        name: "add the webhook persona to scheduler_config",
+        sql: "
+            ALTER TABLE scheduler_config ADD COLUMN persona_name TEXT;
+            ALTER TABLE scheduler_config ADD COLUMN persona_avatar TEXT;
+        ",
+    },
+];
+
+/**
+ * This method return the version of the most recent migration known by the bot.
+ *
+ * @return u32
+ */
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/**
+ * This method return the version of the database schema.
+ * The schema_version table is created if it doesn't exist yet.
+ *
+ * @param client: &Client, the database access
+ *
+ * @return Result<u32, tokio_postgres::Error>, 0 if no migration has been applied
+ */
+async fn current_version(client: &Client) -> Result<u32, tokio_postgres::Error> {
+    create_schema_version(client).await?;
+    read_version(client).await
+}
+
+/**
+ * This method create the schema_version table if it doesn't exist yet.
+ *
+ * @param client: &Client, the database access
+ *
+ * @return Result<(), tokio_postgres::Error>
+ */
+async fn create_schema_version(client: &Client) -> Result<(), tokio_postgres::Error> {
+    client.batch_execute(
+    "
+        CREATE TABLE IF NOT EXISTS schema_version (
+              version INTEGER PRIMARY KEY,
+              name TEXT NOT NULL,
+              applied_at TEXT NOT NULL
+        );
+        "
+    ).await
+}
+
+/**
+ * This method return the version of the database schema, without modifying it.
+ *
+ * @param client: &Client, the database access
+ *
+ * @return Result<u32, tokio_postgres::Error>, 0 if no migration has been applied or the schema_version table doesn't exist
+ */
+async fn read_version(client: &Client) -> Result<u32, tokio_postgres::Error> {
+    let exists: bool = client.query_one("SELECT to_regclass('schema_version') IS NOT NULL", &[]).await?.get(0);
+    if !exists {
+        return Ok(0);
+    }
+    let version: Option<i32> = client.query_one("SELECT MAX(version) FROM schema_version", &[]).await?.get(0);
+    Ok(version.unwrap_or(0) as u32)
+}
+
+/**
+ * This method return the migrations not applied yet on the database,
+ * without applying them. It's used for dry-runs.
+ *
+ * @param database: &PostgresDatabase, the database access
+ *
+ * @return Result<Vec<&'static PostgresMigration>, MigrationError>
+ */
+pub async fn pending_migrations(database: &PostgresDatabase) -> Result<Vec<&'static PostgresMigration>, MigrationError> {
+    let version = read_version(database.client()).await?;
+    if version > latest_version() {
+        return Err(MigrationError::DatabaseTooNew { database: version, binary: latest_version() });
+    }
+    Ok(MIGRATIONS.iter().filter(|migration| migration.version > version).collect())
+}
+
+/**
+ * This method apply every pending migration on the database.
+ * Each migration run in its own transaction and is recorded
+ * in the schema_version table.
+ *
+ * @param database: &PostgresDatabase, the database access
+ *
+ * @return Result<u32, MigrationError>, the version of the database schema
+ */
+pub async fn migrate(database: &PostgresDatabase) -> Result<u32, MigrationError> {
+    let client = database.client();
+    let initial_version = current_version(client).await?;
+    if initial_version > latest_version() {
+        return Err(MigrationError::DatabaseTooNew { database: initial_version, binary: latest_version() });
+    }
+
+    let mut version = initial_version;
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > initial_version) {
+        // Nothing else use the client while migrations run : the transaction is driven by hand
+        client.batch_execute("BEGIN").await?;
+        let applied = async {
+            client.batch_execute(migration.sql).await?;
+            client.execute(
+                "INSERT INTO schema_version (version, name, applied_at) VALUES ($1,$2,$3)",
+                &[&(migration.version as i32), &migration.name, &Utc::now().to_rfc3339()]
+            ).await?;
+            client.batch_execute("COMMIT").await
+        }.await;
+        if let Err(err) = applied {
+            client.batch_execute("ROLLBACK").await?;
+            return Err(err.into());
+        }
+
+        info!("Database migration {} applied : {}", migration.version, migration.name);
+        version = migration.version;
+    }
+    Ok(version)
+}
@@ -0,0 +1,233 @@
+use std::fmt::{Display, Formatter};
+use log::error;
+use rand::{Rng, thread_rng};
+use serenity::builder::CreateEmbed;
+use serenity::model::Timestamp;
+use serenity::utils::Color;
+use crate::backup::BackupError;
+use crate::commands::options::OptionError;
+use crate::confirmations::ConfirmationError;
+use crate::locale::Locale;
+use crate::messenger::MessengerError;
+use crate::permissions::Capability;
+use crate::repositories::RepositoryError;
+
+/*
+This enum describe every error a command can return.
+User errors are explained to the user, internal errors
+are logged with an incident id and the user only sees the id.
+ */
+#[derive(Debug)]
+pub enum BotError {
+    // user errors
+    InvalidOption(OptionError), // an option given to the command is rejected
+    UnknownTip(u64), // there is no tip with this id in the guild
+    DuplicateTitle(String), // a tip of the guild already has this title
+    MissingCapability(Capability), // no role of the member has been granted the capability
+    ManagersOnly, // the command is reserved to the managers of the guild
+    Cooldown(u64), // the command has been used too often : seconds to wait before using it again
+    InvalidArguments { reason: String, usage: String }, // the arguments of a text command can't be read
+    BackupsUnavailable, // the database of the bot is not a sqlite file
+    Confirmation(ConfirmationError), // the button of a confirmation can't be answered
+    // internal errors
+    Repository(RepositoryError), // the storage failed
+    Discord(MessengerError), // discord refused a request
+    Backup(BackupError), // the snapshot of the database can't be written
+}
+
+impl BotError {
+    /**
+     * This method return true if the error is a failure of the bot
+     * and not a mistake of the user.
+     *
+     * @return bool
+     */
+    pub fn is_internal(&self) -> bool {
+        matches!(self, BotError::Repository(_) | BotError::Discord(_) | BotError::Backup(_))
+    }
+
+    /**
+     * This method log internal errors with a new incident id.
+     * User errors are not logged.
+     *
+     * @param source: &str, what was running when the error occurred, like the command name
+     *
+     * @return Option<String>, the incident id given to the user
+     */
+    pub fn report(&self, source: &str) -> Option<String> {
+        if !self.is_internal() {
+            return None;
+        }
+        let incident = new_incident_id();
+        error!("Incident {} in {}. Error:\n{}", incident, source, self);
+        Some(incident)
+    }
+
+    /**
+     * This method make the embed displayed to the user.
+     * Internal errors never show their details, only the incident id.
+     *
+     * @param locale: Locale, the language of the user
+     * @param incident: Option<&str>, the incident id returned by report
+     *
+     * @return CreateEmbed, the embed displayed in the response
+     */
+    pub fn to_embed(&self, locale: Locale, incident: Option<&str>) -> CreateEmbed {
+        let (title, description) = match (self, locale) {
+            (BotError::InvalidOption(err), Locale::English) => (format!("Invalid parameter  `{}`", err.option_name()), err.message(locale)),
+            (BotError::InvalidOption(err), Locale::French) => (format!("Paramètre invalide  `{}`", err.option_name()), err.message(locale)),
+            (BotError::UnknownTip(id), Locale::English) => (
+                String::from("Tip id unknown"),
+                format!("There is no tip with the id {} on this server. Use /tips_list to find the id of a tip.", id),
+            ),
+            (BotError::UnknownTip(id), Locale::French) => (
+                String::from("Astuce inconnue"),
+                format!("Il n'y a pas d'astuce avec l'id {} sur ce serveur. Utilise /tips_list pour trouver l'id d'une astuce.", id),
+            ),
+            (BotError::DuplicateTitle(title), Locale::English) => (
+                String::from("Title already used"),
+                format!("A tip with the title \"{}\" already exists. Choose another title or update the existing tip.", title),
+            ),
+            (BotError::DuplicateTitle(title), Locale::French) => (
+                String::from("Titre déjà utilisé"),
+                format!("Une astuce avec le titre \"{}\" existe déjà. Choisis un autre titre ou modifie l'astuce existante.", title),
+            ),
+            (BotError::MissingCapability(capability), Locale::English) => (
+                String::from("Permission denied"),
+                format!("None of your roles has been granted `{}`. Ask a manager of the server to run `/permissions`.", capability),
+            ),
+            (BotError::MissingCapability(capability), Locale::French) => (
+                String::from("Permission refusée"),
+                format!("Aucun de tes rôles n'a reçu `{}`. Demande à un gestionnaire du serveur d'utiliser `/permissions`.", capability),
+            ),
+            (BotError::ManagersOnly, Locale::English) => (
+                String::from("Permission denied"),
+                String::from("This command is reserved to the administrators and the members who can manage the server."),
+            ),
+            (BotError::ManagersOnly, Locale::French) => (
+                String::from("Permission refusée"),
+                String::from("Cette commande est réservée aux administrateurs et aux membres qui peuvent gérer le serveur."),
+            ),
+            (BotError::Cooldown(seconds), Locale::English) => (
+                String::from("Slow down"),
+                format!("This command has been used too often. Try again in {} s.", seconds),
+            ),
+            (BotError::Cooldown(seconds), Locale::French) => (
+                String::from("Doucement"),
+                format!("Cette commande a été trop utilisée. Réessaie dans {} s.", seconds),
+            ),
+            (BotError::InvalidArguments { reason, usage }, Locale::English) => (
+                String::from("Invalid arguments"),
+                format!("{}\nUsage : `{}`", reason, usage),
+            ),
+            (BotError::InvalidArguments { reason, usage }, Locale::French) => (
+                String::from("Arguments invalides"),
+                format!("{}\nUtilisation : `{}`", reason, usage),
+            ),
+            (BotError::BackupsUnavailable, Locale::English) => (
+                String::from("Backups unavailable"),
+                String::from("The bot can only write snapshots of a sqlite database. Back up the PostgreSQL database with its own tools, like pg_dump."),
+            ),
+            (BotError::BackupsUnavailable, Locale::French) => (
+                String::from("Sauvegardes indisponibles"),
+                String::from("Le bot ne peut sauvegarder qu'une base sqlite. Sauvegarde la base PostgreSQL avec ses propres outils, comme pg_dump."),
+            ),
+            (BotError::Confirmation(ConfirmationError::Expired), Locale::English) => (
+                String::from("Confirmation expired"),
+                String::from("Nothing has been changed. Run the command again to confirm it in time."),
+            ),
+            (BotError::Confirmation(ConfirmationError::Expired), Locale::French) => (
+                String::from("Confirmation expirée"),
+                String::from("Rien n'a été modifié. Relance la commande pour la confirmer à temps."),
+            ),
+            (BotError::Confirmation(ConfirmationError::NotYours), Locale::English) => (
+                String::from("Not your confirmation"),
+                String::from("Only the member who ran the command can confirm or cancel it."),
+            ),
+            (BotError::Confirmation(ConfirmationError::NotYours), Locale::French) => (
+                String::from("Pas ta confirmation"),
+                String::from("Seul le membre qui a lancé la commande peut la confirmer ou l'annuler."),
+            ),
+            (_, Locale::English) => (
+                String::from("Oups.. Something went wrong in the process :("),
+                format!("Please retry later. If it happens again, contact the administrator of the server with the incident id **`{}`**.", incident.unwrap_or("-")),
+            ),
+            (_, Locale::French) => (
+                String::from("Oups.. Quelque chose s'est mal passé :("),
+                format!("Réessaie plus tard. Si le problème persiste, contacte l'administrateur du serveur avec l'identifiant d'incident **`{}`**.", incident.unwrap_or("-")),
+            ),
+        };
+        let color = if self.is_internal() { Color::from_rgb(255, 0, 0) } else { Color::from_rgb(255, 102, 51) };
+
+        CreateEmbed::default()
+            .title(title)
+            .description(description)
+            .timestamp(Timestamp::now())
+            .color(color)
+            .to_owned()
+    }
+}
+
+impl Display for BotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BotError::InvalidOption(err) => write!(f, "{}", err),
+            BotError::UnknownTip(id) => write!(f, "Unknown tip {}.", id),
+            BotError::DuplicateTitle(title) => write!(f, "A tip with the title \"{}\" already exists.", title),
+            BotError::MissingCapability(capability) => write!(f, "The capability {} is required.", capability),
+            BotError::ManagersOnly => write!(f, "Only managers can run this command."),
+            BotError::Cooldown(seconds) => write!(f, "The command can be used again in {} s.", seconds),
+            BotError::InvalidArguments { reason, usage } => write!(f, "{} Usage: {}", reason, usage),
+            BotError::Repository(err) => write!(f, "Storage error: {}", err),
+            BotError::BackupsUnavailable => write!(f, "Backups are only available with a sqlite database."),
+            BotError::Confirmation(ConfirmationError::Expired) => write!(f, "The confirmation has expired."),
+            BotError::Confirmation(ConfirmationError::NotYours) => write!(f, "The confirmation belongs to another user."),
+            BotError::Discord(err) => write!(f, "Discord error: {}", err),
+            BotError::Backup(err) => write!(f, "Backup error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
+
+impl From<ConfirmationError> for BotError {
+    fn from(err: ConfirmationError) -> Self {
+        BotError::Confirmation(err)
+    }
+}
+
+impl From<OptionError> for BotError {
+    fn from(err: OptionError) -> Self {
+        BotError::InvalidOption(err)
+    }
+}
+
+impl From<RepositoryError> for BotError {
+    fn from(err: RepositoryError) -> Self {
+        match err {
+            RepositoryError::DuplicateTitle(title) => BotError::DuplicateTitle(title),
+            err => BotError::Repository(err),
+        }
+    }
+}
+
+impl From<MessengerError> for BotError {
+    fn from(err: MessengerError) -> Self {
+        BotError::Discord(err)
+    }
+}
+
+impl From<BackupError> for BotError {
+    fn from(err: BackupError) -> Self {
+        BotError::Backup(err)
+    }
+}
+
+/**
+ * This method generate a short id to find an incident in the logs.
+ *
+ * @return String, 8 hexadecimal characters
+ */
+pub fn new_incident_id() -> String {
+    format!("{:08X}", thread_rng().gen::<u32>())
+}
@@ -0,0 +1,29 @@
+pub mod admin;
+pub mod backup;
+pub mod clock;
+pub mod logger;
+pub mod messenger;
+pub mod commands;
+pub mod config;
+pub mod confirmations;
+pub mod cooldowns;
+pub mod database;
+pub mod error;
+pub mod locale;
+pub mod permissions;
+pub mod prefixes;
+pub mod repositories;
+pub mod shutdown;
+pub mod utils;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/**
+ * Public type used to share reference of JoinHandle
+ * to keep tracking async task like the tips scheduler.
+ * There is one task per guild, indexed by the guild id.
+ */
+pub type SharedSchedulers = Arc<RwLock<HashMap<u64, JoinHandle<()>>>>;
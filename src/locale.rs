@@ -0,0 +1,27 @@
+/*
+This enum is the language of the messages shown to users.
+Discord gives the language of the user with each interaction.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    French,
+}
+
+impl Locale {
+    /**
+     * This method return the language matching a discord locale.
+     * Languages not translated fall back to english.
+     *
+     * @param locale: &str, the discord locale, like en-US or fr
+     *
+     * @return Locale
+     */
+    pub fn from_discord(locale: &str) -> Locale {
+        match locale.split('-').next().unwrap_or_default() {
+            "fr" => Locale::French,
+            _ => Locale::English,
+        }
+    }
+}
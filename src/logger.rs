@@ -1,49 +1,109 @@
-use std::env;
-use log::{Level, LevelFilter, Metadata, Record, SetLoggerError};
+use std::fmt::{Display, Formatter};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+use crate::config::{LogConfig, LogFormat, LogRotation};
 
-/**
- * This is the logger configuration of the bot.
- * => In the whole project use log marco (info!, error!, ...) 
- * and the logger will print every log above the log level.
- * see .env.example for log level.
+// Name of log files, the date is added by the rotation : gamebot.log.2024-01-31
+pub const LOG_FILE_NAME: &str = "gamebot.log";
+
+/*
+A layer writing log lines, on stdout or in a file.
  */
-struct SimpleLogger;
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
 
-impl log::Log for SimpleLogger {
-    fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= Level::Info
-    }
+/*
+This structure keep the log files writer alive.
+Lines still buffered are written when it's dropped :
+it MUST be kept until the end of the program.
+ */
+pub struct LogGuard {
+    _file: Option<WorkerGuard>,
+}
 
-    fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) {
-            println!("{} - {}", record.level(), record.args());
+/*
+This enum describe every reason the logger can't be initialised.
+ */
+#[derive(Debug)]
+pub enum LoggerError {
+    Filter(String), // the level of a module is invalid
+    File(String), // the log directory can't be used
+    AlreadySet, // a logger has already been set
+}
+
+impl Display for LoggerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoggerError::Filter(err) => write!(f, "Invalid log level. {}", err),
+            LoggerError::File(err) => write!(f, "Can't write log files. {}", err),
+            LoggerError::AlreadySet => write!(f, "The logger has already been initialised."),
         }
     }
+}
 
-    fn flush(&self) {}
+impl std::error::Error for LoggerError {}
+
+/**
+ * This method make a layer writing lines in the given format.
+ *
+ * @param layer: fmt::Layer<Registry, ...>, the layer with its writer
+ * @param format: LogFormat, the format of lines
+ *
+ * @return BoxedLayer
+ */
+fn formatted<W>(layer: fmt::Layer<Registry, fmt::format::DefaultFields, fmt::format::Format, W>, format: LogFormat) -> BoxedLayer
+    where W: for<'writer> fmt::MakeWriter<'writer> + Send + Sync + 'static
+{
+    match format {
+        LogFormat::Human => layer.boxed(),
+        // The current span and its parents are added to every line
+        LogFormat::Json => layer.json().with_current_span(true).with_span_list(true).boxed(),
+    }
 }
 
 /**
- * This method set the logger an initialize the loglevel.
+ * This method set the logger and initialize the log levels.
  * It MUST be called in the beginning of the program !
+ * => In the whole project use log macros (info!, error!, ...)
+ * and the logger will print every log above the level of the module.
+ *
+ * @param config: &LogConfig, the log settings, see config.rs
  *
- * @return Result<(), SetLoggerError>
+ * @return Result<LogGuard, LoggerError>, the guard to keep until the end of the program
  */
-pub fn init() -> Result<(), SetLoggerError> {
-    log::set_logger(&LOGGER)
-        .map(|()|{
-            let level_filter: LevelFilter = match env::var("LOG_LEVEL").expect("Expected an env file with the LOG_LEVEL entry set. {}").as_str() {
-                "Off" => LevelFilter::Off,
-                "Trace" => LevelFilter::Trace,
-                "Debug" => LevelFilter::Debug,
-                "Info" => LevelFilter::Info,
-                "Warn" => LevelFilter::Warn,
-                "Error" => LevelFilter::Error,
-                _ => LevelFilter::Info,
-            };
-            log::set_max_level(level_filter)
-        })
-}
+pub fn init(config: &LogConfig) -> Result<LogGuard, LoggerError> {
+    let filter = EnvFilter::try_new(&config.level).map_err(|err| LoggerError::Filter(err.to_string()))?;
 
+    // 1 - Standard output
+    let mut layers: Vec<BoxedLayer> = Vec::with_capacity(2);
+    if config.stdout {
+        layers.push(formatted(fmt::layer(), config.format));
+    }
 
-static LOGGER: SimpleLogger = SimpleLogger;
\ No newline at end of file
+    // 2 - Rotating files : lines are written by a background thread
+    let mut file_guard = None;
+    if let Some(directory) = &config.directory {
+        let rotation = match config.rotation {
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        };
+        let appender = RollingFileAppender::builder()
+            .rotation(rotation)
+            .filename_prefix(LOG_FILE_NAME)
+            .build(directory)
+            .map_err(|err| LoggerError::File(format!("{}: {}", directory.display(), err)))?;
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        layers.push(formatted(fmt::layer().with_writer(writer).with_ansi(false), config.format));
+        file_guard = Some(guard);
+    }
+
+    // log macros are forwarded to the same layers
+    tracing_subscriber::registry()
+        .with(layers.with_filter(filter))
+        .try_init()
+        .map_err(|_| LoggerError::AlreadySet)?;
+    Ok(LogGuard { _file: file_guard })
+}
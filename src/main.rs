@@ -1,48 +1,354 @@
-extern crate core;
-
-mod logger;
-mod commands;
-mod database;
-mod utils;
-
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::process::exit;
 use std::sync::{Arc};
 use dotenv::dotenv;
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use serenity::{async_trait, Client};
 use serenity::builder::CreateEmbed;
 use serenity::client::{Context, EventHandler};
 use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
 use serenity::model::channel::Message;
 use serenity::model::gateway::Ready;
-use serenity::model::id::GuildId;
-use serenity::model::Timestamp;
+use serenity::model::guild::{Guild, Member, Role, UnavailableGuild};
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
+use serenity::model::{Permissions, Timestamp};
+use serenity::model::user::User;
 use serenity::prelude::GatewayIntents;
 use serenity::utils::Color;
-use tokio::sync::{Mutex, RwLock};
-use tokio::task::JoinHandle;
-use tokio_rusqlite::Connection;
-use crate::database::{run_migrations, SharedConnection};
-use crate::logger::init;
-
-/**
- * Public type used to share reference of JoinHandle
- * to keep tracking async task like the tips scheduler.
- */
-pub type SharedJoinHandle = Arc<RwLock<Option<JoinHandle<()>>>>;
+use tokio::sync::{oneshot, RwLock};
+use tracing::{info_span, Instrument};
+use game_bot4_fun::backup::{self, Backups};
+use game_bot4_fun::clock::SystemClock;
+use game_bot4_fun::commands::options::CommandOptions;
+use game_bot4_fun::commands::registry::{ButtonResponse, CommandRegistry, default_registry};
+use game_bot4_fun::commands::slash_command::{CommandContext, CommandResponse, Visibility};
+use game_bot4_fun::commands::tips_scheduler::scheduler;
+use game_bot4_fun::config::{CliArguments, Config};
+use game_bot4_fun::confirmations::{self, ConfirmationError, Confirmations, CONFIRMATION_TIMEOUT};
+use game_bot4_fun::cooldowns::Cooldowns;
+use game_bot4_fun::database::{Database, run_migrations};
+use game_bot4_fun::error::BotError;
+use game_bot4_fun::locale::Locale;
+use game_bot4_fun::logger;
+use game_bot4_fun::messenger::{AdminChannel, Messenger, MessengerError, OutgoingMessage};
+use game_bot4_fun::messenger::discord::{attachment_type, components, DiscordMessenger};
+use game_bot4_fun::permissions::Invoker;
+use game_bot4_fun::prefixes::Prefixes;
+use game_bot4_fun::repositories::{AuditRepository, PermissionRepository, SchedulerRepository, TipRepository};
+use game_bot4_fun::shutdown::{self, Shutdown};
+use game_bot4_fun::SharedSchedulers;
 
 /**
  * This is the main structure. It's here that
  * every event are handled and response are sent.
  *
- * @member database: SharedConnection, Shared connection to the database to run sql request from everywhere
- * @member tips_scheduler: SharedJoinHandle, Handler of the scheduler to execute action on it.
+ * @member tips: Arc<dyn TipRepository>, Storage of tips shared with every command and scheduler
+ * @member scheduler_configs: Arc<dyn SchedulerRepository>, Storage of the configuration of every tips scheduler
+ * @member permissions: Arc<dyn PermissionRepository>, Storage of the capabilities granted to the roles of every guild
+ * @member audit: Arc<dyn AuditRepository>, Storage of the changes made by commands
+ * @member cooldowns: Arc<Cooldowns>, Uses of the commands limited by the cooldowns of every guild
+ * @member prefixes: Arc<Prefixes>, Prefix of the text commands of every guild
+ * @member backups: Option<Arc<Backups>>, Snapshots of the database, None if it's not a sqlite database
+ * @member confirmations: Arc<Confirmations>, Commands waiting for the user to press confirm or cancel
+ * @member tips_schedulers: SharedSchedulers, Handlers of the scheduler of each guild to execute action on it.
+ * @member registered_guilds: RwLock<HashSet<GuildId>>, Guilds where slash commands have already been registered.
+ * @member commands: CommandRegistry, Every slash command of the bot.
+ * @member config: Config, The configuration of the bot.
+ * @member shutdown: Arc<Shutdown>, Running commands and posts, waited for when the bot stops.
  */
 struct Bot{
-    database: SharedConnection,
-    tips_scheduler: SharedJoinHandle,
+    tips: Arc<dyn TipRepository>,
+    scheduler_configs: Arc<dyn SchedulerRepository>,
+    permissions: Arc<dyn PermissionRepository>,
+    audit: Arc<dyn AuditRepository>,
+    cooldowns: Arc<Cooldowns>,
+    prefixes: Arc<Prefixes>,
+    backups: Option<Arc<Backups>>,
+    confirmations: Arc<Confirmations>,
+    tips_schedulers: SharedSchedulers,
+    registered_guilds: RwLock<HashSet<GuildId>>,
+    commands: CommandRegistry,
+    config: Config,
+    shutdown: Arc<Shutdown>,
+}
+
+impl Bot {
+    /**
+     * This method create the context given to slash commands and to the buttons of their responses.
+     *
+     * @param ctx: &Context, the context of the event
+     * @param member: Option<&Member>, the member of the interaction, None in direct messages
+     * @param user: &User, the user of the interaction
+     * @param channel_id: ChannelId, the channel of the interaction
+     * @param locale: &str, the language of the user
+     *
+     * @return CommandContext
+     */
+    fn interaction_context(&self, ctx: &Context, member: Option<&Member>, user: &User, channel_id: ChannelId, locale: &str) -> CommandContext {
+        // The roles of the member are checked before the command runs, the channel by the cooldowns
+        let invoker = match member {
+            Some(member) => Invoker {
+                user_id: user.id.0,
+                roles: member.roles.iter().map(|role| role.0).collect(),
+                channel_id: channel_id.0,
+                manager: member.permissions.is_some_and(Invoker::is_manager),
+            },
+            None => Invoker { user_id: user.id.0, channel_id: channel_id.0, ..Invoker::default() },
+        };
+        self.context(ctx, invoker, Locale::from_discord(locale))
+    }
+
+    /**
+     * This method create the context given to text commands.
+     * Discord doesn't send the permissions of the author of a message :
+     * they are computed from the roles of the guild.
+     *
+     * @param ctx: &Context, the context of the event
+     * @param msg: &Message, the message of the command : its author and their roles
+     * @param guild_id: u64, the guild where the message has been sent
+     *
+     * @return CommandContext
+     */
+    async fn message_context(&self, ctx: &Context, msg: &Message, guild_id: u64) -> CommandContext {
+        let roles: Vec<u64> = msg.member.as_ref().map(|member| member.roles.iter().map(|role| role.0).collect()).unwrap_or_default();
+        let manager = is_manager(ctx, guild_id, msg.author.id, &roles).await;
+        let invoker = Invoker { user_id: msg.author.id.0, roles, channel_id: msg.channel_id.0, manager };
+        self.context(ctx, invoker, Locale::default())
+    }
+
+    /**
+     * This method create the context given to commands.
+     *
+     * @param ctx: &Context, the context of the event
+     * @param invoker: Invoker, the member who run the command
+     * @param locale: Locale, the language of the member
+     *
+     * @return CommandContext
+     */
+    fn context(&self, ctx: &Context, invoker: Invoker, locale: Locale) -> CommandContext {
+        CommandContext {
+            tips: self.tips.clone(),
+            scheduler_configs: self.scheduler_configs.clone(),
+            schedulers: self.tips_schedulers.clone(),
+            permissions: self.permissions.clone(),
+            audit: self.audit.clone(),
+            cooldowns: self.cooldowns.clone(),
+            prefixes: self.prefixes.clone(),
+            backups: self.backups.clone(),
+            confirmations: self.confirmations.clone(),
+            messenger: Arc::new(DiscordMessenger::new(ctx.http.clone())),
+            clock: Arc::new(SystemClock),
+            shutdown: self.shutdown.clone(),
+            timezone: self.config.timezone,
+            locale,
+            invoker,
+        }
+    }
+
+    /**
+     * This method answer a button pressed under a message of the bot, like the
+     * confirm button of /tips_delete. Unknown buttons are ignored.
+     *
+     * @param ctx: &Context, the context of the event
+     * @param component: &MessageComponentInteraction, the button pressed
+     *
+     * @return Result<(), serenity::Error>
+     */
+    async fn press_button(&self, ctx: &Context, component: &MessageComponentInteraction) -> Result<(), serenity::Error> {
+        let command_ctx = self.interaction_context(ctx, component.member.as_ref(), &component.user, component.channel_id, &component.locale);
+        let (response, update) = match self.commands.dispatch_button(&command_ctx, &component.data.custom_id).await {
+            Some(ButtonResponse::Update(response)) => (response, true),
+            Some(ButtonResponse::Reply(response)) => (response, false),
+            None => {
+                debug!("Received an unknown button");
+                return Ok(());
+            }
+        };
+        let CommandResponse { message, follow_ups, visibility, .. } = response;
+        let ephemeral = visibility == Visibility::Ephemeral;
+
+        // 1 - Replace the message of the button, or answer only the user
+        component.create_interaction_response(&ctx.http, |interaction_response| {
+            interaction_response
+                .kind(if update { InteractionResponseType::UpdateMessage } else { InteractionResponseType::ChannelMessageWithSource })
+                .interaction_response_data(|data| {
+                    data.content(message.content.unwrap_or_default());
+                    data.add_files(message.attachments.into_iter().map(attachment_type));
+                    data.ephemeral(!update).set_embeds(message.embeds).set_components(components(message.buttons))
+                })
+        }).await?;
+
+        // 2 - The follow-ups
+        for follow_up in follow_ups {
+            component.create_followup_message(&ctx.http, |data| {
+                if let Some(content) = follow_up.content {
+                    data.content(content);
+                }
+                data.add_files(follow_up.attachments.into_iter().map(attachment_type));
+                data.ephemeral(ephemeral).set_embeds(follow_up.embeds)
+            }).await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * This method register every slash command of the bot
+     * in the guild given. It does nothing if commands are
+     * already registered there.
+     *
+     * @param ctx: &Context, the context of the event
+     * @param guild_id: GuildId, the guild where commands are registered
+     */
+    async fn register_commands(&self, ctx: &Context, guild_id: GuildId) {
+        if !self.registered_guilds.write().await.insert(guild_id) {
+            return;
+        }
+
+        // Create new application commands
+        let commands = GuildId::set_application_commands(&guild_id, &ctx.http, |commands| {
+            self.commands.register_all(commands)
+        })
+            .await;
+
+        match commands {
+            Ok(commands) => info!("Guild {} now have {} slash commands.", guild_id, commands.len()),
+            Err(why) => {
+                // Allow a new try on the next guild_create event
+                self.registered_guilds.write().await.remove(&guild_id);
+                error!("Failed to register slash commands in the guild {}. Error:\n{}", guild_id, why);
+            }
+        }
+    }
+}
+
+/**
+ * This method send the response of a command, then its follow-ups.
+ * A deferred interaction has already been acknowledged : its "thinking"
+ * message is edited. Files can't be added by an edit, they are sent
+ * in the first follow-up. A public "thinking" message can't become
+ * ephemeral : it's deleted and the response is sent as a follow-up.
+ *
+ * @param ctx: &Context, the context of the event
+ * @param command: &ApplicationCommandInteraction, the interaction to respond to
+ * @param response: CommandResponse, the messages of the response
+ * @param deferred: Option<Visibility>, the visibility of the "thinking" message if the interaction has been acknowledged
+ *
+ * @return Result<Option<MessageId>, serenity::Error>, the follow-up sent instead of the original response, None if the response is the original one
+ */
+async fn respond(ctx: &Context, command: &ApplicationCommandInteraction, response: CommandResponse, deferred: Option<Visibility>) -> Result<Option<MessageId>, serenity::Error> {
+    let CommandResponse { mut message, mut follow_ups, visibility, .. } = response;
+    let ephemeral = visibility == Visibility::Ephemeral;
+    let mut replaced = false;
+
+    // 1 - The response
+    match deferred {
+        Some(Visibility::Public) if ephemeral => {
+            command.delete_original_interaction_response(&ctx.http).await?;
+            follow_ups.insert(0, message);
+            replaced = true;
+        }
+        Some(_) => {
+            let attachments = std::mem::take(&mut message.attachments);
+            command.edit_original_interaction_response(&ctx.http, |edit| {
+                if let Some(content) = message.content {
+                    edit.content(content);
+                }
+                edit.set_components(components(message.buttons)).set_embeds(message.embeds)
+            }).await?;
+            if !attachments.is_empty() {
+                follow_ups.insert(0, OutgoingMessage { attachments, ..OutgoingMessage::default() });
+            }
+        }
+        None => {
+            command.create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|data| {
+                        if let Some(content) = message.content {
+                            data.content(content);
+                        }
+                        data.add_files(message.attachments.into_iter().map(attachment_type));
+                        data.set_components(components(message.buttons));
+                        data.ephemeral(ephemeral).set_embeds(message.embeds)
+                    })
+            }).await?;
+        }
+    }
+
+    // 2 - The follow-ups, in order, seen by the same users
+    let mut first_follow_up = None;
+    for follow_up in follow_ups {
+        let sent = command.create_followup_message(&ctx.http, |data| {
+            if let Some(content) = follow_up.content {
+                data.content(content);
+            }
+            data.add_files(follow_up.attachments.into_iter().map(attachment_type));
+            data.set_components(components(follow_up.buttons));
+            data.ephemeral(ephemeral).set_embeds(follow_up.embeds)
+        }).await?;
+        first_follow_up = first_follow_up.or(Some(sent.id));
+    }
+    Ok(first_follow_up.filter(|_| replaced))
+}
+
+/**
+ * This method return true if the author of a text command is a manager of the guild.
+ * Their permissions are computed from the roles of the guild in the cache,
+ * the roles are fetched from discord when the guild is missing.
+ *
+ * @param ctx: &Context, the context of the event
+ * @param guild_id: u64, the guild where the message has been sent
+ * @param user_id: UserId, the author of the message
+ * @param roles: &[u64], the ids of the roles of the author
+ *
+ * @return bool, false if the roles of the guild can't be fetched
+ */
+async fn is_manager(ctx: &Context, guild_id: u64, user_id: UserId, roles: &[u64]) -> bool {
+    let permissions = |owner_id: UserId, guild_roles: &HashMap<RoleId, Role>| {
+        // The @everyone role has the id of the guild
+        let everyone = guild_roles.get(&RoleId(guild_id)).map(|role| role.permissions).unwrap_or_default();
+        let permissions: Vec<Permissions> = roles.iter()
+            .filter_map(|role| guild_roles.get(&RoleId(*role)))
+            .map(|role| role.permissions)
+            .collect();
+        Invoker::guild_permissions(owner_id == user_id, everyone, &permissions)
+    };
+    if let Some(permissions) = ctx.cache.guild_field(GuildId(guild_id), |guild| permissions(guild.owner_id, &guild.roles)) {
+        return Invoker::is_manager(permissions);
+    }
+    match GuildId(guild_id).to_partial_guild(&ctx.http).await {
+        Ok(guild) => Invoker::is_manager(permissions(guild.owner_id, &guild.roles)),
+        Err(why) => {
+            warn!("Cannot get the roles of the guild {}, the author of the text command is not a manager: {}", guild_id, why);
+            false
+        }
+    }
 }
 
+/**
+ * This method send the response of a text command, then its follow-ups.
+ * Messages can't be ephemeral : the responses only for the user are
+ * sent to them in direct message.
+ *
+ * @param messenger: &dyn Messenger, used to send the messages
+ * @param msg: &Message, the message of the command
+ * @param response: CommandResponse, the messages of the response
+ *
+ * @return Result<(), MessengerError>
+ */
+async fn reply(messenger: &dyn Messenger, msg: &Message, response: CommandResponse) -> Result<(), MessengerError> {
+    let CommandResponse { message, follow_ups, visibility, .. } = response;
+    for message in std::iter::once(message).chain(follow_ups) {
+        match visibility {
+            Visibility::Public => messenger.send(msg.channel_id.0, message).await?,
+            Visibility::Ephemeral => messenger.send_dm(msg.author.id.0, message).await?,
+        };
+    }
+    Ok(())
+}
 
 #[async_trait]
 impl EventHandler for Bot{
@@ -50,68 +356,154 @@ impl EventHandler for Bot{
     // to execute corresponding commands.
     async fn message(&self, ctx: Context, msg: Message) {
         // THis is a simple ping command
-        if msg.content == "!ping"{
-            let msg = msg.channel_id.send_message(&ctx.http, |m| {
-                m.content("I'm alive ;)")
-            }).await;
+        if self.config.features.ping && msg.content == "!ping"{
+            // The bot is stopping : messages are ignored
+            let _activity = match self.shutdown.track() {
+                Some(activity) => activity,
+                None => return,
+            };
+            let span = info_span!("message", guild = msg.guild_id.map(|id| id.0), user = msg.author.id.0);
+            async {
+                debug!("Received !ping");
+                let messenger = DiscordMessenger::new(ctx.http.clone());
+                if let Err(why) = messenger.send(msg.channel_id.0, OutgoingMessage::text("I'm alive ;)")).await {
+                    error!("Failed to send embed message. Error:\n{}", why);
+                }
+            }.instrument(span).await;
+            return;
+        }
 
-            if let Err(why) = msg {
-                error!("Failed to send embed message. Error:\n{}", why);
+        // Text commands, like !tip 12. Messages of bots and direct messages are ignored
+        let guild_id = match msg.guild_id {
+            Some(guild_id) if !msg.author.bot => guild_id.0,
+            _ => return,
+        };
+        let prefix = match self.prefixes.get(guild_id).await {
+            Ok(prefix) => prefix,
+            Err(err) => {
+                error!("Failed to get the prefix of the guild {}. Error:\n{}", guild_id, err);
+                return;
             }
+        };
+        // Other bots may use the same prefix : messages that aren't a command of the bot are ignored
+        if self.commands.find_in_message(&prefix, &msg.content).is_none() {
+            return;
         }
+        // The bot is stopping : messages are ignored
+        let _activity = match self.shutdown.track() {
+            Some(activity) => activity,
+            None => return,
+        };
+        let span = info_span!("message", guild = guild_id, user = msg.author.id.0);
+        async {
+            let command_ctx = self.message_context(&ctx, &msg, guild_id).await;
+            if let Some(response) = self.commands.dispatch_message(&command_ctx, &prefix, &msg.content, &msg.mentions, guild_id).await {
+                debug!("Received a text command");
+                if let Err(why) = reply(command_ctx.messenger.as_ref(), &msg, response).await {
+                    warn!("Cannot respond to the text command: {}", why);
+                }
+            }
+        }.instrument(span).await
     }
 
     // The interaction handler will handle every /commands
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        // Check if it's an application command
+        // Application commands, then the buttons under their responses
         if let Interaction::ApplicationCommand(command) = interaction {
-            println!("Received command interaction: {:#?}", command);
-            // Execute the corresponding command and get the response embed
-            let embed = match command.data.name.as_str() {
-                "tips_list" => {
-                    commands::tips::list::run(&command.data.options, self.database.clone()).await
-                },
-                "tips_create" => {
-                    commands::tips::create::run(&command.data.options, self.database.clone()).await
-                },
-                "tips_read" => {
-                    commands::tips::read::run(&command.data.options, self.database.clone()).await
-                },
-                "tips_update" => {
-                    commands::tips::update::run(&command.data.options, self.database.clone()).await
-                },
-                "tips_delete" => {
-                    commands::tips::delete::run(&command.data.options, self.database.clone()).await
-                },
-                "scheduler_config" => {
-                    commands::tips_scheduler::config::run(&command.data.options, self.database.clone(), self.tips_scheduler.clone(), &ctx.http).await
-                },
-                "scheduler" => {
-                    commands::tips_scheduler::scheduler::run(&command.data.options, self.database.clone(), self.tips_scheduler.clone(), &ctx.http).await
-                },
-                _ => {
-                    CreateEmbed::default()
-                        .title("Not implemented :(")
-                        .colour(Color::from_rgb(255, 204, 0))
-                        .description("Please retry later. If you think it's an error contact the administrator of the server.")
-                        .timestamp(Timestamp::now())
-                        .to_owned()
-                },
-            };
+            // Every log of the command is in this span, values of options are never logged
+            let span = info_span!("interaction", command = %command.data.name, guild = command.guild_id.map(|id| id.0), user = command.user.id.0);
+            async {
+                // Every data is scoped by guild : commands can't be used in direct messages
+                let guild_id = match command.guild_id {
+                    Some(guild_id) => guild_id.0,
+                    None => {
+                        warn!("Received the command {} outside of a guild.", command.data.name);
+                        return;
+                    }
+                };
+                // Execute the corresponding command and get the response embed.
+                // Once the bot is stopping, new commands are refused
+                let activity = self.shutdown.track();
+                let mut deferred = None;
+                let response = match activity {
+                    Some(_) => {
+                        let options = CommandOptions::from_interaction(&command.data.options);
+                        debug!("Received the command with the options [{}]", options.redacted());
+                        // Slow commands are acknowledged first : discord only wait 3 seconds for a response
+                        if self.commands.get(&command.data.name).is_some_and(|slash_command| slash_command.defer()) {
+                            let visibility = self.commands.visibility(&command.data.name, &options);
+                            let acknowledged = match visibility {
+                                Visibility::Public => command.defer(&ctx.http).await,
+                                Visibility::Ephemeral => command.defer_ephemeral(&ctx.http).await,
+                            };
+                            match acknowledged {
+                                Ok(()) => deferred = Some(visibility),
+                                Err(why) => warn!("Cannot defer the response of the slash command: {}", why),
+                            }
+                        }
+                        let command_ctx = self.interaction_context(&ctx, command.member.as_ref(), &command.user, command.channel_id, &command.locale);
+                        self.commands.dispatch(&command_ctx, &command.data.name, guild_id, &options).await
+                    }
+                    None => {
+                        debug!("Command refused : the bot is stopping");
+                        CommandResponse {
+                            visibility: Visibility::Ephemeral,
+                            ..CommandResponse::from(CreateEmbed::default()
+                                .title("The bot is restarting")
+                                .description("Please retry in a few moments.")
+                                .timestamp(Timestamp::now())
+                                .color(Color::from_rgb(255, 102, 51))
+                                .to_owned())
+                        }
+                    }
+                };
 
-            // Send the response in the same channel as the /command
-            if let Err(why) = command
-                .create_interaction_response(&ctx.http, |response| {
-                    response
-                        .kind(InteractionResponseType::ChannelMessageWithSource)
-                        .interaction_response_data(|m| {
-                            m.set_embed(embed)
-                        })
-                })
-                .await
-            {
-                warn!("Cannot respond to slash command: {}", why);
-            }
+                // Send the response in the same channel as the /command
+                let confirmation = response.message.buttons.iter().find_map(|button| confirmations::parse_button_id(&button.custom_id));
+                let follow_up = match respond(&ctx, &command, response, deferred).await {
+                    Ok(follow_up) => follow_up,
+                    Err(why) => {
+                        warn!("Cannot respond to slash command: {}", why);
+                        return;
+                    }
+                };
+                // Once the timeout is over, the buttons of an unanswered confirmation are removed,
+                // from the original response or from the follow-up sent instead of it
+                if let Some((id, _)) = confirmation {
+                    let confirmations = self.confirmations.clone();
+                    let http = ctx.http.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(CONFIRMATION_TIMEOUT).await;
+                        if !confirmations.expire(id) {
+                            return;
+                        }
+                        let expired = BotError::from(ConfirmationError::Expired).to_embed(Locale::from_discord(&command.locale), None);
+                        let edited = match follow_up {
+                            Some(message_id) => command.edit_followup_message(&http, message_id, |edit| {
+                                edit.content("").set_embeds(vec![expired]).set_components(components(Vec::new()))
+                            }).await.map(|_| ()),
+                            None => command.edit_original_interaction_response(&http, |edit| {
+                                edit.content("").set_embeds(vec![expired]).set_components(components(Vec::new()))
+                            }).await.map(|_| ()),
+                        };
+                        if let Err(why) = edited {
+                            debug!("Cannot remove the buttons of an expired confirmation: {}", why);
+                        }
+                    });
+                }
+            }.instrument(span).await
+        } else if let Interaction::MessageComponent(component) = interaction {
+            // The bot is stopping : buttons are ignored
+            let _activity = match self.shutdown.track() {
+                Some(activity) => activity,
+                None => return,
+            };
+            let span = info_span!("button", guild = component.guild_id.map(|id| id.0), user = component.user.id.0);
+            async {
+                if let Err(why) = self.press_button(&ctx, &component).await {
+                    warn!("Cannot respond to the button: {}", why);
+                }
+            }.instrument(span).await
         }
     }
 
@@ -119,54 +511,145 @@ impl EventHandler for Bot{
     // shard is booted, and a READY payload is sent by Discord. This payload
     // contains data like the current user's guild Ids, current user data, private channels, and more.
     async fn ready(&self, ctx: Context, data: Ready) {
-        // Get the guild (server) id
-        let guild_id = data.guilds.first().unwrap().id;
+        // Register slash commands in every guild (server) the bot is in
+        for guild in &data.guilds {
+            self.register_commands(&ctx, guild.id).await;
+        }
 
-        // Create new application commands
-        let commands = GuildId::set_application_commands(&guild_id, &ctx.http, |commands| {
-            commands
-                // tips
-                .create_application_command(|command| commands::tips::list::register(command))
-                .create_application_command(|command| commands::tips::create::register(command))
-                .create_application_command(|command| commands::tips::read::register(command))
-                .create_application_command(|command| commands::tips::update::register(command))
-                .create_application_command(|command| commands::tips::delete::register(command))
-                // scheduler
-                .create_application_command(|command| commands::tips_scheduler::config::register(command))
-                .create_application_command(|command| commands::tips_scheduler::scheduler::register(command))
-        })
-            .await;
+        info!("{} is connected and ready to use in {} guild(s) !", data.user.name, data.guilds.len());
+    }
+
+    // Called when the bot join a new guild or when a guild become available.
+    async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: bool) {
+        self.register_commands(&ctx, guild.id).await;
+    }
 
-        info!("I now have the following guild slash commands: {:#?}", commands);
-        println!("{} is connected and ready to use !", data.user.name);
+    // Called when the bot is removed from a guild or when a guild become unavailable.
+    async fn guild_delete(&self, _ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        // The guild is only unavailable (outage) : keep everything running
+        if incomplete.unavailable {
+            return;
+        }
+        // The bot has been removed : stop the tips scheduler of the guild
+        if let Some(handle) = self.tips_schedulers.write().await.remove(&incomplete.id.0) {
+            handle.abort();
+        }
+        self.registered_guilds.write().await.remove(&incomplete.id);
+        info!("The bot has been removed from the guild {}.", incomplete.id);
     }
 }
 
 
 #[tokio::main]
 async fn main() {
-    // Load .env var in the system environment
-    dotenv().expect("Failed to load .env variables into system env");
-    // Init the logger : see logger.rs
-    init().expect("Failed to init the logger.");
-    // Configure the client with the discord bot token in the environment token
-    let token = env::var("DISCORD_TOKEN").expect("Expected an env file with the DISCORD_TOKEN entry set.");
+    // Load .env var in the system environment, the file is optional
+    dotenv().ok();
+    // Load the configuration : config file < environment < command line, see config.rs
+    let loaded = CliArguments::parse(env::args().skip(1))
+        .and_then(|cli| Config::load(&cli, |name| env::var(name).ok()).map(|config| (cli, config)));
+    let (cli, config) = match loaded {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprint!("{}", err);
+            exit(1);
+        }
+    };
+
+    // Print the configuration without its secrets, then exit.
+    if cli.print_config {
+        println!("{}", config);
+        return;
+    }
+
+    // Init the logger : see logger.rs. The guard flush log files when the bot stops.
+    let _log_guard = match logger::init(&config.log) {
+        Ok(guard) => guard,
+        Err(err) => {
+            eprintln!("Failed to init the logger. {}", err);
+            exit(1);
+        }
+    };
+    // Initiate a connection to the database given by DATABASE_URL (a sqlite file is created if required).
+    // The dry-run only reads the database : it must exist and is left untouched.
+    let connection = if cli.dry_run_migrations {
+        Database::connect_read_only(&config.database_url).await
+    } else {
+        Database::connect(&config.database_url).await
+    };
+    let database = match connection {
+        Ok(database) => database,
+        Err(err) => {
+            error!("Couldn't connect to the database {}. {}", config.database_url, err);
+            return;
+        }
+    };
+    info!("Connected to the database {}", config.database_url);
+
+    // Dry-run : list pending migrations without applying them, then exit.
+    if cli.dry_run_migrations {
+        match database.pending_migrations().await {
+            Ok(pending) if pending.is_empty() => println!("The database is up to date (schema version {}).", database.latest_version()),
+            Ok(pending) => {
+                println!("{} pending migration(s):", pending.len());
+                for (version, name) in pending {
+                    println!("  {} - {}", version, name);
+                }
+            }
+            Err(err) => error!("Failed to list pending migrations. {}", err),
+        }
+        return;
+    }
 
-    // Initiate a connection to the database file, creating the file if required.
-    let database: SharedConnection = Arc::from(Mutex::from(Connection::open("database.sqlite").await
-        .expect("Couldn't connect to database")));
+    // Configure the client with the discord bot token
+    let token = match config.require_token() {
+        Ok(token) => token.to_string(),
+        Err(err) => {
+            eprint!("{}", err);
+            exit(1);
+        }
+    };
 
     // Run migrations, which updates the database's schema to the latest version.
-    run_migrations(database.clone()).await.expect("Failed to run migrations. Error");
+    // Data created before the multi-guild support belong to the home guild
+    if let Err(err) = run_migrations(&database, config.home_guild).await {
+        error!("Failed to run migrations. {}", err);
+        return;
+    }
 
     // Create the main structure which will handle events
+    let shutdown = Arc::new(Shutdown::default());
+    // Snapshots of the database : only sqlite files are copied by the bot
+    let backups = database.backups(config.backup.clone()).map(Arc::new);
+    let scheduled_backups = match (&backups, config.backup.interval) {
+        (Some(backups), Some(interval)) => Some((backups.clone(), interval)),
+        (None, Some(_)) => {
+            warn!("Scheduled backups are only available with a sqlite database.");
+            None
+        }
+        _ => None,
+    };
+    let admin_channel_id = config.admin_channel;
+    let tips_schedulers: SharedSchedulers = Arc::from(RwLock::from(HashMap::new())); // there is no scheduler running
+    let shutdown_timeout = config.shutdown_timeout;
     let bot = Bot{
-        database,
-        tips_scheduler: Arc::from(RwLock::from(None)), // there is no scheduler running
+        tips: database.tip_repository(),
+        scheduler_configs: database.scheduler_repository(),
+        permissions: database.permission_repository(),
+        audit: database.audit_repository(),
+        cooldowns: Arc::new(Cooldowns::new(database.cooldown_repository())),
+        prefixes: Arc::new(Prefixes::new(database.prefix_repository())),
+        backups,
+        confirmations: Arc::new(Confirmations::new()),
+        tips_schedulers: tips_schedulers.clone(),
+        registered_guilds: RwLock::from(HashSet::new()),
+        commands: default_registry(&config.features),
+        config,
+        shutdown: shutdown.clone(),
     };
 
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents = GatewayIntents::GUILD_MESSAGES
+    let intents = GatewayIntents::GUILDS
+        | GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
@@ -175,10 +658,53 @@ async fn main() {
     // which is a requirement by Discord for bot user.
     let mut client = Client::builder(&token, intents).event_handler(bot).await.expect("Error creating client.");
 
+    // Start the scheduled backups, their failures are reported in the admin channel
+    let admin_channel = admin_channel_id.map(|channel| AdminChannel::new(Arc::new(DiscordMessenger::new(client.cache_and_http.http.clone())), channel));
+    let backup_schedule = scheduled_backups.map(|(backups, interval)| {
+        backup::schedule(backups, interval, Arc::new(SystemClock), shutdown.clone(), admin_channel)
+    });
+
+    // Stop the bot gracefully on SIGINT or SIGTERM
+    let shard_manager = client.shard_manager.clone();
+    let (stopped, disconnected) = oneshot::channel();
+    tokio::spawn(async move {
+        let signal = shutdown::signal().await;
+        info!("Received {}, the bot is stopping.", signal);
+        // A second signal stops the bot without waiting
+        tokio::spawn(async {
+            shutdown::signal().await;
+            warn!("Received a second signal, the bot stops now.");
+            exit(1);
+        });
+
+        // 1 - Refuse new commands and wait for the running ones and the tips being posted
+        if !shutdown.stop(shutdown_timeout).await {
+            warn!("{} command(s) or post(s) still running after {}s are interrupted.", shutdown.running(), shutdown_timeout.as_secs());
+        }
+        // 2 - Stop the tips schedulers and the scheduled backups
+        info!("{} tips scheduler(s) stopped.", scheduler::stop_all(&tips_schedulers).await);
+        if let Some(handle) = backup_schedule {
+            handle.abort();
+        }
+        // 3 - Disconnect from discord. Shards not connected yet are ignored by
+        // shutdown_all : the client is dropped once it's done
+        shard_manager.lock().await.shutdown_all().await;
+        let _ = stopped.send(());
+    });
+
     // Finally, start a single shard, and start listening to events.
     //
     // Shards will automatically attempt to reconnect, and will perform exponential backoff until it reconnects.
-    if let Err(error) = client.start().await {
-        error!("Client error : {:?}", error);
+    tokio::select! {
+        result = client.start() => if let Err(error) = result {
+            error!("Client error : {:?}", error);
+        },
+        _ = disconnected => {}
     }
-}
\ No newline at end of file
+
+    // 4 - Close the database, then logs are flushed when the guard is dropped
+    if let Err(err) = database.close().await {
+        error!("Failed to close the database. {}", err);
+    }
+    info!("The bot is stopped.");
+}
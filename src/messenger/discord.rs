@@ -0,0 +1,235 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde_json::Value;
+use serenity::async_trait;
+use serenity::builder::{CreateComponents, CreateEmbed, CreateMessage, EditMessage};
+use serenity::http::{Http, StatusCode};
+use serenity::json::hashmap_to_json_map;
+use serenity::model::channel::AttachmentType;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use serenity::model::webhook::Webhook;
+use crate::messenger::{Attachment, Button, Messenger, MessengerError, OutgoingMessage};
+
+// Name of the webhooks created by the bot, each message gives its own name
+const WEBHOOK_NAME: &str = "GameBot4Fun";
+
+/**
+ * This method turn the 404 responses of discord into NotFound, and the 403 into Forbidden.
+ *
+ * @param err: serenity::Error, the error of the request
+ *
+ * @return MessengerError
+ */
+fn messenger_error(err: serenity::Error) -> MessengerError {
+    match &err {
+        serenity::Error::Http(http_err) if http_err.status_code() == Some(StatusCode::NOT_FOUND) => MessengerError::NotFound,
+        serenity::Error::Http(http_err) if http_err.status_code() == Some(StatusCode::FORBIDDEN) => MessengerError::Forbidden,
+        _ => MessengerError::from(err),
+    }
+}
+
+/**
+ * This method fill the builder of a new message.
+ *
+ * @param builder: &mut CreateMessage, the builder given by serenity
+ * @param message: OutgoingMessage, the message to post
+ *
+ * @return &mut CreateMessage, used to chain operations
+ */
+fn build_message<'a, 'b>(builder: &'a mut CreateMessage<'b>, message: OutgoingMessage) -> &'a mut CreateMessage<'b> {
+    if let Some(content) = message.content {
+        builder.content(content);
+    }
+    for attachment in message.attachments {
+        builder.add_file(attachment_type(attachment));
+    }
+    builder.set_components(components(message.buttons));
+    builder.set_embeds(message.embeds)
+}
+
+/**
+ * This method turn the buttons of a message into its components.
+ * Without button, there is no component : an edit remove the previous buttons.
+ *
+ * @param buttons: Vec<Button>, the buttons, shown in one row
+ *
+ * @return CreateComponents
+ */
+pub fn components(buttons: Vec<Button>) -> CreateComponents {
+    let mut components = CreateComponents::default();
+    if !buttons.is_empty() {
+        components.create_action_row(|row| {
+            for button in buttons {
+                row.create_button(|create| create.custom_id(button.custom_id).label(button.label).style(button.style));
+            }
+            row
+        });
+    }
+    components
+}
+
+/**
+ * This method turn embeds into the json expected by webhooks.
+ *
+ * @param embeds: Vec<CreateEmbed>, the embeds of the message
+ *
+ * @return Vec<Value>
+ */
+fn embeds_json(embeds: Vec<CreateEmbed>) -> Vec<Value> {
+    embeds.into_iter().map(|embed| Value::from(hashmap_to_json_map(embed.0))).collect()
+}
+
+/**
+ * This method turn an attachment into the file type of serenity.
+ *
+ * @param attachment: Attachment, the file to send
+ *
+ * @return AttachmentType<'static>
+ */
+pub fn attachment_type(attachment: Attachment) -> AttachmentType<'static> {
+    AttachmentType::Bytes { data: Cow::Owned(attachment.data), filename: attachment.name }
+}
+
+/*
+This structure post messages on discord with the http client of the bot.
+ */
+#[derive(Clone)]
+pub struct DiscordMessenger {
+    http: Arc<Http>,
+    webhooks: Arc<Mutex<HashMap<u64, Webhook>>>, // the webhook of the bot in each channel, by channel id
+}
+
+impl DiscordMessenger {
+    pub fn new(http: Arc<Http>) -> DiscordMessenger {
+        DiscordMessenger { http, webhooks: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /**
+     * This method return the webhook of the bot in a channel.
+     * It's searched in the webhooks of the channel, or created the first time.
+     *
+     * @param channel: u64, the channel of the webhook
+     *
+     * @return Result<Webhook, MessengerError>, Forbidden without the permission to manage webhooks
+     */
+    async fn webhook(&self, channel: u64) -> Result<Webhook, MessengerError> {
+        if let Some(webhook) = self.webhooks.lock().unwrap().get(&channel) {
+            return Ok(webhook.clone());
+        }
+        let bot_id = self.http.application_id();
+        let existing = ChannelId::from(channel).webhooks(&self.http).await.map_err(messenger_error)?
+            .into_iter()
+            .find(|webhook| {
+                let created_by_bot = match (bot_id, &webhook.user) {
+                    (Some(bot_id), Some(user)) => user.id.0 == bot_id,
+                    _ => true,
+                };
+                webhook.name.as_deref() == Some(WEBHOOK_NAME) && webhook.token.is_some() && created_by_bot
+            });
+        let webhook = match existing {
+            Some(webhook) => webhook,
+            None => ChannelId::from(channel).create_webhook(&self.http, WEBHOOK_NAME).await.map_err(messenger_error)?,
+        };
+        self.webhooks.lock().unwrap().insert(channel, webhook.clone());
+        Ok(webhook)
+    }
+
+    /**
+     * This method post a message with the webhook of the bot in a channel.
+     *
+     * @param channel: u64, the channel where the message is posted
+     * @param name: &str, the name displayed as author of the message
+     * @param avatar_url: Option<&str>, the avatar displayed
+     * @param message: OutgoingMessage, the message to post
+     *
+     * @return Result<u64, MessengerError>, the id of the message posted. NotFound if the webhook has been deleted
+     */
+    async fn execute_webhook(&self, channel: u64, name: &str, avatar_url: Option<&str>, message: OutgoingMessage) -> Result<u64, MessengerError> {
+        let webhook = self.webhook(channel).await?;
+        let sent = webhook
+            .execute(&self.http, true, |builder| {
+                builder.username(name);
+                if let Some(avatar_url) = avatar_url {
+                    builder.avatar_url(avatar_url);
+                }
+                if let Some(content) = message.content {
+                    builder.content(content);
+                }
+                builder.add_files(message.attachments.into_iter().map(attachment_type));
+                builder.embeds(embeds_json(message.embeds))
+            })
+            .await
+            .map_err(messenger_error)?;
+        // Waited for : discord always return the message
+        sent.map(|sent| sent.id.0).ok_or(MessengerError::NotFound)
+    }
+}
+
+#[async_trait]
+impl Messenger for DiscordMessenger {
+    async fn send(&self, channel: u64, message: OutgoingMessage) -> Result<u64, MessengerError> {
+        let sent = ChannelId::from(channel)
+            .send_message(&self.http, |builder| build_message(builder, message))
+            .await
+            .map_err(messenger_error)?;
+        Ok(sent.id.0)
+    }
+
+    async fn send_as(&self, channel: u64, name: &str, avatar_url: Option<&str>, message: OutgoingMessage) -> Result<u64, MessengerError> {
+        match self.execute_webhook(channel, name, avatar_url, message.clone()).await {
+            // The webhook has been deleted by a moderator : it's created again
+            Err(MessengerError::NotFound) if self.webhooks.lock().unwrap().remove(&channel).is_some() => {
+                self.execute_webhook(channel, name, avatar_url, message).await
+            }
+            result => result,
+        }
+    }
+
+    async fn edit(&self, channel: u64, message: u64, edit: OutgoingMessage) -> Result<(), MessengerError> {
+        let edited = ChannelId::from(channel)
+            .edit_message(&self.http, message, |builder: &mut EditMessage| {
+                if let Some(content) = edit.content.clone() {
+                    builder.content(content);
+                }
+                builder.set_components(components(edit.buttons.clone()));
+                builder.set_embeds(edit.embeds.clone())
+            })
+            .await
+            .map_err(messenger_error);
+        match edited {
+            // The message is not authored by the bot : it may have been posted by its webhook
+            Err(MessengerError::Forbidden) => {
+                let webhook = self.webhook(channel).await?;
+                webhook
+                    .edit_message(&self.http, MessageId::from(message), |builder| {
+                        if let Some(content) = edit.content {
+                            builder.content(content);
+                        }
+                        builder.embeds(embeds_json(edit.embeds))
+                    })
+                    .await
+                    .map_err(messenger_error)?;
+                Ok(())
+            }
+            edited => edited.map(|_| ()),
+        }
+    }
+
+    async fn create_thread(&self, channel: u64, message: u64, name: &str) -> Result<u64, MessengerError> {
+        let thread = ChannelId::from(channel)
+            .create_public_thread(&self.http, message, |builder| builder.name(name))
+            .await
+            .map_err(messenger_error)?;
+        Ok(thread.id.0)
+    }
+
+    async fn send_dm(&self, user: u64, message: OutgoingMessage) -> Result<u64, MessengerError> {
+        let channel = UserId::from(user).create_dm_channel(&self.http).await.map_err(messenger_error)?;
+        let sent = channel
+            .send_message(&self.http, |builder| build_message(builder, message))
+            .await
+            .map_err(messenger_error)?;
+        Ok(sent.id.0)
+    }
+}
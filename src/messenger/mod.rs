@@ -0,0 +1,210 @@
+pub mod discord;
+pub mod recording;
+
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use log::error;
+use serenity::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::component::ButtonStyle;
+use serenity::utils::Color;
+
+/*
+This structure is a file attached to a message.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub name: String, // name of the file displayed on discord, like tips.txt
+    pub data: Vec<u8>,
+}
+
+/*
+This structure is a button under a message. Discord send an interaction
+with its custom id when it's pressed.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Button {
+    pub custom_id: String,
+    pub label: String,
+    pub style: ButtonStyle,
+}
+
+/*
+This structure is a message posted by the bot : a text, embeds, files or all of them.
+Discord accept up to 10 embeds per message.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct OutgoingMessage {
+    pub content: Option<String>,
+    pub embeds: Vec<CreateEmbed>,
+    pub attachments: Vec<Attachment>, // files can't be added when a message is edited
+    pub buttons: Vec<Button>, // in one row under the embeds
+}
+
+impl OutgoingMessage {
+    /**
+     * This method create a message containing only a text.
+     *
+     * @param content: impl Into<String>, the text of the message
+     *
+     * @return OutgoingMessage
+     */
+    pub fn text(content: impl Into<String>) -> OutgoingMessage {
+        OutgoingMessage { content: Some(content.into()), ..OutgoingMessage::default() }
+    }
+
+    /**
+     * This method create a message containing only an embed.
+     *
+     * @param embed: CreateEmbed, the embed of the message
+     *
+     * @return OutgoingMessage
+     */
+    pub fn embed(embed: CreateEmbed) -> OutgoingMessage {
+        OutgoingMessage { embeds: vec![embed], ..OutgoingMessage::default() }
+    }
+
+    /**
+     * This method add a file to the message.
+     *
+     * @param name: impl Into<String>, the name of the file
+     * @param data: Vec<u8>, the content of the file
+     *
+     * @return OutgoingMessage, used to chain operations
+     */
+    pub fn attach(mut self, name: impl Into<String>, data: Vec<u8>) -> OutgoingMessage {
+        self.attachments.push(Attachment { name: name.into(), data });
+        self
+    }
+}
+
+/*
+This enum describe every reason a message can't be posted or edited.
+ */
+#[derive(Debug)]
+pub enum MessengerError {
+    NotFound, // the channel, the message or the user doesn't exist anymore
+    Forbidden, // the bot is missing a permission in the channel, like manage webhooks
+    Discord(Box<serenity::Error>), // discord refused the request (permissions, network, ...)
+}
+
+impl Display for MessengerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessengerError::NotFound => write!(f, "The channel or the message doesn't exist anymore."),
+            MessengerError::Forbidden => write!(f, "The bot is missing a permission in the channel."),
+            MessengerError::Discord(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for MessengerError {}
+
+impl From<serenity::Error> for MessengerError {
+    fn from(err: serenity::Error) -> Self {
+        MessengerError::Discord(Box::new(err))
+    }
+}
+
+/**
+ * This trait is everything the bot post on discord outside of
+ * interaction responses. Commands and the scheduler only use it,
+ * so they can run without a discord connection.
+ * Ids of channels, messages and users are discord ids.
+ */
+#[async_trait]
+pub trait Messenger: Send + Sync {
+    /**
+     * This method post a message in a channel.
+     *
+     * @param channel: u64, the channel (or thread) where the message is posted
+     * @param message: OutgoingMessage, the message to post
+     *
+     * @return Result<u64, MessengerError>, the id of the message posted
+     */
+    async fn send(&self, channel: u64, message: OutgoingMessage) -> Result<u64, MessengerError>;
+
+    /**
+     * This method post a message in a channel through the webhook of the bot,
+     * with an other name and avatar. The webhook is created the first time.
+     *
+     * @param channel: u64, the channel where the message is posted
+     * @param name: &str, the name displayed as author of the message
+     * @param avatar_url: Option<&str>, the avatar displayed. None: the default avatar of webhooks
+     * @param message: OutgoingMessage, the message to post, buttons are ignored
+     *
+     * @return Result<u64, MessengerError>, the id of the message posted. Forbidden if the bot can't manage webhooks
+     */
+    async fn send_as(&self, channel: u64, name: &str, avatar_url: Option<&str>, message: OutgoingMessage) -> Result<u64, MessengerError>;
+
+    /**
+     * This method replace the content of a message posted by the bot, or by its webhook.
+     *
+     * @param channel: u64, the channel of the message
+     * @param message: u64, the message to edit
+     * @param edit: OutgoingMessage, the new content and embeds
+     *
+     * @return Result<(), MessengerError>, NotFound if the message has been deleted
+     */
+    async fn edit(&self, channel: u64, message: u64, edit: OutgoingMessage) -> Result<(), MessengerError>;
+
+    /**
+     * This method start a public thread from a message.
+     *
+     * @param channel: u64, the channel of the message
+     * @param message: u64, the message starting the thread
+     * @param name: &str, the name of the thread
+     *
+     * @return Result<u64, MessengerError>, the id of the thread, used as a channel
+     */
+    async fn create_thread(&self, channel: u64, message: u64, name: &str) -> Result<u64, MessengerError>;
+
+    /**
+     * This method send a direct message to a user.
+     *
+     * @param user: u64, the user receiving the message
+     * @param message: OutgoingMessage, the message to send
+     *
+     * @return Result<u64, MessengerError>, the id of the message sent
+     */
+    async fn send_dm(&self, user: u64, message: OutgoingMessage) -> Result<u64, MessengerError>;
+}
+
+/*
+This structure post the reports of the bot to administrators,
+in the channel set by ADMIN_CHANNEL_ID.
+ */
+#[derive(Clone)]
+pub struct AdminChannel {
+    messenger: Arc<dyn Messenger>,
+    channel: u64, // the admin channel
+}
+
+impl AdminChannel {
+    /**
+     * This method create the reports posted in a channel.
+     *
+     * @param messenger: Arc<dyn Messenger>, used to post the reports
+     * @param channel: u64, the admin channel
+     *
+     * @return AdminChannel
+     */
+    pub fn new(messenger: Arc<dyn Messenger>, channel: u64) -> AdminChannel {
+        AdminChannel { messenger, channel }
+    }
+
+    /**
+     * This method post a failure in the admin channel.
+     * A report that can't be posted is only logged.
+     *
+     * @param title: &str, what failed
+     * @param description: &str, the reason of the failure
+     */
+    pub async fn report(&self, title: &str, description: &str) {
+        let mut embed = CreateEmbed::default();
+        embed.title(title).description(description).color(Color::from_rgb(255, 0, 0));
+        if let Err(why) = self.messenger.send(self.channel, OutgoingMessage::embed(embed)).await {
+            error!("Failed to report \"{}\" in the admin channel {}. Error:\n{}", title, self.channel, why);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use serenity::async_trait;
+use crate::messenger::{Messenger, MessengerError, OutgoingMessage};
+
+/*
+This enum is a request received by the recording messenger.
+ */
+#[derive(Debug, Clone)]
+pub enum Outbound {
+    Sent { channel: u64, message: u64, content: OutgoingMessage },
+    SentAs { channel: u64, message: u64, name: String, avatar_url: Option<String>, content: OutgoingMessage }, // posted through a webhook
+    Edited { channel: u64, message: u64, content: OutgoingMessage },
+    ThreadCreated { channel: u64, message: u64, thread: u64, name: String },
+    DirectMessage { user: u64, message: u64, content: OutgoingMessage },
+}
+
+/*
+This structure hold every request and message of the recording messenger.
+Ids of messages and threads are given in order, starting at 1.
+ */
+#[derive(Default)]
+struct Recording {
+    outbound: Vec<Outbound>,
+    messages: HashSet<u64>, // messages that still exist, they can be edited
+    last_id: u64,
+    unavailable: bool, // every request fail, like during a discord outage
+    webhooks_denied: bool, // the bot can't manage the webhooks of any channel
+}
+
+/*
+This structure record what the bot would post on discord,
+without any connection. It's used to test commands and the scheduler.
+ */
+#[derive(Default)]
+pub struct RecordingMessenger {
+    recording: Mutex<Recording>,
+}
+
+impl RecordingMessenger {
+    pub fn new() -> RecordingMessenger {
+        RecordingMessenger::default()
+    }
+
+    /**
+     * This method return every request received, in order.
+     *
+     * @return Vec<Outbound>
+     */
+    pub fn outbound(&self) -> Vec<Outbound> {
+        self.recording.lock().unwrap().outbound.clone()
+    }
+
+    /**
+     * This method return the messages posted in a channel, in order.
+     *
+     * @param channel: u64, the channel
+     *
+     * @return Vec<OutgoingMessage>
+     */
+    pub fn sent_to(&self, channel: u64) -> Vec<OutgoingMessage> {
+        self.outbound().into_iter()
+            .filter_map(|outbound| match outbound {
+                Outbound::Sent { channel: sent_channel, content, .. } if sent_channel == channel => Some(content),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /**
+     * This method delete a message, like a moderator would.
+     * Editing it then fails with NotFound.
+     *
+     * @param message: u64, the message to delete
+     */
+    pub fn delete_message(&self, message: u64) {
+        self.recording.lock().unwrap().messages.remove(&message);
+    }
+
+    /**
+     * This method make every request fail, or succeed again.
+     *
+     * @param unavailable: bool, true to simulate a discord outage
+     */
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.recording.lock().unwrap().unavailable = unavailable;
+    }
+
+    /**
+     * This method remove the permission to manage webhooks, or give it back.
+     * Messages sent through a webhook then fail with Forbidden.
+     *
+     * @param denied: bool, true to simulate a missing permission
+     */
+    pub fn set_webhooks_denied(&self, denied: bool) {
+        self.recording.lock().unwrap().webhooks_denied = denied;
+    }
+
+    /**
+     * This method record a request and return the new id it creates.
+     *
+     * @param outbound: impl FnOnce(u64) -> Outbound, build the request from the new id
+     *
+     * @return Result<u64, MessengerError>
+     */
+    fn record(&self, outbound: impl FnOnce(u64) -> Outbound) -> Result<u64, MessengerError> {
+        let mut recording = self.recording.lock().unwrap();
+        if recording.unavailable {
+            return Err(MessengerError::from(serenity::Error::Other("discord is unavailable")));
+        }
+        recording.last_id += 1;
+        let id = recording.last_id;
+        recording.messages.insert(id);
+        recording.outbound.push(outbound(id));
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl Messenger for RecordingMessenger {
+    async fn send(&self, channel: u64, message: OutgoingMessage) -> Result<u64, MessengerError> {
+        self.record(|id| Outbound::Sent { channel, message: id, content: message })
+    }
+
+    async fn send_as(&self, channel: u64, name: &str, avatar_url: Option<&str>, message: OutgoingMessage) -> Result<u64, MessengerError> {
+        if self.recording.lock().unwrap().webhooks_denied {
+            return Err(MessengerError::Forbidden);
+        }
+        self.record(|id| Outbound::SentAs {
+            channel,
+            message: id,
+            name: name.to_string(),
+            avatar_url: avatar_url.map(str::to_string),
+            content: message,
+        })
+    }
+
+    async fn edit(&self, channel: u64, message: u64, edit: OutgoingMessage) -> Result<(), MessengerError> {
+        let mut recording = self.recording.lock().unwrap();
+        if recording.unavailable {
+            return Err(MessengerError::from(serenity::Error::Other("discord is unavailable")));
+        }
+        if !recording.messages.contains(&message) {
+            return Err(MessengerError::NotFound);
+        }
+        recording.outbound.push(Outbound::Edited { channel, message, content: edit });
+        Ok(())
+    }
+
+    async fn create_thread(&self, channel: u64, message: u64, name: &str) -> Result<u64, MessengerError> {
+        if !self.recording.lock().unwrap().messages.contains(&message) {
+            return Err(MessengerError::NotFound);
+        }
+        self.record(|thread| Outbound::ThreadCreated { channel, message, thread, name: name.to_string() })
+    }
+
+    async fn send_dm(&self, user: u64, message: OutgoingMessage) -> Result<u64, MessengerError> {
+        self.record(|id| Outbound::DirectMessage { user, message: id, content: message })
+    }
+}
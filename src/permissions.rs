@@ -0,0 +1,145 @@
+use std::fmt::{Display, Formatter};
+use serde::{Serialize, Serializer};
+use serenity::model::Permissions;
+use crate::error::BotError;
+use crate::repositories::PermissionRepository;
+
+// Discord permission needed to see the commands reserved to a capability by default.
+// Roles granted a capability have no discord permission in common : every member
+// who can write sees them, the bot checks the capability before running them.
+pub const CAPABILITY_COMMAND_PERMISSIONS: Permissions = Permissions::SEND_MESSAGES;
+// Discord permission needed to see the commands reserved to managers by default
+pub const MANAGER_COMMAND_PERMISSIONS: Permissions = Permissions::MANAGE_GUILD;
+
+/*
+This enum is an action of the bot that can be granted to roles
+with the command /permissions.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    TipsWrite, // create and update tips
+    TipsDelete, // delete tips
+    SchedulerManage, // configure, start and stop the tips scheduler
+    CooldownBypass, // use commands without being limited by their cooldowns
+}
+
+impl Capability {
+    // Every capability, in the order they are displayed
+    pub const ALL: [Capability; 4] = [Capability::TipsWrite, Capability::TipsDelete, Capability::SchedulerManage, Capability::CooldownBypass];
+
+    /**
+     * This method return the name of the capability, as stored and typed by users.
+     *
+     * @return &'static str, like tips.write
+     */
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::TipsWrite => "tips.write",
+            Capability::TipsDelete => "tips.delete",
+            Capability::SchedulerManage => "scheduler.manage",
+            Capability::CooldownBypass => "cooldowns.bypass",
+        }
+    }
+
+    /**
+     * This method return the capability with the given name.
+     *
+     * @param name: &str, the name of the capability, like tips.write
+     *
+     * @return Option<Capability>, None if the name is unknown
+     */
+    pub fn parse(name: &str) -> Option<Capability> {
+        Capability::ALL.into_iter().find(|capability| capability.as_str() == name)
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Stored and displayed by its name, like tips.write
+impl Serialize for Capability {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/*
+This enum is who can run a command. It's checked by the registry
+before the command is executed.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Everyone, // every member of the guild
+    Capability(Capability), // managers and the roles granted the capability
+    Managers, // only managers
+}
+
+/*
+This structure is the member who run a command.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Invoker {
+    pub user_id: u64,
+    pub roles: Vec<u64>, // ids of the roles of the member, without @everyone
+    pub channel_id: u64, // the channel where the command has been run
+    pub manager: bool, // the member is administrator or can manage the guild : every command is allowed
+}
+
+impl Invoker {
+    /**
+     * This method return true if discord permissions of the member
+     * make them a manager of the bot.
+     *
+     * @param permissions: Permissions, the permissions of the member in the channel
+     *
+     * @return bool
+     */
+    pub fn is_manager(permissions: Permissions) -> bool {
+        permissions.administrator() || permissions.manage_guild()
+    }
+
+    /**
+     * This method compute the permissions of a member in the guild from its roles.
+     * Discord send them with interactions, not with messages.
+     *
+     * @param owner: bool, the member owns the guild : every permission is granted
+     * @param everyone: Permissions, the permissions of the @everyone role
+     * @param roles: &[Permissions], the permissions of the other roles of the member
+     *
+     * @return Permissions
+     */
+    pub fn guild_permissions(owner: bool, everyone: Permissions, roles: &[Permissions]) -> Permissions {
+        if owner {
+            return Permissions::all();
+        }
+        roles.iter().fold(everyone, |permissions, role| permissions | *role)
+    }
+}
+
+/**
+ * This method check that the member can run a command.
+ * The id of the @everyone role is the id of the guild : a capability
+ * granted to it is granted to every member.
+ *
+ * @param permissions: &dyn PermissionRepository, the roles granted each capability
+ * @param invoker: &Invoker, the member who run the command
+ * @param access: Access, who can run the command
+ * @param guild_id: u64, the id of the guild where the command has been run.
+ *
+ * @return Result<(), BotError>, MissingCapability or ManagersOnly if the member can't run the command
+ */
+pub async fn authorize(permissions: &dyn PermissionRepository, invoker: &Invoker, access: Access, guild_id: u64) -> Result<(), BotError> {
+    match access {
+        Access::Everyone => Ok(()),
+        _ if invoker.manager => Ok(()),
+        Access::Managers => Err(BotError::ManagersOnly),
+        Access::Capability(capability) => {
+            let granted = permissions.list(guild_id).await?.into_iter()
+                .any(|grant| grant.capability == capability && (grant.role_id == guild_id || invoker.roles.contains(&grant.role_id)));
+            if granted { Ok(()) } else { Err(BotError::MissingCapability(capability)) }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::repositories::{PrefixRepository, RepositoryError};
+
+// Prefix of the text commands of guilds that haven't chosen one
+pub const DEFAULT_PREFIX: &str = "!";
+
+// Longest prefix a guild can choose
+pub const MAX_PREFIX_LENGTH: usize = 5;
+
+/*
+This structure give the prefix of the text commands of each guild.
+Every message is checked : prefixes are read once from the storage
+and kept until they are changed.
+ */
+pub struct Prefixes {
+    storage: Arc<dyn PrefixRepository>,
+    cache: Mutex<HashMap<u64, String>>, // the prefix of each guild already read
+}
+
+impl Prefixes {
+    /**
+     * This method create the prefixes of every guild.
+     *
+     * @param storage: Arc<dyn PrefixRepository>, the storage of the prefixes chosen by guilds
+     *
+     * @return Prefixes
+     */
+    pub fn new(storage: Arc<dyn PrefixRepository>) -> Prefixes {
+        Prefixes { storage, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /**
+     * This method return the prefix of the text commands of the guild.
+     *
+     * @param guild_id: u64, the guild of the prefix
+     *
+     * @return Result<String, RepositoryError>, the default prefix if the guild hasn't chosen one
+     */
+    pub async fn get(&self, guild_id: u64) -> Result<String, RepositoryError> {
+        if let Some(prefix) = self.cache.lock().unwrap().get(&guild_id) {
+            return Ok(prefix.clone());
+        }
+        let prefix = self.storage.prefix(guild_id).await?.unwrap_or_else(|| String::from(DEFAULT_PREFIX));
+        self.cache.lock().unwrap().insert(guild_id, prefix.clone());
+        Ok(prefix)
+    }
+
+    /**
+     * This method set the prefix of the text commands of the guild.
+     *
+     * @param guild_id: u64, the guild of the prefix
+     * @param prefix: Option<String>, the prefix or None to use the default prefix
+     *
+     * @return Result<String, RepositoryError>, the prefix replaced
+     */
+    pub async fn set(&self, guild_id: u64, prefix: Option<String>) -> Result<String, RepositoryError> {
+        let previous = self.get(guild_id).await?;
+        let result = self.storage.set_prefix(guild_id, prefix).await;
+        self.cache.lock().unwrap().remove(&guild_id);
+        result.map(|_| previous)
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use serenity::async_trait;
+use crate::cooldowns::CooldownScope;
+use crate::repositories::{AuditEntry, AuditFilter, AuditRepository, CooldownRepository, CooldownRule, NewAuditEntry, NewTip, PermissionGrant, PermissionRepository, PrefixRepository, RepositoryError, SchedulerConfig, SchedulerRepository, Tip, TipChanges, TipPost, TipRepository};
+
+/*
+This structure hold every tip and post of the in-memory storage.
+Ids are given in order, like the AUTOINCREMENT of sqlite.
+ */
+#[derive(Default)]
+struct TipStore {
+    tips: Vec<(u64, Tip)>, // the guild of the tip and the tip
+    posts: Vec<TipPost>,
+    last_tip_id: u64,
+    last_post_id: u64,
+}
+
+/*
+This structure store tips in memory. Nothing is saved:
+it's used to run commands without a database file.
+ */
+#[derive(Default)]
+pub struct InMemoryTipRepository {
+    store: Mutex<TipStore>,
+}
+
+impl InMemoryTipRepository {
+    pub fn new() -> InMemoryTipRepository {
+        InMemoryTipRepository::default()
+    }
+}
+
+#[async_trait]
+impl TipRepository for InMemoryTipRepository {
+    async fn list(&self, guild_id: u64, tags: &[String]) -> Result<Vec<Tip>, RepositoryError> {
+        let store = self.store.lock().unwrap();
+        Ok(store.tips.iter()
+            .filter(|(guild, tip)| *guild == guild_id && (tags.is_empty() || tags.iter().any(|tag| tip.tags.contains(tag.as_str()))))
+            .map(|(_, tip)| tip.clone())
+            .collect())
+    }
+
+    async fn get(&self, guild_id: u64, id: u64) -> Result<Option<Tip>, RepositoryError> {
+        let store = self.store.lock().unwrap();
+        Ok(store.tips.iter()
+            .find(|(guild, tip)| *guild == guild_id && tip.id == id)
+            .map(|(_, tip)| tip.clone()))
+    }
+
+    async fn create(&self, guild_id: u64, tip: NewTip) -> Result<Tip, RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+        if store.tips.iter().any(|(guild, other)| *guild == guild_id && other.title == tip.title) {
+            return Err(RepositoryError::DuplicateTitle(tip.title));
+        }
+
+        store.last_tip_id += 1;
+        let created = Tip { id: store.last_tip_id, title: tip.title, content: tip.content, tags: tip.tags };
+        store.tips.push((guild_id, created.clone()));
+        Ok(created)
+    }
+
+    async fn update(&self, guild_id: u64, id: u64, changes: TipChanges) -> Result<Option<Tip>, RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+        if let Some(title) = &changes.title {
+            if store.tips.iter().any(|(guild, other)| *guild == guild_id && other.id != id && &other.title == title) {
+                return Err(RepositoryError::DuplicateTitle(title.to_owned()));
+            }
+        }
+
+        Ok(store.tips.iter_mut()
+            .find(|(guild, tip)| *guild == guild_id && tip.id == id)
+            .map(|(_, tip)| {
+                if let Some(title) = changes.title {
+                    tip.title = title;
+                }
+                if let Some(content) = changes.content {
+                    tip.content = content;
+                }
+                if let Some(tags) = changes.tags {
+                    tip.tags = tags;
+                }
+                tip.clone()
+            }))
+    }
+
+    async fn delete(&self, guild_id: u64, id: u64) -> Result<bool, RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+        let count = store.tips.len();
+        store.tips.retain(|(guild, tip)| !(*guild == guild_id && tip.id == id));
+        let deleted = store.tips.len() != count;
+        if deleted {
+            store.posts.retain(|post| post.tip_id != id);
+        }
+        Ok(deleted)
+    }
+
+    async fn save_post(&self, tip_id: u64, channel: u64, message: u64) -> Result<(), RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+        store.last_post_id += 1;
+        let id = store.last_post_id;
+        store.posts.push(TipPost { id, tip_id, channel, message });
+        Ok(())
+    }
+
+    async fn posts(&self, tip_id: u64) -> Result<Vec<TipPost>, RepositoryError> {
+        let store = self.store.lock().unwrap();
+        Ok(store.posts.iter().filter(|post| post.tip_id == tip_id).cloned().collect())
+    }
+
+    async fn forget_posts(&self, post_ids: &[u64]) -> Result<(), RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+        store.posts.retain(|post| !post_ids.contains(&post.id));
+        Ok(())
+    }
+}
+
+/*
+This structure store the scheduler configurations in memory.
+ */
+#[derive(Default)]
+pub struct InMemorySchedulerRepository {
+    configs: Mutex<HashMap<u64, SchedulerConfig>>,
+}
+
+impl InMemorySchedulerRepository {
+    pub fn new() -> InMemorySchedulerRepository {
+        InMemorySchedulerRepository::default()
+    }
+}
+
+#[async_trait]
+impl SchedulerRepository for InMemorySchedulerRepository {
+    async fn get(&self, guild_id: u64) -> Result<Option<SchedulerConfig>, RepositoryError> {
+        Ok(self.configs.lock().unwrap().get(&guild_id).cloned())
+    }
+
+    async fn save(&self, guild_id: u64, config: SchedulerConfig) -> Result<(), RepositoryError> {
+        self.configs.lock().unwrap().insert(guild_id, config);
+        Ok(())
+    }
+}
+
+/*
+This structure store the capabilities granted to roles in memory.
+ */
+#[derive(Default)]
+pub struct InMemoryPermissionRepository {
+    grants: Mutex<Vec<(u64, PermissionGrant)>>, // the guild of the role and the grant
+}
+
+impl InMemoryPermissionRepository {
+    pub fn new() -> InMemoryPermissionRepository {
+        InMemoryPermissionRepository::default()
+    }
+}
+
+#[async_trait]
+impl PermissionRepository for InMemoryPermissionRepository {
+    async fn list(&self, guild_id: u64) -> Result<Vec<PermissionGrant>, RepositoryError> {
+        let mut grants: Vec<PermissionGrant> = self.grants.lock().unwrap().iter()
+            .filter(|(guild, _)| *guild == guild_id)
+            .map(|(_, grant)| grant.clone())
+            .collect();
+        grants.sort_by_key(|grant| grant.role_id);
+        Ok(grants)
+    }
+
+    async fn grant(&self, guild_id: u64, grant: PermissionGrant) -> Result<bool, RepositoryError> {
+        let mut grants = self.grants.lock().unwrap();
+        if grants.iter().any(|(guild, other)| *guild == guild_id && *other == grant) {
+            return Ok(false);
+        }
+        grants.push((guild_id, grant));
+        Ok(true)
+    }
+
+    async fn revoke(&self, guild_id: u64, grant: PermissionGrant) -> Result<bool, RepositoryError> {
+        let mut grants = self.grants.lock().unwrap();
+        let count = grants.len();
+        grants.retain(|(guild, other)| !(*guild == guild_id && *other == grant));
+        Ok(grants.len() != count)
+    }
+}
+
+/*
+This structure store the cooldown rules in memory.
+ */
+#[derive(Default)]
+pub struct InMemoryCooldownRepository {
+    rules: Mutex<Vec<(u64, CooldownRule)>>, // the guild of the rule and the rule
+}
+
+impl InMemoryCooldownRepository {
+    pub fn new() -> InMemoryCooldownRepository {
+        InMemoryCooldownRepository::default()
+    }
+}
+
+#[async_trait]
+impl CooldownRepository for InMemoryCooldownRepository {
+    async fn list(&self, guild_id: u64) -> Result<Vec<CooldownRule>, RepositoryError> {
+        let mut rules: Vec<CooldownRule> = self.rules.lock().unwrap().iter()
+            .filter(|(guild, _)| *guild == guild_id)
+            .map(|(_, rule)| rule.clone())
+            .collect();
+        rules.sort_by(|a, b| (&a.command, a.scope.as_str()).cmp(&(&b.command, b.scope.as_str())));
+        Ok(rules)
+    }
+
+    async fn set(&self, guild_id: u64, rule: CooldownRule) -> Result<Option<CooldownRule>, RepositoryError> {
+        let mut rules = self.rules.lock().unwrap();
+        match rules.iter_mut().find(|(guild, other)| *guild == guild_id && other.command == rule.command && other.scope == rule.scope) {
+            Some((_, other)) => Ok(Some(std::mem::replace(other, rule))),
+            None => {
+                rules.push((guild_id, rule));
+                Ok(None)
+            }
+        }
+    }
+
+    async fn remove(&self, guild_id: u64, command: &str, scope: CooldownScope) -> Result<Option<CooldownRule>, RepositoryError> {
+        let mut rules = self.rules.lock().unwrap();
+        let position = rules.iter().position(|(guild, rule)| *guild == guild_id && rule.command == command && rule.scope == scope);
+        Ok(position.map(|position| rules.remove(position).1))
+    }
+}
+
+/*
+This structure store the prefix of the text commands in memory.
+ */
+#[derive(Default)]
+pub struct InMemoryPrefixRepository {
+    prefixes: Mutex<HashMap<u64, String>>,
+}
+
+impl InMemoryPrefixRepository {
+    pub fn new() -> InMemoryPrefixRepository {
+        InMemoryPrefixRepository::default()
+    }
+}
+
+#[async_trait]
+impl PrefixRepository for InMemoryPrefixRepository {
+    async fn prefix(&self, guild_id: u64) -> Result<Option<String>, RepositoryError> {
+        Ok(self.prefixes.lock().unwrap().get(&guild_id).cloned())
+    }
+
+    async fn set_prefix(&self, guild_id: u64, prefix: Option<String>) -> Result<(), RepositoryError> {
+        let mut prefixes = self.prefixes.lock().unwrap();
+        match prefix {
+            Some(prefix) => prefixes.insert(guild_id, prefix),
+            None => prefixes.remove(&guild_id),
+        };
+        Ok(())
+    }
+}
+
+/*
+This structure store the audit log in memory.
+ */
+#[derive(Default)]
+pub struct InMemoryAuditRepository {
+    entries: Mutex<Vec<(u64, AuditEntry)>>, // the guild of the entry and the entry
+    mirror_channels: Mutex<HashMap<u64, u64>>,
+}
+
+impl InMemoryAuditRepository {
+    pub fn new() -> InMemoryAuditRepository {
+        InMemoryAuditRepository::default()
+    }
+}
+
+#[async_trait]
+impl AuditRepository for InMemoryAuditRepository {
+    async fn record(&self, guild_id: u64, entry: NewAuditEntry) -> Result<AuditEntry, RepositoryError> {
+        let mut entries = self.entries.lock().unwrap();
+        let recorded = AuditEntry {
+            id: entries.len() as u64 + 1,
+            actor: entry.actor,
+            command: entry.command,
+            arguments: entry.arguments,
+            before: entry.before,
+            after: entry.after,
+            created_at: entry.created_at,
+        };
+        entries.push((guild_id, recorded.clone()));
+        Ok(recorded)
+    }
+
+    async fn search(&self, guild_id: u64, filter: &AuditFilter) -> Result<Vec<AuditEntry>, RepositoryError> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries.iter().rev()
+            .filter(|(guild, entry)| *guild == guild_id
+                && filter.actor.is_none_or(|actor| entry.actor == actor)
+                && filter.command.as_ref().is_none_or(|command| &entry.command == command)
+                && filter.since.is_none_or(|since| entry.created_at >= since)
+                && filter.until.is_none_or(|until| entry.created_at < until))
+            .map(|(_, entry)| entry.clone())
+            .collect())
+    }
+
+    async fn mirror_channel(&self, guild_id: u64) -> Result<Option<u64>, RepositoryError> {
+        Ok(self.mirror_channels.lock().unwrap().get(&guild_id).copied())
+    }
+
+    async fn set_mirror_channel(&self, guild_id: u64, channel: Option<u64>) -> Result<(), RepositoryError> {
+        let mut mirror_channels = self.mirror_channels.lock().unwrap();
+        match channel {
+            Some(channel) => mirror_channels.insert(guild_id, channel),
+            None => mirror_channels.remove(&guild_id),
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,452 @@
+pub mod sqlite;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod memory;
+
+use std::fmt::{Display, Formatter};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::async_trait;
+use crate::cooldowns::CooldownScope;
+use crate::permissions::Capability;
+
+/*
+This structure is a tip as it is stored,
+whatever the storage used.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Tip {
+    pub id: u64,
+    pub title: String,
+    pub content: String,
+    pub tags: String,
+}
+
+/*
+This structure hold the values of a tip to create.
+It's read from the files imported with gamebot-admin.
+ */
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewTip {
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: String,
+}
+
+/*
+This structure hold the values of a tip to update.
+A None value keep the current one.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct TipChanges {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub tags: Option<String>,
+}
+
+/*
+This structure is a message where a tip has been posted.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TipPost {
+    pub id: u64,
+    pub tip_id: u64,
+    pub channel: u64,
+    pub message: u64,
+}
+
+/*
+This structure is the configuration of the tips scheduler of a guild.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SchedulerConfig {
+    pub channel: u64,
+    pub hour: u32,
+    pub minute: u32,
+    pub persona: Option<Persona>, // tips are posted through a webhook with this name and avatar. None: as the bot
+}
+
+/*
+This structure is the name and the avatar of the webhook posting the tips of a scheduler.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Persona {
+    pub name: String, // like Tip of the Day
+    pub avatar_url: Option<String>, // None: the default avatar of webhooks
+}
+
+/*
+This structure is a capability granted to a role of a guild.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PermissionGrant {
+    pub role_id: u64, // the id of the guild for @everyone
+    pub capability: Capability,
+}
+
+/*
+This structure is a limit of the uses of a command : at most `uses`
+uses every `period` seconds, counted per user, channel or guild.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CooldownRule {
+    pub command: String, // the name of the command, without /
+    pub scope: CooldownScope, // who shares the uses
+    pub uses: u32,
+    pub period: u64, // seconds
+}
+
+/*
+This structure is a change made by a command, as written in the audit log.
+Values before and after the change are JSON, None when there is nothing
+before (a creation) or after (a deletion).
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub actor: u64, // the id of the user who run the command
+    pub command: String,
+    pub arguments: String, // the options given to the command, see CommandOptions::describe
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/*
+This structure hold the values of an audit entry to write.
+ */
+#[derive(Clone, Debug)]
+pub struct NewAuditEntry {
+    pub actor: u64,
+    pub command: String,
+    pub arguments: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/*
+This structure select audit entries. A None value doesn't filter.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct AuditFilter {
+    pub actor: Option<u64>,
+    pub command: Option<String>,
+    pub since: Option<DateTime<Utc>>, // included
+    pub until: Option<DateTime<Utc>>, // excluded
+}
+
+/**
+ * This method format a date of the audit log. Dates are stored
+ * as text and compared as text : they must all have the same format.
+ *
+ * @param date: DateTime<Utc>, the date to store
+ *
+ * @return String, like 2024-01-15T12:19:30Z
+ */
+pub fn audit_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/*
+This enum describe every reason a repository can fail.
+ */
+#[derive(Debug)]
+pub enum RepositoryError {
+    Database(tokio_rusqlite::Error), // the query failed
+    #[cfg(feature = "postgres")]
+    Postgres(tokio_postgres::Error), // the query on the PostgreSQL database failed
+    DuplicateTitle(String), // a tip of the guild already has this title
+}
+
+impl Display for RepositoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepositoryError::Database(err) => write!(f, "{}", err),
+            #[cfg(feature = "postgres")]
+            RepositoryError::Postgres(err) => write!(f, "{}", err),
+            RepositoryError::DuplicateTitle(title) => write!(f, "A tip with the title \"{}\" already exists.", title),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+impl From<tokio_rusqlite::Error> for RepositoryError {
+    fn from(err: tokio_rusqlite::Error) -> Self {
+        RepositoryError::Database(err)
+    }
+}
+
+impl From<rusqlite::Error> for RepositoryError {
+    fn from(err: rusqlite::Error) -> Self {
+        RepositoryError::Database(tokio_rusqlite::Error::Rusqlite(err))
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<tokio_postgres::Error> for RepositoryError {
+    fn from(err: tokio_postgres::Error) -> Self {
+        RepositoryError::Postgres(err)
+    }
+}
+
+/**
+ * This trait is the storage of tips and of the messages
+ * where they have been posted. Tips are scoped by guild.
+ */
+#[async_trait]
+pub trait TipRepository: Send + Sync {
+    /**
+     * This method return the tips of the guild.
+     *
+     * @param guild_id: u64, the guild of the tips
+     * @param tags: &[String], only return tips having one of these tags. Every tip if empty.
+     *
+     * @return Result<Vec<Tip>, RepositoryError>
+     */
+    async fn list(&self, guild_id: u64, tags: &[String]) -> Result<Vec<Tip>, RepositoryError>;
+
+    /**
+     * This method return a tip of the guild.
+     *
+     * @param guild_id: u64, the guild of the tip
+     * @param id: u64, the id of the tip
+     *
+     * @return Result<Option<Tip>, RepositoryError>, None if the tip doesn't exist
+     */
+    async fn get(&self, guild_id: u64, id: u64) -> Result<Option<Tip>, RepositoryError>;
+
+    /**
+     * This method create a new tip in the guild.
+     *
+     * @param guild_id: u64, the guild of the tip
+     * @param tip: NewTip, the values of the tip
+     *
+     * @return Result<Tip, RepositoryError>, the tip created
+     */
+    async fn create(&self, guild_id: u64, tip: NewTip) -> Result<Tip, RepositoryError>;
+
+    /**
+     * This method update a tip of the guild.
+     *
+     * @param guild_id: u64, the guild of the tip
+     * @param id: u64, the id of the tip
+     * @param changes: TipChanges, the new values
+     *
+     * @return Result<Option<Tip>, RepositoryError>, the tip updated or None if it doesn't exist
+     */
+    async fn update(&self, guild_id: u64, id: u64, changes: TipChanges) -> Result<Option<Tip>, RepositoryError>;
+
+    /**
+     * This method delete a tip of the guild and forget where it has been posted.
+     *
+     * @param guild_id: u64, the guild of the tip
+     * @param id: u64, the id of the tip
+     *
+     * @return Result<bool, RepositoryError>, false if the tip doesn't exist
+     */
+    async fn delete(&self, guild_id: u64, id: u64) -> Result<bool, RepositoryError>;
+
+    /**
+     * This method save the location of a message containing a tip.
+     *
+     * @param tip_id: u64, the id of the tip posted
+     * @param channel: u64, the id of the channel where the tip has been posted
+     * @param message: u64, the id of the message containing the tip
+     *
+     * @return Result<(), RepositoryError>
+     */
+    async fn save_post(&self, tip_id: u64, channel: u64, message: u64) -> Result<(), RepositoryError>;
+
+    /**
+     * This method return every message where the tip has been posted.
+     *
+     * @param tip_id: u64, the id of the tip
+     *
+     * @return Result<Vec<TipPost>, RepositoryError>
+     */
+    async fn posts(&self, tip_id: u64) -> Result<Vec<TipPost>, RepositoryError>;
+
+    /**
+     * This method forget messages where a tip has been posted.
+     *
+     * @param post_ids: &[u64], the ids of the posts to forget
+     *
+     * @return Result<(), RepositoryError>
+     */
+    async fn forget_posts(&self, post_ids: &[u64]) -> Result<(), RepositoryError>;
+}
+
+/**
+ * This trait is the storage of the configuration
+ * of the tips scheduler of every guild.
+ */
+#[async_trait]
+pub trait SchedulerRepository: Send + Sync {
+    /**
+     * This method return the configuration of the guild.
+     *
+     * @param guild_id: u64, the guild of the configuration
+     *
+     * @return Result<Option<SchedulerConfig>, RepositoryError>, None if the guild is not configured yet
+     */
+    async fn get(&self, guild_id: u64) -> Result<Option<SchedulerConfig>, RepositoryError>;
+
+    /**
+     * This method create or replace the configuration of the guild.
+     *
+     * @param guild_id: u64, the guild of the configuration
+     * @param config: SchedulerConfig, the new configuration
+     *
+     * @return Result<(), RepositoryError>
+     */
+    async fn save(&self, guild_id: u64, config: SchedulerConfig) -> Result<(), RepositoryError>;
+}
+
+/**
+ * This trait is the storage of the capabilities
+ * granted to the roles of every guild.
+ */
+#[async_trait]
+pub trait PermissionRepository: Send + Sync {
+    /**
+     * This method return every capability granted in the guild,
+     * ordered by role. Unknown capabilities are ignored.
+     *
+     * @param guild_id: u64, the guild of the roles
+     *
+     * @return Result<Vec<PermissionGrant>, RepositoryError>
+     */
+    async fn list(&self, guild_id: u64) -> Result<Vec<PermissionGrant>, RepositoryError>;
+
+    /**
+     * This method grant a capability to a role.
+     *
+     * @param guild_id: u64, the guild of the role
+     * @param grant: PermissionGrant, the role and the capability
+     *
+     * @return Result<bool, RepositoryError>, false if the role already had the capability
+     */
+    async fn grant(&self, guild_id: u64, grant: PermissionGrant) -> Result<bool, RepositoryError>;
+
+    /**
+     * This method revoke a capability from a role.
+     *
+     * @param guild_id: u64, the guild of the role
+     * @param grant: PermissionGrant, the role and the capability
+     *
+     * @return Result<bool, RepositoryError>, false if the role didn't have the capability
+     */
+    async fn revoke(&self, guild_id: u64, grant: PermissionGrant) -> Result<bool, RepositoryError>;
+}
+
+/**
+ * This trait is the storage of the cooldown rules of every guild.
+ * There is at most one rule per command and scope.
+ */
+#[async_trait]
+pub trait CooldownRepository: Send + Sync {
+    /**
+     * This method return every rule of the guild, ordered by command.
+     * Rules with an unknown scope are ignored.
+     *
+     * @param guild_id: u64, the guild of the rules
+     *
+     * @return Result<Vec<CooldownRule>, RepositoryError>
+     */
+    async fn list(&self, guild_id: u64) -> Result<Vec<CooldownRule>, RepositoryError>;
+
+    /**
+     * This method save a rule, replacing the rule of the same command and scope.
+     *
+     * @param guild_id: u64, the guild of the rule
+     * @param rule: CooldownRule, the rule to save
+     *
+     * @return Result<Option<CooldownRule>, RepositoryError>, the rule replaced
+     */
+    async fn set(&self, guild_id: u64, rule: CooldownRule) -> Result<Option<CooldownRule>, RepositoryError>;
+
+    /**
+     * This method delete the rule of a command and a scope.
+     *
+     * @param guild_id: u64, the guild of the rule
+     * @param command: &str, the name of the command
+     * @param scope: CooldownScope, the scope of the rule
+     *
+     * @return Result<Option<CooldownRule>, RepositoryError>, the rule deleted, None if there was none
+     */
+    async fn remove(&self, guild_id: u64, command: &str, scope: CooldownScope) -> Result<Option<CooldownRule>, RepositoryError>;
+}
+
+/**
+ * This trait is the storage of the prefix of the text commands of every guild.
+ */
+#[async_trait]
+pub trait PrefixRepository: Send + Sync {
+    /**
+     * This method return the prefix chosen by the guild.
+     *
+     * @param guild_id: u64, the guild of the prefix
+     *
+     * @return Result<Option<String>, RepositoryError>, None if the guild uses the default prefix
+     */
+    async fn prefix(&self, guild_id: u64) -> Result<Option<String>, RepositoryError>;
+
+    /**
+     * This method set the prefix of the guild.
+     *
+     * @param guild_id: u64, the guild of the prefix
+     * @param prefix: Option<String>, the prefix or None to use the default prefix
+     *
+     * @return Result<(), RepositoryError>
+     */
+    async fn set_prefix(&self, guild_id: u64, prefix: Option<String>) -> Result<(), RepositoryError>;
+}
+
+/**
+ * This trait is the storage of the audit log : every change made
+ * by a command, and where it is mirrored in each guild.
+ */
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    /**
+     * This method write an entry in the audit log of the guild.
+     *
+     * @param guild_id: u64, the guild where the command has been run
+     * @param entry: NewAuditEntry, the change made
+     *
+     * @return Result<AuditEntry, RepositoryError>, the entry written
+     */
+    async fn record(&self, guild_id: u64, entry: NewAuditEntry) -> Result<AuditEntry, RepositoryError>;
+
+    /**
+     * This method return the entries of the guild, the most recent first.
+     *
+     * @param guild_id: u64, the guild of the entries
+     * @param filter: &AuditFilter, the entries to return
+     *
+     * @return Result<Vec<AuditEntry>, RepositoryError>
+     */
+    async fn search(&self, guild_id: u64, filter: &AuditFilter) -> Result<Vec<AuditEntry>, RepositoryError>;
+
+    /**
+     * This method return the channel where new entries of the guild are mirrored.
+     *
+     * @param guild_id: u64, the guild of the channel
+     *
+     * @return Result<Option<u64>, RepositoryError>, None if entries are not mirrored
+     */
+    async fn mirror_channel(&self, guild_id: u64) -> Result<Option<u64>, RepositoryError>;
+
+    /**
+     * This method set the channel where new entries of the guild are mirrored.
+     *
+     * @param guild_id: u64, the guild of the channel
+     * @param channel: Option<u64>, the channel or None to stop mirroring
+     *
+     * @return Result<(), RepositoryError>
+     */
+    async fn set_mirror_channel(&self, guild_id: u64, channel: Option<u64>) -> Result<(), RepositoryError>;
+}
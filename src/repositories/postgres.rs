@@ -0,0 +1,402 @@
+use chrono::{DateTime, Utc};
+use log::warn;
+use serenity::async_trait;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::Row;
+use crate::database::postgres::PostgresDatabase;
+use crate::cooldowns::CooldownScope;
+use crate::permissions::Capability;
+use crate::repositories::{audit_date, AuditEntry, AuditFilter, AuditRepository, CooldownRepository, CooldownRule, NewAuditEntry, NewTip, PermissionGrant, PermissionRepository, Persona, PrefixRepository, RepositoryError, SchedulerConfig, SchedulerRepository, Tip, TipChanges, TipPost, TipRepository};
+
+/**
+ * This method build a tip from a row selecting id, title, content and tags.
+ *
+ * @param row: &Row, the row fetched
+ *
+ * @return Tip
+ */
+fn tip_from_row(row: &Row) -> Tip {
+    Tip {
+        id: row.get::<_, i64>(0) as u64,
+        title: row.get(1),
+        content: row.get(2),
+        tags: row.get(3),
+    }
+}
+
+/**
+ * This method turn the violation of the UNIQUE (guild_id, title)
+ * constraint into a DuplicateTitle error.
+ *
+ * @param err: tokio_postgres::Error, the error of the query
+ * @param title: String, the title inserted
+ *
+ * @return RepositoryError
+ */
+fn title_error(err: tokio_postgres::Error, title: String) -> RepositoryError {
+    if err.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+        return RepositoryError::DuplicateTitle(title);
+    }
+    RepositoryError::Postgres(err)
+}
+
+/*
+This structure store tips in the PostgreSQL database.
+ */
+#[derive(Clone)]
+pub struct PostgresTipRepository {
+    database: PostgresDatabase,
+}
+
+impl PostgresTipRepository {
+    pub fn new(database: PostgresDatabase) -> PostgresTipRepository {
+        PostgresTipRepository { database }
+    }
+}
+
+#[async_trait]
+impl TipRepository for PostgresTipRepository {
+    async fn list(&self, guild_id: u64, tags: &[String]) -> Result<Vec<Tip>, RepositoryError> {
+        // Every tip if there is no tag, like the sqlite LIKE the match is case insensitive
+        let patterns: Vec<String> = tags.iter().map(|tag| format!("%{}%", tag)).collect();
+        let rows = self.database.client().query(
+            "SELECT id, title, content, tags FROM tips WHERE guild_id = $1 AND (cardinality($2::text[]) = 0 OR tags ILIKE ANY($2)) ORDER BY id",
+            &[&(guild_id as i64), &patterns]
+        ).await?;
+        Ok(rows.iter().map(tip_from_row).collect())
+    }
+
+    async fn get(&self, guild_id: u64, id: u64) -> Result<Option<Tip>, RepositoryError> {
+        let row = self.database.client().query_opt(
+            "SELECT id, title, content, tags FROM tips WHERE id = $1 AND guild_id = $2",
+            &[&(id as i64), &(guild_id as i64)]
+        ).await?;
+        Ok(row.as_ref().map(tip_from_row))
+    }
+
+    async fn create(&self, guild_id: u64, tip: NewTip) -> Result<Tip, RepositoryError> {
+        let row = self.database.client().query_one(
+            "INSERT INTO tips (guild_id, title, content, tags) VALUES ($1,$2,$3,$4) RETURNING id, title, content, tags",
+            &[&(guild_id as i64), &tip.title, &tip.content, &tip.tags]
+        ).await;
+        row.map(|row| tip_from_row(&row)).map_err(|err| title_error(err, tip.title))
+    }
+
+    async fn update(&self, guild_id: u64, id: u64, changes: TipChanges) -> Result<Option<Tip>, RepositoryError> {
+        // A NULL value keep the current one
+        let row = self.database.client().query_opt(
+            "UPDATE tips SET title = COALESCE($3, title), content = COALESCE($4, content), tags = COALESCE($5, tags)
+             WHERE id = $1 AND guild_id = $2 RETURNING id, title, content, tags",
+            &[&(id as i64), &(guild_id as i64), &changes.title, &changes.content, &changes.tags]
+        ).await;
+        row.map(|row| row.as_ref().map(tip_from_row))
+            .map_err(|err| title_error(err, changes.title.unwrap_or_default()))
+    }
+
+    async fn delete(&self, guild_id: u64, id: u64) -> Result<bool, RepositoryError> {
+        let client = self.database.client();
+        let affected_row = client.execute("DELETE FROM tips WHERE id = $1 AND guild_id = $2", &[&(id as i64), &(guild_id as i64)]).await?;
+        // Forget messages where the tip has been posted, they can't be edited anymore
+        if affected_row == 1 {
+            client.execute("DELETE FROM tip_posts WHERE tip_id = $1", &[&(id as i64)]).await?;
+        }
+        Ok(affected_row == 1)
+    }
+
+    async fn save_post(&self, tip_id: u64, channel: u64, message: u64) -> Result<(), RepositoryError> {
+        self.database.client().execute(
+            "INSERT INTO tip_posts (tip_id, channel, message) VALUES ($1,$2,$3)",
+            &[&(tip_id as i64), &(channel as i64), &(message as i64)]
+        ).await?;
+        Ok(())
+    }
+
+    async fn posts(&self, tip_id: u64) -> Result<Vec<TipPost>, RepositoryError> {
+        let rows = self.database.client().query(
+            "SELECT id, tip_id, channel, message FROM tip_posts WHERE tip_id = $1 ORDER BY id",
+            &[&(tip_id as i64)]
+        ).await?;
+        Ok(rows.iter().map(|row| TipPost {
+            id: row.get::<_, i64>(0) as u64,
+            tip_id: row.get::<_, i64>(1) as u64,
+            channel: row.get::<_, i64>(2) as u64,
+            message: row.get::<_, i64>(3) as u64,
+        }).collect())
+    }
+
+    async fn forget_posts(&self, post_ids: &[u64]) -> Result<(), RepositoryError> {
+        let post_ids: Vec<i64> = post_ids.iter().map(|id| *id as i64).collect();
+        self.database.client().execute("DELETE FROM tip_posts WHERE id = ANY($1)", &[&post_ids]).await?;
+        Ok(())
+    }
+}
+
+/*
+This structure store the scheduler configurations in the PostgreSQL database.
+ */
+#[derive(Clone)]
+pub struct PostgresSchedulerRepository {
+    database: PostgresDatabase,
+}
+
+impl PostgresSchedulerRepository {
+    pub fn new(database: PostgresDatabase) -> PostgresSchedulerRepository {
+        PostgresSchedulerRepository { database }
+    }
+}
+
+#[async_trait]
+impl SchedulerRepository for PostgresSchedulerRepository {
+    async fn get(&self, guild_id: u64) -> Result<Option<SchedulerConfig>, RepositoryError> {
+        let row = self.database.client().query_opt(
+            "SELECT channel, hour, minute, persona_name, persona_avatar FROM scheduler_config WHERE guild_id = $1",
+            &[&(guild_id as i64)]
+        ).await?;
+        Ok(row.map(|row| SchedulerConfig {
+            channel: row.get::<_, i64>(0) as u64,
+            hour: row.get::<_, i32>(1) as u32,
+            minute: row.get::<_, i32>(2) as u32,
+            persona: row.get::<_, Option<String>>(3).map(|name| Persona { name, avatar_url: row.get(4) }),
+        }))
+    }
+
+    async fn save(&self, guild_id: u64, config: SchedulerConfig) -> Result<(), RepositoryError> {
+        self.database.client().execute(
+            "INSERT INTO scheduler_config (guild_id, channel, hour, minute, persona_name, persona_avatar) VALUES ($1,$2,$3,$4,$5,$6)
+             ON CONFLICT (guild_id) DO UPDATE SET channel = excluded.channel, hour = excluded.hour, minute = excluded.minute,
+             persona_name = excluded.persona_name, persona_avatar = excluded.persona_avatar",
+            &[&(guild_id as i64), &(config.channel as i64), &(config.hour as i32), &(config.minute as i32),
+                &config.persona.as_ref().map(|persona| persona.name.as_str()), &config.persona.as_ref().and_then(|persona| persona.avatar_url.as_deref())]
+        ).await?;
+        Ok(())
+    }
+}
+
+/*
+This structure store the capabilities granted to roles in the PostgreSQL database.
+ */
+#[derive(Clone)]
+pub struct PostgresPermissionRepository {
+    database: PostgresDatabase,
+}
+
+impl PostgresPermissionRepository {
+    pub fn new(database: PostgresDatabase) -> PostgresPermissionRepository {
+        PostgresPermissionRepository { database }
+    }
+}
+
+#[async_trait]
+impl PermissionRepository for PostgresPermissionRepository {
+    async fn list(&self, guild_id: u64) -> Result<Vec<PermissionGrant>, RepositoryError> {
+        let rows = self.database.client().query(
+            "SELECT role_id, capability FROM permissions WHERE guild_id = $1 ORDER BY role_id, capability",
+            &[&(guild_id as i64)]
+        ).await?;
+        Ok(rows.iter()
+            .filter_map(|row| {
+                let role_id = row.get::<_, i64>(0) as u64;
+                let name: String = row.get(1);
+                match Capability::parse(&name) {
+                    Some(capability) => Some(PermissionGrant { role_id, capability }),
+                    None => {
+                        warn!("Unknown capability {} granted to the role {} is ignored", name, role_id);
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+
+    async fn grant(&self, guild_id: u64, grant: PermissionGrant) -> Result<bool, RepositoryError> {
+        let affected_row = self.database.client().execute(
+            "INSERT INTO permissions (guild_id, role_id, capability) VALUES ($1,$2,$3) ON CONFLICT DO NOTHING",
+            &[&(guild_id as i64), &(grant.role_id as i64), &grant.capability.as_str()]
+        ).await?;
+        Ok(affected_row == 1)
+    }
+
+    async fn revoke(&self, guild_id: u64, grant: PermissionGrant) -> Result<bool, RepositoryError> {
+        let affected_row = self.database.client().execute(
+            "DELETE FROM permissions WHERE guild_id = $1 AND role_id = $2 AND capability = $3",
+            &[&(guild_id as i64), &(grant.role_id as i64), &grant.capability.as_str()]
+        ).await?;
+        Ok(affected_row == 1)
+    }
+}
+
+/*
+This structure store the cooldown rules in the PostgreSQL database.
+ */
+#[derive(Clone)]
+pub struct PostgresCooldownRepository {
+    database: PostgresDatabase,
+}
+
+impl PostgresCooldownRepository {
+    pub fn new(database: PostgresDatabase) -> PostgresCooldownRepository {
+        PostgresCooldownRepository { database }
+    }
+}
+
+#[async_trait]
+impl CooldownRepository for PostgresCooldownRepository {
+    async fn list(&self, guild_id: u64) -> Result<Vec<CooldownRule>, RepositoryError> {
+        let rows = self.database.client().query(
+            "SELECT command, scope, uses, period FROM cooldowns WHERE guild_id = $1 ORDER BY command, scope",
+            &[&(guild_id as i64)]
+        ).await?;
+        Ok(rows.iter()
+            .filter_map(|row| {
+                let command: String = row.get(0);
+                let name: String = row.get(1);
+                match CooldownScope::parse(&name) {
+                    Some(scope) => Some(CooldownRule { command, scope, uses: row.get::<_, i32>(2) as u32, period: row.get::<_, i64>(3) as u64 }),
+                    None => {
+                        warn!("Unknown cooldown scope {} of the command {} is ignored", name, command);
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+
+    async fn set(&self, guild_id: u64, rule: CooldownRule) -> Result<Option<CooldownRule>, RepositoryError> {
+        // Every part of the statement sees the table before the change : previous is the rule replaced
+        let row = self.database.client().query_opt(
+            "WITH previous AS (
+                 SELECT uses, period FROM cooldowns WHERE guild_id = $1 AND command = $2 AND scope = $3
+             ), saved AS (
+                 INSERT INTO cooldowns (guild_id, command, scope, uses, period) VALUES ($1,$2,$3,$4,$5)
+                 ON CONFLICT (guild_id, command, scope) DO UPDATE SET uses = EXCLUDED.uses, period = EXCLUDED.period
+             )
+             SELECT uses, period FROM previous",
+            &[&(guild_id as i64), &rule.command, &rule.scope.as_str(), &(rule.uses as i32), &(rule.period as i64)]
+        ).await?;
+        Ok(row.map(|row| CooldownRule { uses: row.get::<_, i32>(0) as u32, period: row.get::<_, i64>(1) as u64, ..rule }))
+    }
+
+    async fn remove(&self, guild_id: u64, command: &str, scope: CooldownScope) -> Result<Option<CooldownRule>, RepositoryError> {
+        let row = self.database.client().query_opt(
+            "DELETE FROM cooldowns WHERE guild_id = $1 AND command = $2 AND scope = $3 RETURNING uses, period",
+            &[&(guild_id as i64), &command, &scope.as_str()]
+        ).await?;
+        Ok(row.map(|row| CooldownRule { command: command.to_string(), scope, uses: row.get::<_, i32>(0) as u32, period: row.get::<_, i64>(1) as u64 }))
+    }
+}
+
+/*
+This structure store the prefix of the text commands in the PostgreSQL database.
+ */
+#[derive(Clone)]
+pub struct PostgresPrefixRepository {
+    database: PostgresDatabase,
+}
+
+impl PostgresPrefixRepository {
+    pub fn new(database: PostgresDatabase) -> PostgresPrefixRepository {
+        PostgresPrefixRepository { database }
+    }
+}
+
+#[async_trait]
+impl PrefixRepository for PostgresPrefixRepository {
+    async fn prefix(&self, guild_id: u64) -> Result<Option<String>, RepositoryError> {
+        let row = self.database.client().query_opt("SELECT prefix FROM command_prefixes WHERE guild_id = $1", &[&(guild_id as i64)]).await?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    async fn set_prefix(&self, guild_id: u64, prefix: Option<String>) -> Result<(), RepositoryError> {
+        let client = self.database.client();
+        match prefix {
+            Some(prefix) => client.execute(
+                "INSERT INTO command_prefixes (guild_id, prefix) VALUES ($1,$2) ON CONFLICT (guild_id) DO UPDATE SET prefix = excluded.prefix",
+                &[&(guild_id as i64), &prefix]
+            ).await?,
+            None => client.execute("DELETE FROM command_prefixes WHERE guild_id = $1", &[&(guild_id as i64)]).await?,
+        };
+        Ok(())
+    }
+}
+
+/*
+This structure store the audit log in the PostgreSQL database.
+Dates are stored as text, like in the sqlite database.
+ */
+#[derive(Clone)]
+pub struct PostgresAuditRepository {
+    database: PostgresDatabase,
+}
+
+impl PostgresAuditRepository {
+    pub fn new(database: PostgresDatabase) -> PostgresAuditRepository {
+        PostgresAuditRepository { database }
+    }
+}
+
+#[async_trait]
+impl AuditRepository for PostgresAuditRepository {
+    async fn record(&self, guild_id: u64, entry: NewAuditEntry) -> Result<AuditEntry, RepositoryError> {
+        let row = self.database.client().query_one(
+            "INSERT INTO audit_log (guild_id, actor, command, arguments, before_value, after_value, created_at) VALUES ($1,$2,$3,$4,$5,$6,$7) RETURNING id",
+            &[&(guild_id as i64), &(entry.actor as i64), &entry.command, &entry.arguments, &entry.before, &entry.after, &audit_date(entry.created_at)]
+        ).await?;
+        Ok(AuditEntry {
+            id: row.get::<_, i64>(0) as u64,
+            actor: entry.actor,
+            command: entry.command,
+            arguments: entry.arguments,
+            before: entry.before,
+            after: entry.after,
+            created_at: entry.created_at,
+        })
+    }
+
+    async fn search(&self, guild_id: u64, filter: &AuditFilter) -> Result<Vec<AuditEntry>, RepositoryError> {
+        // A NULL parameter doesn't filter
+        let rows = self.database.client().query(
+            "SELECT id, actor, command, arguments, before_value, after_value, created_at FROM audit_log
+             WHERE guild_id = $1 AND ($2::BIGINT IS NULL OR actor = $2) AND ($3::TEXT IS NULL OR command = $3)
+             AND ($4::TEXT IS NULL OR created_at >= $4) AND ($5::TEXT IS NULL OR created_at < $5)
+             ORDER BY id DESC",
+            &[&(guild_id as i64), &filter.actor.map(|actor| actor as i64), &filter.command, &filter.since.map(audit_date), &filter.until.map(audit_date)]
+        ).await?;
+        Ok(rows.iter()
+            .filter_map(|row| {
+                let created_at: String = row.get(6);
+                match DateTime::parse_from_rfc3339(&created_at) {
+                    Ok(created_at) => Some(AuditEntry {
+                        id: row.get::<_, i64>(0) as u64,
+                        actor: row.get::<_, i64>(1) as u64,
+                        command: row.get(2),
+                        arguments: row.get(3),
+                        before: row.get(4),
+                        after: row.get(5),
+                        created_at: created_at.with_timezone(&Utc),
+                    }),
+                    Err(err) => {
+                        warn!("The audit entry {} has an invalid date and is ignored. Error:\n{}", row.get::<_, i64>(0), err);
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+
+    async fn mirror_channel(&self, guild_id: u64) -> Result<Option<u64>, RepositoryError> {
+        let row = self.database.client().query_opt("SELECT channel FROM audit_mirror WHERE guild_id = $1", &[&(guild_id as i64)]).await?;
+        Ok(row.map(|row| row.get::<_, i64>(0) as u64))
+    }
+
+    async fn set_mirror_channel(&self, guild_id: u64, channel: Option<u64>) -> Result<(), RepositoryError> {
+        let client = self.database.client();
+        match channel {
+            Some(channel) => client.execute(
+                "INSERT INTO audit_mirror (guild_id, channel) VALUES ($1,$2) ON CONFLICT (guild_id) DO UPDATE SET channel = excluded.channel",
+                &[&(guild_id as i64), &(channel as i64)]
+            ).await?,
+            None => client.execute("DELETE FROM audit_mirror WHERE guild_id = $1", &[&(guild_id as i64)]).await?,
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,516 @@
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use rusqlite::{OptionalExtension, params, params_from_iter, Row};
+use rusqlite::types::Value;
+use serenity::async_trait;
+use crate::database::pool::DatabasePool;
+use crate::cooldowns::CooldownScope;
+use crate::permissions::Capability;
+use crate::repositories::{audit_date, AuditEntry, AuditFilter, AuditRepository, CooldownRepository, CooldownRule, NewAuditEntry, NewTip, PermissionGrant, PermissionRepository, Persona, PrefixRepository, RepositoryError, SchedulerConfig, SchedulerRepository, Tip, TipChanges, TipPost, TipRepository};
+
+/**
+ * This method build a tip from a row selecting id, title, content and tags.
+ *
+ * @param row: &Row, the row fetched
+ *
+ * @return rusqlite::Result<Tip>
+ */
+fn tip_from_row(row: &Row) -> rusqlite::Result<Tip> {
+    Ok(
+        Tip{
+            id: row.get(0)?,
+            title: row.get(1)?,
+            content: row.get(2)?,
+            tags: row.get(3)?,
+        }
+    )
+}
+
+/**
+ * This method turn the violation of the UNIQUE (guild_id, title)
+ * constraint into a DuplicateTitle error.
+ *
+ * @param err: tokio_rusqlite::Error, the error of the query
+ * @param title: String, the title inserted
+ *
+ * @return RepositoryError
+ */
+fn title_error(err: tokio_rusqlite::Error, title: String) -> RepositoryError {
+    match &err {
+        tokio_rusqlite::Error::Rusqlite(rusqlite::Error::SqliteFailure(failure, _))
+            if failure.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE => RepositoryError::DuplicateTitle(title),
+        _ => RepositoryError::Database(err),
+    }
+}
+
+/**
+ * This method build an audit entry from a row selecting
+ * id, actor, command, arguments, before_value, after_value and created_at.
+ *
+ * @param row: &Row, the row fetched
+ *
+ * @return rusqlite::Result<AuditEntry>
+ */
+fn audit_entry_from_row(row: &Row) -> rusqlite::Result<AuditEntry> {
+    let created_at: String = row.get(6)?;
+    Ok(
+        AuditEntry{
+            id: row.get(0)?,
+            actor: row.get(1)?,
+            command: row.get(2)?,
+            arguments: row.get(3)?,
+            before: row.get(4)?,
+            after: row.get(5)?,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .map_err(|err| rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(err)))?
+                .with_timezone(&Utc),
+        }
+    )
+}
+
+/*
+This structure store tips in the sqlite database.
+ */
+#[derive(Clone)]
+pub struct SqliteTipRepository {
+    database: DatabasePool,
+}
+
+impl SqliteTipRepository {
+    pub fn new(database: DatabasePool) -> SqliteTipRepository {
+        SqliteTipRepository { database }
+    }
+}
+
+#[async_trait]
+impl TipRepository for SqliteTipRepository {
+    async fn list(&self, guild_id: u64, tags: &[String]) -> Result<Vec<Tip>, RepositoryError> {
+        // transform the tags into a list of query parameters : %unreal%, %tools%
+        let mut values: Vec<Value> = vec![Value::Integer(guild_id as i64)];
+        values.extend(tags.iter().map(|tag| Value::Text(format!("%{}%", tag))));
+
+        let tips = self.database.read(move |conn| {
+            // format tags to be in the query like this : SELECT ... ... LIKE ?2 OR tags LIKE ?3
+            let query = if values.len() > 1 {
+                let tags_placeholder = (2..=values.len()).map(|i| format!("tags LIKE ?{}", i)).collect::<Vec<_>>().join(" OR ");
+                format!("SELECT id, title, content, tags FROM tips WHERE guild_id = ?1 AND ({})", tags_placeholder)
+            } else {
+                String::from("SELECT id, title, content, tags FROM tips WHERE guild_id = ?1")
+            };
+            debug!("Query executed for the list of tips : {}", query);
+
+            let mut stmt = conn.prepare(&query)?;
+            let rows_data = stmt.query_map(params_from_iter(values), tip_from_row)?
+                .collect::<Result<Vec<Tip>, rusqlite::Error>>()?;
+            Ok::<_, rusqlite::Error>(rows_data)
+        }).await?;
+        Ok(tips)
+    }
+
+    async fn get(&self, guild_id: u64, id: u64) -> Result<Option<Tip>, RepositoryError> {
+        let tip = self.database.read(move |conn| {
+            conn.query_row("SELECT id, title, content, tags FROM tips WHERE id = ?1 AND guild_id = ?2", params![id, guild_id], tip_from_row)
+                .optional()
+        }).await?;
+        Ok(tip)
+    }
+
+    async fn create(&self, guild_id: u64, tip: NewTip) -> Result<Tip, RepositoryError> {
+        let title = tip.title.clone();
+        let created = self.database.write(move |conn| {
+            let query = "INSERT INTO tips (guild_id, title, content, tags) VALUES (?1,?2,?3,?4)";
+            conn.execute(query, params![guild_id, tip.title, tip.content, tip.tags])?;
+            Ok(Tip { id: conn.last_insert_rowid() as u64, title: tip.title, content: tip.content, tags: tip.tags })
+        }).await;
+        created.map_err(|err| title_error(err, title))
+    }
+
+    async fn update(&self, guild_id: u64, id: u64, changes: TipChanges) -> Result<Option<Tip>, RepositoryError> {
+        let title = changes.title.clone().unwrap_or_default();
+        let updated = self.database.write(move |conn| {
+            // Prepare the sql query : values are given as parameters ?3, ?4, ...
+            let mut updated_columns: Vec<&str> = Vec::with_capacity(3);
+            let mut values: Vec<Value> = vec![Value::Integer(id as i64), Value::Integer(guild_id as i64)];
+            for (column, value) in [("title", changes.title), ("content", changes.content), ("tags", changes.tags)] {
+                if let Some(value) = value {
+                    updated_columns.push(column);
+                    values.push(Value::Text(value));
+                }
+            }
+
+            if !updated_columns.is_empty() {
+                let set_clause = updated_columns.iter().enumerate()
+                    .map(|(index, column)| format!("{}=?{}", column, index + 3))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                let query = format!("UPDATE tips SET {} WHERE id = ?1 AND guild_id = ?2", set_clause);
+                debug!("Update query run : {}", query);
+                conn.execute(&query, params_from_iter(values))?;
+            }
+
+            conn.query_row("SELECT id, title, content, tags FROM tips WHERE id = ?1 AND guild_id = ?2", params![id, guild_id], tip_from_row)
+                .optional()
+        }).await;
+        updated.map_err(|err| title_error(err, title))
+    }
+
+    async fn delete(&self, guild_id: u64, id: u64) -> Result<bool, RepositoryError> {
+        let deleted = self.database.write(move |conn| {
+            let affected_row = conn.execute("DELETE FROM tips WHERE id = ?1 AND guild_id = ?2", params![id, guild_id])?;
+            // Forget messages where the tip has been posted, they can't be edited anymore
+            if affected_row == 1 {
+                conn.execute("DELETE FROM tip_posts WHERE tip_id = ?1", params![id])?;
+            }
+            Ok(affected_row == 1)
+        }).await?;
+        Ok(deleted)
+    }
+
+    async fn save_post(&self, tip_id: u64, channel: u64, message: u64) -> Result<(), RepositoryError> {
+        self.database.write(move |conn| {
+            conn.execute(
+                "INSERT INTO tip_posts (tip_id, channel, message) VALUES (?1,?2,?3)",
+                params![tip_id, channel, message]
+            )?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    async fn posts(&self, tip_id: u64) -> Result<Vec<TipPost>, RepositoryError> {
+        let posts = self.database.read(move |conn| {
+            let mut stmt = conn.prepare("SELECT id, tip_id, channel, message FROM tip_posts WHERE tip_id = ?1")?;
+            let rows_data = stmt.query_map([tip_id], |row|
+                Ok(
+                    TipPost{
+                        id: row.get(0)?,
+                        tip_id: row.get(1)?,
+                        channel: row.get(2)?,
+                        message: row.get(3)?,
+                    }
+                )
+            )?
+                .collect::<Result<Vec<TipPost>, rusqlite::Error>>()?;
+            Ok::<_, rusqlite::Error>(rows_data)
+        }).await?;
+        Ok(posts)
+    }
+
+    async fn forget_posts(&self, post_ids: &[u64]) -> Result<(), RepositoryError> {
+        let post_ids = post_ids.to_vec();
+        self.database.write(move |conn| {
+            for id in post_ids {
+                conn.execute("DELETE FROM tip_posts WHERE id = ?1", params![id])?;
+            }
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+}
+
+/*
+This structure store the scheduler configurations in the sqlite database.
+ */
+#[derive(Clone)]
+pub struct SqliteSchedulerRepository {
+    database: DatabasePool,
+}
+
+impl SqliteSchedulerRepository {
+    pub fn new(database: DatabasePool) -> SqliteSchedulerRepository {
+        SqliteSchedulerRepository { database }
+    }
+}
+
+#[async_trait]
+impl SchedulerRepository for SqliteSchedulerRepository {
+    async fn get(&self, guild_id: u64) -> Result<Option<SchedulerConfig>, RepositoryError> {
+        let config = self.database.read(move |conn| {
+            conn.query_row("SELECT channel, hour, minute, persona_name, persona_avatar FROM scheduler_config WHERE guild_id = ?1", [guild_id], |row|
+                Ok(
+                    SchedulerConfig{
+                        channel: row.get(0)?,
+                        hour: row.get(1)?,
+                        minute: row.get(2)?,
+                        persona: match row.get::<_, Option<String>>(3)? {
+                            Some(name) => Some(Persona { name, avatar_url: row.get(4)? }),
+                            None => None,
+                        },
+                    }
+                )
+            ).optional()
+        }).await?;
+        Ok(config)
+    }
+
+    async fn save(&self, guild_id: u64, config: SchedulerConfig) -> Result<(), RepositoryError> {
+        self.database.write(move |conn| {
+            conn.execute(
+                "INSERT INTO scheduler_config (guild_id, channel, hour, minute, persona_name, persona_avatar) VALUES (?1,?2,?3,?4,?5,?6)
+                 ON CONFLICT (guild_id) DO UPDATE SET channel = excluded.channel, hour = excluded.hour, minute = excluded.minute,
+                 persona_name = excluded.persona_name, persona_avatar = excluded.persona_avatar",
+                params![guild_id, config.channel, config.hour, config.minute,
+                    config.persona.as_ref().map(|persona| &persona.name), config.persona.as_ref().and_then(|persona| persona.avatar_url.as_ref())]
+            )?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+}
+
+/*
+This structure store the capabilities granted to roles in the sqlite database.
+ */
+#[derive(Clone)]
+pub struct SqlitePermissionRepository {
+    database: DatabasePool,
+}
+
+impl SqlitePermissionRepository {
+    pub fn new(database: DatabasePool) -> SqlitePermissionRepository {
+        SqlitePermissionRepository { database }
+    }
+}
+
+#[async_trait]
+impl PermissionRepository for SqlitePermissionRepository {
+    async fn list(&self, guild_id: u64) -> Result<Vec<PermissionGrant>, RepositoryError> {
+        let rows = self.database.read(move |conn| {
+            let mut stmt = conn.prepare("SELECT role_id, capability FROM permissions WHERE guild_id = ?1 ORDER BY role_id, capability")?;
+            let rows_data = stmt.query_map([guild_id], |row| Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<Result<Vec<(u64, String)>, rusqlite::Error>>()?;
+            Ok::<_, rusqlite::Error>(rows_data)
+        }).await?;
+        Ok(rows.into_iter()
+            .filter_map(|(role_id, name)| match Capability::parse(&name) {
+                Some(capability) => Some(PermissionGrant { role_id, capability }),
+                None => {
+                    warn!("Unknown capability {} granted to the role {} is ignored", name, role_id);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn grant(&self, guild_id: u64, grant: PermissionGrant) -> Result<bool, RepositoryError> {
+        let granted = self.database.write(move |conn| {
+            let affected_row = conn.execute(
+                "INSERT OR IGNORE INTO permissions (guild_id, role_id, capability) VALUES (?1,?2,?3)",
+                params![guild_id, grant.role_id, grant.capability.as_str()]
+            )?;
+            Ok(affected_row == 1)
+        }).await?;
+        Ok(granted)
+    }
+
+    async fn revoke(&self, guild_id: u64, grant: PermissionGrant) -> Result<bool, RepositoryError> {
+        let revoked = self.database.write(move |conn| {
+            let affected_row = conn.execute(
+                "DELETE FROM permissions WHERE guild_id = ?1 AND role_id = ?2 AND capability = ?3",
+                params![guild_id, grant.role_id, grant.capability.as_str()]
+            )?;
+            Ok(affected_row == 1)
+        }).await?;
+        Ok(revoked)
+    }
+}
+
+/*
+This structure store the cooldown rules in the sqlite database.
+ */
+#[derive(Clone)]
+pub struct SqliteCooldownRepository {
+    database: DatabasePool,
+}
+
+impl SqliteCooldownRepository {
+    pub fn new(database: DatabasePool) -> SqliteCooldownRepository {
+        SqliteCooldownRepository { database }
+    }
+}
+
+/**
+ * This method read the uses and the period of a rule.
+ *
+ * @param row: &Row, a row with the uses and the period, in this order
+ *
+ * @return rusqlite::Result<(u32, u64)>
+ */
+fn cooldown_limit_from_row(row: &Row) -> rusqlite::Result<(u32, u64)> {
+    Ok((row.get(0)?, row.get(1)?))
+}
+
+#[async_trait]
+impl CooldownRepository for SqliteCooldownRepository {
+    async fn list(&self, guild_id: u64) -> Result<Vec<CooldownRule>, RepositoryError> {
+        let rows = self.database.read(move |conn| {
+            let mut stmt = conn.prepare("SELECT command, scope, uses, period FROM cooldowns WHERE guild_id = ?1 ORDER BY command, scope")?;
+            let rows_data = stmt.query_map([guild_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, u32>(2)?, row.get::<_, u64>(3)?)))?
+                .collect::<Result<Vec<(String, String, u32, u64)>, rusqlite::Error>>()?;
+            Ok::<_, rusqlite::Error>(rows_data)
+        }).await?;
+        Ok(rows.into_iter()
+            .filter_map(|(command, name, uses, period)| match CooldownScope::parse(&name) {
+                Some(scope) => Some(CooldownRule { command, scope, uses, period }),
+                None => {
+                    warn!("Unknown cooldown scope {} of the command {} is ignored", name, command);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn set(&self, guild_id: u64, rule: CooldownRule) -> Result<Option<CooldownRule>, RepositoryError> {
+        let previous = self.database.write(move |conn| {
+            let previous = conn.query_row(
+                "SELECT uses, period FROM cooldowns WHERE guild_id = ?1 AND command = ?2 AND scope = ?3",
+                params![guild_id, rule.command, rule.scope.as_str()],
+                cooldown_limit_from_row
+            ).optional()?;
+            conn.execute(
+                "INSERT INTO cooldowns (guild_id, command, scope, uses, period) VALUES (?1,?2,?3,?4,?5)
+                 ON CONFLICT (guild_id, command, scope) DO UPDATE SET uses = excluded.uses, period = excluded.period",
+                params![guild_id, rule.command, rule.scope.as_str(), rule.uses, rule.period]
+            )?;
+            Ok(previous.map(|(uses, period)| CooldownRule { uses, period, ..rule }))
+        }).await?;
+        Ok(previous)
+    }
+
+    async fn remove(&self, guild_id: u64, command: &str, scope: CooldownScope) -> Result<Option<CooldownRule>, RepositoryError> {
+        let command = command.to_string();
+        let removed = self.database.write(move |conn| {
+            let removed = conn.query_row(
+                "SELECT uses, period FROM cooldowns WHERE guild_id = ?1 AND command = ?2 AND scope = ?3",
+                params![guild_id, command, scope.as_str()],
+                cooldown_limit_from_row
+            ).optional()?;
+            conn.execute(
+                "DELETE FROM cooldowns WHERE guild_id = ?1 AND command = ?2 AND scope = ?3",
+                params![guild_id, command, scope.as_str()]
+            )?;
+            Ok(removed.map(|(uses, period)| CooldownRule { command, scope, uses, period }))
+        }).await?;
+        Ok(removed)
+    }
+}
+
+/*
+This structure store the prefix of the text commands in the sqlite database.
+ */
+#[derive(Clone)]
+pub struct SqlitePrefixRepository {
+    database: DatabasePool,
+}
+
+impl SqlitePrefixRepository {
+    pub fn new(database: DatabasePool) -> SqlitePrefixRepository {
+        SqlitePrefixRepository { database }
+    }
+}
+
+#[async_trait]
+impl PrefixRepository for SqlitePrefixRepository {
+    async fn prefix(&self, guild_id: u64) -> Result<Option<String>, RepositoryError> {
+        let prefix = self.database.read(move |conn| {
+            conn.query_row("SELECT prefix FROM command_prefixes WHERE guild_id = ?1", [guild_id], |row| row.get(0))
+                .optional()
+        }).await?;
+        Ok(prefix)
+    }
+
+    async fn set_prefix(&self, guild_id: u64, prefix: Option<String>) -> Result<(), RepositoryError> {
+        self.database.write(move |conn| {
+            match prefix {
+                Some(prefix) => conn.execute(
+                    "INSERT INTO command_prefixes (guild_id, prefix) VALUES (?1,?2) ON CONFLICT (guild_id) DO UPDATE SET prefix = excluded.prefix",
+                    params![guild_id, prefix]
+                )?,
+                None => conn.execute("DELETE FROM command_prefixes WHERE guild_id = ?1", params![guild_id])?,
+            };
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+}
+
+/*
+This structure store the audit log in the sqlite database.
+ */
+#[derive(Clone)]
+pub struct SqliteAuditRepository {
+    database: DatabasePool,
+}
+
+impl SqliteAuditRepository {
+    pub fn new(database: DatabasePool) -> SqliteAuditRepository {
+        SqliteAuditRepository { database }
+    }
+}
+
+#[async_trait]
+impl AuditRepository for SqliteAuditRepository {
+    async fn record(&self, guild_id: u64, entry: NewAuditEntry) -> Result<AuditEntry, RepositoryError> {
+        let recorded = self.database.write(move |conn| {
+            conn.execute(
+                "INSERT INTO audit_log (guild_id, actor, command, arguments, before_value, after_value, created_at) VALUES (?1,?2,?3,?4,?5,?6,?7)",
+                params![guild_id, entry.actor, entry.command, entry.arguments, entry.before, entry.after, audit_date(entry.created_at)]
+            )?;
+            Ok(AuditEntry {
+                id: conn.last_insert_rowid() as u64,
+                actor: entry.actor,
+                command: entry.command,
+                arguments: entry.arguments,
+                before: entry.before,
+                after: entry.after,
+                created_at: entry.created_at,
+            })
+        }).await?;
+        Ok(recorded)
+    }
+
+    async fn search(&self, guild_id: u64, filter: &AuditFilter) -> Result<Vec<AuditEntry>, RepositoryError> {
+        // A NULL parameter doesn't filter
+        let values: Vec<Value> = vec![
+            Value::Integer(guild_id as i64),
+            filter.actor.map_or(Value::Null, |actor| Value::Integer(actor as i64)),
+            filter.command.clone().map_or(Value::Null, Value::Text),
+            filter.since.map_or(Value::Null, |since| Value::Text(audit_date(since))),
+            filter.until.map_or(Value::Null, |until| Value::Text(audit_date(until))),
+        ];
+        let entries = self.database.read(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, actor, command, arguments, before_value, after_value, created_at FROM audit_log
+                 WHERE guild_id = ?1 AND (?2 IS NULL OR actor = ?2) AND (?3 IS NULL OR command = ?3)
+                 AND (?4 IS NULL OR created_at >= ?4) AND (?5 IS NULL OR created_at < ?5)
+                 ORDER BY id DESC"
+            )?;
+            let rows_data = stmt.query_map(params_from_iter(values), audit_entry_from_row)?
+                .collect::<Result<Vec<AuditEntry>, rusqlite::Error>>()?;
+            Ok::<_, rusqlite::Error>(rows_data)
+        }).await?;
+        Ok(entries)
+    }
+
+    async fn mirror_channel(&self, guild_id: u64) -> Result<Option<u64>, RepositoryError> {
+        let channel = self.database.read(move |conn| {
+            conn.query_row("SELECT channel FROM audit_mirror WHERE guild_id = ?1", [guild_id], |row| row.get(0))
+                .optional()
+        }).await?;
+        Ok(channel)
+    }
+
+    async fn set_mirror_channel(&self, guild_id: u64, channel: Option<u64>) -> Result<(), RepositoryError> {
+        self.database.write(move |conn| {
+            match channel {
+                Some(channel) => conn.execute(
+                    "INSERT INTO audit_mirror (guild_id, channel) VALUES (?1,?2) ON CONFLICT (guild_id) DO UPDATE SET channel = excluded.channel",
+                    params![guild_id, channel]
+                )?,
+                None => conn.execute("DELETE FROM audit_mirror WHERE guild_id = ?1", params![guild_id])?,
+            };
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use log::warn;
+use tokio::sync::Notify;
+
+/*
+This structure track the work running when the bot stops.
+Commands and scheduler posts are tracked while they run : once the
+shutdown begins no new work is accepted and the bot waits for the
+running one to finish.
+ */
+#[derive(Default)]
+pub struct Shutdown {
+    stopping: AtomicBool, // true once the shutdown began
+    running: AtomicUsize, // number of commands and posts running
+    idle: Notify, // notified when the last running work finish
+}
+
+/*
+This structure is a running command or post. The work is
+finished when it's dropped.
+ */
+pub struct Activity {
+    shutdown: Arc<Shutdown>,
+}
+
+impl Drop for Activity {
+    fn drop(&mut self) {
+        if self.shutdown.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shutdown.idle.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    /**
+     * This method record the start of a command or a post.
+     *
+     * @return Option<Activity>, None if the bot is stopping : the work must not start
+     */
+    pub fn track(self: &Arc<Self>) -> Option<Activity> {
+        // Counted before the check : the shutdown can't miss a work accepted at the same time
+        self.running.fetch_add(1, Ordering::SeqCst);
+        let activity = Activity { shutdown: self.clone() };
+        if self.is_stopping() {
+            return None;
+        }
+        Some(activity)
+    }
+
+    /**
+     * This method return true once the shutdown began.
+     *
+     * @return bool
+     */
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /**
+     * This method return the number of commands and posts running.
+     *
+     * @return usize
+     */
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /**
+     * This method begin the shutdown : no new work is accepted,
+     * then it wait for the running one.
+     *
+     * @param timeout: Duration, the longest wait
+     *
+     * @return bool, false if some work was still running after the timeout
+     */
+    pub async fn stop(&self, timeout: Duration) -> bool {
+        self.stopping.store(true, Ordering::SeqCst);
+        tokio::time::timeout(timeout, async {
+            loop {
+                // Created before the check so a notification sent in between isn't lost
+                let idle = self.idle.notified();
+                if self.running() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        }).await.is_ok()
+    }
+}
+
+/**
+ * This method wait for a signal asking the bot to stop :
+ * SIGINT (Ctrl+C) or SIGTERM (docker stop, systemctl stop).
+ *
+ * @return &'static str, the name of the signal received
+ */
+pub async fn signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = ctrl_c() => "SIGINT",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(err) => {
+                warn!("Can't listen to SIGTERM, only SIGINT stops the bot. Error:\n{}", err);
+                ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        ctrl_c().await;
+        "Ctrl+C"
+    }
+}
+
+/**
+ * This method wait for Ctrl+C. If it can't be listened to, it never return.
+ */
+async fn ctrl_c() {
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        warn!("Can't listen to Ctrl+C. Error:\n{}", err);
+        std::future::pending::<()>().await;
+    }
+}
@@ -1,91 +1,27 @@
 use serenity::builder::CreateEmbed;
-use serenity::model::application::interaction::application_command::{CommandDataOption, CommandDataOptionValue};
 use serenity::model::Timestamp;
 use serenity::utils::Color;
+use crate::commands::options::OptionError;
 
 /**
- * This method return the string value of the requested parameter.
- * If no value found or the index is out of bounds, a string error
- * is returned.
+ * This method check and format the tags given by a user.
+ * Tags are lowercase csv with no spaces around coma : tag1,tag2,tag3
+ * so spaces are removed and upper case letters are lowered.
  *
- * @param options: &[CommandDataOption], The interaction options wrapper
- * @param index: usize, the index of the parameter in the options list
- * @param name: &str, the name of the parameter to customize error message
+ * @param name: &str, the name of the option containing tags
+ * @param tags: String, the tags given by the user
  *
- * @return Result<String, String>, the value of the parameter or the error message
+ * @return Result<String, OptionError>, the formatted tags or the reason they are rejected
  */
-pub fn get_required_string_param_from_options(options: &[CommandDataOption], index: usize, name: &str,) -> Result<String, String>
+pub fn parse_tags(name: &str, tags: String) -> Result<String, OptionError>
 {
-    match options.get(index) {
-        Some(option) => match &option.resolved {
-            Some(resolved) => match resolved {
-                CommandDataOptionValue::String(content) => Ok(content.to_owned()),
-                _ => {
-                    return Err(format!("Incorrect type for the parameter {}.", name));
-                }
-            },
-            None => {
-                return Err(format!("Missing parameter {}.", name));
-            }
-        },
-        None => {
-            return Err(format!("Missing parameter {}.", name));
-        }
+    let tags_list: Vec<String> = tags.split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .collect();
+    if tags_list.iter().any(|tag| tag.is_empty()) {
+        return Err(OptionError::invalid(name, "A tag is empty.\nExpected lowercase csv with no spaces around coma. like this : tag1,tag2,tag3,..."));
     }
-}
-/**
- * This method return the string value of the requested parameter.
- * If no value found or the index is out of bounds, a string error
- * is returned.
- *
- * @param options: &[CommandDataOption], The interaction options wrapper
- * @param index: usize, the index of the parameter in the options list
- * @param name: &str, the name of the parameter to customize error message
- *
- * @return Result<u64, String>, the value of the parameter or the error message
- */
-pub fn get_required_integer_param_from_options(options: &[CommandDataOption], index: usize, name: &str,) -> Result<u64, String>
-{
-    match options.get(index) {
-        Some(option) => match &option.resolved {
-            Some(resolved) => match resolved {
-                CommandDataOptionValue::Integer(content) => {
-                    Ok(content.to_owned() as u64)
-                },
-                _ => {
-                    return Err(format!("Incorrect type for the parameter {}.", name));
-                }
-            },
-            None => {
-                return Err(format!("Missing parameter {}.", name));
-            }
-        },
-        None => {
-            return Err(format!("Missing parameter {}.", name));
-        }
-    }
-}
-
-
-/**
- * This method return the string value of the requested parameter.
- * If no value found or the index is out of bounds, a string error
- * is returned.
- *
- * @param options: &[CommandDataOption], The interaction options wrapper
- * @param index: usize, the index of the parameter in the options list
- *
- * @return String, the value of the parameter or an empty string instead
- */
-pub fn get_optional_string_param_from_options(options: &[CommandDataOption], index: usize) -> String
-{
-    options.get(index)
-        .map_or(String::from(""), |opt| {
-            if let Some(CommandDataOptionValue::String(tmp)) = opt.resolved.to_owned() {
-                return tmp;
-            }
-            String::from("")
-        })
+    Ok(tags_list.join(","))
 }
 
 /**
@@ -107,7 +43,7 @@ pub fn display_full_tip_in_embed(title: String, content: String, tags_opt: Optio
         .timestamp(Timestamp::now())
         .color(Color::from_rgb(102, 255, 255))
         .to_owned();
-    return if tags != "" {
+    if !tags.is_empty() {
         embed.footer(|f| {
             f.text(format!("#: {}", tags))
         })
@@ -120,16 +56,16 @@ pub fn display_full_tip_in_embed(title: String, content: String, tags_opt: Optio
  * This method return the string message needed to display
  * a tips in the chat as list style.  >"id - title #tags"
  *
- * @param id: u32,
+ * @param id: u64,
  * @param title: String,
  * @param tags_string: String,
  *
  * @return String, the formatted message
  */
-pub fn display_minimized_tip(id: u32, title: String, tags_string: Option<String>) -> String
+pub fn display_minimized_tip(id: u64, title: String, tags_string: Option<String>) -> String
 {
     let tags = tags_string.unwrap_or_else(|| String::from(""));
-    return if tags != "" {
+    if !tags.is_empty() {
         format!("*{}* - **{}**    #{}",
             id,
             title,
@@ -143,22 +79,3 @@ pub fn display_minimized_tip(id: u32, title: String, tags_string: Option<String>
     }
 
 }
-
-/**
- * This method make a generic embed for error messages.
- *
- * @param source: &str, The source of the error.
- * @param err: String, The error message.
- *
- * @return CreateEmbed, the embed displayed in the response
- */
-pub fn make_error_embed(source: &str, err: String) -> CreateEmbed
-{
-    CreateEmbed::default()
-        .title("Oups.. Something went wrong in the process :(")
-        .description(
-            format!("From {}, error:\n{}", source,err)
-        ).timestamp(Timestamp::now())
-        .color(Color::from_rgb(255, 0, 0))
-        .to_owned()
-}
\ No newline at end of file
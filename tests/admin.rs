@@ -0,0 +1,105 @@
+//! gamebot-admin runs on a sqlite database, without discord.
+
+use game_bot4_fun::admin::{self, AdminArguments, AdminCommand, AdminError, ADMIN_ACTOR};
+use game_bot4_fun::config::Config;
+use game_bot4_fun::database::Database;
+use game_bot4_fun::repositories::{AuditFilter, NewTip, SchedulerConfig};
+
+const GUILD_ID: u64 = 1;
+
+fn parse(args: &[&str]) -> Result<AdminArguments, AdminError> {
+    AdminArguments::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+// Run a command, return what it printed
+async fn run(database: &Database, config: &Config, args: &[&str]) -> Result<String, AdminError> {
+    let mut out = Vec::new();
+    let command = parse(args)?.command;
+    admin::run(database, config, command, &mut out).await?;
+    Ok(String::from_utf8(out).expect("The output is not text"))
+}
+
+#[test]
+fn commands_and_configuration_flags_are_split() {
+    let arguments = parse(&["tips", "edit", "--guild", "1", "--database-url", "sqlite:admin.sqlite", "--id=3", "--tags", "Unity, Shaders"]).expect("Invalid arguments");
+    match arguments.command {
+        AdminCommand::TipsEdit { guild_id, id, changes } => {
+            assert_eq!((guild_id, id), (1, 3));
+            assert_eq!(changes.tags.as_deref(), Some("unity,shaders"));
+            assert!(changes.title.is_none() && changes.content.is_none());
+        }
+        command => panic!("Unexpected command {:?}", command),
+    }
+    assert_eq!(arguments.config.overrides.database_url.as_deref(), Some("sqlite:admin.sqlite"));
+
+    assert!(matches!(parse(&[]).unwrap().command, AdminCommand::Help));
+    assert!(matches!(parse(&["tips", "show", "--id", "3"]), Err(AdminError::Usage(problem)) if problem == "--guild is required."));
+    assert!(matches!(parse(&["tips", "show", "--guild", "one", "--id", "3"]), Err(AdminError::Usage(_))));
+    assert!(matches!(parse(&["tips", "rename"]), Err(AdminError::Usage(_))));
+    assert!(matches!(parse(&["validate", "--guild", "1", "--unknown", "value"]), Err(AdminError::Config(_))));
+}
+
+#[tokio::test]
+async fn tips_are_managed_without_the_bot() {
+    let path = std::env::temp_dir().join(format!("gamebot_admin_test_{}.sqlite", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    let config = Config::load(&parse(&["--database-url", &url]).unwrap().config, |_| None).expect("Invalid configuration");
+    assert!(config.token.is_none());
+    let database = Database::connect(&config.database_url).await.expect("Failed to open the database");
+    let with_guild = |args: &[&'static str]| [args, &["--guild", "1"]].concat();
+
+    // 1 - Migrations are run on demand, nothing else is done before
+    assert!(matches!(run(&database, &config, &with_guild(&["tips", "list"])).await, Err(AdminError::PendingMigrations(8))));
+    assert!(run(&database, &config, &["migrations", "status"]).await.unwrap().contains("pending: 7 - create command_prefixes table"));
+    run(&database, &config, &["migrations", "run"]).await.expect("Failed to migrate");
+    assert_eq!(run(&database, &config, &["migrations", "status"]).await.unwrap(), "Schema version 8 of 8.\n");
+
+    // 2 - Create, edit, show, delete
+    let created = run(&database, &config, &with_guild(&["tips", "create", "--title", "Shader tip", "--content", "Use the frame debugger", "--tags", "unity"])).await.unwrap();
+    assert_eq!(created, "Tip 1 created.\n");
+    run(&database, &config, &with_guild(&["tips", "create", "--title", "Second tip", "--content", "content"])).await.unwrap();
+    run(&database, &config, &with_guild(&["tips", "edit", "--id", "1", "--content", "Use RenderDoc"])).await.unwrap();
+    assert_eq!(run(&database, &config, &with_guild(&["tips", "show", "--id", "1"])).await.unwrap(), "Shader tip\n\nUse RenderDoc\n\n#: unity\n");
+    assert_eq!(run(&database, &config, &with_guild(&["tips", "list", "--tags", "unity"])).await.unwrap(), "id\ttitle\ttags\n1\tShader tip\tunity\n");
+    assert!(matches!(run(&database, &config, &with_guild(&["tips", "edit", "--id", "1", "--title", " "])).await, Err(AdminError::Usage(_))));
+    run(&database, &config, &with_guild(&["tips", "delete", "--id", "2"])).await.unwrap();
+    assert!(matches!(run(&database, &config, &with_guild(&["tips", "delete", "--id", "2"])).await, Err(AdminError::UnknownTip(2))));
+
+    // 3 - Export, then import in another guild : duplicates and invalid tips are skipped
+    let file = std::env::temp_dir().join(format!("gamebot_admin_test_{}.json", std::process::id()));
+    let file_name = file.display().to_string();
+    run(&database, &config, &["tips", "export", "--guild", "1", "--file", &file_name]).await.unwrap();
+    let mut exported: Vec<serde_json::Value> = serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    exported.push(serde_json::json!({ "title": "Empty tip", "content": "" }));
+    exported.push(serde_json::json!({ "title": "Shader tip", "content": "Twice", "tags": "" }));
+    std::fs::write(&file, serde_json::to_string(&exported).unwrap()).unwrap();
+    let imported = run(&database, &config, &["tips", "import", "--guild", "2", "--file", &file_name]).await.unwrap();
+    assert!(imported.contains("Skipped \"Empty tip\": the content is empty.") && imported.contains("Skipped \"Shader tip\": a tip with this title already exists."));
+    assert!(imported.ends_with("1 tip(s) imported, 2 skipped.\n"));
+    assert_eq!(database.tip_repository().list(2, &[]).await.unwrap()[0].content, "Use RenderDoc");
+
+    // 4 - Scheduler and validation
+    assert_eq!(run(&database, &config, &with_guild(&["scheduler", "show"])).await.unwrap(), "The tips scheduler of this guild is not configured.\n");
+    database.scheduler_repository().save(GUILD_ID, SchedulerConfig { channel: 42, hour: 9, minute: 5, persona: None }).await.unwrap();
+    assert_eq!(run(&database, &config, &with_guild(&["scheduler", "show"])).await.unwrap(), "channel = 42\nhour = 09:05\n");
+    assert!(run(&database, &config, &with_guild(&["validate"])).await.is_ok());
+    let tip = NewTip { title: String::from("Bad tags"), content: "a".repeat(5000), tags: String::from("Unity, shaders") };
+    let tip = database.tip_repository().create(GUILD_ID, tip).await.unwrap();
+    let mut out = Vec::new();
+    let validated = admin::run(&database, &config, parse(&with_guild(&["validate"])).unwrap().command, &mut out).await;
+    assert!(matches!(validated, Err(AdminError::Invalid(2))));
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(&format!("tip {}: the content is longer than 4096 characters.", tip.id)));
+    assert!(out.contains(&format!("tip {}: the tags \"Unity, shaders\" are not lowercase csv", tip.id)));
+
+    // 5 - Changes are written in the audit log of the guild
+    let entries = database.audit_repository().search(GUILD_ID, &AuditFilter::default()).await.unwrap();
+    let arguments: Vec<&str> = entries.iter().map(|entry| entry.arguments.as_str()).collect();
+    assert_eq!(arguments, ["tips delete id=2", "tips edit id=1", "tips create title=\"Second tip\"", "tips create title=\"Shader tip\""]);
+    assert!(entries.iter().all(|entry| entry.actor == ADMIN_ACTOR && entry.command == "gamebot-admin"));
+
+    database.close().await.unwrap();
+    for path in [file.display().to_string(), path.display().to_string(), format!("{}-wal", path.display()), format!("{}-shm", path.display())] {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,157 @@
+//! Snapshots of a sqlite database : retention, verification, restore and the report of scheduled failures.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{TimeZone, Utc};
+use game_bot4_fun::admin::{self, AdminArguments, AdminError};
+use game_bot4_fun::backup::{self, BackupError, SnapshotKind};
+use game_bot4_fun::clock::ManualClock;
+use game_bot4_fun::config::{BackupConfig, Config};
+use game_bot4_fun::database::{Database, run_migrations};
+use game_bot4_fun::database::url::DatabaseUrl;
+use game_bot4_fun::messenger::AdminChannel;
+use game_bot4_fun::messenger::recording::RecordingMessenger;
+use game_bot4_fun::repositories::NewTip;
+use game_bot4_fun::shutdown::Shutdown;
+
+const GUILD_ID: u64 = 1;
+
+// A directory of the test, removed before it runs
+fn temp_dir(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("gamebot_backup_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).expect("Failed to create the test directory");
+    directory
+}
+
+fn tip(title: &str) -> NewTip {
+    NewTip { title: title.to_string(), content: String::from("content"), tags: String::new() }
+}
+
+#[test]
+fn next_snapshot_follows_the_most_recent_one() {
+    let now = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+    let day = Duration::from_secs(24 * 3600);
+    assert_eq!(backup::next_snapshot_time(None, now, day), now);
+    assert_eq!(backup::next_snapshot_time(Some(now - chrono::Duration::hours(3)), now, day), now + chrono::Duration::hours(21));
+    assert_eq!(backup::next_snapshot_time(Some(now - chrono::Duration::days(2)), now, day), now);
+}
+
+#[tokio::test]
+async fn snapshots_are_kept_verified_and_restored() {
+    let directory = temp_dir("restore");
+    let url = format!("sqlite:{}", directory.join("database.sqlite").display());
+    let args = ["--database-url", &url, "--backup-directory", &directory.join("snapshots").display().to_string()].map(String::from);
+    let config = Config::load(&AdminArguments::parse(args).unwrap().config, |name| (name == "BACKUP_KEEP").then(|| String::from("2")))
+        .expect("Invalid configuration");
+    let database = Database::connect(&config.database_url).await.expect("Failed to open the database");
+    run_migrations(&database, None).await.expect("Failed to migrate");
+    let tips = database.tip_repository();
+    tips.create(GUILD_ID, tip("Kept tip")).await.unwrap();
+
+    // 1 - Only the most recent snapshots are kept
+    let backups = database.backups(config.backup.clone()).expect("Sqlite databases can be backed up");
+    let start = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+    for hour in 0..3 {
+        backups.snapshot(start + chrono::Duration::hours(hour), SnapshotKind::Scheduled).await.expect("Failed to write the snapshot");
+    }
+    let snapshots = backups.list().unwrap();
+    let names: Vec<String> = snapshots.iter().map(|snapshot| snapshot.file_name()).collect();
+    assert_eq!(names, ["database-20240115-130000.sqlite", "database-20240115-140000.sqlite"]);
+    assert_eq!(backup::verify_snapshot(&snapshots[1].path).unwrap(), database.latest_version());
+
+    // 2 - The snapshot replaces the database
+    tips.create(GUILD_ID, tip("Tip created after the snapshot")).await.unwrap();
+    let restored = backups.restore(&snapshots[1].path, start + chrono::Duration::days(1)).await.unwrap();
+    assert_eq!(restored.version, database.latest_version());
+    assert_eq!(restored.previous, directory.join("snapshots").join("before-restore-20240116-120000.sqlite"));
+    assert_eq!(backup::verify_snapshot(&restored.previous).unwrap(), database.latest_version());
+    let titles: Vec<String> = tips.list(GUILD_ID, &[]).await.unwrap().into_iter().map(|tip| tip.title).collect();
+    assert_eq!(titles, ["Kept tip"]);
+
+    // 3 - Files that aren't snapshots of the bot are refused before the database is touched
+    let garbage = directory.join("garbage.sqlite");
+    std::fs::write(&garbage, vec![42; 8192]).unwrap();
+    assert!(matches!(backup::verify_snapshot(&garbage), Err(BackupError::Corrupted(..))));
+    let foreign = directory.join("foreign.sqlite");
+    rusqlite::Connection::open(&foreign).unwrap().execute_batch("CREATE TABLE notes (text TEXT);").unwrap();
+    assert!(matches!(backup::verify_snapshot(&foreign), Err(BackupError::NotABotDatabase(_))));
+    let newer = directory.join("newer.sqlite");
+    std::fs::copy(&snapshots[1].path, &newer).unwrap();
+    rusqlite::Connection::open(&newer).unwrap()
+        .execute("INSERT INTO schema_version (version, name, applied_at) VALUES (999, 'from the future', '')", []).unwrap();
+    assert!(matches!(backup::verify_snapshot(&newer), Err(BackupError::TooNew { database: 999, .. })));
+    assert!(matches!(backups.restore(&directory.join("missing.sqlite"), Utc::now()).await, Err(BackupError::File(..))));
+
+    // 4 - gamebot-admin, the copies made before a restore are not deleted by the retention
+    let mut out = Vec::new();
+    let restore = AdminArguments::parse(["backup", "restore", "--file", &newer.display().to_string()].map(String::from)).unwrap();
+    assert!(matches!(admin::run(&database, &config, restore.command, &mut out).await, Err(AdminError::Backup(BackupError::TooNew { .. }))));
+    let restore = AdminArguments::parse(["backup", "restore", "--file", &snapshots[0].path.display().to_string()].map(String::from)).unwrap();
+    admin::run(&database, &config, restore.command, &mut out).await.expect("Failed to restore");
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("The current database has been saved in") && out.contains("The database has been restored from"));
+    assert_eq!(backups.list().unwrap().len(), 2);
+
+    // 5 - Snapshots written with /backup Now have their own retention : they never delete the scheduled ones
+    let scheduled: Vec<String> = backups.list().unwrap().iter().map(|snapshot| snapshot.file_name()).collect();
+    for hour in 0..3 {
+        backups.snapshot(start + chrono::Duration::days(2) + chrono::Duration::hours(hour), SnapshotKind::Manual).await.expect("Failed to write the snapshot");
+    }
+    let snapshots = backups.list().unwrap();
+    let names = |kind| snapshots.iter().filter(|snapshot| snapshot.kind == kind).map(|snapshot| snapshot.file_name()).collect::<Vec<String>>();
+    assert_eq!(names(SnapshotKind::Scheduled), scheduled);
+    assert_eq!(names(SnapshotKind::Manual), ["manual-20240117-130000.sqlite", "manual-20240117-140000.sqlite"]);
+
+    database.close().await.unwrap();
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[tokio::test(start_paused = true)]
+async fn failed_scheduled_snapshots_are_reported() {
+    const ADMIN_CHANNEL_ID: u64 = 99;
+    let directory = temp_dir("report");
+    // The backup directory is a file : the snapshots can't be written
+    let blocked = directory.join("blocked");
+    std::fs::write(&blocked, "not a directory").unwrap();
+    let url = DatabaseUrl::parse(&format!("sqlite:{}", directory.join("database.sqlite").display())).expect("Invalid url");
+    let database = Database::connect(&url).await.expect("Failed to open the database");
+    run_migrations(&database, None).await.expect("Failed to migrate");
+    let backups = database.backups(BackupConfig { directory: blocked.clone(), interval: None, keep: 2 }).unwrap();
+
+    let messenger = Arc::new(RecordingMessenger::new());
+    let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap());
+    let admin_channel = AdminChannel::new(messenger.clone(), ADMIN_CHANNEL_ID);
+    let shutdown = Arc::new(Shutdown::default());
+    let handle = backup::schedule(Arc::new(backups), Duration::from_secs(3600), Arc::new(clock), shutdown.clone(), Some(admin_channel));
+    tokio::time::sleep(Duration::from_secs(60)).await;
+
+    // 1 - The failure is reported in the admin channel
+    let reports = messenger.sent_to(ADMIN_CHANNEL_ID);
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0].embeds[0].0;
+    assert_eq!(report.get("title").and_then(|value| value.as_str()), Some("Scheduled backup failed"));
+    assert!(report.get("description").and_then(|value| value.as_str()).unwrap().contains(&blocked.display().to_string()));
+
+    // 2 - The bot stops at once : waiting for the retry isn't a running work
+    assert_eq!(shutdown.running(), 0);
+    assert!(shutdown.stop(Duration::from_secs(5)).await);
+    assert!(!handle.is_finished());
+
+    handle.abort();
+    database.close().await.unwrap();
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[test]
+fn backup_settings_are_validated() {
+    let config = Config::load(&Default::default(), |_| None).expect("Invalid configuration");
+    let BackupConfig { directory, interval, keep } = config.backup;
+    assert_eq!((directory, interval, keep), (PathBuf::from("backups"), None, 7));
+
+    let config = Config::load(&Default::default(), |name| (name == "BACKUP_INTERVAL").then(|| String::from("6"))).unwrap();
+    assert_eq!(config.backup.interval, Some(Duration::from_secs(6 * 3600)));
+    assert!(Config::load(&Default::default(), |name| (name == "BACKUP_KEEP").then(|| String::from("0"))).is_err());
+    assert!(Config::load(&Default::default(), |name| (name == "BACKUP_INTERVAL").then(|| String::from("daily"))).is_err());
+}
@@ -0,0 +1,711 @@
+//! Every command and the tips scheduler run against the in-memory storage,
+//! what they post on discord is recorded by the RecordingMessenger.
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{TimeZone, Utc};
+use serenity::builder::{CreateApplicationCommands, CreateEmbed};
+use serenity::model::channel::PartialChannel;
+use serenity::model::guild::Role;
+use serenity::model::Permissions;
+use serenity::model::user::User;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use game_bot4_fun::clock::ManualClock;
+use game_bot4_fun::commands::message;
+use game_bot4_fun::commands::options::CommandOptions;
+use game_bot4_fun::commands::registry::{ButtonResponse, CommandRegistry, default_registry};
+use game_bot4_fun::commands::slash_command::{CommandContext, CommandResponse, Visibility};
+use game_bot4_fun::config::FeatureToggles;
+use game_bot4_fun::confirmations::{self, CONFIRMATION_TIMEOUT};
+use game_bot4_fun::messenger::{Messenger, OutgoingMessage};
+use game_bot4_fun::messenger::recording::{Outbound, RecordingMessenger};
+use game_bot4_fun::permissions::{Access, Invoker};
+use game_bot4_fun::repositories::{NewAuditEntry, NewTip, TipRepository};
+use game_bot4_fun::repositories::memory::InMemoryTipRepository;
+
+const GUILD_ID: u64 = 1;
+const CHANNEL_ID: u64 = 42;
+
+fn text(embed: &CreateEmbed, field: &str) -> String {
+    embed.0.get(field).and_then(|value| value.as_str()).unwrap_or_default().to_string()
+}
+
+fn string(value: &str) -> CommandDataOptionValue {
+    CommandDataOptionValue::String(value.to_string())
+}
+
+fn channel(id: u64) -> CommandDataOptionValue {
+    let channel: PartialChannel = serde_json::from_value(serde_json::json!({ "id": id.to_string(), "type": 0 }))
+        .expect("Invalid channel");
+    CommandDataOptionValue::Channel(channel)
+}
+
+fn role(id: u64) -> CommandDataOptionValue {
+    let role: Role = serde_json::from_value(serde_json::json!({
+        "id": id.to_string(), "guild_id": GUILD_ID.to_string(), "color": 0, "hoist": false, "managed": false,
+        "name": "helpers", "permissions": "0", "position": 1, "icon": null, "unicode_emoji": null,
+    })).expect("Invalid role");
+    CommandDataOptionValue::Role(role)
+}
+
+fn user(id: u64) -> CommandDataOptionValue {
+    let user: User = serde_json::from_value(serde_json::json!({ "id": id.to_string(), "username": "helper", "discriminator": "0001", "avatar": null }))
+        .expect("Invalid user");
+    CommandDataOptionValue::User(user, None)
+}
+
+fn action(action: &str) -> CommandOptions {
+    CommandOptions::new().with("action", string(action))
+}
+
+struct Bot {
+    ctx: CommandContext,
+    registry: CommandRegistry,
+    tips: Arc<InMemoryTipRepository>,
+    messenger: Arc<RecordingMessenger>,
+}
+
+impl Bot {
+    fn new() -> Bot {
+        let tips = Arc::new(InMemoryTipRepository::new());
+        let messenger = Arc::new(RecordingMessenger::new());
+        let ctx = CommandContext {
+            tips: tips.clone(),
+            messenger: messenger.clone(),
+            // The scheduler of the tests is configured to post a tip at 12:20
+            ..CommandContext::in_memory(Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 15, 12, 19, 30).unwrap())))
+        };
+        Bot { ctx, registry: default_registry(&FeatureToggles::default()), tips, messenger }
+    }
+
+    // The first embed of the response
+    async fn run(&self, command: &str, options: CommandOptions) -> CreateEmbed {
+        self.respond(command, options).await.message.embeds.remove(0)
+    }
+
+    async fn respond(&self, command: &str, options: CommandOptions) -> CommandResponse {
+        self.registry.dispatch(&self.ctx, command, GUILD_ID, &options).await
+    }
+
+    // Send a message starting with the default prefix
+    async fn message(&self, content: &str) -> Option<CommandResponse> {
+        self.registry.dispatch_message(&self.ctx, "!", content, &[], GUILD_ID).await
+    }
+
+    // Run the command as a member who is not a manager
+    async fn respond_as(&self, roles: &[u64], command: &str, options: CommandOptions) -> CommandResponse {
+        let ctx = CommandContext { invoker: Invoker { user_id: 7, roles: roles.to_vec(), channel_id: CHANNEL_ID, manager: false }, ..self.ctx.clone() };
+        self.registry.dispatch(&ctx, command, GUILD_ID, &options).await
+    }
+
+    // Press a button under the response, as the given user. The message of the button is updated
+    async fn press(&self, user_id: u64, response: &CommandResponse, label: &str) -> CommandResponse {
+        match self.press_button(user_id, response, label).await {
+            ButtonResponse::Update(response) => response,
+            ButtonResponse::Reply(_) => panic!("The message of the button is not updated"),
+        }
+    }
+
+    async fn press_button(&self, user_id: u64, response: &CommandResponse, label: &str) -> ButtonResponse {
+        let button = response.message.buttons.iter().find(|button| button.label == label).expect("No such button");
+        let ctx = CommandContext { invoker: Invoker { user_id, ..Invoker::default() }, ..self.ctx.clone() };
+        self.registry.dispatch_button(&ctx, &button.custom_id).await.expect("Not a button of the bot")
+    }
+
+    async fn create_tips(&self, indexes: Range<usize>) {
+        for index in indexes {
+            let tip = NewTip { title: format!("Tip number {:04} about shaders", index), content: String::from("content"), tags: String::from("unity,shaders") };
+            self.tips.create(GUILD_ID, tip).await.unwrap();
+        }
+    }
+
+    async fn create_tip(&self, title: &str) -> u64 {
+        let options = CommandOptions::new()
+            .with("title", string(title))
+            .with("content", string("content"))
+            .with("tags", string("unity"));
+        assert_eq!(text(&self.run("tips_create", options).await, "title"), title);
+        self.tips.list(GUILD_ID, &[]).await.unwrap().into_iter()
+            .find(|tip| tip.title == title)
+            .expect("Tip not created")
+            .id
+    }
+}
+
+#[tokio::test]
+async fn tip_commands_only_answer_with_embeds() {
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+
+    assert!(text(&bot.run("tips_list", CommandOptions::new().with("tags", string("unity"))).await, "description").contains("Shader tip"));
+    assert_eq!(text(&bot.run("tips_read", CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64))).await, "title"), "Shader tip");
+
+    let update = CommandOptions::new()
+        .with("id", CommandDataOptionValue::Integer(id as i64))
+        .with("content", string("new content"));
+    assert_eq!(text(&bot.run("tips_update", update).await, "description"), "new content");
+
+    let delete = CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64));
+    let preview = bot.respond("tips_delete", delete).await;
+    assert_eq!(text(&bot.press(0, &preview, "Confirm").await.message.embeds[0], "title"), "Tip deleted successfully :)");
+
+    // The tip was never posted : nothing is sent or edited
+    assert!(bot.messenger.outbound().is_empty());
+}
+
+#[tokio::test]
+async fn errors_and_admin_commands_are_ephemeral() {
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+    let read = |id: u64| CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64));
+
+    // 1 - Up to the user, public by default
+    assert_eq!(bot.respond("tips_read", read(id)).await.visibility, Visibility::Public);
+    assert_eq!(bot.respond("tips_read", read(id).with("visibility", string("ephemeral"))).await.visibility, Visibility::Ephemeral);
+    // 2 - Errors are only shown to the user
+    assert_eq!(bot.respond("tips_read", read(id + 1).with("visibility", string("public"))).await.visibility, Visibility::Ephemeral);
+    assert_eq!(bot.respond("unknown", CommandOptions::new()).await.visibility, Visibility::Ephemeral);
+    // 3 - Admin commands are always ephemeral
+    let config = CommandOptions::new()
+        .with("message_chanel", channel(CHANNEL_ID))
+        .with("hour", string("12:20"))
+        .with("visibility", string("public"));
+    assert_eq!(bot.respond("scheduler_config", config).await.visibility, Visibility::Ephemeral);
+    assert_eq!(bot.respond("scheduler", action("info")).await.visibility, Visibility::Ephemeral);
+    assert_eq!(bot.respond("tips_delete", read(id)).await.visibility, Visibility::Ephemeral);
+
+    // 4 - Only commands letting the user choose have the visibility option
+    let mut commands = CreateApplicationCommands::default();
+    bot.registry.register_all(&mut commands);
+    let has_visibility = |name: &str| commands.0.iter()
+        .find(|command| command["name"] == name).expect("Command not registered")["options"]
+        .as_array().is_some_and(|options| options.iter().any(|option| option["name"] == "visibility"));
+    assert!(has_visibility("tips_list") && has_visibility("tips_read") && has_visibility("tips_delete"));
+    assert!(!has_visibility("scheduler_config") && !has_visibility("scheduler"));
+}
+
+#[tokio::test]
+async fn capabilities_are_checked_before_commands_run() {
+    const HELPERS: u64 = 10;
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+    let create = |title: &str| CommandOptions::new()
+        .with("title", string(title))
+        .with("content", string("content"));
+    let delete = CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64));
+    let permission = |action: &str, role_id: u64, capability: &str| CommandOptions::new()
+        .with("action", string(action))
+        .with("role", role(role_id))
+        .with("capability", string(capability));
+
+    // 1 - Without any grant, only managers can write tips
+    let denied = bot.respond_as(&[HELPERS], "tips_create", create("Blueprint tip")).await;
+    assert_eq!(text(&denied.message.embeds[0], "title"), "Permission denied");
+    assert!(text(&denied.message.embeds[0], "description").contains("tips.write"));
+    assert_eq!(denied.visibility, Visibility::Ephemeral);
+    assert_eq!(bot.tips.list(GUILD_ID, &[]).await.unwrap().len(), 1);
+    let read = CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64));
+    assert_eq!(text(&bot.respond_as(&[], "tips_read", read).await.message.embeds[0], "title"), "Shader tip");
+    // /permissions is reserved to managers
+    let granted = bot.respond_as(&[HELPERS], "permissions", permission("grant", HELPERS, "tips.write")).await;
+    assert_eq!(text(&granted.message.embeds[0], "title"), "Permission denied");
+
+    // 2 - A manager grants tips.write to the role
+    assert_eq!(text(&bot.run("permissions", permission("grant", HELPERS, "tips.write")).await, "title"), "Permission granted");
+    assert_eq!(text(&bot.run("permissions", permission("grant", HELPERS, "tips.write")).await, "title"), "Nothing to change");
+    assert_eq!(text(&bot.respond_as(&[HELPERS], "tips_create", create("Blueprint tip")).await.message.embeds[0], "title"), "Blueprint tip");
+    assert_eq!(text(&bot.respond_as(&[11], "tips_create", create("Material tip")).await.message.embeds[0], "title"), "Permission denied");
+    assert_eq!(text(&bot.respond_as(&[HELPERS], "tips_delete", delete.clone()).await.message.embeds[0], "title"), "Permission denied");
+
+    // 3 - A capability granted to @everyone (the id of the guild) is granted to every member
+    bot.run("permissions", permission("grant", GUILD_ID, "tips.delete")).await;
+    let listed = text(&bot.run("permissions", action("list")).await, "description");
+    assert!(listed.contains("`tips.write` : <@&10>") && listed.contains("`tips.delete` : @everyone") && listed.contains("`scheduler.manage` : managers only"));
+    let preview = bot.respond_as(&[], "tips_delete", delete).await;
+    assert_eq!(text(&bot.press(7, &preview, "Confirm").await.message.embeds[0], "title"), "Tip deleted successfully :)");
+
+    // 4 - Revoked capabilities are denied again
+    assert_eq!(text(&bot.run("permissions", permission("revoke", HELPERS, "tips.write")).await, "title"), "Permission revoked");
+    assert_eq!(text(&bot.run("permissions", permission("revoke", HELPERS, "tips.write")).await, "title"), "Nothing to change");
+    assert_eq!(text(&bot.respond_as(&[HELPERS], "tips_create", create("Material tip")).await.message.embeds[0], "title"), "Permission denied");
+    let missing_role = bot.run("permissions", action("grant").with("capability", string("tips.write"))).await;
+    assert_eq!(text(&missing_role, "title"), "Invalid parameter  `role`");
+
+    // 5 - Discord hides admin commands from members without the default permissions,
+    // commands reserved to a capability stay visible to the roles granted it
+    let mut commands = CreateApplicationCommands::default();
+    bot.registry.register_all(&mut commands);
+    let default_permissions = |name: &str| commands.0.iter()
+        .find(|command| command["name"] == name).expect("Command not registered")
+        .get("default_member_permissions").and_then(|value| value.as_str()).map(String::from);
+    for command in bot.registry.commands() {
+        let expected = match command.access() {
+            Access::Everyone => None,
+            Access::Capability(_) => Some(Permissions::SEND_MESSAGES.bits().to_string()),
+            Access::Managers => Some(Permissions::MANAGE_GUILD.bits().to_string()),
+        };
+        assert_eq!(default_permissions(command.name()), expected, "/{}", command.name());
+    }
+    assert_eq!(default_permissions("tips_create"), Some(Permissions::SEND_MESSAGES.bits().to_string()));
+    assert_eq!(default_permissions("scheduler"), Some(Permissions::SEND_MESSAGES.bits().to_string()));
+    assert_eq!(default_permissions("permissions"), Some(Permissions::MANAGE_GUILD.bits().to_string()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn audit_log_records_who_changed_what() {
+    const HELPERS: u64 = 10;
+    const MOD_LOG: u64 = 43;
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+    let search = |filters: &[(&str, CommandDataOptionValue)]| filters.iter()
+        .fold(action("search"), |options, (name, value)| options.with(name, value.clone()));
+
+    // 1 - Two days later, a helper renames the tip. New entries are posted in the mod-log channel
+    tokio::time::sleep(Duration::from_secs(2 * 24 * 3600)).await;
+    let grant = action("grant").with("role", role(HELPERS)).with("capability", string("tips.write"));
+    bot.run("permissions", grant).await;
+    bot.run("audit", action("mirror").with("channel", channel(MOD_LOG))).await;
+    let update = CommandOptions::new()
+        .with("id", CommandDataOptionValue::Integer(id as i64))
+        .with("title", string("Shader, \"tips\""));
+    bot.respond_as(&[HELPERS], "tips_update", update).await;
+    // Reading commands and failures are not written
+    bot.run("tips_read", CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64))).await;
+    bot.respond_as(&[], "tips_create", CommandOptions::new().with("title", string("Denied")).with("content", string("content"))).await;
+
+    let mirrored: Vec<String> = bot.messenger.sent_to(MOD_LOG).iter().map(|message| text(&message.embeds[0], "title")).collect();
+    assert_eq!(mirrored, ["/audit", "/tips_update"]);
+    let posted = &bot.messenger.sent_to(MOD_LOG)[1].embeds[0];
+    assert!(text(posted, "description").starts_with("Run by <@7>"));
+    assert!(posted.0["fields"][0]["value"].as_str().unwrap().contains("\"title\":\"Shader tip\""));
+
+    // 2 - Search by user, command and days, in the timezone of the bot
+    let searched = bot.run("audit", search(&[("user", user(7))])).await;
+    assert_eq!(text(&searched, "description").lines().count(), 1);
+    assert!(text(&searched, "description").contains(&format!("<@7> **/tips_update** id={}, title=\"Shader, \\\"tips\\\"\"", id)));
+    let searched = bot.run("audit", search(&[("command", string("/tips_create"))])).await;
+    assert!(text(&searched, "description").contains("<t:1705321170:f> <@0> **/tips_create**"));
+    assert_eq!(searched.0["footer"]["text"], "1 of 1 entries. Export them to see the values before and after each change.");
+    let searched = bot.run("audit", search(&[("from", string("2024-01-16"))])).await;
+    assert_eq!(text(&searched, "description").lines().count(), 3);
+    assert!(text(&searched, "description").lines().next().unwrap().contains("/tips_update"));
+    let searched = bot.run("audit", search(&[("to", string("2024-01-16"))])).await;
+    assert_eq!(text(&searched, "description").lines().count(), 1);
+    let searched = bot.run("audit", search(&[("from", string("2024-01-18"))])).await;
+    assert_eq!(text(&searched, "description"), "No entry matches the filters.");
+    let searched = bot.run("audit", search(&[("from", string("2024-01-17")), ("to", string("2024-01-16"))])).await;
+    assert_eq!(text(&searched, "title"), "Invalid parameter  `to`");
+
+    // 3 - Export the values before and after each change as CSV
+    let exported = bot.respond("audit", action("export").with("user", user(7))).await;
+    let file = &exported.message.attachments[0];
+    assert_eq!(file.name, "audit.csv");
+    let file = String::from_utf8(file.data.clone()).unwrap();
+    let lines: Vec<&str> = file.lines().collect();
+    assert_eq!(lines[0], "id,date,actor,command,arguments,before,after");
+    assert!(lines[1].starts_with("4,2024-01-17T12:19:30+00:00,7,tips_update,"));
+    assert!(lines[1].contains("\"{\"\"content\"\":\"\"content\"\",\"\"id\"\":1,"));
+    assert_eq!(lines.len(), 2);
+    // Values read as formulas by spreadsheets are escaped
+    let formulas = NewAuditEntry {
+        actor: 8, command: String::from("tips_update"), arguments: String::from("=HYPERLINK(\"https://example.com\", \"tip\")"),
+        before: Some(String::from("+1")), after: Some(String::from("@SUM(A1)")), created_at: Utc.with_ymd_and_hms(2024, 1, 17, 12, 0, 0).unwrap(),
+    };
+    bot.ctx.audit.record(GUILD_ID, formulas).await.unwrap();
+    let exported = bot.respond("audit", action("export").with("user", user(8))).await;
+    let file = String::from_utf8(exported.message.attachments[0].data.clone()).unwrap();
+    assert!(file.lines().nth(1).unwrap().ends_with(",tips_update,\"'=HYPERLINK(\"\"https://example.com\"\", \"\"tip\"\")\",'+1,'@SUM(A1)"));
+
+    // 4 - Only managers read the log
+    let denied = bot.respond_as(&[HELPERS], "audit", action("search")).await;
+    assert_eq!(text(&denied.message.embeds[0], "title"), "Permission denied");
+    assert_eq!(denied.visibility, Visibility::Ephemeral);
+}
+
+#[tokio::test(start_paused = true)]
+async fn cooldowns_limit_members_unless_they_bypass_them() {
+    const MODERATORS: u64 = 11;
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+    let read = || CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64));
+    let cooldown = |command: &str, scope: &str, uses: i64, period: i64| action("set")
+        .with("command", string(command))
+        .with("scope", string(scope))
+        .with("uses", CommandDataOptionValue::Integer(uses))
+        .with("period", CommandDataOptionValue::Integer(period));
+    let title = |response: CommandResponse| text(&response.message.embeds[0], "title");
+
+    // 1 - Two reads per minute for each user
+    assert_eq!(text(&bot.run("cooldowns", cooldown("tips_read", "user", 2, 60)).await, "title"), "Cooldown set");
+    assert_eq!(title(bot.respond_as(&[], "tips_read", read()).await), "Shader tip");
+    tokio::time::sleep(Duration::from_secs(20)).await;
+    assert_eq!(title(bot.respond_as(&[], "tips_read", read()).await), "Shader tip");
+    let limited = bot.respond_as(&[], "tips_read", read()).await;
+    assert_eq!(limited.visibility, Visibility::Ephemeral);
+    assert_eq!(text(&limited.message.embeds[0], "title"), "Slow down");
+    assert!(text(&limited.message.embeds[0], "description").contains("Try again in 40 s."));
+    // Managers are not limited and their uses are not counted
+    assert_eq!(text(&bot.run("tips_read", read()).await, "title"), "Shader tip");
+
+    // 2 - Roles granted cooldowns.bypass are not limited
+    let grant = action("grant").with("role", role(MODERATORS)).with("capability", string("cooldowns.bypass"));
+    bot.run("permissions", grant).await;
+    assert_eq!(title(bot.respond_as(&[MODERATORS], "tips_read", read()).await), "Shader tip");
+
+    // 3 - The first use expires after the period
+    tokio::time::sleep(Duration::from_secs(40)).await;
+    assert_eq!(title(bot.respond_as(&[], "tips_read", read()).await), "Shader tip");
+    assert_eq!(title(bot.respond_as(&[], "tips_read", read()).await), "Slow down");
+
+    // 4 - Rules are changed at runtime
+    let replaced = bot.respond("cooldowns", cooldown("tips_read", "user", 3, 60)).await;
+    assert_eq!(replaced.audit.and_then(|change| change.before), Some(serde_json::json!({ "command": "tips_read", "scope": "user", "uses": 2, "period": 60 })));
+    assert_eq!(title(bot.respond_as(&[], "tips_read", read()).await), "Shader tip");
+    bot.run("cooldowns", cooldown("tips_list", "guild", 1, 10)).await;
+    // The uses of members bypassing the cooldowns don't count against the others
+    bot.respond_as(&[MODERATORS], "tips_list", CommandOptions::new()).await;
+    bot.run("tips_list", CommandOptions::new()).await;
+    assert_ne!(title(bot.respond_as(&[], "tips_list", CommandOptions::new()).await), "Slow down");
+    assert_eq!(title(bot.respond_as(&[], "tips_list", CommandOptions::new()).await), "Slow down");
+    let listed = text(&bot.run("cooldowns", action("list")).await, "description");
+    assert!(listed.contains("`/tips_list` : 1 use(s) every 10 s per guild\n- `/tips_read` : 3 use(s) every 60 s per user"));
+    let remove = action("remove").with("command", string("/tips_read"));
+    assert_eq!(text(&bot.run("cooldowns", remove.clone()).await, "title"), "Cooldown removed");
+    assert_eq!(text(&bot.run("cooldowns", remove).await, "title"), "Nothing to change");
+    assert_eq!(title(bot.respond_as(&[], "tips_read", read()).await), "Shader tip");
+    let unknown = bot.run("cooldowns", cooldown("unknown", "user", 1, 10)).await;
+    assert_eq!(text(&unknown, "title"), "Invalid parameter  `command`");
+
+    // 5 - Only the commands of the registry can be chosen, and only managers change the rules
+    let mut commands = CreateApplicationCommands::default();
+    bot.registry.register_all(&mut commands);
+    let choices: Vec<&str> = commands.0.iter()
+        .find(|command| command["name"] == "cooldowns").expect("Command not registered")["options"][1]["choices"]
+        .as_array().unwrap().iter().map(|choice| choice["value"].as_str().unwrap()).collect();
+    assert!(choices.contains(&"tips_read") && choices.contains(&"audit") && !choices.contains(&"cooldowns"));
+    assert_eq!(title(bot.respond_as(&[MODERATORS], "cooldowns", action("list")).await), "Permission denied");
+}
+
+#[tokio::test]
+async fn prefix_messages_run_the_same_commands() {
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+    let title = |response: Option<CommandResponse>| text(&response.expect("Not a command").message.embeds[0], "title");
+
+    // 1 - Aliases and options in the order of the slash command
+    assert_eq!(title(bot.message(&format!("!tip {}", id)).await), "Shader tip");
+    assert_eq!(title(bot.message(&format!("!tips_read id={}", id)).await), "Shader tip");
+    assert_eq!(title(bot.message("!tip random").await), "Shader tip");
+    let listed = bot.message("!tips unity,shaders").await.expect("Not a command");
+    assert!(text(listed.embeds()[0], "description").contains("Shader tip"));
+
+    // 2 - Quotes keep spaces in one argument
+    assert_eq!(message::split_arguments(r#"12 "new content" a\ b """#), Ok(vec![String::from("12"), String::from("new content"), String::from("a b"), String::new()]));
+    let updated = bot.message(&format!(r#"!tips_update {} content="Use the \"frame debugger\"""#, id)).await.expect("Not a command");
+    assert_eq!(text(&updated.message.embeds[0], "description"), r#"Use the "frame debugger""#);
+
+    // 3 - Wrong arguments show the usage, other messages are ignored
+    let unclosed = bot.message("!tip \"12").await.expect("Not a command");
+    assert_eq!(unclosed.visibility, Visibility::Ephemeral);
+    assert_eq!(text(&unclosed.message.embeds[0], "title"), "Invalid arguments");
+    assert!(text(&unclosed.message.embeds[0], "description").contains("`!tip [id] [random]`"));
+    assert_eq!(title(bot.message(&format!("!tip {} 13", id)).await), "Invalid arguments");
+    assert_eq!(title(bot.message("!tip twelve").await), "Invalid parameter  `id`");
+    assert!(bot.message("!unknown 12").await.is_none());
+    assert!(bot.message("hello !tip 12").await.is_none());
+    // The command is known before the permissions of the author are computed
+    assert_eq!(bot.registry.find_in_message("!", "!tip 12").map(|command| command.name()), Some("tips_read"));
+    assert!(["!!!", "!lol", "hello !tip 12", "?tip 12"].iter().all(|content| bot.registry.find_in_message("!", content).is_none()));
+
+    // 4 - Managers change the prefix of the guild
+    let changed = bot.respond("prefix", CommandOptions::new().with("prefix", string("?"))).await;
+    assert_eq!(text(&changed.message.embeds[0], "title"), "Prefix updated");
+    assert_eq!(changed.audit.and_then(|change| change.before), Some(serde_json::json!({ "prefix": "!" })));
+    assert_eq!(bot.ctx.prefixes.get(GUILD_ID).await.unwrap(), "?");
+    assert_eq!(text(&bot.run("prefix", CommandOptions::new().with("prefix", string("/"))).await, "title"), "Invalid parameter  `prefix`");
+    assert_eq!(title(Some(bot.respond_as(&[], "prefix", CommandOptions::new()).await)), "Permission denied");
+    bot.run("prefix", CommandOptions::new()).await;
+    assert_eq!(bot.ctx.prefixes.get(GUILD_ID).await.unwrap(), "!");
+
+    // 5 - Authors of text commands are managers through the permissions of their roles
+    let moderator = Invoker::guild_permissions(false, Permissions::SEND_MESSAGES, &[Permissions::MANAGE_MESSAGES]);
+    let manager = Invoker::guild_permissions(false, Permissions::SEND_MESSAGES, &[Permissions::MANAGE_MESSAGES, Permissions::MANAGE_GUILD]);
+    assert!(!Invoker::is_manager(moderator) && Invoker::is_manager(manager));
+    assert!(Invoker::is_manager(Invoker::guild_permissions(true, Permissions::empty(), &[])));
+    let author = |permissions| CommandContext { invoker: Invoker { user_id: 7, manager: Invoker::is_manager(permissions), ..Invoker::default() }, ..bot.ctx.clone() };
+    assert_eq!(title(bot.registry.dispatch_message(&author(moderator), "!", "!prefix ?", &[], GUILD_ID).await), "Permission denied");
+    assert_eq!(title(bot.registry.dispatch_message(&author(manager), "!", "!prefix ?", &[], GUILD_ID).await), "Prefix updated");
+    assert_eq!(bot.ctx.prefixes.get(GUILD_ID).await.unwrap(), "?");
+}
+
+#[tokio::test]
+async fn backups_are_reserved_to_managers_of_a_sqlite_database() {
+    let bot = Bot::new();
+    assert!(bot.registry.get("backup").unwrap().defer());
+    assert_eq!(text(&bot.respond_as(&[], "backup", action("now")).await.message.embeds[0], "title"), "Permission denied");
+    // The in-memory storage can't be saved
+    let response = bot.respond("backup", action("now")).await;
+    assert_eq!(response.visibility, Visibility::Ephemeral);
+    assert_eq!(text(&response.message.embeds[0], "title"), "Backups unavailable");
+}
+
+#[tokio::test(start_paused = true)]
+async fn deletions_wait_for_the_confirmation_of_the_user() {
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+    let delete = CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64));
+    let exists = || async { bot.tips.get(GUILD_ID, id).await.unwrap().is_some() };
+
+    // 1 - The preview shows the tip, the buttons are only answered for the member who run the command
+    let preview = bot.respond_as(&[], "tips_delete", delete.clone()).await;
+    assert_eq!(text(&preview.message.embeds[0], "title"), "Permission denied");
+    assert!(preview.message.buttons.is_empty());
+    let preview = bot.respond("tips_delete", delete.clone()).await;
+    assert_eq!(text(&preview.message.embeds[0], "title"), "Shader tip");
+    assert!(preview.message.content.as_deref().unwrap().starts_with(&format!("Delete the tip {} ?", id)));
+    let labels: Vec<&str> = preview.message.buttons.iter().map(|button| button.label.as_str()).collect();
+    assert_eq!(labels, ["Confirm", "Cancel"]);
+    match bot.press_button(7, &preview, "Confirm").await {
+        ButtonResponse::Reply(reply) => {
+            assert_eq!(text(&reply.message.embeds[0], "title"), "Not your confirmation");
+            assert_eq!(reply.visibility, Visibility::Ephemeral);
+        }
+        ButtonResponse::Update(_) => panic!("An other member answered the confirmation"),
+    }
+    assert!(exists().await);
+
+    // 2 - Cancelled, then expired : nothing is deleted
+    assert_eq!(text(&bot.press(0, &preview, "Cancel").await.message.embeds[0], "title"), "Cancelled");
+    assert_eq!(text(&bot.press(0, &preview, "Confirm").await.message.embeds[0], "title"), "Confirmation expired");
+    // After a restart, the buttons of a previous confirmation don't answer the new ones
+    let restarted = Bot::new();
+    restarted.create_tip("Shader tip").await;
+    restarted.respond("tips_delete", delete.clone()).await;
+    assert_eq!(text(&restarted.press(0, &preview, "Confirm").await.message.embeds[0], "title"), "Confirmation expired");
+    assert!(restarted.tips.get(GUILD_ID, id).await.unwrap().is_some());
+    let preview = bot.respond("tips_delete", delete.clone()).await;
+    tokio::time::sleep(CONFIRMATION_TIMEOUT + Duration::from_secs(1)).await;
+    assert_eq!(text(&bot.press(0, &preview, "Confirm").await.message.embeds[0], "title"), "Confirmation expired");
+    assert!(exists().await);
+
+    // 3 - Confirmed, from a text command : the deletion is written in the audit log
+    let preview = bot.message(&format!("!tips_delete {}", id)).await.expect("Not a command");
+    assert_eq!(text(&bot.press(0, &preview, "Confirm").await.message.embeds[0], "title"), "Tip deleted successfully :)");
+    assert!(!exists().await);
+    let searched = bot.run("audit", action("search").with("command", string("tips_delete"))).await;
+    assert_eq!(text(&searched, "description").lines().count(), 1);
+    assert!(bot.registry.dispatch_button(&bot.ctx, "other:1").await.is_none());
+    assert_eq!(confirmations::parse_button_id(&confirmations::button_id(12, false)), Some((12, false)));
+}
+
+#[tokio::test]
+async fn help_is_generated_from_the_registration_of_commands() {
+    let bot = Bot::new();
+    let field = |embed: &CreateEmbed, name: &str| embed.0["fields"].as_array().into_iter().flatten()
+        .find(|field| field["name"] == name)
+        .and_then(|field| field["value"].as_str().map(String::from))
+        .unwrap_or_default();
+
+    // 1 - A page per category, every command is listed once
+    let pages = bot.respond("help", CommandOptions::new()).await;
+    assert_eq!(pages.visibility, Visibility::Ephemeral);
+    let titles: Vec<String> = pages.embeds().iter().map(|page| text(page, "title")).collect();
+    assert_eq!(titles, ["Help : Tips", "Help : Scheduler", "Help : Administration", "Help : General"]);
+    let listed = pages.embeds().iter().map(|page| text(page, "description")).collect::<String>();
+    for name in bot.registry.names() {
+        assert_eq!(listed.matches(&format!("`/{}` :", name)).count(), 1, "/{} is not listed once", name);
+    }
+    assert!(listed.contains("`/tips_delete` : Delete the tip using it's id. *Managers and roles granted `tips.delete`.*"));
+
+    // 2 - The details of a command : options, types, choices, permission and examples
+    let details = bot.run("help", CommandOptions::new().with("command", string("tip"))).await;
+    assert_eq!(text(&details, "title"), "Help : /tips_read");
+    assert_eq!(field(&details, "Usage"), "`/tips_read [id] [random] [visibility]`\n`!tips_read [id] [random]`\n`!tip [id] [random]`");
+    assert!(field(&details, "Options").contains("- `random` (yes/no, optional)"));
+    assert_eq!(field(&details, "Permission"), "Everyone");
+    assert_eq!(field(&details, "Examples"), "`!tip 12`\n`!tip random`");
+    let details = bot.run("help", CommandOptions::new().with("command", string("/backup"))).await;
+    assert!(field(&details, "Options").contains("- `action` (text, required) : The action you want to execute. One of : now, list."));
+    assert_eq!(field(&details, "Permission"), "Managers only.\nHidden by default to the members without the permission : Manage Guilds");
+    assert_eq!(text(&bot.run("help", CommandOptions::new().with("command", string("dance"))).await, "title"), "Invalid parameter  `command`");
+    let typed = bot.message("!help tips").await.expect("Not a command");
+    assert_eq!(text(&typed.message.embeds[0], "title"), "Help : /tips_list");
+
+    // 3 - The examples can be typed
+    for name in bot.registry.names() {
+        let command = bot.registry.get(name).unwrap();
+        for example in command.examples() {
+            assert!(message::parse_options("!", name, command, example, &[]).is_ok(), "!{} {} is not valid", name, example);
+        }
+    }
+}
+
+#[tokio::test]
+async fn long_lists_are_split_in_follow_ups_or_sent_as_a_file() {
+    let bot = Bot::new();
+    assert!(bot.registry.get("tips_list").unwrap().defer());
+    assert!(!bot.registry.get("tips_read").unwrap().defer());
+
+    // 1 - A few pages : the first one is the response, the next ones are follow-ups
+    bot.create_tips(0..150).await;
+    let response = bot.respond("tips_list", CommandOptions::new()).await;
+    let pages = response.embeds();
+    assert!(pages.len() > 1 && !response.follow_ups.is_empty());
+    assert_eq!(pages[0].0["footer"]["text"], format!("Page 1/{}", pages.len()));
+    let listed: String = pages.iter().map(|page| text(page, "description")).collect();
+    assert!(pages.iter().all(|page| text(page, "description").len() <= 4096));
+    assert!((0..150).all(|index| listed.contains(&format!("Tip number {:04} about shaders", index))));
+    assert!(response.message.attachments.is_empty());
+
+    // 2 - Too many pages : the first page and the whole list in a file
+    bot.create_tips(150..1150).await;
+    let response = bot.respond("tips_list", CommandOptions::new()).await;
+    assert!(response.follow_ups.is_empty());
+    assert_eq!(response.message.embeds.len(), 1);
+    let file = &response.message.attachments[0];
+    assert_eq!(file.name, "tips.txt");
+    let file = String::from_utf8(file.data.clone()).unwrap();
+    assert_eq!(file.lines().count(), 1 + 1150);
+    assert!(file.lines().nth(1).unwrap().ends_with("\tTip number 0000 about shaders\tunity,shaders"));
+}
+
+#[tokio::test]
+async fn tips_longer_than_a_page_are_cut() {
+    let bot = Bot::new();
+    bot.tips.create(GUILD_ID, NewTip { title: "é".repeat(5000), content: String::from("content"), tags: String::from("unity") }).await.unwrap();
+    bot.create_tip("Shader tip").await;
+
+    let response = bot.respond("tips_list", CommandOptions::new()).await;
+    let pages: Vec<String> = response.embeds().iter().map(|page| text(page, "description")).collect();
+    assert_eq!(pages.len(), 2);
+    assert!(pages.iter().all(|page| page.chars().count() <= 4096 && page.len() <= 4096));
+    assert!(pages[0].starts_with("*1* - **éé") && pages[0].trim_end().ends_with('…'));
+    assert!(pages[1].contains("Shader tip"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn scheduler_posts_tips_and_updates_edit_them() {
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+
+    let config = CommandOptions::new()
+        .with("message_chanel", channel(CHANNEL_ID))
+        .with("hour", string("12:20"));
+    bot.run("scheduler_config", config).await;
+    assert_eq!(text(&bot.run("scheduler", action("start")).await, "title"), "Scheduler is now running:");
+
+    // 1 - The tip is posted and its message remembered
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    let posted = bot.messenger.sent_to(CHANNEL_ID);
+    assert_eq!(posted.len(), 1);
+    assert_eq!(text(&posted[0].embeds[0], "title"), "Shader tip");
+    let posts = bot.tips.posts(id).await.unwrap();
+    assert_eq!(posts.len(), 1);
+    assert!(text(&bot.run("scheduler", action("info")).await, "description").contains("RUNNING"));
+
+    // 2 - Updating the tip edits the message : one request per post, the response is deferred
+    assert!(bot.registry.get("tips_update").unwrap().defer());
+    let update = CommandOptions::new()
+        .with("id", CommandDataOptionValue::Integer(id as i64))
+        .with("content", string("new content"));
+    let updated = bot.run("tips_update", update.clone()).await;
+    assert!(updated.0.contains_key("author"));
+    match bot.messenger.outbound().last() {
+        Some(Outbound::Edited { channel, message, content }) => {
+            assert_eq!((*channel, *message), (CHANNEL_ID, posts[0].message));
+            assert_eq!(text(&content.embeds[0], "description"), "new content");
+        }
+        other => panic!("The message has not been edited: {:?}", other),
+    }
+
+    // 3 - Deleted messages are forgotten
+    bot.messenger.delete_message(posts[0].message);
+    bot.run("tips_update", update).await;
+    assert!(bot.tips.posts(id).await.unwrap().is_empty());
+
+    assert!(text(&bot.run("scheduler", action("stop")).await, "description").contains("STOPPED"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn scheduler_posts_through_a_webhook_with_its_persona() {
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+
+    // 1 - The name and the avatar are checked, a name is enough to post through a webhook
+    let invalid = CommandOptions::new().with("webhook_name", string("Discord tips"));
+    assert_eq!(text(&bot.run("scheduler_config", invalid).await, "title"), "Invalid parameter  `webhook_name`");
+    let invalid = CommandOptions::new().with("webhook_avatar", string("http://example.com/tip.png"));
+    assert_eq!(text(&bot.run("scheduler_config", invalid).await, "title"), "Invalid parameter  `webhook_avatar`");
+    let config = CommandOptions::new()
+        .with("message_chanel", channel(CHANNEL_ID))
+        .with("hour", string("12:20"))
+        .with("webhook_avatar", string("https://example.com/tip.png"));
+    assert!(text(&bot.run("scheduler_config", config).await, "description").contains("- Posted as : webhook **Tip of the Day**"));
+    bot.run("scheduler", action("start")).await;
+
+    // 2 - The tip is posted with the persona, and edited like the other posts
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    let message = match bot.messenger.outbound().as_slice() {
+        [Outbound::SentAs { channel, message, name, avatar_url, content }] => {
+            assert_eq!((*channel, name.as_str(), avatar_url.as_deref()), (CHANNEL_ID, "Tip of the Day", Some("https://example.com/tip.png")));
+            assert_eq!(text(&content.embeds[0], "title"), "Shader tip");
+            *message
+        }
+        other => panic!("The tip has not been posted through a webhook: {:?}", other),
+    };
+    assert_eq!(bot.tips.posts(id).await.unwrap()[0].message, message);
+    let update = CommandOptions::new()
+        .with("id", CommandDataOptionValue::Integer(id as i64))
+        .with("content", string("new content"));
+    bot.run("tips_update", update).await;
+    assert!(matches!(bot.messenger.outbound().last(), Some(Outbound::Edited { message: edited, .. }) if *edited == message));
+
+    // 3 - Without the permission to manage webhooks, the bot post the tip itself
+    bot.messenger.set_webhooks_denied(true);
+    tokio::time::sleep(Duration::from_secs(24 * 60 * 60)).await;
+    assert_eq!(bot.messenger.sent_to(CHANNEL_ID).len(), 1);
+
+    // 4 - The name is kept when the avatar changes, post_as=bot alone forget the persona
+    let config = CommandOptions::new().with("webhook_name", string("Daily tip"));
+    bot.run("scheduler_config", config).await;
+    let config = CommandOptions::new().with("post_as", string("webhook")).with("webhook_avatar", string("https://example.com/daily.png"));
+    assert!(text(&bot.run("scheduler_config", config).await, "description").contains("webhook **Daily tip**"));
+    // A persona given with post_as=bot is refused instead of being dropped
+    let config = CommandOptions::new().with("post_as", string("bot")).with("webhook_name", string("Daily tip"));
+    assert_eq!(text(&bot.run("scheduler_config", config).await, "title"), "Invalid parameter  `webhook_name`");
+    assert!(bot.ctx.scheduler_configs.get(GUILD_ID).await.unwrap().unwrap().persona.is_some());
+    let config = CommandOptions::new().with("post_as", string("bot"));
+    assert!(text(&bot.run("scheduler_config", config).await, "description").contains("- Posted as : the bot"));
+    bot.run("scheduler", action("stop")).await;
+}
+
+#[tokio::test]
+async fn failed_edits_keep_posts_to_retry() {
+    let bot = Bot::new();
+    let id = bot.create_tip("Shader tip").await;
+    let message = bot.messenger.send(CHANNEL_ID, OutgoingMessage::text("tip")).await.unwrap();
+    bot.tips.save_post(id, CHANNEL_ID, message).await.unwrap();
+
+    // A failed edit is counted, the post is kept to retry later
+    bot.messenger.set_unavailable(true);
+    let update = CommandOptions::new()
+        .with("id", CommandDataOptionValue::Integer(id as i64))
+        .with("title", string("Shader tips"));
+    let updated = bot.run("tips_update", update).await;
+    assert_eq!(text(&updated, "title"), "Shader tips");
+    assert_eq!(updated.0["author"]["name"], "0 posted message(s) updated, 1 failed");
+    assert_eq!(bot.tips.posts(id).await.unwrap().len(), 1);
+
+    // Threads and direct messages
+    bot.messenger.set_unavailable(false);
+    let thread = bot.messenger.create_thread(CHANNEL_ID, message, "Discussion").await.unwrap();
+    bot.messenger.send(thread, OutgoingMessage::text("reply")).await.unwrap();
+    bot.messenger.send_dm(7, OutgoingMessage::text("hello")).await.unwrap();
+    assert!(matches!(bot.messenger.outbound().as_slice(), [
+        Outbound::Sent { .. },
+        Outbound::ThreadCreated { .. },
+        Outbound::Sent { channel, .. },
+        Outbound::DirectMessage { user: 7, .. },
+    ] if *channel == thread));
+}
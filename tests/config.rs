@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::fs;
+use game_bot4_fun::config::{CliArguments, Config, PartialConfig};
+
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+    move |name| vars.get(name).cloned()
+}
+
+fn cli(args: &[&str]) -> CliArguments {
+    CliArguments::parse(args.iter().map(|arg| arg.to_string())).expect("Invalid arguments")
+}
+
+#[test]
+fn layers_override_each_other() {
+    let file = PartialConfig::from_toml(r#"
+        token = "file-token"
+        database_url = "sqlite:file.sqlite"
+        timezone = "Europe/Paris"
+        home_guild = 1
+        shutdown_timeout = 20
+        [log]
+        level = "warn"
+        [features]
+        scheduler = false
+    "#, "config.toml").expect("Invalid file");
+    let env = PartialConfig::from_env(env(&[
+        ("DATABASE_URL", "sqlite:env.sqlite"),
+        ("HOME_GUILD_ID", "2"),
+        ("LOG_LEVEL", "Debug"),
+        ("SHUTDOWN_TIMEOUT", "5"),
+    ])).expect("Invalid environment");
+    let cli = cli(&["--home-guild=3", "--enable", "scheduler", "--disable", "ping"]);
+
+    let config = file.merge(env).merge(cli.overrides).validate().expect("Invalid configuration");
+    assert_eq!(config.token.as_deref(), Some("file-token"));
+    assert_eq!(config.database_url.to_string(), "sqlite:env.sqlite");
+    assert_eq!(config.home_guild, Some(3));
+    assert_eq!(config.timezone.map(|timezone| timezone.name()), Some("Europe/Paris"));
+    assert_eq!(config.log.level, "Debug");
+    assert_eq!(config.shutdown_timeout.as_secs(), 5);
+    assert!(config.features.scheduler);
+    assert!(!config.features.ping);
+}
+
+#[test]
+fn token_file_is_read_and_never_printed() {
+    let path = std::env::temp_dir().join(format!("gamebot_token_{}", std::process::id()));
+    fs::write(&path, "secret-token\n").expect("Failed to write the token file");
+
+    let file = PartialConfig::from_toml(r#"token = "file-token""#, "config.toml").expect("Invalid file");
+    let env = PartialConfig::from_env(env(&[("DISCORD_TOKEN_FILE", path.to_str().unwrap())])).expect("Invalid environment");
+    let config = file.merge(env).validate().expect("Invalid configuration");
+    fs::remove_file(&path).ok();
+
+    assert_eq!(config.require_token(), Ok("secret-token"));
+    assert!(!config.to_string().contains("secret-token"));
+}
+
+#[test]
+fn every_problem_is_reported() {
+    let invalid_env = env(&[
+        ("HOME_GUILD_ID", "my guild"),
+        ("FEATURE_PING", "maybe"),
+        ("DATABASE_URL", "mysql://localhost/tips"),
+    ]);
+    let err = Config::load(&cli(&["--timezone", "Mars/Olympus", "--log-level", "game_bot4_fun=loud"]), invalid_env)
+        .expect_err("The configuration must be rejected");
+    // Environment variables are parsed before the configuration is validated
+    assert_eq!(err.problems.len(), 2);
+
+    let err = Config::load(&cli(&["--timezone", "Mars/Olympus", "--log-level", "game_bot4_fun=loud", "--database-url", "mysql://localhost"]), env(&[]))
+        .expect_err("The configuration must be rejected");
+    assert_eq!(err.problems.len(), 3, "{}", err);
+    assert!(err.to_string().contains("Mars/Olympus"));
+
+    assert!(Config::load(&cli(&["--log-level", "info,game_bot4_fun=loud"]), env(&[])).is_err());
+    assert!(PartialConfig::from_toml("unknown = 1", "config.toml").is_err());
+    assert!(CliArguments::parse(vec![String::from("--unknown")]).is_err());
+    assert!(Config::load(&cli(&[]), env(&[])).expect("Invalid configuration").require_token().is_err());
+}
@@ -0,0 +1,45 @@
+use serenity::builder::CreateEmbed;
+use game_bot4_fun::commands::options::OptionError;
+use game_bot4_fun::error::BotError;
+use game_bot4_fun::locale::Locale;
+use game_bot4_fun::repositories::RepositoryError;
+
+fn text(embed: &CreateEmbed, field: &str) -> String {
+    embed.0.get(field).and_then(|value| value.as_str()).unwrap_or_default().to_string()
+}
+
+#[test]
+fn internal_errors_only_show_the_incident_id() {
+    let err = BotError::from(RepositoryError::Database(tokio_rusqlite::Error::ConnectionClosed));
+    assert!(err.is_internal());
+
+    let incident = err.report("tips_read").expect("Internal errors have an incident id");
+    assert_eq!(incident.len(), 8);
+    let embed = err.to_embed(Locale::English, Some(&incident));
+    assert!(text(&embed, "description").contains(&incident));
+    assert!(!text(&embed, "description").contains("ConnectionClosed"));
+    assert!(!text(&embed, "description").to_lowercase().contains("connection"));
+}
+
+#[test]
+fn user_errors_are_explained_in_the_language_of_the_user() {
+    let duplicate = BotError::from(RepositoryError::DuplicateTitle(String::from("Shader tip")));
+    assert!(!duplicate.is_internal());
+    assert_eq!(duplicate.report("tips_create"), None);
+    assert!(text(&duplicate.to_embed(Locale::English, None), "description").contains("already exists"));
+    assert!(text(&duplicate.to_embed(Locale::French, None), "description").contains("existe déjà"));
+
+    let unknown = BotError::UnknownTip(12);
+    assert_eq!(text(&unknown.to_embed(Locale::from_discord("en-US"), None), "title"), "Tip id unknown");
+    assert_eq!(text(&unknown.to_embed(Locale::from_discord("fr"), None), "title"), "Astuce inconnue");
+
+    let option = BotError::from(OptionError::Missing(String::from("id")));
+    assert!(text(&option.to_embed(Locale::English, None), "title").contains("`id`"));
+    assert_eq!(text(&option.to_embed(Locale::English, None), "description"), "The parameter id is required.");
+    assert_eq!(text(&option.to_embed(Locale::French, None), "title"), "Paramètre invalide  `id`");
+    assert_eq!(text(&option.to_embed(Locale::French, None), "description"), "Le paramètre id est obligatoire.");
+    let wrong_type = BotError::from(OptionError::wrong_type::<i64>("hour"));
+    assert_eq!(text(&wrong_type.to_embed(Locale::French, None), "description"), "Le paramètre hour a un mauvais format.\nAttendu : un nombre entier.");
+    let out_of_range = BotError::from(OptionError::OutOfRange { name: String::from("hour"), min: 0, max: 23 });
+    assert_eq!(text(&out_of_range.to_embed(Locale::French, None), "description"), "Le paramètre hour doit être entre 0 et 23.");
+}
@@ -0,0 +1,192 @@
+//! The migrations of the sqlite database : versions, dry-runs, failures and the data of the legacy schema.
+//! Each test works on its own database file, opened again with rusqlite to look at its schema.
+
+use std::path::{Path, PathBuf};
+use rusqlite::Connection;
+use game_bot4_fun::database::{ConnectionError, Database, run_migrations};
+use game_bot4_fun::database::migrations::MigrationError;
+use game_bot4_fun::database::url::DatabaseUrl;
+use game_bot4_fun::repositories::NewTip;
+
+// The schema of the bot before migrations existed : one guild, no schema_version table
+const LEGACY_SCHEMA: &str = "
+    CREATE TABLE tips (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          title TEXT NOT NULL UNIQUE,
+          content TEXT NOT NULL,
+          tags TEXT
+    );
+    CREATE TABLE scheduler_config (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          channel INTEGER NOT NULL,
+          hour INTEGER NOT NULL,
+          minute INTEGER NOT NULL
+    );
+";
+
+fn database_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("gamebot_migrations_{}_{}.sqlite", name, std::process::id()));
+    remove_database(&path);
+    path
+}
+
+fn remove_database(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+fn url(path: &Path) -> DatabaseUrl {
+    DatabaseUrl::parse(&format!("sqlite:{}", path.display())).expect("Invalid url")
+}
+
+async fn connect(path: &Path) -> Database {
+    Database::connect(&url(path)).await.expect("Failed to open the database")
+}
+
+fn table_exists(conn: &Connection, table: &str) -> bool {
+    conn.query_row("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)", [table], |row| row.get(0)).unwrap()
+}
+
+fn columns(conn: &Connection, table: &str) -> Vec<String> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+    let columns = stmt.query_map([], |row| row.get(1)).unwrap().collect::<Result<Vec<String>, _>>().unwrap();
+    columns
+}
+
+#[tokio::test]
+async fn dry_run_lists_pending_migrations_without_touching_the_database() {
+    let path = database_path("dry_run");
+
+    // 1 - A missing file is an error, it isn't created
+    assert!(matches!(Database::connect_read_only(&url(&path)).await, Err(ConnectionError::MissingFile(_))));
+    assert!(!path.exists());
+
+    // 2 - On a legacy database, the pending migrations are listed without writing anything
+    Connection::open(&path).unwrap().execute_batch(LEGACY_SCHEMA).unwrap();
+    let content = std::fs::read(&path).unwrap();
+    let database = Database::connect_read_only(&url(&path)).await.expect("Failed to open the database");
+    let pending = database.pending_migrations().await.expect("Failed to list migrations");
+    assert_eq!(pending.len(), database.latest_version() as usize);
+    database.close().await.unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), content);
+    for suffix in ["-wal", "-shm"] {
+        assert!(!Path::new(&format!("{}{}", path.display(), suffix)).exists());
+    }
+    let conn = Connection::open(&path).unwrap();
+    let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+    assert_eq!(journal_mode, "delete");
+    assert!(!table_exists(&conn, "schema_version"));
+    drop(conn);
+    remove_database(&path);
+
+    // 3 - On a new database, every migration is pending
+    let database = connect(&path).await;
+    let pending = database.pending_migrations().await.expect("Failed to list migrations");
+    let versions: Vec<u32> = pending.iter().map(|(version, _)| *version).collect();
+    assert_eq!(versions, (1..=database.latest_version()).collect::<Vec<u32>>());
+    assert_eq!(pending[0].1, "create tips and scheduler_config tables");
+    assert!(!table_exists(&Connection::open(&path).unwrap(), "schema_version"));
+
+    // 4 - Migrated, then migrated again : nothing changes
+    run_migrations(&database, None).await.expect("Failed to migrate");
+    assert!(database.pending_migrations().await.unwrap().is_empty());
+    let applied = |conn: &Connection| conn.prepare("SELECT version, applied_at FROM schema_version ORDER BY version").unwrap()
+        .query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?))).unwrap()
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    let before = applied(&Connection::open(&path).unwrap());
+    assert_eq!(before.len(), database.latest_version() as usize);
+    run_migrations(&database, None).await.expect("Migrations must be idempotent");
+    assert_eq!(applied(&Connection::open(&path).unwrap()), before);
+
+    database.close().await.unwrap();
+    remove_database(&path);
+}
+
+#[tokio::test]
+async fn failed_migration_is_rolled_back() {
+    let path = database_path("rollback");
+    let database = connect(&path).await;
+    run_migrations(&database, None).await.expect("Failed to migrate");
+    let latest = database.latest_version();
+
+    // The last migration adds persona_name then persona_avatar : the second one already exists
+    Connection::open(&path).unwrap().execute_batch(&format!("
+        DELETE FROM schema_version WHERE version = {};
+        ALTER TABLE scheduler_config DROP COLUMN persona_name;
+    ", latest)).unwrap();
+    assert!(matches!(run_migrations(&database, None).await, Err(MigrationError::Database(_))));
+
+    // The version doesn't advance and the column added before the failure is removed
+    let pending: Vec<u32> = database.pending_migrations().await.unwrap().iter().map(|(version, _)| *version).collect();
+    assert_eq!(pending, [latest]);
+    let columns = columns(&Connection::open(&path).unwrap(), "scheduler_config");
+    assert!(!columns.contains(&String::from("persona_name")) && columns.contains(&String::from("persona_avatar")));
+
+    database.close().await.unwrap();
+    remove_database(&path);
+}
+
+#[tokio::test]
+async fn database_newer_than_the_bot_is_refused() {
+    let path = database_path("too_new");
+    let database = connect(&path).await;
+    run_migrations(&database, None).await.expect("Failed to migrate");
+    let latest = database.latest_version();
+    Connection::open(&path).unwrap()
+        .execute("INSERT INTO schema_version (version, name, applied_at) VALUES (?1, 'from the future', '')", [latest + 1]).unwrap();
+
+    assert!(matches!(run_migrations(&database, None).await,
+        Err(MigrationError::DatabaseTooNew { database, binary }) if database == latest + 1 && binary == latest));
+    assert!(matches!(database.pending_migrations().await, Err(MigrationError::DatabaseTooNew { .. })));
+
+    database.close().await.unwrap();
+    remove_database(&path);
+}
+
+#[tokio::test]
+async fn legacy_data_is_given_to_the_home_guild() {
+    const HOME_GUILD_ID: u64 = 1234;
+    let path = database_path("legacy");
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(LEGACY_SCHEMA).unwrap();
+    conn.execute_batch("
+        INSERT INTO tips (id, title, content, tags) VALUES (3, 'Shader tip', 'Use the frame debugger', 'unity');
+        INSERT INTO tips (id, title, content, tags) VALUES (7, 'Blueprint tip', 'Collapse nodes', 'unreal');
+        INSERT INTO scheduler_config (channel, hour, minute) VALUES (42, 9, 30);
+    ").unwrap();
+    drop(conn);
+    let legacy_rows = |conn: &Connection| conn.prepare("SELECT id, title FROM tips ORDER BY id").unwrap()
+        .query_map([], |row| Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?))).unwrap()
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    let before = legacy_rows(&Connection::open(&path).unwrap());
+    let database = connect(&path).await;
+
+    // 1 - Without HOME_GUILD_ID, the migration is refused and the data is left as it was
+    assert!(matches!(run_migrations(&database, None).await, Err(MigrationError::MissingHomeGuild)));
+    let conn = Connection::open(&path).unwrap();
+    assert_eq!(legacy_rows(&conn), before);
+    assert!(!columns(&conn, "tips").contains(&String::from("guild_id")));
+    assert!(!columns(&conn, "scheduler_config").contains(&String::from("guild_id")));
+    let pending = database.pending_migrations().await.unwrap();
+    assert_eq!(pending[0], (3, "scope tips and scheduler_config by guild"));
+
+    // 2 - With it, ids are kept and every row belongs to the home guild
+    run_migrations(&database, Some(HOME_GUILD_ID)).await.expect("Failed to migrate");
+    let tips = database.tip_repository();
+    assert_eq!(tips.get(HOME_GUILD_ID, 3).await.unwrap().unwrap().title, "Shader tip");
+    assert_eq!(tips.get(HOME_GUILD_ID, 7).await.unwrap().unwrap().content, "Collapse nodes");
+    assert_eq!(tips.list(HOME_GUILD_ID, &[]).await.unwrap().len(), 2);
+    let guilds: Vec<u64> = conn.prepare("SELECT DISTINCT guild_id FROM tips UNION SELECT DISTINCT guild_id FROM scheduler_config").unwrap()
+        .query_map([], |row| row.get(0)).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(guilds, [HOME_GUILD_ID]);
+    let config = database.scheduler_repository().get(HOME_GUILD_ID).await.unwrap().expect("Configuration lost");
+    assert_eq!((config.channel, config.hour, config.minute, config.persona), (42, 9, 30, None));
+    // New tips don't reuse the ids of the legacy ones
+    let created = tips.create(HOME_GUILD_ID, NewTip { title: String::from("New tip"), content: String::from("content"), tags: String::new() }).await.unwrap();
+    assert!(created.id > 7);
+
+    drop(conn);
+    database.close().await.unwrap();
+    remove_database(&path);
+}
@@ -0,0 +1,121 @@
+//! The options of the commands are extracted by name as typed values,
+//! whatever the order discord sends them in.
+
+use serenity::model::channel::{Attachment, PartialChannel};
+use serenity::model::guild::Role;
+use serenity::model::user::User;
+use serenity::model::prelude::interaction::application_command::{CommandDataOption, CommandDataOptionValue};
+use game_bot4_fun::commands::options::{CommandOptions, OptionError};
+
+// An option as received in an interaction, with its resolved value
+fn data_option(name: &str, kind: u8, value: Option<CommandDataOptionValue>, options: Vec<CommandDataOption>) -> CommandDataOption {
+    let mut option: CommandDataOption = serde_json::from_value(serde_json::json!({ "name": name, "type": kind }))
+        .expect("Invalid option");
+    option.resolved = value;
+    option.options = options;
+    option
+}
+
+fn string(name: &str, value: &str) -> CommandDataOption {
+    data_option(name, 3, Some(CommandDataOptionValue::String(value.to_string())), vec![])
+}
+
+fn integer(name: &str, value: i64) -> CommandDataOption {
+    data_option(name, 4, Some(CommandDataOptionValue::Integer(value)), vec![])
+}
+
+#[test]
+fn options_are_found_by_name_in_any_order() {
+    let sent = [string("title", "Shader tip"), integer("hour", 9), string("tags", "unity")];
+    let mut reversed = sent.clone();
+    reversed.reverse();
+
+    for options in [CommandOptions::from_interaction(&sent), CommandOptions::from_interaction(&reversed)] {
+        assert_eq!(options.get::<String>("title"), Ok(String::from("Shader tip")));
+        assert_eq!(options.get::<String>("tags"), Ok(String::from("unity")));
+        assert_eq!(options.get::<i64>("hour"), Ok(9));
+        assert_eq!(options.get_optional::<String>("content"), Ok(None));
+        assert_eq!(options.get::<String>("content"), Err(OptionError::Missing(String::from("content"))));
+    }
+}
+
+#[test]
+fn mismatched_values_are_rejected() {
+    let options = CommandOptions::from_interaction(&[string("hour", "nine"), integer("minute", -5)]);
+
+    assert_eq!(options.get::<i64>("hour"), Err(OptionError::WrongType { name: String::from("hour"), expected: "an integer", expected_fr: "un nombre entier" }));
+    assert_eq!(options.get_optional::<bool>("hour"), Err(OptionError::WrongType { name: String::from("hour"), expected: "True or False", expected_fr: "Vrai ou Faux" }));
+    // A negative integer isn't a positive one, but it is a number
+    assert_eq!(options.get::<u64>("minute"), Err(OptionError::WrongType { name: String::from("minute"), expected: "a positive integer", expected_fr: "un nombre entier positif" }));
+    assert_eq!(options.get::<f64>("minute"), Ok(-5.0));
+    assert_eq!(options.get::<String>("minute").unwrap_err().option_name(), "minute");
+}
+
+#[test]
+fn integers_are_checked_against_the_range() {
+    let out_of_range = |name: &str| OptionError::OutOfRange { name: name.to_string(), min: 0, max: 23 };
+    let options = CommandOptions::from_interaction(&[integer("min", 0), integer("max", 23), integer("below", -1), integer("above", 24)]);
+
+    // 1 - Both bounds are accepted
+    assert_eq!(options.get_in_range("min", 0..=23), Ok(0));
+    assert_eq!(options.get_in_range("max", 0..=23), Ok(23));
+
+    // 2 - The values just outside are refused
+    assert_eq!(options.get_in_range("below", 0..=23), Err(out_of_range("below")));
+    assert_eq!(options.get_in_range("above", 0..=23), Err(out_of_range("above")));
+    assert_eq!(options.get_optional_in_range("above", 0..=23), Err(out_of_range("above")));
+
+    // 3 - A missing option is only an error when it is required
+    assert_eq!(options.get_optional_in_range("hour", 0..=23), Ok(None));
+    assert_eq!(options.get_in_range("hour", 0..=23), Err(OptionError::Missing(String::from("hour"))));
+}
+
+#[test]
+fn subcommands_are_found_through_their_group() {
+    // A command like /persona set name:"Tip of the Day" : a group holding a subcommand
+    let set = data_option("set", 1, None, vec![string("name", "Tip of the Day")]);
+    let options = CommandOptions::from_interaction(&[data_option("persona", 2, None, vec![set])]);
+
+    let (group, group_options) = options.subcommand().expect("Group not found");
+    assert_eq!(group, "persona");
+    let (subcommand, subcommand_options) = group_options.subcommand().expect("Subcommand not found");
+    assert_eq!(subcommand, "set");
+    assert_eq!(subcommand_options.get::<String>("name"), Ok(String::from("Tip of the Day")));
+    assert_eq!(options.redacted(), "persona(set(name))");
+
+    // Without subcommand, the error names it
+    assert_eq!(subcommand_options.subcommand().unwrap_err(), OptionError::Missing(String::from("subcommand")));
+}
+
+#[test]
+fn discord_objects_are_converted() {
+    let channel: PartialChannel = serde_json::from_value(serde_json::json!({ "id": "42", "type": 0 }))
+        .expect("Invalid channel");
+    let user: User = serde_json::from_value(serde_json::json!({ "id": "7", "username": "helper", "discriminator": "0001", "avatar": null }))
+        .expect("Invalid user");
+    let role: Role = serde_json::from_value(serde_json::json!({
+        "id": "5", "guild_id": "1", "color": 0, "hoist": false, "managed": false,
+        "name": "helpers", "permissions": "0", "position": 1, "icon": null, "unicode_emoji": null,
+    })).expect("Invalid role");
+    let attachment: Attachment = serde_json::from_value(serde_json::json!({
+        "id": "9", "filename": "tips.json", "size": 12, "url": "https://cdn.example.com/tips.json",
+        "proxy_url": "https://media.example.com/tips.json", "height": null, "width": null,
+    })).expect("Invalid attachment");
+    let options = CommandOptions::new()
+        .with("channel", CommandDataOptionValue::Channel(channel))
+        .with("user", CommandDataOptionValue::User(user, None))
+        .with("role", CommandDataOptionValue::Role(role))
+        .with("file", CommandDataOptionValue::Attachment(attachment));
+
+    assert_eq!(options.get::<PartialChannel>("channel").unwrap().id.0, 42);
+    assert_eq!(options.get::<User>("user").unwrap().id.0, 7);
+    assert_eq!(options.get::<Role>("role").unwrap().id.0, 5);
+    assert_eq!(options.get::<Attachment>("file").unwrap().filename, "tips.json");
+    assert_eq!(options.describe(), "channel=<#42>, user=<@7>, role=<@&5>, file=\"tips.json\"");
+
+    // Each object is only converted to its own type
+    assert_eq!(options.get::<Role>("user").unwrap_err(), OptionError::WrongType { name: String::from("user"), expected: "a role", expected_fr: "un rôle" });
+    assert_eq!(options.get::<User>("channel").unwrap_err(), OptionError::WrongType { name: String::from("channel"), expected: "a user", expected_fr: "un utilisateur" });
+    assert_eq!(options.get::<PartialChannel>("file").unwrap_err(), OptionError::WrongType { name: String::from("file"), expected: "a channel", expected_fr: "un salon" });
+    assert_eq!(options.get::<Attachment>("role").unwrap_err(), OptionError::WrongType { name: String::from("role"), expected: "a file", expected_fr: "un fichier" });
+}
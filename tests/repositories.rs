@@ -0,0 +1,105 @@
+//! The in-memory repositories used by the tests of the commands behave like
+//! the sqlite ones : the same checks run on both.
+
+use game_bot4_fun::database::{Database, run_migrations};
+use game_bot4_fun::database::url::DatabaseUrl;
+use game_bot4_fun::repositories::{NewTip, Persona, RepositoryError, SchedulerConfig, SchedulerRepository, Tip, TipChanges, TipRepository};
+use game_bot4_fun::repositories::memory::{InMemorySchedulerRepository, InMemoryTipRepository};
+
+const GUILD_ID: u64 = 1;
+const OTHER_GUILD_ID: u64 = 2;
+
+fn new_tip(title: &str, tags: &str) -> NewTip {
+    NewTip { title: title.to_string(), content: String::from("content"), tags: tags.to_string() }
+}
+
+// The titles of the tips, sorted : the order of the list is not specified
+fn titles(tips: Vec<Tip>) -> Vec<String> {
+    let mut titles: Vec<String> = tips.into_iter().map(|tip| tip.title).collect();
+    titles.sort();
+    titles
+}
+
+async fn exercise_tips(tips: &dyn TipRepository) {
+    let shader = tips.create(GUILD_ID, new_tip("Shader tip", "unity,shaders")).await.expect("Failed to create");
+    let blueprint = tips.create(GUILD_ID, new_tip("Blueprint tip", "unreal")).await.expect("Failed to create");
+    let other = tips.create(OTHER_GUILD_ID, new_tip("Other tip", "unity")).await.expect("Failed to create");
+    assert_ne!(shader.id, blueprint.id);
+
+    // 1 - Every tip is scoped by guild : the tips of an other guild are invisible
+    assert_eq!(titles(tips.list(GUILD_ID, &[]).await.unwrap()), ["Blueprint tip", "Shader tip"]);
+    assert_eq!(titles(tips.list(GUILD_ID, &[String::from("unity")]).await.unwrap()), ["Shader tip"]);
+    assert_eq!(titles(tips.list(GUILD_ID, &[String::from("unreal"), String::from("shaders")]).await.unwrap()), ["Blueprint tip", "Shader tip"]);
+    assert_eq!(tips.get(GUILD_ID, shader.id).await.unwrap(), Some(shader.clone()));
+    assert_eq!(tips.get(GUILD_ID, other.id).await.unwrap(), None);
+    let changes = TipChanges { content: Some(String::from("changed")), ..TipChanges::default() };
+    assert_eq!(tips.update(GUILD_ID, other.id, changes.clone()).await.unwrap(), None);
+    assert!(!tips.delete(GUILD_ID, other.id).await.unwrap());
+    assert_eq!(tips.get(OTHER_GUILD_ID, other.id).await.unwrap().unwrap().content, "content");
+
+    // 2 - Only the given values are updated
+    let updated = tips.update(GUILD_ID, shader.id, changes).await.unwrap().expect("Tip not updated");
+    assert_eq!((updated.title.as_str(), updated.content.as_str(), updated.tags.as_str()), ("Shader tip", "changed", "unity,shaders"));
+    assert_eq!(tips.update(GUILD_ID, shader.id + 100, TipChanges::default()).await.unwrap(), None);
+
+    // 3 - Titles are unique in a guild, not between guilds
+    assert!(matches!(tips.create(GUILD_ID, new_tip("Shader tip", "")).await, Err(RepositoryError::DuplicateTitle(title)) if title == "Shader tip"));
+    let rename = TipChanges { title: Some(String::from("Shader tip")), ..TipChanges::default() };
+    assert!(matches!(tips.update(GUILD_ID, blueprint.id, rename).await, Err(RepositoryError::DuplicateTitle(_))));
+    let keep_title = TipChanges { title: Some(String::from("Shader tip")), ..TipChanges::default() };
+    assert!(tips.update(GUILD_ID, shader.id, keep_title).await.unwrap().is_some());
+    assert!(tips.create(OTHER_GUILD_ID, new_tip("Shader tip", "")).await.is_ok());
+
+    // 4 - Posts are remembered by tip, forgotten one by one or with their tip
+    tips.save_post(shader.id, 42, 1000).await.unwrap();
+    tips.save_post(shader.id, 43, 1001).await.unwrap();
+    tips.save_post(blueprint.id, 42, 1002).await.unwrap();
+    let posts = tips.posts(shader.id).await.unwrap();
+    let locations: Vec<(u64, u64, u64)> = posts.iter().map(|post| (post.tip_id, post.channel, post.message)).collect();
+    assert_eq!(locations, [(shader.id, 42, 1000), (shader.id, 43, 1001)]);
+    tips.forget_posts(&[posts[0].id]).await.unwrap();
+    let remaining: Vec<u64> = tips.posts(shader.id).await.unwrap().iter().map(|post| post.message).collect();
+    assert_eq!(remaining, [1001]);
+    tips.forget_posts(&[]).await.unwrap();
+    assert_eq!(tips.posts(blueprint.id).await.unwrap().len(), 1);
+    assert!(tips.delete(GUILD_ID, shader.id).await.unwrap());
+    assert!(tips.posts(shader.id).await.unwrap().is_empty());
+    assert_eq!(tips.posts(blueprint.id).await.unwrap().len(), 1);
+    assert!(!tips.delete(GUILD_ID, shader.id).await.unwrap());
+}
+
+async fn exercise_schedulers(configs: &dyn SchedulerRepository) {
+    assert_eq!(configs.get(GUILD_ID).await.unwrap(), None);
+    let config = SchedulerConfig { channel: 42, hour: 9, minute: 30, persona: None };
+    configs.save(GUILD_ID, config.clone()).await.unwrap();
+    assert_eq!(configs.get(GUILD_ID).await.unwrap(), Some(config));
+    assert_eq!(configs.get(OTHER_GUILD_ID).await.unwrap(), None);
+
+    // The configuration is replaced
+    let persona = Persona { name: String::from("Tip of the Day"), avatar_url: Some(String::from("https://example.com/tip.png")) };
+    let config = SchedulerConfig { channel: 43, hour: 18, minute: 0, persona: Some(persona) };
+    configs.save(GUILD_ID, config.clone()).await.unwrap();
+    assert_eq!(configs.get(GUILD_ID).await.unwrap(), Some(config));
+}
+
+#[tokio::test]
+async fn in_memory_repositories() {
+    exercise_tips(&InMemoryTipRepository::new()).await;
+    exercise_schedulers(&InMemorySchedulerRepository::new()).await;
+}
+
+#[tokio::test]
+async fn sqlite_repositories() {
+    let path = std::env::temp_dir().join(format!("gamebot_repositories_test_{}.sqlite", std::process::id()));
+    let url = DatabaseUrl::parse(&format!("sqlite:{}", path.display())).expect("Invalid url");
+    let database = Database::connect(&url).await.expect("Failed to open the database");
+    run_migrations(&database, None).await.expect("Failed to run migrations");
+
+    exercise_tips(database.tip_repository().as_ref()).await;
+    exercise_schedulers(database.scheduler_repository().as_ref()).await;
+
+    database.close().await.expect("Failed to close the database");
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
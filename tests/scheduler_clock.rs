@@ -0,0 +1,152 @@
+//! The tips scheduler runs with a ManualClock on a paused tokio runtime :
+//! days pass in milliseconds and every post time is checked exactly.
+
+use std::sync::Arc;
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Europe::Paris;
+use chrono_tz::Tz;
+use game_bot4_fun::clock::{Clock, ManualClock};
+use game_bot4_fun::commands::slash_command::CommandContext;
+use game_bot4_fun::commands::tips_scheduler::scheduler::{next_post_time, start, stop};
+use game_bot4_fun::messenger::recording::RecordingMessenger;
+use game_bot4_fun::repositories::{NewTip, SchedulerConfig, SchedulerRepository, TipRepository};
+use game_bot4_fun::repositories::memory::{InMemorySchedulerRepository, InMemoryTipRepository};
+
+const GUILD_ID: u64 = 1;
+const CHANNEL_ID: u64 = 42;
+
+fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+}
+
+struct Scheduler {
+    ctx: CommandContext,
+    clock: ManualClock,
+    messenger: Arc<RecordingMessenger>,
+}
+
+impl Scheduler {
+    async fn new(now: DateTime<Utc>, timezone: Tz, hour: u32, minute: u32) -> Scheduler {
+        let tips = Arc::new(InMemoryTipRepository::new());
+        tips.create(GUILD_ID, NewTip { title: String::from("Shader tip"), content: String::from("content"), tags: String::from("unity") })
+            .await.unwrap();
+        let configs = Arc::new(InMemorySchedulerRepository::new());
+        configs.save(GUILD_ID, SchedulerConfig { channel: CHANNEL_ID, hour, minute, persona: None }).await.unwrap();
+        let clock = ManualClock::new(now);
+        let messenger = Arc::new(RecordingMessenger::new());
+        let ctx = CommandContext {
+            tips,
+            scheduler_configs: configs,
+            messenger: messenger.clone(),
+            timezone: Some(timezone),
+            ..CommandContext::in_memory(Arc::new(clock))
+        };
+        Scheduler { ctx, clock, messenger }
+    }
+
+    async fn start(&self) {
+        start(&self.ctx, GUILD_ID).await.unwrap();
+    }
+
+    async fn stop(&self) {
+        stop(self.ctx.scheduler_configs.as_ref(), self.ctx.schedulers.clone(), GUILD_ID).await.unwrap();
+    }
+
+    // Let the time pass until the given time, the scheduler runs meanwhile
+    async fn wait_until(&self, time: DateTime<Utc>) {
+        tokio::time::sleep((time - self.clock.now()).to_std().unwrap()).await;
+    }
+
+    fn posts(&self) -> usize {
+        self.messenger.sent_to(CHANNEL_ID).len()
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn tip_is_posted_at_the_configured_time() {
+    let scheduler = Scheduler::new(utc(2024, 1, 15, 12, 0), chrono_tz::UTC, 12, 20).await;
+    scheduler.start().await;
+
+    scheduler.wait_until(utc(2024, 1, 15, 12, 19)).await;
+    assert_eq!(scheduler.posts(), 0);
+    scheduler.wait_until(utc(2024, 1, 15, 12, 21)).await;
+    assert_eq!(scheduler.posts(), 1);
+    assert_eq!(next_post_time(utc(2024, 1, 15, 12, 20), 12, 20, Some(chrono_tz::UTC)), utc(2024, 1, 16, 12, 20));
+}
+
+#[tokio::test(start_paused = true)]
+async fn one_tip_is_posted_every_day() {
+    let scheduler = Scheduler::new(utc(2024, 1, 15, 13, 0), chrono_tz::UTC, 12, 20).await;
+    scheduler.start().await;
+
+    // The hour of the first day is passed : 7 posts from the 16th to the 22th
+    scheduler.wait_until(utc(2024, 1, 22, 23, 0)).await;
+    assert_eq!(scheduler.posts(), 7);
+}
+
+#[tokio::test(start_paused = true)]
+async fn restart_does_not_post_twice_the_same_day() {
+    let scheduler = Scheduler::new(utc(2024, 1, 15, 12, 0), chrono_tz::UTC, 12, 20).await;
+    scheduler.start().await;
+    scheduler.wait_until(utc(2024, 1, 15, 12, 25)).await;
+    assert_eq!(scheduler.posts(), 1);
+
+    scheduler.stop().await;
+    scheduler.start().await;
+    scheduler.wait_until(utc(2024, 1, 16, 12, 19)).await;
+    assert_eq!(scheduler.posts(), 1);
+    scheduler.wait_until(utc(2024, 1, 16, 12, 21)).await;
+    assert_eq!(scheduler.posts(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn stopped_scheduler_posts_nothing() {
+    let scheduler = Scheduler::new(utc(2024, 1, 15, 12, 0), chrono_tz::UTC, 12, 20).await;
+    scheduler.start().await;
+    scheduler.stop().await;
+
+    scheduler.wait_until(utc(2024, 1, 18, 0, 0)).await;
+    assert_eq!(scheduler.posts(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn skipped_hour_is_posted_one_hour_later() {
+    // 2024-03-31 in Paris : 02:00 CET becomes 03:00 CEST
+    assert_eq!(next_post_time(utc(2024, 3, 30, 12, 0), 2, 30, Some(Paris)), utc(2024, 3, 31, 1, 30));
+    assert_eq!(next_post_time(utc(2024, 3, 31, 1, 30), 2, 30, Some(Paris)), utc(2024, 4, 1, 0, 30));
+
+    let scheduler = Scheduler::new(utc(2024, 3, 30, 12, 0), Paris, 2, 30).await;
+    scheduler.start().await;
+    scheduler.wait_until(utc(2024, 3, 31, 1, 29)).await;
+    assert_eq!(scheduler.posts(), 0);
+    scheduler.wait_until(utc(2024, 3, 31, 1, 31)).await;
+    assert_eq!(scheduler.posts(), 1);
+    scheduler.wait_until(utc(2024, 4, 1, 0, 31)).await;
+    assert_eq!(scheduler.posts(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn repeated_hour_is_posted_once() {
+    // 2024-10-27 in Paris : 03:00 CEST becomes 02:00 CET, 02:30 happens twice
+    assert_eq!(next_post_time(utc(2024, 10, 26, 12, 0), 2, 30, Some(Paris)), utc(2024, 10, 27, 0, 30));
+    assert_eq!(next_post_time(utc(2024, 10, 27, 0, 30), 2, 30, Some(Paris)), utc(2024, 10, 28, 1, 30));
+
+    let scheduler = Scheduler::new(utc(2024, 10, 26, 12, 0), Paris, 2, 30).await;
+    scheduler.start().await;
+    scheduler.wait_until(utc(2024, 10, 28, 0, 0)).await;
+    assert_eq!(scheduler.posts(), 1);
+    scheduler.wait_until(utc(2024, 10, 28, 1, 31)).await;
+    assert_eq!(scheduler.posts(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn guild_without_tips_keeps_its_scheduler_running() {
+    let scheduler = Scheduler::new(utc(2024, 1, 15, 12, 0), chrono_tz::UTC, 12, 20).await;
+    scheduler.ctx.tips.delete(GUILD_ID, 1).await.unwrap();
+    scheduler.start().await;
+
+    scheduler.wait_until(utc(2024, 1, 17, 0, 0)).await;
+    assert_eq!(scheduler.posts(), 0);
+    let schedulers = scheduler.ctx.schedulers.read().await;
+    assert!(!schedulers[&GUILD_ID].is_finished());
+}
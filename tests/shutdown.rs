@@ -0,0 +1,71 @@
+//! When the bot stops, running commands and tip posts are waited for,
+//! new ones are refused and the tips schedulers are stopped.
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{TimeZone, Utc};
+use game_bot4_fun::clock::ManualClock;
+use game_bot4_fun::commands::slash_command::CommandContext;
+use game_bot4_fun::commands::tips_scheduler::scheduler::{start, stop_all};
+use game_bot4_fun::messenger::recording::RecordingMessenger;
+use game_bot4_fun::repositories::{NewTip, SchedulerConfig, SchedulerRepository, TipRepository};
+use game_bot4_fun::repositories::memory::{InMemorySchedulerRepository, InMemoryTipRepository};
+use game_bot4_fun::shutdown::Shutdown;
+
+const GUILD_ID: u64 = 1;
+const CHANNEL_ID: u64 = 42;
+
+#[tokio::test(start_paused = true)]
+async fn running_work_is_waited_for() {
+    let shutdown = Arc::new(Shutdown::default());
+    let activity = shutdown.track().expect("The bot is not stopping");
+
+    // The command finish after 2 seconds
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        drop(activity);
+    });
+    let started = tokio::time::Instant::now();
+    assert!(shutdown.stop(Duration::from_secs(10)).await);
+    assert_eq!(started.elapsed(), Duration::from_secs(2));
+
+    // New work is refused
+    assert!(shutdown.track().is_none());
+    assert_eq!(shutdown.running(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn work_still_running_after_the_timeout_is_reported() {
+    let shutdown = Arc::new(Shutdown::default());
+    let _activity = shutdown.track().expect("The bot is not stopping");
+
+    assert!(!shutdown.stop(Duration::from_secs(5)).await);
+    assert_eq!(shutdown.running(), 1);
+    assert!(shutdown.is_stopping());
+}
+
+#[tokio::test(start_paused = true)]
+async fn stopping_scheduler_posts_nothing() {
+    let tips = Arc::new(InMemoryTipRepository::new());
+    tips.create(GUILD_ID, NewTip { title: String::from("Shader tip"), content: String::from("content"), tags: String::from("unity") })
+        .await.unwrap();
+    let configs = Arc::new(InMemorySchedulerRepository::new());
+    configs.save(GUILD_ID, SchedulerConfig { channel: CHANNEL_ID, hour: 12, minute: 20, persona: None }).await.unwrap();
+    let messenger = Arc::new(RecordingMessenger::new());
+    let ctx = CommandContext {
+        tips,
+        scheduler_configs: configs,
+        messenger: messenger.clone(),
+        ..CommandContext::in_memory(Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap())))
+    };
+    start(&ctx, GUILD_ID).await.unwrap();
+
+    // The shutdown began before the post : the scheduler ends without posting
+    assert!(ctx.shutdown.stop(Duration::from_secs(5)).await);
+    tokio::time::sleep(Duration::from_secs(30 * 60)).await;
+    assert!(messenger.sent_to(CHANNEL_ID).is_empty());
+    assert!(ctx.schedulers.read().await[&GUILD_ID].is_finished());
+
+    assert_eq!(stop_all(&ctx.schedulers).await, 1);
+    assert!(ctx.schedulers.read().await.is_empty());
+}
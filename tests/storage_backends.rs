@@ -0,0 +1,198 @@
+//! Migrations, every tip and scheduler command, the permissions, the cooldowns, the prefixes and the audit log run on each storage backend.
+//!
+//! The PostgreSQL backend is tested with `cargo test --features postgres` when
+//! POSTGRES_TEST_URL is set. Its database is emptied first : use a throwaway database.
+
+use std::sync::Arc;
+use serenity::model::channel::PartialChannel;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use game_bot4_fun::clock::SystemClock;
+use game_bot4_fun::commands::options::CommandOptions;
+use game_bot4_fun::commands::registry::{ButtonResponse, default_registry};
+use game_bot4_fun::commands::slash_command::{CommandContext, CommandResponse};
+use game_bot4_fun::config::FeatureToggles;
+use game_bot4_fun::cooldowns::CooldownScope;
+use game_bot4_fun::database::{Database, run_migrations};
+use game_bot4_fun::database::url::DatabaseUrl;
+use game_bot4_fun::permissions::Capability;
+use game_bot4_fun::repositories::{AuditFilter, CooldownRule, PermissionGrant};
+
+const GUILD_ID: u64 = 1;
+const OTHER_GUILD_ID: u64 = 2;
+
+// A field of the first embed of the response
+fn text(response: &CommandResponse, field: &str) -> String {
+    response.message.embeds[0].0.get(field).and_then(|value| value.as_str()).unwrap_or_default().to_string()
+}
+
+fn string(value: &str) -> CommandDataOptionValue {
+    CommandDataOptionValue::String(value.to_string())
+}
+
+fn channel(id: u64) -> CommandDataOptionValue {
+    let channel: PartialChannel = serde_json::from_value(serde_json::json!({ "id": id.to_string(), "type": 0 }))
+        .expect("Invalid channel");
+    CommandDataOptionValue::Channel(channel)
+}
+
+async fn exercise(database: Database) {
+    // Migrations
+    run_migrations(&database, None).await.expect("Failed to run migrations");
+    assert!(database.pending_migrations().await.expect("Failed to list migrations").is_empty());
+    run_migrations(&database, None).await.expect("Migrations must be idempotent");
+
+    let ctx = CommandContext { timezone: None, ..CommandContext::in_memory(Arc::new(SystemClock)) }.with_database(&database);
+    let registry = default_registry(&FeatureToggles::default());
+
+    // /tips_create
+    let shader = CommandOptions::new()
+        .with("title", string("Shader tip"))
+        .with("content", string("Use the shader graph"))
+        .with("tags", string("shaders,unity"));
+    assert_eq!(text(&registry.dispatch(&ctx, "tips_create", GUILD_ID, &shader).await, "title"), "Shader tip");
+    let duplicate = registry.dispatch(&ctx, "tips_create", GUILD_ID, &shader).await;
+    assert!(text(&duplicate, "description").contains("already exists"));
+    let blueprint = CommandOptions::new()
+        .with("title", string("Blueprint tip"))
+        .with("content", string("Collapse nodes"))
+        .with("tags", string("unreal"));
+    assert_eq!(text(&registry.dispatch(&ctx, "tips_create", GUILD_ID, &blueprint).await, "title"), "Blueprint tip");
+    // the same title can be used in another guild
+    assert_eq!(text(&registry.dispatch(&ctx, "tips_create", OTHER_GUILD_ID, &shader).await, "title"), "Shader tip");
+
+    // /tips_list
+    let listed = registry.dispatch(&ctx, "tips_list", GUILD_ID, &CommandOptions::new().with("tags", string("shaders"))).await;
+    assert!(text(&listed, "description").contains("Shader tip"));
+    assert!(!text(&listed, "description").contains("Blueprint tip"));
+    let listed = registry.dispatch(&ctx, "tips_list", GUILD_ID, &CommandOptions::new()).await;
+    assert!(text(&listed, "description").contains("Shader tip") && text(&listed, "description").contains("Blueprint tip"));
+
+    let tips = ctx.tips.list(GUILD_ID, &[]).await.expect("Failed to list tips");
+    let id = tips.iter().find(|tip| tip.title == "Shader tip").expect("Tip not created").id;
+    let id_option = || CommandOptions::new().with("id", CommandDataOptionValue::Integer(id as i64));
+
+    // /tips_read
+    assert_eq!(text(&registry.dispatch(&ctx, "tips_read", GUILD_ID, &id_option()).await, "title"), "Shader tip");
+    assert_eq!(text(&registry.dispatch(&ctx, "tips_read", OTHER_GUILD_ID, &id_option()).await, "title"), "Tip id unknown");
+
+    // /tips_update
+    let update = id_option()
+        .with("title", string("Shader tips"))
+        .with("edit_posts", CommandDataOptionValue::Boolean(false));
+    let updated = registry.dispatch(&ctx, "tips_update", GUILD_ID, &update).await;
+    assert_eq!(text(&updated, "title"), "Shader tips");
+    assert_eq!(text(&updated, "description"), "Use the shader graph");
+    let update = id_option().with("title", string("Blueprint tip"));
+    assert!(text(&registry.dispatch(&ctx, "tips_update", GUILD_ID, &update).await, "description").contains("already exists"));
+
+    // /tips_delete
+    assert_eq!(text(&registry.dispatch(&ctx, "tips_delete", OTHER_GUILD_ID, &id_option()).await, "title"), "Tip id unknown");
+    let preview = registry.dispatch(&ctx, "tips_delete", GUILD_ID, &id_option()).await;
+    match registry.dispatch_button(&ctx, &preview.message.buttons[0].custom_id).await {
+        Some(ButtonResponse::Update(deleted)) => assert_eq!(text(&deleted, "title"), "Tip deleted successfully :)"),
+        _ => panic!("The deletion is not confirmed"),
+    }
+    assert_eq!(text(&registry.dispatch(&ctx, "tips_read", GUILD_ID, &id_option()).await, "title"), "Tip id unknown");
+
+    // /scheduler_config and /scheduler
+    let hour_only = CommandOptions::new().with("hour", string("08:30"));
+    assert_eq!(text(&registry.dispatch(&ctx, "scheduler_config", GUILD_ID, &hour_only).await, "title"), "Config not initialised !");
+    let config = hour_only.with("message_chanel", channel(42));
+    assert_eq!(text(&registry.dispatch(&ctx, "scheduler_config", GUILD_ID, &config).await, "title"), "Here is the new config of the tips scheduler :");
+    let config = CommandOptions::new().with("hour", string("09:15")).with("webhook_name", string("Daily tip"));
+    registry.dispatch(&ctx, "scheduler_config", GUILD_ID, &config).await;
+    let info = registry.dispatch(&ctx, "scheduler", GUILD_ID, &CommandOptions::new().with("action", string("info"))).await;
+    assert!(text(&info, "description").contains("<#42>"));
+    assert!(text(&info, "description").contains("09H15"));
+    assert!(text(&info, "description").contains("webhook **Daily tip**"));
+    assert!(text(&info, "description").contains("STOPPED"));
+    let info = registry.dispatch(&ctx, "scheduler", OTHER_GUILD_ID, &CommandOptions::new().with("action", string("info"))).await;
+    assert_eq!(text(&info, "title"), "Config not initialised !");
+
+    // Capabilities granted with /permissions
+    let grant = PermissionGrant { role_id: 10, capability: Capability::TipsWrite };
+    assert!(ctx.permissions.grant(GUILD_ID, grant.clone()).await.expect("Failed to grant"));
+    assert!(!ctx.permissions.grant(GUILD_ID, grant.clone()).await.expect("Failed to grant"));
+    assert_eq!(ctx.permissions.list(GUILD_ID).await.expect("Failed to list permissions"), vec![grant.clone()]);
+    assert!(ctx.permissions.list(OTHER_GUILD_ID).await.expect("Failed to list permissions").is_empty());
+    assert!(ctx.permissions.revoke(GUILD_ID, grant.clone()).await.expect("Failed to revoke"));
+    assert!(!ctx.permissions.revoke(GUILD_ID, grant).await.expect("Failed to revoke"));
+    assert!(ctx.permissions.list(GUILD_ID).await.expect("Failed to list permissions").is_empty());
+
+    // Cooldown rules set with /cooldowns
+    let rule = CooldownRule { command: String::from("tips_list"), scope: CooldownScope::Channel, uses: 3, period: 60 };
+    assert_eq!(ctx.cooldowns.set(GUILD_ID, rule.clone()).await.expect("Failed to set the cooldown"), None);
+    let replaced = CooldownRule { uses: 5, ..rule.clone() };
+    assert_eq!(ctx.cooldowns.set(GUILD_ID, replaced.clone()).await.expect("Failed to set the cooldown"), Some(rule));
+    assert_eq!(ctx.cooldowns.rules(GUILD_ID).await.expect("Failed to list cooldowns"), vec![replaced.clone()]);
+    assert!(ctx.cooldowns.rules(OTHER_GUILD_ID).await.expect("Failed to list cooldowns").is_empty());
+    assert_eq!(ctx.cooldowns.remove(GUILD_ID, "tips_list", CooldownScope::User).await.expect("Failed to remove the cooldown"), None);
+    assert_eq!(ctx.cooldowns.remove(GUILD_ID, "tips_list", CooldownScope::Channel).await.expect("Failed to remove the cooldown"), Some(replaced));
+    assert!(ctx.cooldowns.rules(GUILD_ID).await.expect("Failed to list cooldowns").is_empty());
+
+    // Prefix of the text commands set with /prefix, the cache is refreshed after a change
+    let prefixes = database.prefix_repository();
+    assert_eq!(ctx.prefixes.get(GUILD_ID).await.expect("Failed to get the prefix"), "!");
+    assert_eq!(ctx.prefixes.set(GUILD_ID, Some(String::from("gb!"))).await.expect("Failed to set the prefix"), "!");
+    assert_eq!(ctx.prefixes.set(GUILD_ID, Some(String::from("?"))).await.expect("Failed to set the prefix"), "gb!");
+    assert_eq!(ctx.prefixes.get(GUILD_ID).await.expect("Failed to get the prefix"), "?");
+    assert_eq!(prefixes.prefix(OTHER_GUILD_ID).await.expect("Failed to get the prefix"), None);
+    ctx.prefixes.set(GUILD_ID, None).await.expect("Failed to set the prefix");
+    assert_eq!(prefixes.prefix(GUILD_ID).await.expect("Failed to get the prefix"), None);
+
+    // Audit log of the commands above : failed commands are not written
+    let entries = ctx.audit.search(GUILD_ID, &AuditFilter::default()).await.expect("Failed to search the audit log");
+    let commands: Vec<&str> = entries.iter().map(|entry| entry.command.as_str()).collect();
+    assert_eq!(commands, ["scheduler_config", "scheduler_config", "tips_delete", "tips_update", "tips_create", "tips_create"]);
+    assert!(entries[2].before.as_deref().is_some_and(|before| before.contains("Shader tips")) && entries[2].after.is_none());
+    assert!(entries[3].arguments.contains("title=\"Shader tips\""));
+    let filter = AuditFilter { command: Some(String::from("tips_create")), ..AuditFilter::default() };
+    let created = ctx.audit.search(GUILD_ID, &filter).await.expect("Failed to search the audit log");
+    assert!(created[1].after.as_deref().is_some_and(|after| after.contains("Shader tip")));
+    let filter = AuditFilter { since: Some(entries[0].created_at + chrono::Duration::seconds(1)), ..AuditFilter::default() };
+    assert!(ctx.audit.search(GUILD_ID, &filter).await.expect("Failed to search the audit log").is_empty());
+    let filter = AuditFilter { actor: Some(7), ..AuditFilter::default() };
+    assert!(ctx.audit.search(GUILD_ID, &filter).await.expect("Failed to search the audit log").is_empty());
+    assert_eq!(ctx.audit.search(OTHER_GUILD_ID, &AuditFilter::default()).await.expect("Failed to search the audit log").len(), 1);
+    ctx.audit.set_mirror_channel(GUILD_ID, Some(43)).await.expect("Failed to set the mod-log channel");
+    assert_eq!(ctx.audit.mirror_channel(GUILD_ID).await.expect("Failed to get the mod-log channel"), Some(43));
+    ctx.audit.set_mirror_channel(GUILD_ID, None).await.expect("Failed to set the mod-log channel");
+    assert_eq!(ctx.audit.mirror_channel(GUILD_ID).await.expect("Failed to get the mod-log channel"), None);
+
+    // The database is closed when the bot stops
+    database.close().await.expect("Failed to close the database");
+    assert!(ctx.tips.list(GUILD_ID, &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn sqlite_backend_runs_every_command() {
+    let path = std::env::temp_dir().join(format!("gamebot_storage_test_{}.sqlite", std::process::id()));
+    let url = DatabaseUrl::parse(&format!("sqlite:{}", path.display())).expect("Invalid url");
+    let database = Database::connect(&url).await.expect("Failed to open the database");
+
+    exercise(database).await;
+    // The WAL has been written back in the database file
+    assert!(!std::path::Path::new(&format!("{}-wal", path.display())).exists());
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+#[cfg(feature = "postgres")]
+#[tokio::test]
+async fn postgres_backend_runs_every_command() {
+    let url = match std::env::var("POSTGRES_TEST_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!("POSTGRES_TEST_URL is not set : the PostgreSQL backend is not tested.");
+            return;
+        }
+    };
+    let database = Database::connect(&DatabaseUrl::parse(&url).expect("Invalid url")).await.expect("Failed to connect to PostgreSQL");
+    if let Database::Postgres(postgres) = &database {
+        postgres.client().batch_execute("DROP SCHEMA public CASCADE; CREATE SCHEMA public;").await.expect("Failed to empty the database");
+    }
+
+    exercise(database).await;
+}